@@ -0,0 +1,49 @@
+//! CERT record certificate types.
+
+//------------ CertType ------------------------------------------------------
+
+int_enum! {
+    /// CERT record certificate types.
+    ///
+    /// The type field of a CERT record specifies the format of the
+    /// certificate or CRL contained in the record.
+    ///
+    /// For the currently registered values see the [IANA registration].
+    /// This type is complete as of the registry update of 2006-03-01.
+    ///
+    /// [IANA registration]: https://www.iana.org/assignments/cert-rr-types/cert-rr-types.xhtml
+    =>
+    CertType, u16;
+
+    /// An X.509 certificate as per PKIX.
+    (Pkix => 1, b"PKIX")
+
+    /// An SPKI certificate.
+    (Spki => 2, b"SPKI")
+
+    /// An OpenPGP packet.
+    (Pgp => 3, b"PGP")
+
+    /// The URL of an X.509 data object.
+    (Ipkix => 4, b"IPKIX")
+
+    /// The URL of an SPKI certificate.
+    (Ispki => 5, b"ISPKI")
+
+    /// The fingerprint and URL of an OpenPGP packet.
+    (Ipgp => 6, b"IPGP")
+
+    /// An attribute certificate.
+    (Acpkix => 7, b"ACPKIX")
+
+    /// The URL of an attribute certificate.
+    (Iacpkix => 8, b"IACPKIX")
+
+    /// A URI private type.
+    (Uri => 253, b"URI")
+
+    /// An OID private type.
+    (Oid => 254, b"OID")
+}
+
+int_enum_str_with_decimal!(CertType, u16, "unknown certificate type");
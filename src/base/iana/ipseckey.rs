@@ -0,0 +1,42 @@
+//! IPSECKEY record parameters.
+
+//------------ IpseckeyAlg ---------------------------------------------------
+
+int_enum! {
+    /// IPSECKEY public key algorithms.
+    ///
+    /// The algorithm field of an IPSECKEY record specifies the algorithm of
+    /// the public key contained in the record.
+    ///
+    /// For the currently registered values see the [IANA registration].
+    /// This type is complete as of the registry update of 2023-03-28.
+    ///
+    /// [IANA registration]: https://www.iana.org/assignments/ipseckey-rr-parameters/ipseckey-rr-parameters.xhtml#ipseckey-rr-parameters-1
+    =>
+    IpseckeyAlg, u8;
+
+    /// No key is present.
+    (NoKey => 0, b"NONE")
+
+    /// A DSA key as defined in [RFC 2536].
+    ///
+    /// [RFC 2536]: https://tools.ietf.org/html/rfc2536
+    (Dsa => 1, b"DSA")
+
+    /// An RSA key as defined in [RFC 3110].
+    ///
+    /// [RFC 3110]: https://tools.ietf.org/html/rfc3110
+    (Rsa => 2, b"RSA")
+
+    /// An ECDSA key as defined in [RFC 8005].
+    ///
+    /// [RFC 8005]: https://tools.ietf.org/html/rfc8005
+    (Ecdsa => 3, b"ECDSA")
+
+    /// An EdDSA key as defined in [RFC 9373].
+    ///
+    /// [RFC 9373]: https://tools.ietf.org/html/rfc9373
+    (EdDsa => 4, b"EdDSA")
+}
+
+int_enum_str_decimal!(IpseckeyAlg, u8);
@@ -25,27 +25,37 @@
 //! re-exported here. This is mostly so we can have associated types like
 //! `FromStrError` without having to resort to devilishly long names.
 
+pub use self::cert::CertType;
 pub use self::class::Class;
 pub use self::digestalg::DigestAlg;
 pub use self::exterr::ExtendedErrorCode;
+pub use self::ipseckey::IpseckeyAlg;
 pub use self::nsec3::Nsec3HashAlg;
 pub use self::opcode::Opcode;
 pub use self::opt::OptionCode;
 pub use self::rcode::{OptRcode, Rcode, TsigRcode};
 pub use self::rtype::Rtype;
 pub use self::secalg::SecAlg;
+pub use self::sshfp::{SshfpAlg, SshfpType};
 pub use self::svcb::SvcbParamKey;
+pub use self::tkey::TkeyMode;
+pub use self::tlsa::{TlsaCertUsage, TlsaMatchingType, TlsaSelector};
 
 #[macro_use]
 mod macros;
 
+pub mod cert;
 pub mod class;
 pub mod digestalg;
 pub mod exterr;
+pub mod ipseckey;
 pub mod nsec3;
 pub mod opcode;
 pub mod opt;
 pub mod rcode;
 pub mod rtype;
 pub mod secalg;
+pub mod sshfp;
 pub mod svcb;
+pub mod tkey;
+pub mod tlsa;
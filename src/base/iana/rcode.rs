@@ -554,7 +554,7 @@ impl OptRcode {
     /// Returns the two parts of an extended rcode value.
     pub fn to_parts(self) -> (Rcode, u8) {
         let res = self.to_int();
-        (Rcode::from_int(res as u8), (res >> 8) as u8)
+        (Rcode::from_int(res as u8), (res >> 4) as u8)
     }
 
     /// Returns the rcode part of the extended rcode.
@@ -616,6 +616,24 @@ impl fmt::Display for OptRcode {
     }
 }
 
+//--- PartialEq and Eq
+
+impl cmp::PartialEq for OptRcode {
+    fn eq(&self, other: &OptRcode) -> bool {
+        self.to_int() == other.to_int()
+    }
+}
+
+impl cmp::Eq for OptRcode {}
+
+//--- Hash
+
+impl hash::Hash for OptRcode {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.to_int().hash(state)
+    }
+}
+
 //------------ TsigRcode ----------------------------------------------------
 
 int_enum! {
@@ -0,0 +1,72 @@
+//! SSHFP record parameters.
+
+//------------ SshfpAlg ------------------------------------------------------
+
+int_enum! {
+    /// SSHFP public key algorithms.
+    ///
+    /// The algorithm field of an SSHFP record specifies the algorithm of
+    /// the SSH public key whose fingerprint is contained in the record.
+    ///
+    /// For the currently registered values see the [IANA registration].
+    /// This type is complete as of the registry update of 2015-08-19.
+    ///
+    /// [IANA registration]: https://www.iana.org/assignments/dns-sshfp-rr-parameters/dns-sshfp-rr-parameters.xhtml#dns-sshfp-rr-parameters-1
+    =>
+    SshfpAlg, u8;
+
+    /// RSA as defined in [RFC 4255].
+    ///
+    /// [RFC 4255]: https://tools.ietf.org/html/rfc4255
+    (Rsa => 1, b"RSA")
+
+    /// DSA as defined in [RFC 4255].
+    ///
+    /// [RFC 4255]: https://tools.ietf.org/html/rfc4255
+    (Dsa => 2, b"DSA")
+
+    /// ECDSA as defined in [RFC 6594].
+    ///
+    /// [RFC 6594]: https://tools.ietf.org/html/rfc6594
+    (Ecdsa => 3, b"ECDSA")
+
+    /// Ed25519 as defined in [RFC 7479].
+    ///
+    /// [RFC 7479]: https://tools.ietf.org/html/rfc7479
+    (Ed25519 => 4, b"Ed25519")
+
+    /// Ed448 as defined in [RFC 8709].
+    ///
+    /// [RFC 8709]: https://tools.ietf.org/html/rfc8709
+    (Ed448 => 6, b"Ed448")
+}
+
+int_enum_str_decimal!(SshfpAlg, u8);
+
+//------------ SshfpType -----------------------------------------------------
+
+int_enum! {
+    /// SSHFP fingerprint types.
+    ///
+    /// The fingerprint type field of an SSHFP record specifies the message
+    /// digest algorithm used to calculate the fingerprint of the public key.
+    ///
+    /// For the currently registered values see the [IANA registration].
+    /// This type is complete as of the registry update of 2015-08-19.
+    ///
+    /// [IANA registration]: https://www.iana.org/assignments/dns-sshfp-rr-parameters/dns-sshfp-rr-parameters.xhtml#dns-sshfp-rr-parameters-2
+    =>
+    SshfpType, u8;
+
+    /// SHA-1 as defined in [RFC 4255].
+    ///
+    /// [RFC 4255]: https://tools.ietf.org/html/rfc4255
+    (Sha1 => 1, b"SHA-1")
+
+    /// SHA-256 as defined in [RFC 6594].
+    ///
+    /// [RFC 6594]: https://tools.ietf.org/html/rfc6594
+    (Sha256 => 2, b"SHA-256")
+}
+
+int_enum_str_decimal!(SshfpType, u8);
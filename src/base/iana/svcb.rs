@@ -1,43 +1,60 @@
 //! Service Binding (SVCB) Parameter Registry
 
-use core::fmt;
-
 //------------ Service Binding Parameters -----------
 
 int_enum! {
+    /// Service binding parameter keys.
+    ///
+    /// The keys identify the parameters of SVCB and HTTPS records. Each
+    /// key is represented by a 16 bit value. The enum wraps these values.
+    ///
+    /// The mnemonics are the key names used in the presentation format.
+    /// Keys without a name are written as `key` followed by the decimal
+    /// value, e.g., `key65333`.
+    ///
+    /// See [RFC 9460] and the [Service Parameter Keys IANA registry] for
+    /// the defined values.
+    ///
+    /// [RFC 9460]: https://tools.ietf.org/html/rfc9460
+    /// [Service Parameter Keys IANA registry]: https://www.iana.org/assignments/dns-svcb/dns-svcb.xhtml
     =>
     SvcbParamKey, u16;
 
-    (Mandatory => 0, b"Mandatory keys in this RR")
-    (Alpn => 1, b"Additional supported protocols")
-    (NoDefaultAlpn => 2, b"Additional supported protocols")
-    (Port => 3, b"Port for alternative endpoint")
-    (Ipv4Hint => 4, b"IPv4 address hints")
-    // https://datatracker.ietf.org/doc/draft-ietf-tls-esni/
-    (Ech => 5, b"Encrypted ClientHello info")
-    (Ipv6Hint => 6, b"IPv6 address hints")
-    // https://datatracker.ietf.org/doc/draft-ietf-add-svcb-dns/
-    (DohPath => 7, b"DNS over HTTPS path template")
+    /// Mandatory keys in this RR.
+    (Mandatory => 0, b"mandatory")
+
+    /// Additional supported protocols.
+    (Alpn => 1, b"alpn")
+
+    /// No support for default protocol.
+    (NoDefaultAlpn => 2, b"no-default-alpn")
+
+    /// Port for alternative endpoint.
+    (Port => 3, b"port")
+
+    /// IPv4 address hints.
+    (Ipv4Hint => 4, b"ipv4hint")
+
+    /// Encrypted ClientHello info.
+    (Ech => 5, b"ech")
+
+    /// IPv6 address hints.
+    (Ipv6Hint => 6, b"ipv6hint")
+
+    /// DNS over HTTPS path template.
+    ///
+    /// See [RFC 9461](https://tools.ietf.org/html/rfc9461).
+    (DohPath => 7, b"dohpath")
 }
 
+int_enum_str_with_prefix!(
+    SvcbParamKey,
+    "key",
+    b"key",
+    u16,
+    "unknown service parameter key"
+);
+
 pub const SVCB_PARAM_KEY_PRIVATE_RANGE_BEGIN: u16 = 65280;
 pub const SVCB_PARAM_KEY_PRIVATE_RANGE_END: u16 = 65534;
 pub const SVCB_PARAM_KEY_INVALID: u16 = 65535;
-
-impl fmt::Display for SvcbParamKey {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let s = match self {
-            Self::Mandatory => "mandatory",
-            Self::Alpn => "alpn",
-            Self::NoDefaultAlpn => "nodefaultalpn",
-            Self::Port => "port",
-            Self::Ipv4Hint => "ipv4hint",
-            Self::Ech => "ech",
-            Self::Ipv6Hint => "ipv6hint",
-            Self::DohPath => "dohpath",
-            Self::Int(n) => return write!(f, "key{}", n),
-        };
-
-        f.write_str(s)
-    }
-}
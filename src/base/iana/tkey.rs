@@ -0,0 +1,45 @@
+//! TKEY record parameters.
+
+//------------ TkeyMode ------------------------------------------------------
+
+int_enum! {
+    /// TKEY modes.
+    ///
+    /// The mode field of a TKEY record specifies the scheme used to
+    /// establish or delete a shared secret key.
+    ///
+    /// For the currently registered values see the [IANA registration].
+    /// This type contains all values defined by RFC 2930.
+    ///
+    /// [IANA registration]: https://www.iana.org/assignments/tkey-modes/tkey-modes.xhtml
+    =>
+    TkeyMode, u16;
+
+    /// The server assigns the key as defined in [RFC 2930].
+    ///
+    /// [RFC 2930]: https://tools.ietf.org/html/rfc2930
+    (ServerAssignment => 1, b"SERVER-ASSIGNMENT")
+
+    /// The key is agreed through a Diffie-Hellman exchange as defined in
+    /// [RFC 2930].
+    ///
+    /// [RFC 2930]: https://tools.ietf.org/html/rfc2930
+    (DiffieHellman => 2, b"DH")
+
+    /// The key is negotiated via GSS-API as defined in [RFC 2930].
+    ///
+    /// [RFC 2930]: https://tools.ietf.org/html/rfc2930
+    (GssApi => 3, b"GSS-API")
+
+    /// The resolver assigns the key as defined in [RFC 2930].
+    ///
+    /// [RFC 2930]: https://tools.ietf.org/html/rfc2930
+    (ResolverAssignment => 4, b"RESOLVER-ASSIGNMENT")
+
+    /// An existing key is deleted as defined in [RFC 2930].
+    ///
+    /// [RFC 2930]: https://tools.ietf.org/html/rfc2930
+    (KeyDeletion => 5, b"DELETE")
+}
+
+int_enum_str_decimal!(TkeyMode, u16);
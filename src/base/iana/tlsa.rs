@@ -0,0 +1,93 @@
+//! TLSA record parameters.
+
+//------------ TlsaCertUsage -------------------------------------------------
+
+int_enum! {
+    /// TLSA certificate usages.
+    ///
+    /// The certificate usage field of a TLSA record specifies how the
+    /// certificate association data is to be matched against the
+    /// certificate chain presented by a TLS server.
+    ///
+    /// For the currently registered values see the [IANA registration].
+    /// This type is complete as of the registry update of 2012-07-19.
+    ///
+    /// [IANA registration]: https://www.iana.org/assignments/dane-parameters/dane-parameters.xhtml#certificate-usages
+    =>
+    TlsaCertUsage, u8;
+
+    /// A trust anchor that also has to pass PKIX validation.
+    (PkixTa => 0, b"PKIX-TA")
+
+    /// An end entity certificate that also has to pass PKIX validation.
+    (PkixEe => 1, b"PKIX-EE")
+
+    /// A trust anchor asserted by the domain.
+    (DaneTa => 2, b"DANE-TA")
+
+    /// An end entity certificate asserted by the domain.
+    (DaneEe => 3, b"DANE-EE")
+
+    /// Reserved for private use.
+    (PrivCert => 255, b"PrivCert")
+}
+
+int_enum_str_decimal!(TlsaCertUsage, u8);
+
+//------------ TlsaSelector --------------------------------------------------
+
+int_enum! {
+    /// TLSA selectors.
+    ///
+    /// The selector field of a TLSA record specifies which part of a
+    /// certificate is matched against the certificate association data.
+    ///
+    /// For the currently registered values see the [IANA registration].
+    /// This type is complete as of the registry update of 2012-07-19.
+    ///
+    /// [IANA registration]: https://www.iana.org/assignments/dane-parameters/dane-parameters.xhtml#selectors
+    =>
+    TlsaSelector, u8;
+
+    /// The full certificate.
+    (Cert => 0, b"Cert")
+
+    /// The DER-encoded SubjectPublicKeyInfo of the certificate.
+    (Spki => 1, b"SPKI")
+
+    /// Reserved for private use.
+    (PrivSel => 255, b"PrivSel")
+}
+
+int_enum_str_decimal!(TlsaSelector, u8);
+
+//------------ TlsaMatchingType ----------------------------------------------
+
+int_enum! {
+    /// TLSA matching types.
+    ///
+    /// The matching type field of a TLSA record specifies how the selected
+    /// part of the certificate is compared to the certificate association
+    /// data.
+    ///
+    /// For the currently registered values see the [IANA registration].
+    /// This type is complete as of the registry update of 2012-07-19.
+    ///
+    /// [IANA registration]: https://www.iana.org/assignments/dane-parameters/dane-parameters.xhtml#matching-types
+    =>
+    TlsaMatchingType, u8;
+
+    /// The selected content is compared exactly.
+    (Full => 0, b"Full")
+
+    /// The SHA-256 hash of the selected content is compared.
+    (Sha256 => 1, b"SHA2-256")
+
+    /// The SHA-512 hash of the selected content is compared.
+    (Sha512 => 2, b"SHA2-512")
+
+    /// Reserved for private use.
+    (PrivMatch => 255, b"PrivMatch")
+}
+
+int_enum_str_decimal!(TlsaMatchingType, u8);
@@ -70,6 +70,10 @@ pub use self::parsed::{ParsedDname, ParsedDnameIter, ParsedSuffixIter};
 pub use self::relative::{
     DnameIter, RelativeDname, RelativeDnameError, StripSuffixError,
 };
+pub use self::reverse::{
+    addr_from_dname, classless_dname, classless_zone_name, dname_from_addr,
+    dname_from_prefix, prefix_from_dname, ReverseNameError,
+};
 pub use self::traits::{
     ToDname, ToEitherDname, ToLabelIter, ToRelativeDname,
 };
@@ -81,5 +85,6 @@ mod dname;
 mod label;
 mod parsed;
 mod relative;
+mod reverse;
 mod traits;
 mod uncertain;
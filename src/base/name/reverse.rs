@@ -0,0 +1,447 @@
+//! Domain names for reverse lookups.
+//!
+//! This is a private module for tidiness. Its functions and types are
+//! re-exported by the parent module.
+
+use super::super::net::IpAddr;
+use super::super::octets::{
+    EmptyBuilder, FromBuilder, OctetsBuilder, ShortBuf,
+};
+use super::builder::{DnameBuilder, PushError};
+use super::dname::Dname;
+use super::label::Label;
+use super::traits::ToDname;
+use core::fmt;
+
+//------------ Addresses -----------------------------------------------------
+
+/// Returns the reverse domain name for an IP address.
+///
+/// For an IPv4 address, this is the name below `in-addr.arpa` as defined
+/// in section 3.5 of [RFC 1035]. For instance, the name for `192.0.2.12`
+/// is `12.2.0.192.in-addr.arpa`. For an IPv6 address, this is the name
+/// below `ip6.arpa` with one label for each nibble as defined in section
+/// 2.5 of [RFC 3596]. Nibbles are given as lowercase hex digits.
+///
+/// The function only fails if the name doesn’t fit into the octets
+/// sequence.
+///
+/// [RFC 1035]: https://tools.ietf.org/html/rfc1035
+/// [RFC 3596]: https://tools.ietf.org/html/rfc3596
+pub fn dname_from_addr<Octets>(
+    addr: IpAddr,
+) -> Result<Dname<Octets>, PushError>
+where
+    Octets: FromBuilder,
+    <Octets as FromBuilder>::Builder:
+        OctetsBuilder + EmptyBuilder + AsMut<[u8]>,
+{
+    match addr {
+        IpAddr::V4(_) => reverse_dname(addr, 4),
+        IpAddr::V6(_) => reverse_dname(addr, 32),
+    }
+}
+
+/// Returns the IP address for a reverse domain name.
+///
+/// This is the reverse of [`dname_from_addr`]. The function accepts names
+/// with exactly four labels below `in-addr.arpa` or 32 labels below
+/// `ip6.arpa` and returns `None` for all other names. Labels are compared
+/// ignoring ASCII case.
+pub fn addr_from_dname<N: ToDname>(name: &N) -> Option<IpAddr> {
+    match prefix_from_dname(name)? {
+        (addr @ IpAddr::V4(_), 32) | (addr @ IpAddr::V6(_), 128) => {
+            Some(addr)
+        }
+        _ => None,
+    }
+}
+
+//------------ Prefixes ------------------------------------------------------
+
+/// Returns the reverse zone name for an address prefix.
+///
+/// The zone contains the reverse names of all addresses that start with
+/// the first `prefix_len` bits of `addr`. For instance, the zone for
+/// `192.0.2.0/24` is `2.0.192.in-addr.arpa` and the zone for
+/// `2001:db8::/32` is `8.b.d.0.1.0.0.2.ip6.arpa`.
+///
+/// Since reverse names have one label per octet for IPv4 and one label
+/// per nibble for IPv6, the prefix length must be a multiple of 8 for
+/// IPv4 and of 4 for IPv6. Smaller IPv4 networks can be delegated
+/// through [`classless_zone_name`].
+pub fn dname_from_prefix<Octets>(
+    addr: IpAddr,
+    prefix_len: u8,
+) -> Result<Dname<Octets>, ReverseNameError>
+where
+    Octets: FromBuilder,
+    <Octets as FromBuilder>::Builder:
+        OctetsBuilder + EmptyBuilder + AsMut<[u8]>,
+{
+    let labels = match addr {
+        IpAddr::V4(_) if prefix_len <= 32 && prefix_len % 8 == 0 => {
+            prefix_len / 8
+        }
+        IpAddr::V6(_) if prefix_len <= 128 && prefix_len % 4 == 0 => {
+            prefix_len / 4
+        }
+        _ => return Err(ReverseNameError::PrefixLen),
+    };
+    reverse_dname(addr, usize::from(labels)).map_err(Into::into)
+}
+
+/// Returns the address prefix for a reverse zone name.
+///
+/// This is the reverse of [`dname_from_prefix`]. It returns the address
+/// with all bits beyond the prefix set to zero and the prefix length. The
+/// function returns `None` if the name isn’t below `in-addr.arpa` or
+/// `ip6.arpa` or if any of its labels doesn’t describe an octet or
+/// nibble, respectively.
+pub fn prefix_from_dname<N: ToDname>(name: &N) -> Option<(IpAddr, u8)> {
+    let mut labels = name.iter_labels().rev();
+    if !labels.next()?.is_root() || !label_eq(labels.next()?, b"arpa") {
+        return None;
+    }
+    let family = labels.next()?;
+    if label_eq(family, b"in-addr") {
+        let mut octets = [0u8; 4];
+        let mut len = 0;
+        for label in labels {
+            *octets.get_mut(len)? = parse_octet(label)?;
+            len += 1;
+        }
+        Some((octets.into(), (len * 8) as u8))
+    } else if label_eq(family, b"ip6") {
+        let mut octets = [0u8; 16];
+        let mut len = 0;
+        for label in labels {
+            let nibble = parse_nibble(label)?;
+            let octet = octets.get_mut(len / 2)?;
+            *octet |= if len % 2 == 0 { nibble << 4 } else { nibble };
+            len += 1;
+        }
+        Some((octets.into(), (len * 4) as u8))
+    } else {
+        None
+    }
+}
+
+//------------ Classless Delegation ------------------------------------------
+
+/// Returns the zone name for classless delegation of an IPv4 network.
+///
+/// [RFC 2317] describes how to delegate the reverse names of IPv4 networks
+/// smaller than a /24. The parent zone contains a CNAME record for each
+/// address that points to a name in a zone for the network, which can then
+/// be delegated. This function returns the name of that zone using the
+/// form given in the RFC: the first address of the network and the prefix
+/// length separated by a slash as the left-most label followed by the
+/// reverse zone of the /24 network. For instance, the zone for
+/// `192.0.2.64/26` is `64/26.2.0.192.in-addr.arpa`.
+///
+/// The prefix length must be between 25 and 31 and `addr` must be an IPv4
+/// address.
+///
+/// [RFC 2317]: https://tools.ietf.org/html/rfc2317
+pub fn classless_zone_name<Octets>(
+    addr: IpAddr,
+    prefix_len: u8,
+) -> Result<Dname<Octets>, ReverseNameError>
+where
+    Octets: FromBuilder,
+    <Octets as FromBuilder>::Builder:
+        OctetsBuilder + EmptyBuilder + AsMut<[u8]>,
+{
+    classless_dname_impl(addr, prefix_len, false)
+}
+
+/// Returns the name of an IPv4 address within its classless zone.
+///
+/// This is the target of the CNAME record for `addr` in the parent zone,
+/// i.e., the last octet of the address prepended to the name returned by
+/// [`classless_zone_name`]. For instance, the name for `192.0.2.65` in the
+/// network `192.0.2.64/26` is `65.64/26.2.0.192.in-addr.arpa`.
+pub fn classless_dname<Octets>(
+    addr: IpAddr,
+    prefix_len: u8,
+) -> Result<Dname<Octets>, ReverseNameError>
+where
+    Octets: FromBuilder,
+    <Octets as FromBuilder>::Builder:
+        OctetsBuilder + EmptyBuilder + AsMut<[u8]>,
+{
+    classless_dname_impl(addr, prefix_len, true)
+}
+
+fn classless_dname_impl<Octets>(
+    addr: IpAddr,
+    prefix_len: u8,
+    host: bool,
+) -> Result<Dname<Octets>, ReverseNameError>
+where
+    Octets: FromBuilder,
+    <Octets as FromBuilder>::Builder:
+        OctetsBuilder + EmptyBuilder + AsMut<[u8]>,
+{
+    let octets = match addr {
+        IpAddr::V4(addr) if (25..=31).contains(&prefix_len) => addr.octets(),
+        _ => return Err(ReverseNameError::PrefixLen),
+    };
+    let mut res = DnameBuilder::<Octets::Builder>::with_capacity(40);
+    if host {
+        push_decimal(&mut res, octets[3])?;
+        res.end_label();
+    }
+    push_decimal(&mut res, octets[3] & (0xFF << (32 - prefix_len)))?;
+    res.push(b'/')?;
+    push_decimal(&mut res, prefix_len)?;
+    res.end_label();
+    for &octet in octets[..3].iter().rev() {
+        push_decimal(&mut res, octet)?;
+        res.end_label();
+    }
+    res.append_label(b"in-addr")?;
+    res.append_label(b"arpa")?;
+    res.into_dname().map_err(Into::into)
+}
+
+//------------ Helpers -------------------------------------------------------
+
+/// Creates the reverse name for the first `labels` octets or nibbles.
+fn reverse_dname<Octets>(
+    addr: IpAddr,
+    labels: usize,
+) -> Result<Dname<Octets>, PushError>
+where
+    Octets: FromBuilder,
+    <Octets as FromBuilder>::Builder:
+        OctetsBuilder + EmptyBuilder + AsMut<[u8]>,
+{
+    let mut res = DnameBuilder::<Octets::Builder>::with_capacity(74);
+    match addr {
+        IpAddr::V4(addr) => {
+            for &octet in addr.octets()[..labels].iter().rev() {
+                push_decimal(&mut res, octet)?;
+                res.end_label();
+            }
+            res.append_label(b"in-addr")?;
+        }
+        IpAddr::V6(addr) => {
+            let octets = addr.octets();
+            for idx in (0..labels).rev() {
+                let nibble = if idx % 2 == 0 {
+                    octets[idx / 2] >> 4
+                } else {
+                    octets[idx / 2] & 0x0F
+                };
+                res.append_label(&[hexdigit(nibble)])?;
+            }
+            res.append_label(b"ip6")?;
+        }
+    }
+    res.append_label(b"arpa")?;
+    res.into_dname()
+}
+
+/// Pushes the decimal representation of `value` to the current label.
+fn push_decimal<Builder: OctetsBuilder + AsMut<[u8]>>(
+    builder: &mut DnameBuilder<Builder>,
+    value: u8,
+) -> Result<(), PushError> {
+    if value >= 100 {
+        builder.push(b'0' + value / 100)?;
+    }
+    if value >= 10 {
+        builder.push(b'0' + (value / 10) % 10)?;
+    }
+    builder.push(b'0' + value % 10)
+}
+
+fn hexdigit(nibble: u8) -> u8 {
+    b"0123456789abcdef"[usize::from(nibble & 0x0F)]
+}
+
+/// Compares a label to an ASCII string ignoring case.
+fn label_eq(label: &Label, s: &[u8]) -> bool {
+    label.as_slice().eq_ignore_ascii_case(s)
+}
+
+/// Parses a label containing the decimal representation of an octet.
+///
+/// Leading zeros are not allowed.
+fn parse_octet(label: &Label) -> Option<u8> {
+    let slice = label.as_slice();
+    if slice.is_empty()
+        || slice.len() > 3
+        || (slice.len() > 1 && slice[0] == b'0')
+    {
+        return None;
+    }
+    let mut res = 0u16;
+    for &ch in slice {
+        if !ch.is_ascii_digit() {
+            return None;
+        }
+        res = res * 10 + u16::from(ch - b'0');
+    }
+    if res > 255 {
+        None
+    } else {
+        Some(res as u8)
+    }
+}
+
+/// Parses a label containing a single hex digit.
+fn parse_nibble(label: &Label) -> Option<u8> {
+    match *label.as_slice() {
+        [ch @ b'0'..=b'9'] => Some(ch - b'0'),
+        [ch @ b'a'..=b'f'] => Some(ch - b'a' + 10),
+        [ch @ b'A'..=b'F'] => Some(ch - b'A' + 10),
+        _ => None,
+    }
+}
+
+//------------ ReverseNameError ----------------------------------------------
+
+/// A reverse domain name could not be created.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReverseNameError {
+    /// The prefix length cannot be expressed in a reverse name.
+    PrefixLen,
+
+    /// The name didn’t fit into the octets sequence.
+    ShortBuf,
+}
+
+//--- From
+
+impl From<PushError> for ReverseNameError {
+    fn from(_: PushError) -> Self {
+        ReverseNameError::ShortBuf
+    }
+}
+
+//--- Display and Error
+
+impl fmt::Display for ReverseNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReverseNameError::PrefixLen => {
+                f.write_str("invalid prefix length for reverse name")
+            }
+            ReverseNameError::ShortBuf => ShortBuf.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReverseNameError {}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+    use std::vec::Vec;
+
+    fn name(s: &str) -> Dname<Vec<u8>> {
+        Dname::from_str(s).unwrap()
+    }
+
+    fn addr(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn addrs() {
+        let v6 = name(
+            "f.e.d.0.c.b.a.9.1.0.0.0.8.7.6.5.\
+             0.0.0.0.4.3.2.1.8.b.d.0.1.0.0.2.ip6.arpa",
+        );
+        assert_eq!(
+            dname_from_addr::<Vec<u8>>(addr("192.0.2.12")).unwrap(),
+            name("12.2.0.192.in-addr.arpa")
+        );
+        assert_eq!(
+            dname_from_addr::<Vec<u8>>(addr(
+                "2001:db8:1234::5678:1:9abc:def"
+            ))
+            .unwrap()
+            .as_slice(),
+            v6.as_slice()
+        );
+        assert_eq!(
+            addr_from_dname(&name("12.2.0.192.IN-ADDR.ARPA")),
+            Some(addr("192.0.2.12"))
+        );
+        assert_eq!(
+            addr_from_dname(&v6),
+            Some(addr("2001:db8:1234::5678:1:9abc:def"))
+        );
+        assert_eq!(addr_from_dname(&name("2.0.192.in-addr.arpa")), None);
+        assert_eq!(addr_from_dname(&name("012.2.0.192.in-addr.arpa")), None);
+        assert_eq!(addr_from_dname(&name("256.2.0.192.in-addr.arpa")), None);
+        assert_eq!(addr_from_dname(&name("1.12.2.0.192.in-addr.arpa")), None);
+        assert_eq!(addr_from_dname(&name("12.2.0.192.example.com")), None);
+    }
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(
+            dname_from_prefix::<Vec<u8>>(addr("192.0.2.12"), 24).unwrap(),
+            name("2.0.192.in-addr.arpa")
+        );
+        assert_eq!(
+            dname_from_prefix::<Vec<u8>>(addr("192.0.2.12"), 0).unwrap(),
+            name("in-addr.arpa")
+        );
+        assert_eq!(
+            dname_from_prefix::<Vec<u8>>(addr("2001:db8::"), 36).unwrap(),
+            name("0.8.b.d.0.1.0.0.2.ip6.arpa")
+        );
+        assert_eq!(
+            dname_from_prefix::<Vec<u8>>(addr("192.0.2.12"), 25),
+            Err(ReverseNameError::PrefixLen)
+        );
+        assert_eq!(
+            dname_from_prefix::<Vec<u8>>(addr("2001:db8::"), 130),
+            Err(ReverseNameError::PrefixLen)
+        );
+        assert_eq!(
+            prefix_from_dname(&name("2.0.192.in-addr.arpa")),
+            Some((addr("192.0.2.0"), 24))
+        );
+        assert_eq!(
+            prefix_from_dname(&name("0.8.b.d.0.1.0.0.2.ip6.arpa")),
+            Some((addr("2001:db8::"), 36))
+        );
+        assert_eq!(
+            prefix_from_dname(&name("ip6.arpa")),
+            Some((addr("::"), 0))
+        );
+        assert_eq!(prefix_from_dname(&name("arpa")), None);
+    }
+
+    #[test]
+    fn classless() {
+        assert_eq!(
+            classless_zone_name::<Vec<u8>>(addr("192.0.2.65"), 26).unwrap(),
+            name("64/26.2.0.192.in-addr.arpa")
+        );
+        assert_eq!(
+            classless_dname::<Vec<u8>>(addr("192.0.2.65"), 26).unwrap(),
+            name("65.64/26.2.0.192.in-addr.arpa")
+        );
+        assert_eq!(
+            classless_zone_name::<Vec<u8>>(addr("192.0.2.65"), 24),
+            Err(ReverseNameError::PrefixLen)
+        );
+        assert_eq!(
+            classless_zone_name::<Vec<u8>>(addr("2001:db8::"), 28),
+            Err(ReverseNameError::PrefixLen)
+        );
+    }
+}
@@ -45,6 +45,21 @@ impl ClientSubnet {
         builder.push(&Self::new(source_prefix_len, scope_prefix_len, addr))
     }
 
+    /// Creates an option for the network of `addr`.
+    ///
+    /// The address is truncated to `source_prefix_len` bits.
+    pub fn from_addr(addr: IpAddr, source_prefix_len: u8) -> ClientSubnet {
+        Self::new(source_prefix_len, 0, addr)
+    }
+
+    /// Creates an option asking not to use the client’s address at all.
+    ///
+    /// This is an option with a source prefix length of zero as described
+    /// in section 7.1.2 of RFC 7871.
+    pub fn opt_out() -> ClientSubnet {
+        Self::new(0, 0, IpAddr::from([0u8; 4]))
+    }
+
     pub fn source_prefix_len(&self) -> u8 {
         self.source_prefix_len
     }
@@ -54,6 +69,31 @@ impl ClientSubnet {
     pub fn addr(&self) -> IpAddr {
         self.addr
     }
+
+    /// Returns whether the option asks not to use the client’s address.
+    pub fn is_opt_out(&self) -> bool {
+        self.source_prefix_len == 0
+    }
+
+    /// Returns the prefix length an answer with this option applies to.
+    ///
+    /// This is the scope prefix length unless it is longer than the source
+    /// prefix length in which case section 7.3.1 of RFC 7871 requires the
+    /// source prefix length to be used.
+    pub fn cache_prefix_len(&self) -> u8 {
+        core::cmp::min(self.scope_prefix_len, self.source_prefix_len)
+    }
+
+    /// Returns whether `addr` is within the scope of an answer.
+    ///
+    /// This is the case if `addr` is of the same family as the option’s
+    /// address and both are equal in the first
+    /// [`cache_prefix_len`][Self::cache_prefix_len] bits.
+    pub fn scope_contains(&self, addr: IpAddr) -> bool {
+        same_family(self.addr, addr)
+            && truncate_addr(self.addr, self.cache_prefix_len())
+                == truncate_addr(addr, self.cache_prefix_len())
+    }
 }
 
 //--- Parse and Compose
@@ -164,6 +204,21 @@ impl Compose for ClientSubnet {
     }
 }
 
+/// Truncates an address to the given prefix length.
+///
+/// All bits of the address beyond the first `prefix_len` bits are set to
+/// zero. Prefix lengths longer than the address leave it unchanged.
+pub fn truncate_addr(addr: IpAddr, prefix_len: u8) -> IpAddr {
+    addr_apply_mask(addr, normalize_prefix_len(addr, prefix_len)).0
+}
+
+fn same_family(left: IpAddr, right: IpAddr) -> bool {
+    matches!(
+        (left, right),
+        (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_))
+    )
+}
+
 fn prefix_bytes(bits: usize) -> usize {
     (bits + 7) / 8
 }
@@ -267,4 +322,33 @@ mod tests {
     check!(prefix_min, "192.0.2.0", 0, "0.0.0.0", true);
     check!(prefix_max, "192.0.2.0", 32, "192.0.2.0", true);
     check!(prefix_too_long, "192.0.2.0", 100, "192.0.2.0", false);
+
+    #[test]
+    fn truncate() {
+        let addr = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert_eq!(
+            truncate_addr(addr("192.0.2.255"), 25),
+            addr("192.0.2.128")
+        );
+        assert_eq!(truncate_addr(addr("192.0.2.255"), 0), addr("0.0.0.0"));
+        assert_eq!(truncate_addr(addr("192.0.2.1"), 64), addr("192.0.2.1"));
+        assert_eq!(
+            truncate_addr(addr("2001:db8:ffff::1"), 36),
+            addr("2001:db8:f000::")
+        );
+    }
+
+    #[test]
+    fn scope() {
+        let addr = |s: &str| s.parse::<IpAddr>().unwrap();
+        let opt = ClientSubnet::new(24, 16, addr("192.0.2.0"));
+        assert_eq!(opt.cache_prefix_len(), 16);
+        assert!(opt.scope_contains(addr("192.0.99.1")));
+        assert!(!opt.scope_contains(addr("192.1.2.1")));
+        assert!(!opt.scope_contains(addr("2001:db8::1")));
+
+        let opt = ClientSubnet::new(16, 24, addr("192.0.0.0"));
+        assert_eq!(opt.cache_prefix_len(), 16);
+        assert!(ClientSubnet::opt_out().is_opt_out());
+    }
 }
@@ -1,21 +1,55 @@
 //! EDNS Options form RFC 7873
+//!
+//! This module also contains the construction and verification of
+//! interoperable server cookies as defined in [RFC 9018].
+//!
+//! [RFC 9018]: https://tools.ietf.org/html/rfc9018
 
 use super::super::iana::OptionCode;
 use super::super::message_builder::OptBuilder;
+use super::super::net::IpAddr;
 use super::super::octets::{
     Compose, OctetsBuilder, Parse, ParseError, Parser, ShortBuf
 };
+use super::super::serial::Serial;
 use super::CodeOptData;
+use core::convert::TryInto;
+use core::fmt;
 
 
 //------------ Cookie --------------------------------------------------------
 
+/// Option data for a DNS cookie.
+///
+/// A cookie consists of an eight octet client cookie chosen by the client
+/// and, optionally, a server cookie of between 8 and 32 octets chosen by
+/// the server. A client that doesn’t know the server cookie yet sends the
+/// client cookie only. The server answers with the client cookie and its
+/// server cookie which the client then includes in subsequent requests.
+///
+/// Servers can create interoperable server cookies as described in
+/// [RFC 9018] via [`create_response`][Self::create_response] and verify
+/// them via [`check_server_hash`][Self::check_server_hash].
+///
+/// [RFC 9018]: https://tools.ietf.org/html/rfc9018
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub struct Cookie([u8; 8]);
+pub struct Cookie {
+    /// The client cookie.
+    client: [u8; 8],
+
+    /// The server cookie if present.
+    server: Option<ServerCookie>,
+}
 
 impl Cookie {
-    pub fn new(cookie: [u8; 8]) -> Self {
-        Cookie(cookie)
+    /// Creates a new cookie with only a client cookie.
+    pub fn new(client: [u8; 8]) -> Self {
+        Cookie { client, server: None }
+    }
+
+    /// Creates a new cookie with both a client and a server cookie.
+    pub fn with_server(client: [u8; 8], server: ServerCookie) -> Self {
+        Cookie { client, server: Some(server) }
     }
 
     pub fn push<Target: OctetsBuilder + AsRef<[u8]> + AsMut<[u8]>>(
@@ -25,8 +59,95 @@ impl Cookie {
         builder.push(&Self::new(cookie))
     }
 
+    /// Returns the client cookie.
+    pub fn client(self) -> [u8; 8] {
+        self.client
+    }
+
+    /// Returns the client cookie.
+    ///
+    /// This is the same as [`client`][Self::client].
     pub fn cookie(self) -> [u8; 8] {
-        self.0
+        self.client
+    }
+
+    /// Returns the server cookie if there is one.
+    pub fn server(&self) -> Option<&ServerCookie> {
+        self.server.as_ref()
+    }
+
+    /// Creates the cookie for a response as described in RFC 9018.
+    ///
+    /// The returned cookie contains the client cookie of `self` and a
+    /// newly created version 1 server cookie for the given `timestamp`,
+    /// the address of the client, and the server’s secret.
+    pub fn create_response(
+        &self,
+        timestamp: Serial,
+        client_ip: IpAddr,
+        secret: &[u8; 16],
+    ) -> Self {
+        let mut server = [0u8; 16];
+        server[0] = 1;
+        server[4..8].copy_from_slice(&timestamp.into_int().to_be_bytes());
+        let hash = self.server_hash(&server[..8], client_ip, secret);
+        server[8..].copy_from_slice(&hash);
+        Self::with_server(
+            self.client,
+            ServerCookie::from_slice(&server).expect("valid length")
+        )
+    }
+
+    /// Checks that the server cookie was created by us as per RFC 9018.
+    ///
+    /// Returns `true` if the cookie contains a version 1 server cookie
+    /// created with `secret` for `client_ip` whose timestamp is no more
+    /// than one hour before and five minutes after `now`.
+    pub fn check_server_hash(
+        &self,
+        client_ip: IpAddr,
+        secret: &[u8; 16],
+        now: Serial,
+    ) -> bool {
+        let server = match self.server {
+            Some(ref server) => server.as_slice(),
+            None => return false,
+        };
+        if server.len() != 16 || server[0] != 1 {
+            return false
+        }
+        let timestamp = Serial(u32::from_be_bytes(
+            server[4..8].try_into().expect("four octets")
+        ));
+        if timestamp > now.add(300) || timestamp.add(3600) < now {
+            return false
+        }
+        self.server_hash(&server[..8], client_ip, secret) == server[8..]
+    }
+
+    /// Calculates the hash part of an RFC 9018 server cookie.
+    ///
+    /// The `prefix` contains the version, reserved, and timestamp fields.
+    fn server_hash(
+        &self,
+        prefix: &[u8],
+        client_ip: IpAddr,
+        secret: &[u8; 16],
+    ) -> [u8; 8] {
+        let mut input = [0u8; 32];
+        input[..8].copy_from_slice(&self.client);
+        input[8..16].copy_from_slice(prefix);
+        let len = match client_ip {
+            IpAddr::V4(addr) => {
+                input[16..20].copy_from_slice(&addr.octets());
+                20
+            }
+            IpAddr::V6(addr) => {
+                input[16..32].copy_from_slice(&addr.octets());
+                32
+            }
+        };
+        siphash24(secret, &input[..len])
     }
 }
 
@@ -35,13 +156,26 @@ impl Cookie {
 
 impl<Ref: AsRef<[u8]>> Parse<Ref> for Cookie {
     fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
-        let mut res = [0u8; 8];
-        parser.parse_buf(&mut res[..])?;
-        Ok(Self::new(res))
+        let mut client = [0u8; 8];
+        parser.parse_buf(&mut client[..])?;
+        let len = parser.remaining();
+        if len == 0 {
+            return Ok(Self::new(client))
+        }
+        if !(ServerCookie::MIN_LEN..=ServerCookie::MAX_LEN).contains(&len) {
+            return Err(ParseError::form_error("invalid server cookie length"))
+        }
+        let mut server = ServerCookie {
+            len: len as u8,
+            octets: [0; ServerCookie::MAX_LEN],
+        };
+        parser.parse_buf(&mut server.octets[..len])?;
+        Ok(Self::with_server(client, server))
     }
 
     fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
-        parser.advance(8)
+        parser.advance_to_end();
+        Ok(())
     }
 }
 
@@ -51,7 +185,11 @@ impl Compose for Cookie {
         &self,
         target: &mut T
     ) -> Result<(), ShortBuf> {
-        target.append_slice(&self.0[..])
+        target.append_slice(&self.client[..])?;
+        if let Some(ref server) = self.server {
+            target.append_slice(server.as_slice())?;
+        }
+        Ok(())
     }
 }
 
@@ -62,3 +200,216 @@ impl CodeOptData for Cookie {
     const CODE: OptionCode = OptionCode::Cookie;
 }
 
+
+//------------ ServerCookie --------------------------------------------------
+
+/// A server cookie.
+///
+/// Server cookies are opaque octet sequences of between 8 and 32 octets.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ServerCookie {
+    /// The length of the cookie.
+    len: u8,
+
+    /// The octets of the cookie. Octets beyond `len` are always zero.
+    octets: [u8; ServerCookie::MAX_LEN],
+}
+
+impl ServerCookie {
+    /// The minimum length of a server cookie.
+    pub const MIN_LEN: usize = 8;
+
+    /// The maximum length of a server cookie.
+    pub const MAX_LEN: usize = 32;
+
+    /// Creates a server cookie from a slice.
+    ///
+    /// Returns an error if the slice is shorter than 8 or longer than 32
+    /// octets.
+    pub fn from_slice(slice: &[u8]) -> Result<Self, ServerCookieError> {
+        if !(Self::MIN_LEN..=Self::MAX_LEN).contains(&slice.len()) {
+            return Err(ServerCookieError)
+        }
+        let mut res = ServerCookie {
+            len: slice.len() as u8,
+            octets: [0; Self::MAX_LEN],
+        };
+        res.octets[..slice.len()].copy_from_slice(slice);
+        Ok(res)
+    }
+
+    /// Returns a slice of the cookie’s octets.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.octets[..usize::from(self.len)]
+    }
+}
+
+impl AsRef<[u8]> for ServerCookie {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+
+//------------ ServerCookieError ---------------------------------------------
+
+/// A server cookie had an invalid length.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ServerCookieError;
+
+impl fmt::Display for ServerCookieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid server cookie length")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ServerCookieError {}
+
+
+//------------ siphash24 -----------------------------------------------------
+
+/// Calculates the SipHash-2-4 of `data` using `key`.
+fn siphash24(key: &[u8; 16], data: &[u8]) -> [u8; 8] {
+    fn round(v: &mut [u64; 4]) {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    }
+
+    fn compress(v: &mut [u64; 4], m: u64) {
+        v[3] ^= m;
+        round(v);
+        round(v);
+        v[0] ^= m;
+    }
+
+    let k0 = u64::from_le_bytes(key[..8].try_into().expect("eight octets"));
+    let k1 = u64::from_le_bytes(key[8..].try_into().expect("eight octets"));
+    let mut v = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        compress(
+            &mut v,
+            u64::from_le_bytes(chunk.try_into().expect("eight octets"))
+        );
+    }
+    let mut last = (data.len() as u64) << 56;
+    for (i, octet) in chunks.remainder().iter().enumerate() {
+        last |= u64::from(*octet) << (8 * i);
+    }
+    compress(&mut v, last);
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        round(&mut v);
+    }
+    (v[0] ^ v[1] ^ v[2] ^ v[3]).to_le_bytes()
+}
+
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::octets::Octets512;
+
+    fn hex(s: &str) -> std::vec::Vec<u8> {
+        crate::utils::base16::decode(s).unwrap()
+    }
+
+    #[test]
+    fn siphash_reference() {
+        // Test vector from the SipHash paper.
+        let key: [u8; 16] = hex("000102030405060708090a0b0c0d0e0f")
+            .try_into().unwrap();
+        let data = hex("000102030405060708090a0b0c0d0e");
+        assert_eq!(
+            u64::from_le_bytes(siphash24(&key, &data)),
+            0xa129_ca61_49be_45e5
+        );
+    }
+
+    #[test]
+    fn compose_parse() {
+        let cookie = Cookie::with_server(
+            [1, 2, 3, 4, 5, 6, 7, 8],
+            ServerCookie::from_slice(&[9; 12]).unwrap()
+        );
+        let mut buf = Octets512::new();
+        cookie.compose(&mut buf).unwrap();
+        assert_eq!(buf.as_ref().len(), 20);
+        let parsed = Cookie::parse(
+            &mut Parser::from_ref(buf.as_ref())
+        ).unwrap();
+        assert_eq!(parsed, cookie);
+        assert_eq!(parsed.server().unwrap().as_slice(), &[9; 12]);
+
+        let parsed = Cookie::parse(
+            &mut Parser::from_ref(b"12345678".as_ref())
+        ).unwrap();
+        assert!(parsed.server().is_none());
+
+        assert!(Cookie::parse(
+            &mut Parser::from_ref(b"123456789abc".as_ref())
+        ).is_err());
+        assert!(ServerCookie::from_slice(&[0; 33]).is_err());
+    }
+
+    #[test]
+    fn rfc9018_vectors() {
+        // Appendix A.1: Learning a new server cookie.
+        let cookie = Cookie::new(
+            hex("2464c4abcf10c957").try_into().unwrap()
+        );
+        let secret: [u8; 16] = hex("e5e973e5a6b2a43f48e7dc849e37bfcf")
+            .try_into().unwrap();
+        let client_ip = "198.51.100.100".parse().unwrap();
+        let response = cookie.create_response(
+            Serial(1559731985), client_ip, &secret
+        );
+        assert_eq!(
+            response.server().unwrap().as_slice(),
+            hex("010000005cf79f111f8130c3eee29480").as_slice()
+        );
+        assert!(response.check_server_hash(
+            client_ip, &secret, Serial(1559731985)
+        ));
+        assert!(!response.check_server_hash(
+            "198.51.100.101".parse().unwrap(), &secret, Serial(1559731985)
+        ));
+        assert!(!response.check_server_hash(
+            client_ip, &secret, Serial(1559731985 + 3601)
+        ));
+
+        // Appendix A.3: Another client.
+        let cookie = Cookie::new(
+            hex("22681ab97d52c298").try_into().unwrap()
+        );
+        let secret: [u8; 16] = hex("dd3bdf9344b678b185a6f5cb60fca715")
+            .try_into().unwrap();
+        let client_ip =
+            "2001:db8:220:1:59de:d0f4:8769:82b8".parse().unwrap();
+        let response = cookie.create_response(
+            Serial(1559741817), client_ip, &secret
+        );
+        assert_eq!(
+            response.server().unwrap().as_slice(),
+            hex("010000005cf7c57926556bd0934c72f8").as_slice()
+        );
+    }
+}
@@ -33,10 +33,11 @@
 //!   as zone files –, i.e., the textual representation of DNS data. This
 //!   module will be re-implemented in the near future and will be renamed to
 //!   _zonefiles._
-#![cfg_attr(feature = "resolv", doc = "* [resolv]:")]
-#![cfg_attr(not(feature = "resolv"), doc = "* resolv:")]
-//!   An asynchronous DNS resolver based on the
-//!   [Tokio](https://tokio.rs/) async runtime.
+#![cfg_attr(feature = "resolv-core", doc = "* [resolv]:")]
+#![cfg_attr(not(feature = "resolv-core"), doc = "* resolv:")]
+//!   An asynchronous DNS resolver running on the
+//!   [Tokio](https://tokio.rs/), [async-std](https://async.rs/), or
+//!   [smol](https://github.com/smol-rs/smol) async runtimes.
 #![cfg_attr(feature = "sign", doc = "* [sign]:")]
 #![cfg_attr(not(feature = "sign"), doc = "* sign:")]
 //!   Experimental support for DNSSEC signing.
@@ -72,10 +73,20 @@
 //! * `random`: Enables a number of methods that rely on a random number
 //!   generator being available in the system.
 //! * `resolv`: Enables the asynchronous stub resolver via the
-#![cfg_attr(feature = "resolv", doc = "  [resolv]")]
-#![cfg_attr(not(feature = "resolv"), doc = "  resolv")]
-//!   module.
+#![cfg_attr(feature = "resolv-core", doc = "  [resolv]")]
+#![cfg_attr(not(feature = "resolv-core"), doc = "  resolv")]
+//!   module using Tokio as its runtime. This enables the `resolv-core` and
+//!   `resolv-tokio` features.
+//! * `resolv-async-std`: Enables support for the async-std runtime in the
+//!   stub resolver.
+//! * `resolv-core`: Enables the stub resolver without picking a runtime.
+//!   This includes the blocking `SyncStubResolver` which needs no runtime
+//!   at all.
+//! * `resolv-smol`: Enables support for the smol runtime in the stub
+//!   resolver.
 //! * `resolv-sync`: Enables the synchronous version of the stub resolver.
+//! * `resolv-tokio`: Enables support for the Tokio runtime in the stub
+//!   resolver.
 //! * `ring`: Enables crypto functionality via the
 //!   [ring](https://github.com/briansmith/ring) crate.
 //! * `serde`: Enables serde serialization for a number of basic types.
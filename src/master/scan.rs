@@ -123,6 +123,15 @@ impl<C: CharSource> Scanner<C> {
         !matches!(self.peek(), Ok(Some(_)))
     }
 
+    /// Returns whether the current entry has no more tokens.
+    ///
+    /// This is the case if the next token is a newline or the end of data
+    /// has been reached. It can be used when scanning record data that ends
+    /// in optional fields.
+    pub fn entry_ended(&mut self) -> Result<bool, ScanError> {
+        Ok(!matches!(self.peek()?, Some(Token::Symbol(_))))
+    }
+
     /// Returns the current position of the scanner.
     pub fn pos(&self) -> Pos {
         self.cur_pos
@@ -343,6 +352,80 @@ impl<C: CharSource> Scanner<C> {
         )
     }
 
+    /// Scans a key with an optional value.
+    ///
+    /// The method scans a word that consists of a key optionally followed
+    /// by an equals sign and a value, such as the service parameters of
+    /// SVCB records. The value can be given in quotes directly after the
+    /// equals sign, e.g., `alpn="h2,h3"`.
+    ///
+    /// Once the word ends, the closure `finalop` is given the key as a
+    /// `String` and the value as `Bytes` if there was an equals sign. The
+    /// closure can fail, resulting in an error and back-tracking to the
+    /// beginning of the word.
+    pub fn scan_key_value<U, G>(&mut self, finalop: G) -> Result<U, ScanError>
+    where
+        G: FnOnce(String, Option<Bytes>) -> Result<U, SyntaxError>,
+    {
+        match self.peek()? {
+            Some(Token::Symbol(ch)) => {
+                if !ch.is_word_char() {
+                    return self.err(SyntaxError::Unexpected(ch));
+                }
+            }
+            Some(Token::Newline) => {
+                return self.err(SyntaxError::UnexpectedNewline)
+            }
+            None => return self.err(SyntaxError::UnexpectedEof),
+        };
+        let mut key = String::new();
+        let mut value: Option<BytesMut> = None;
+        while let Some(ch) = self.cond_read_symbol(Symbol::is_word_char)? {
+            match (ch, value.as_mut()) {
+                (Symbol::Char('='), None) => value = Some(BytesMut::new()),
+                (ch, None) => match ch.into_char() {
+                    Ok(ch) => key.push(ch),
+                    Err(err) => return self.err_cur(err.into()),
+                },
+                (ch, Some(value)) => match ch.into_octet() {
+                    Ok(ch) => value.put_u8(ch),
+                    Err(err) => return self.err_cur(err.into()),
+                },
+            }
+        }
+        if let Some(value) = value.as_mut() {
+            if value.is_empty() {
+                if let Some(Token::Symbol(Symbol::Char('"'))) = self.peek()? {
+                    self.read()?;
+                    loop {
+                        match self.read()? {
+                            Some(Token::Symbol(Symbol::Char('"'))) => break,
+                            Some(Token::Symbol(ch)) => {
+                                match ch.into_octet() {
+                                    Ok(ch) => value.put_u8(ch),
+                                    Err(err) => return self.err(err.into()),
+                                }
+                            }
+                            Some(Token::Newline) => {
+                                return self
+                                    .err(SyntaxError::UnexpectedNewline)
+                            }
+                            None => {
+                                return self.err(SyntaxError::UnexpectedEof)
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let res = match finalop(key, value.map(BytesMut::freeze)) {
+            Ok(res) => res,
+            Err(err) => return self.err(err),
+        };
+        self.skip_delimiter()?;
+        Ok(res)
+    }
+
     /// Scans over a mandatory newline.
     ///
     /// A newline is either an optional comment followed by a newline sequence
@@ -1092,3 +1092,289 @@ macro_rules! dname_type {
         }
     }
 }
+
+//------------ tlsa_type! ---------------------------------------------------
+
+/// A macro for implementing a record data type in the format of TLSA.
+///
+/// This format is shared by the TLSA and SMIMEA record types. Implements
+/// some basic methods plus the comparison, parsing, composing, scanning,
+/// and formatting traits.
+///
+/// In presentation format, the association data is given in Base 16. Empty
+/// association data is represented by a single `-`.
+macro_rules! tlsa_type {
+    ($(#[$attr:meta])* ( $target:ident, $rtype:ident ) ) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(bound(
+                serialize = "
+                    Octets: crate::base::octets::SerializeOctets
+                        + AsRef<[u8]>
+                ",
+                deserialize = "
+                    Octets:
+                        crate::base::octets::FromBuilder
+                        + crate::base::octets::DeserializeOctets<'de>,
+                    <Octets as crate::base::octets::FromBuilder>::Builder:
+                        OctetsBuilder<Octets = Octets>
+                        + crate::base::octets::EmptyBuilder,
+                ",
+            ))
+        )]
+        pub struct $target<Octets> {
+            usage: TlsaCertUsage,
+            selector: TlsaSelector,
+            matching_type: TlsaMatchingType,
+            #[cfg_attr(
+                feature = "serde",
+                serde(with = "crate::utils::base16::serde")
+            )]
+            data: Octets,
+        }
+
+        impl<Octets> $target<Octets> {
+            pub fn new(
+                usage: TlsaCertUsage,
+                selector: TlsaSelector,
+                matching_type: TlsaMatchingType,
+                data: Octets,
+            ) -> Self {
+                $target {
+                    usage,
+                    selector,
+                    matching_type,
+                    data,
+                }
+            }
+
+            pub fn usage(&self) -> TlsaCertUsage {
+                self.usage
+            }
+
+            pub fn selector(&self) -> TlsaSelector {
+                self.selector
+            }
+
+            pub fn matching_type(&self) -> TlsaMatchingType {
+                self.matching_type
+            }
+
+            /// Returns the certificate association data.
+            pub fn data(&self) -> &Octets {
+                &self.data
+            }
+
+            pub fn into_data(self) -> Octets {
+                self.data
+            }
+        }
+
+        impl<SrcOctets> $target<SrcOctets> {
+            pub fn flatten_into<Octets>(
+                self,
+            ) -> Result<$target<Octets>, PushError>
+            where
+                Octets: OctetsFrom<SrcOctets>,
+            {
+                let Self {
+                    usage,
+                    selector,
+                    matching_type,
+                    data,
+                } = self;
+                Ok($target::new(
+                    usage,
+                    selector,
+                    matching_type,
+                    data.octets_into()?,
+                ))
+            }
+        }
+
+        //--- OctetsFrom
+
+        impl<Octets, SrcOctets> OctetsFrom<$target<SrcOctets>>
+        for $target<Octets>
+        where
+            Octets: OctetsFrom<SrcOctets>,
+        {
+            fn octets_from(
+                source: $target<SrcOctets>,
+            ) -> Result<Self, ShortBuf> {
+                Ok($target::new(
+                    source.usage,
+                    source.selector,
+                    source.matching_type,
+                    Octets::octets_from(source.data)?,
+                ))
+            }
+        }
+
+        //--- PartialEq and Eq
+
+        impl<Octets, Other> PartialEq<$target<Other>> for $target<Octets>
+        where
+            Octets: AsRef<[u8]>,
+            Other: AsRef<[u8]>,
+        {
+            fn eq(&self, other: &$target<Other>) -> bool {
+                self.usage == other.usage
+                    && self.selector == other.selector
+                    && self.matching_type == other.matching_type
+                    && self.data.as_ref().eq(other.data.as_ref())
+            }
+        }
+
+        impl<Octets: AsRef<[u8]>> Eq for $target<Octets> {}
+
+        //--- PartialOrd, CanonicalOrd, and Ord
+
+        impl<Octets, Other> PartialOrd<$target<Other>> for $target<Octets>
+        where
+            Octets: AsRef<[u8]>,
+            Other: AsRef<[u8]>,
+        {
+            fn partial_cmp(
+                &self,
+                other: &$target<Other>,
+            ) -> Option<Ordering> {
+                Some(self.canonical_cmp(other))
+            }
+        }
+
+        impl<Octets, Other> CanonicalOrd<$target<Other>> for $target<Octets>
+        where
+            Octets: AsRef<[u8]>,
+            Other: AsRef<[u8]>,
+        {
+            fn canonical_cmp(&self, other: &$target<Other>) -> Ordering {
+                match self.usage.cmp(&other.usage) {
+                    Ordering::Equal => {}
+                    other => return other,
+                }
+                match self.selector.cmp(&other.selector) {
+                    Ordering::Equal => {}
+                    other => return other,
+                }
+                match self.matching_type.cmp(&other.matching_type) {
+                    Ordering::Equal => {}
+                    other => return other,
+                }
+                self.data.as_ref().cmp(other.data.as_ref())
+            }
+        }
+
+        impl<Octets: AsRef<[u8]>> Ord for $target<Octets> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.canonical_cmp(other)
+            }
+        }
+
+        //--- Hash
+
+        impl<Octets: AsRef<[u8]>> hash::Hash for $target<Octets> {
+            fn hash<H: hash::Hasher>(&self, state: &mut H) {
+                self.usage.hash(state);
+                self.selector.hash(state);
+                self.matching_type.hash(state);
+                self.data.as_ref().hash(state);
+            }
+        }
+
+        //--- Parse and Compose
+
+        impl<Ref: OctetsRef> Parse<Ref> for $target<Ref::Range> {
+            fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+                let len = match parser.remaining().checked_sub(3) {
+                    Some(len) => len,
+                    None => return Err(ParseError::ShortInput),
+                };
+                Ok(Self::new(
+                    TlsaCertUsage::parse(parser)?,
+                    TlsaSelector::parse(parser)?,
+                    TlsaMatchingType::parse(parser)?,
+                    parser.parse_octets(len)?,
+                ))
+            }
+
+            fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+                if parser.remaining() < 3 {
+                    return Err(ParseError::ShortInput);
+                }
+                parser.advance_to_end();
+                Ok(())
+            }
+        }
+
+        impl<Octets: AsRef<[u8]>> Compose for $target<Octets> {
+            fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+                &self,
+                target: &mut T,
+            ) -> Result<(), ShortBuf> {
+                target.append_all(|buf| {
+                    self.usage.compose(buf)?;
+                    self.selector.compose(buf)?;
+                    self.matching_type.compose(buf)?;
+                    buf.append_slice(self.data.as_ref())
+                })
+            }
+        }
+
+        //--- Scan and Display
+
+        #[cfg(feature = "master")]
+        impl Scan for $target<Bytes> {
+            fn scan<C: CharSource>(
+                scanner: &mut Scanner<C>,
+            ) -> Result<Self, ScanError> {
+                let usage = TlsaCertUsage::scan(scanner)?;
+                let selector = TlsaSelector::scan(scanner)?;
+                let matching_type = TlsaMatchingType::scan(scanner)?;
+                let data = if let Ok(()) = scanner.skip_literal("-") {
+                    Bytes::new()
+                } else {
+                    scanner.scan_hex_words(Ok)?
+                };
+                Ok(Self::new(usage, selector, matching_type, data))
+            }
+        }
+
+        impl<Octets: AsRef<[u8]>> fmt::Display for $target<Octets> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    f,
+                    "{} {} {} ",
+                    self.usage, self.selector, self.matching_type
+                )?;
+                if self.data.as_ref().is_empty() {
+                    f.write_str("-")
+                } else {
+                    base16::display(&self.data, f)
+                }
+            }
+        }
+
+        //--- Debug
+
+        impl<Octets: AsRef<[u8]>> fmt::Debug for $target<Octets> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_struct(stringify!($target))
+                    .field("usage", &self.usage)
+                    .field("selector", &self.selector)
+                    .field("matching_type", &self.matching_type)
+                    .field("data", &self.data.as_ref())
+                    .finish()
+            }
+        }
+
+        //--- RtypeRecordData
+
+        impl<Octets> RtypeRecordData for $target<Octets> {
+            const RTYPE: Rtype = Rtype::$rtype;
+        }
+    }
+}
@@ -28,13 +28,34 @@
 mod macros;
 
 pub mod rfc1035;
+pub mod rfc1183;
+pub mod rfc1706;
+pub mod rfc1876;
+pub mod rfc2163;
+pub mod rfc2230;
 pub mod rfc2782;
 pub mod rfc2845;
+pub mod rfc2930;
+pub mod rfc3123;
+pub mod rfc3403;
 pub mod rfc3596;
+pub mod rfc4025;
 pub mod rfc4034;
+pub mod rfc4255;
+pub mod rfc4398;
+pub mod rfc4701;
 pub mod rfc5155;
 pub mod rfc6672;
+pub mod rfc6698;
+pub mod rfc6742;
+pub mod rfc7043;
 pub mod rfc7344;
+pub mod rfc7477;
+pub mod rfc7553;
+pub mod rfc7929;
+pub mod rfc8005;
+pub mod rfc8162;
+pub mod rfc8659;
 pub mod svcb;
 
 // The rdata_types! macro (defined in self::macros) re-exports the record data
@@ -69,11 +90,41 @@ rdata_types! {
             Ptr<N>,
             Soa<N>,
             Txt<O>,
+            Wks<O>,
         }
         pseudo {
             Null<O>,
         }
     }
+    rfc1183::{
+        zone {
+            Rp<N>,
+            Afsdb<N>,
+            X25<O>,
+            Isdn<O>,
+            Rt<N>,
+        }
+    }
+    rfc1706::{
+        zone {
+            Nsap<O>,
+        }
+    }
+    rfc1876::{
+        zone {
+            Loc,
+        }
+    }
+    rfc2163::{
+        zone {
+            Px<N>,
+        }
+    }
+    rfc2230::{
+        zone {
+            Kx<N>,
+        }
+    }
     rfc2782::{
         zone {
             Srv<N>,
@@ -84,11 +135,31 @@ rdata_types! {
             Tsig<O, N>,
         }
     }
+    rfc2930::{
+        pseudo {
+            Tkey<O, N>,
+        }
+    }
+    rfc3123::{
+        zone {
+            Apl<O>,
+        }
+    }
+    rfc3403::{
+        zone {
+            Naptr<O, N>,
+        }
+    }
     rfc3596::{
         zone {
             Aaaa,
         }
     }
+    rfc4025::{
+        zone {
+            Ipseckey<O, N>,
+        }
+    }
     rfc4034::{
         zone {
             Dnskey<O>,
@@ -97,9 +168,19 @@ rdata_types! {
             Ds<O>,
         }
     }
-    rfc6672::{
+    rfc4255::{
         zone {
-            Dname<N>,
+            Sshfp<O>,
+        }
+    }
+    rfc4398::{
+        zone {
+            Cert<O>,
+        }
+    }
+    rfc4701::{
+        zone {
+            Dhcid<O>,
         }
     }
     rfc5155::{
@@ -108,14 +189,68 @@ rdata_types! {
             Nsec3param<O>,
         }
     }
+    rfc6672::{
+        zone {
+            Dname<N>,
+        }
+    }
+    rfc6698::{
+        zone {
+            Tlsa<O>,
+        }
+    }
+    rfc6742::{
+        zone {
+            Nid,
+            L32,
+            L64,
+            Lp<N>,
+        }
+    }
+    rfc7043::{
+        zone {
+            Eui48,
+            Eui64,
+        }
+    }
     rfc7344::{
         zone {
             Cdnskey<O>,
             Cds<O>,
         }
     }
+    rfc7477::{
+        zone {
+            Csync<O>,
+        }
+    }
+    rfc7553::{
+        zone {
+            Uri<O>,
+        }
+    }
+    rfc7929::{
+        zone {
+            Openpgpkey<O>,
+        }
+    }
+    rfc8005::{
+        zone {
+            Hip<O>,
+        }
+    }
+    rfc8162::{
+        zone {
+            Smimea<O>,
+        }
+    }
+    rfc8659::{
+        zone {
+            Caa<O>,
+        }
+    }
     svcb::{
-        pseudo {
+        zone {
             Svcb<O, N>,
             Https<O, N>,
         }
@@ -1928,6 +1928,342 @@ impl<Builder: OctetsBuilder + EmptyBuilder> Default for TxtBuilder<Builder> {
     }
 }
 
+//------------ Wks ----------------------------------------------------------
+
+/// Wks record data.
+///
+/// Wks records describe the well known services supported by a particular
+/// protocol on a particular internet address. The services are given as a
+/// bitmap of port numbers where the bit for port 0 is the most significant
+/// bit of the first octet.
+///
+/// The Wks record type is defined in RFC 1035, section 3.4.2. Its use is
+/// discouraged by RFC 1123, section 2.2, but it still may appear in zone
+/// files.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Wks<Octets> {
+    address: Ipv4Addr,
+    protocol: u8,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "crate::base::octets::SerializeOctets::serialize_octets",
+            deserialize_with = "crate::base::octets::DeserializeOctets::deserialize_octets",
+            bound(
+                serialize = "Octets: crate::base::octets::SerializeOctets",
+                deserialize = "Octets: crate::base::octets::DeserializeOctets<'de>",
+            )
+        )
+    )]
+    bitmap: Octets,
+}
+
+impl<Octets> Wks<Octets> {
+    /// Creates a new Wks record data from the components.
+    pub fn new(address: Ipv4Addr, protocol: u8, bitmap: Octets) -> Self {
+        Wks {
+            address,
+            protocol,
+            bitmap,
+        }
+    }
+
+    /// The IPv4 address the services are provided on.
+    pub fn address(&self) -> Ipv4Addr {
+        self.address
+    }
+
+    /// The IP protocol number of the services.
+    ///
+    /// This will typically be 6 for TCP or 17 for UDP.
+    pub fn protocol(&self) -> u8 {
+        self.protocol
+    }
+
+    /// The raw bitmap of the ports of the services.
+    pub fn bitmap(&self) -> &Octets {
+        &self.bitmap
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Wks<Octets> {
+    /// Returns whether a service is provided on the given port.
+    pub fn has_port(&self, port: u16) -> bool {
+        self.bitmap
+            .as_ref()
+            .get(usize::from(port >> 3))
+            .map(|octet| octet & (0x80 >> (port & 0x07)) != 0)
+            .unwrap_or(false)
+    }
+
+    /// Returns an iterator over the ports of the services in order.
+    pub fn ports(&self) -> WksPorts<'_> {
+        WksPorts::new(self.bitmap.as_ref())
+    }
+}
+
+impl<SrcOctets> Wks<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Wks<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        let Self {
+            address,
+            protocol,
+            bitmap,
+        } = self;
+        Ok(Wks::new(address, protocol, bitmap.octets_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Wks<SrcOctets>> for Wks<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Wks<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(Wks::new(
+            source.address,
+            source.protocol,
+            Octets::octets_from(source.bitmap)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Wks<Other>> for Wks<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Wks<Other>) -> bool {
+        self.address == other.address
+            && self.protocol == other.protocol
+            && self.bitmap.as_ref() == other.bitmap.as_ref()
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Wks<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Wks<Other>> for Wks<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Wks<Other>) -> Option<Ordering> {
+        Some(self.canonical_cmp(other))
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Wks<Other>> for Wks<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Wks<Other>) -> Ordering {
+        match self.address.octets().cmp(&other.address.octets()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.protocol.cmp(&other.protocol) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.bitmap.as_ref().cmp(other.bitmap.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Wks<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Wks<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.address.hash(state);
+        self.protocol.hash(state);
+        self.bitmap.as_ref().hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Wks<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let address = Ipv4Addr::parse(parser)?;
+        let protocol = u8::parse(parser)?;
+        let len = parser.remaining();
+        Ok(Self::new(address, protocol, parser.parse_octets(len)?))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Wks<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.address.compose(target)?;
+            self.protocol.compose(target)?;
+            target.append_slice(self.bitmap.as_ref())
+        })
+    }
+}
+
+//--- Scan and Display
+
+/// The protocol mnemonics accepted when scanning Wks record data.
+#[cfg(feature = "master")]
+const WKS_PROTOCOLS: &[(&str, u8)] = &[("tcp", 6), ("udp", 17)];
+
+/// The service mnemonics accepted when scanning Wks record data.
+///
+/// This is a small selection of well known services from the IANA
+/// service name registry. Other services need to be given by their port
+/// number.
+#[cfg(feature = "master")]
+const WKS_SERVICES: &[(&str, u16)] = &[
+    ("ftp-data", 20),
+    ("ftp", 21),
+    ("ssh", 22),
+    ("telnet", 23),
+    ("smtp", 25),
+    ("time", 37),
+    ("whois", 43),
+    ("domain", 53),
+    ("tftp", 69),
+    ("gopher", 70),
+    ("finger", 79),
+    ("http", 80),
+    ("kerberos", 88),
+    ("pop3", 110),
+    ("sunrpc", 111),
+    ("auth", 113),
+    ("nntp", 119),
+    ("ntp", 123),
+    ("imap", 143),
+    ("snmp", 161),
+    ("ldap", 389),
+    ("https", 443),
+    ("submission", 587),
+];
+
+/// Scans a decimal number or one of the given mnemonics.
+#[cfg(feature = "master")]
+fn scan_mnemonic<C, T>(
+    scanner: &mut Scanner<C>,
+    mnemonics: &[(&str, T)],
+) -> Result<T, ScanError>
+where
+    C: CharSource,
+    T: Copy + FromStr,
+{
+    scanner.scan_string_word(|word| {
+        if let Ok(value) = T::from_str(&word) {
+            return Ok(value);
+        }
+        mnemonics
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&word))
+            .map(|(_, value)| *value)
+            .ok_or(SyntaxError::UnknownMnemonic)
+    })
+}
+
+#[cfg(feature = "master")]
+impl Scan for Wks<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        let address = scanner.scan_string_word(|word| {
+            Ipv4Addr::from_str(&word).map_err(Into::into)
+        })?;
+        let protocol = scan_mnemonic(scanner, WKS_PROTOCOLS)?;
+        let mut bitmap = BytesMut::new();
+        while !scanner.entry_ended()? {
+            let port = scan_mnemonic(scanner, WKS_SERVICES)?;
+            let idx = usize::from(port >> 3);
+            if bitmap.len() <= idx {
+                bitmap.resize(idx + 1, 0);
+            }
+            bitmap[idx] |= 0x80 >> (port & 0x07);
+        }
+        Ok(Self::new(address, protocol, bitmap.freeze()))
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Wks<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.address, self.protocol)?;
+        for port in self.ports() {
+            write!(f, " {}", port)?;
+        }
+        Ok(())
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Wks<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Wks(")?;
+        fmt::Display::fmt(self, f)?;
+        f.write_str(")")
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Wks<Octets> {
+    const RTYPE: Rtype = Rtype::Wks;
+}
+
+//------------ WksPorts -----------------------------------------------------
+
+/// An iterator over the ports in the bitmap of Wks record data.
+#[derive(Clone, Debug)]
+pub struct WksPorts<'a> {
+    bitmap: &'a [u8],
+    port: usize,
+}
+
+impl<'a> WksPorts<'a> {
+    fn new(bitmap: &'a [u8]) -> Self {
+        WksPorts { bitmap, port: 0 }
+    }
+}
+
+impl<'a> Iterator for WksPorts<'a> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Ports beyond 65535 can’t be represented, so we stop there even
+        // if the bitmap is longer.
+        while self.port <= usize::from(u16::MAX) {
+            let octet = *self.bitmap.get(self.port >> 3)?;
+            let port = self.port;
+            self.port += 1;
+            if octet & (0x80 >> (port & 0x07)) != 0 {
+                return Some(port as u16);
+            }
+        }
+        None
+    }
+}
+
 //============ Testing ======================================================
 
 #[cfg(test)]
@@ -2086,4 +2422,42 @@ mod test {
             ],
         );
     }
+
+    #[test]
+    fn wks_parse_compose_display() {
+        use std::string::ToString;
+
+        let rdata = b"\xc0\x00\x02\x01\x06\x00\x00\x40\x80\x00\x00\x00\x00\x00\x00\x01";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let wks = Wks::parse(&mut parser).unwrap();
+        assert_eq!(wks.address(), Ipv4Addr::new(192, 0, 2, 1));
+        assert_eq!(wks.protocol(), 6);
+        assert!(wks.has_port(24));
+        assert!(!wks.has_port(25));
+        assert!(!wks.has_port(1000));
+        assert_eq!(wks.ports().collect::<Vec<_>>(), [17, 24, 87]);
+        assert_eq!(wks.to_string(), "192.0.2.1 6 17 24 87");
+
+        let mut buf = Vec::new();
+        wks.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn wks_scan() {
+        let mut scanner =
+            Scanner::new("192.0.2.1 TCP smtp 80 domain\n192.0.2.2 17\n");
+        let wks = Wks::scan(&mut scanner).unwrap();
+        assert_eq!(wks.protocol(), 6);
+        assert_eq!(wks.ports().collect::<Vec<_>>(), [25, 53, 80]);
+        assert_eq!(wks.bitmap().len(), 11);
+        scanner.scan_newline().unwrap();
+        let wks = Wks::scan(&mut scanner).unwrap();
+        assert_eq!(wks.protocol(), 17);
+        assert_eq!(wks.ports().count(), 0);
+
+        let mut scanner = Scanner::new("192.0.2.1 tcp no-such-service\n");
+        assert!(Wks::scan(&mut scanner).is_err());
+    }
 }
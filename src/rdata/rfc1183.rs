@@ -0,0 +1,1040 @@
+//! Record data from [RFC 1183]: new resource record definitions.
+//!
+//! This RFC defines the Rp, Afsdb, X25, Isdn, and Rt record types. They
+//! are experimental but may still be encountered in zone files.
+//!
+//! Domain names in the record data of all these types are not compressed
+//! when composing, as required by [RFC 3597], but are lowercased in the
+//! canonical form per [RFC 4034].
+//!
+//! [RFC 1183]: https://tools.ietf.org/html/rfc1183
+//! [RFC 3597]: https://tools.ietf.org/html/rfc3597
+//! [RFC 4034]: https://tools.ietf.org/html/rfc4034
+
+use crate::base::charstr::CharStr;
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::{Dname, ParsedDname, PushError, ToDname};
+use crate::base::octets::{
+    Compose, EmptyBuilder, FromBuilder, OctetsBuilder, OctetsFrom,
+    OctetsInto, OctetsRef, Parse, ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Rp -----------------------------------------------------------
+
+/// Rp record data.
+///
+/// The Rp record specifies the mailbox of the person responsible for the
+/// owner name and a domain name that has Txt records with further
+/// information about them.
+///
+/// The Rp record type is defined in RFC 1183, section 2.2.
+#[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rp<N> {
+    mbox: N,
+    txt: N,
+}
+
+impl<N> Rp<N> {
+    /// Creates a new Rp record data from the components.
+    pub fn new(mbox: N, txt: N) -> Self {
+        Rp { mbox, txt }
+    }
+
+    /// The mailbox of the responsible person.
+    ///
+    /// The mailbox is encoded as a domain name the same way as in Soa
+    /// records. If it is the root name, no mailbox is available.
+    pub fn mbox(&self) -> &N {
+        &self.mbox
+    }
+
+    /// The domain name of the Txt records with further information.
+    ///
+    /// If this is the root name, no such records are available.
+    pub fn txt(&self) -> &N {
+        &self.txt
+    }
+}
+
+impl<Ref> Rp<ParsedDname<Ref>>
+where
+    Ref: OctetsRef,
+{
+    pub fn flatten_into<Octets>(self) -> Result<Rp<Dname<Octets>>, PushError>
+    where
+        Octets: OctetsFrom<Ref::Range> + FromBuilder,
+        <Octets as FromBuilder>::Builder: EmptyBuilder,
+    {
+        let Self { mbox, txt } = self;
+        Ok(Rp::new(mbox.flatten_into()?, txt.flatten_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Name, SrcName> OctetsFrom<Rp<SrcName>> for Rp<Name>
+where
+    Name: OctetsFrom<SrcName>,
+{
+    fn octets_from(source: Rp<SrcName>) -> Result<Self, ShortBuf> {
+        Ok(Rp::new(
+            Name::octets_from(source.mbox)?,
+            Name::octets_from(source.txt)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<N, NN> PartialEq<Rp<NN>> for Rp<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn eq(&self, other: &Rp<NN>) -> bool {
+        self.mbox.name_eq(&other.mbox) && self.txt.name_eq(&other.txt)
+    }
+}
+
+impl<N: ToDname> Eq for Rp<N> {}
+
+//--- PartialOrd, Ord, and CanonicalOrd
+
+impl<N, NN> PartialOrd<Rp<NN>> for Rp<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn partial_cmp(&self, other: &Rp<NN>) -> Option<Ordering> {
+        match self.mbox.name_cmp(&other.mbox) {
+            Ordering::Equal => {}
+            other => return Some(other),
+        }
+        Some(self.txt.name_cmp(&other.txt))
+    }
+}
+
+impl<N: ToDname> Ord for Rp<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.mbox.name_cmp(&other.mbox) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.txt.name_cmp(&other.txt)
+    }
+}
+
+impl<N: ToDname, NN: ToDname> CanonicalOrd<Rp<NN>> for Rp<N> {
+    fn canonical_cmp(&self, other: &Rp<NN>) -> Ordering {
+        match self.mbox.lowercase_composed_cmp(&other.mbox) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.txt.lowercase_composed_cmp(&other.txt)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Rp<ParsedDname<Ref>> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Ok(Self::new(
+            ParsedDname::parse(parser)?,
+            ParsedDname::parse(parser)?,
+        ))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        ParsedDname::skip(parser)?;
+        ParsedDname::skip(parser)?;
+        Ok(())
+    }
+}
+
+impl<N: ToDname> Compose for Rp<N> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.mbox.compose(target)?;
+            self.txt.compose(target)
+        })
+    }
+
+    fn compose_canonical<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.mbox.compose_canonical(target)?;
+            self.txt.compose_canonical(target)
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl<N: Scan> Scan for Rp<N> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(N::scan(scanner)?, N::scan(scanner)?))
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Rp<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}. {}.", self.mbox, self.txt)
+    }
+}
+
+//--- RtypeRecordData
+
+impl<N> RtypeRecordData for Rp<N> {
+    const RTYPE: Rtype = Rtype::Rp;
+}
+
+//------------ Afsdb --------------------------------------------------------
+
+/// Afsdb record data.
+///
+/// The Afsdb record specifies the location of an AFS database server or a
+/// DCE authenticated name server for the cell named by the owner name.
+///
+/// The Afsdb record type is defined in RFC 1183, section 1.
+#[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Afsdb<N> {
+    subtype: u16,
+    hostname: N,
+}
+
+impl<N> Afsdb<N> {
+    /// Creates a new Afsdb record data from the components.
+    pub fn new(subtype: u16, hostname: N) -> Self {
+        Afsdb { subtype, hostname }
+    }
+
+    /// The kind of server provided by the host.
+    ///
+    /// A value of 1 denotes an AFS version 3.0 volume location server, a
+    /// value of 2 a DCE authenticated name server.
+    pub fn subtype(&self) -> u16 {
+        self.subtype
+    }
+
+    /// The name of the host providing the server.
+    pub fn hostname(&self) -> &N {
+        &self.hostname
+    }
+}
+
+impl<Ref> Afsdb<ParsedDname<Ref>>
+where
+    Ref: OctetsRef,
+{
+    pub fn flatten_into<Octets>(
+        self,
+    ) -> Result<Afsdb<Dname<Octets>>, PushError>
+    where
+        Octets: OctetsFrom<Ref::Range> + FromBuilder,
+        <Octets as FromBuilder>::Builder: EmptyBuilder,
+    {
+        let Self { subtype, hostname } = self;
+        Ok(Afsdb::new(subtype, hostname.flatten_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Name, SrcName> OctetsFrom<Afsdb<SrcName>> for Afsdb<Name>
+where
+    Name: OctetsFrom<SrcName>,
+{
+    fn octets_from(source: Afsdb<SrcName>) -> Result<Self, ShortBuf> {
+        Ok(Afsdb::new(
+            source.subtype,
+            Name::octets_from(source.hostname)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<N, NN> PartialEq<Afsdb<NN>> for Afsdb<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn eq(&self, other: &Afsdb<NN>) -> bool {
+        self.subtype == other.subtype
+            && self.hostname.name_eq(&other.hostname)
+    }
+}
+
+impl<N: ToDname> Eq for Afsdb<N> {}
+
+//--- PartialOrd, Ord, and CanonicalOrd
+
+impl<N, NN> PartialOrd<Afsdb<NN>> for Afsdb<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn partial_cmp(&self, other: &Afsdb<NN>) -> Option<Ordering> {
+        match self.subtype.partial_cmp(&other.subtype) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        Some(self.hostname.name_cmp(&other.hostname))
+    }
+}
+
+impl<N: ToDname> Ord for Afsdb<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.subtype.cmp(&other.subtype) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.hostname.name_cmp(&other.hostname)
+    }
+}
+
+impl<N: ToDname, NN: ToDname> CanonicalOrd<Afsdb<NN>> for Afsdb<N> {
+    fn canonical_cmp(&self, other: &Afsdb<NN>) -> Ordering {
+        match self.subtype.cmp(&other.subtype) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.hostname.lowercase_composed_cmp(&other.hostname)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Afsdb<ParsedDname<Ref>> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Ok(Self::new(u16::parse(parser)?, ParsedDname::parse(parser)?))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        u16::skip(parser)?;
+        ParsedDname::skip(parser)
+    }
+}
+
+impl<N: ToDname> Compose for Afsdb<N> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.subtype.compose(target)?;
+            self.hostname.compose(target)
+        })
+    }
+
+    fn compose_canonical<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.subtype.compose(target)?;
+            self.hostname.compose_canonical(target)
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl<N: Scan> Scan for Afsdb<N> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(u16::scan(scanner)?, N::scan(scanner)?))
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Afsdb<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}.", self.subtype, self.hostname)
+    }
+}
+
+//--- RtypeRecordData
+
+impl<N> RtypeRecordData for Afsdb<N> {
+    const RTYPE: Rtype = Rtype::Afsdb;
+}
+
+//------------ X25 ----------------------------------------------------------
+
+/// X25 record data.
+///
+/// The X25 record specifies the X.121 address of the owner name on a
+/// public switched data network. The address is a character string of
+/// decimal digits.
+///
+/// The X25 record type is defined in RFC 1183, section 3.1.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Octets: AsRef<[u8]> + crate::base::octets::SerializeOctets",
+        deserialize = "Octets: \
+                crate::base::octets::FromBuilder \
+                + crate::base::octets::DeserializeOctets<'de>, \
+            <Octets as FromBuilder>::Builder: EmptyBuilder ",
+    ))
+)]
+pub struct X25<Octets> {
+    psdn_address: CharStr<Octets>,
+}
+
+impl<Octets> X25<Octets> {
+    /// Creates a new X25 record data from the PSDN address.
+    pub fn new(psdn_address: CharStr<Octets>) -> Self {
+        X25 { psdn_address }
+    }
+
+    /// The X.121 address on the public switched data network.
+    pub fn psdn_address(&self) -> &CharStr<Octets> {
+        &self.psdn_address
+    }
+}
+
+impl<SrcOctets> X25<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<X25<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        Ok(X25::new(self.psdn_address.octets_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<X25<SrcOctets>> for X25<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: X25<SrcOctets>) -> Result<Self, ShortBuf> {
+        CharStr::octets_from(source.psdn_address).map(Self::new)
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<X25<Other>> for X25<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &X25<Other>) -> bool {
+        self.psdn_address.eq(&other.psdn_address)
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for X25<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<X25<Other>> for X25<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &X25<Other>) -> Option<Ordering> {
+        self.psdn_address.partial_cmp(&other.psdn_address)
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<X25<Other>> for X25<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &X25<Other>) -> Ordering {
+        self.psdn_address.canonical_cmp(&other.psdn_address)
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for X25<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.psdn_address.cmp(&other.psdn_address)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for X25<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.psdn_address.hash(state)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for X25<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        CharStr::parse(parser).map(Self::new)
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        CharStr::skip(parser)
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for X25<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        self.psdn_address.compose(target)
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for X25<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        CharStr::scan(scanner).map(Self::new)
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for X25<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\"", self.psdn_address)
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for X25<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("X25")
+            .field("psdn_address", &self.psdn_address)
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for X25<Octets> {
+    const RTYPE: Rtype = Rtype::X25;
+}
+
+//------------ Isdn ---------------------------------------------------------
+
+/// Isdn record data.
+///
+/// The Isdn record specifies the ISDN address of the owner name, i.e., a
+/// telephone number, and an optional subaddress. Both are character
+/// strings of decimal digits.
+///
+/// The Isdn record type is defined in RFC 1183, section 3.2.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Octets: AsRef<[u8]> + crate::base::octets::SerializeOctets",
+        deserialize = "Octets: \
+                crate::base::octets::FromBuilder \
+                + crate::base::octets::DeserializeOctets<'de>, \
+            <Octets as FromBuilder>::Builder: EmptyBuilder ",
+    ))
+)]
+pub struct Isdn<Octets> {
+    address: CharStr<Octets>,
+    subaddress: Option<CharStr<Octets>>,
+}
+
+impl<Octets> Isdn<Octets> {
+    /// Creates a new Isdn record data from the components.
+    pub fn new(
+        address: CharStr<Octets>,
+        subaddress: Option<CharStr<Octets>>,
+    ) -> Self {
+        Isdn {
+            address,
+            subaddress,
+        }
+    }
+
+    /// The ISDN address.
+    pub fn address(&self) -> &CharStr<Octets> {
+        &self.address
+    }
+
+    /// The ISDN subaddress if present.
+    pub fn subaddress(&self) -> Option<&CharStr<Octets>> {
+        self.subaddress.as_ref()
+    }
+}
+
+impl<SrcOctets> Isdn<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Isdn<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        let Self {
+            address,
+            subaddress,
+        } = self;
+        Ok(Isdn::new(
+            address.octets_into()?,
+            match subaddress {
+                Some(subaddress) => Some(subaddress.octets_into()?),
+                None => None,
+            },
+        ))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Isdn<SrcOctets>> for Isdn<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Isdn<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(Isdn::new(
+            CharStr::octets_from(source.address)?,
+            match source.subaddress {
+                Some(subaddress) => Some(CharStr::octets_from(subaddress)?),
+                None => None,
+            },
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Isdn<Other>> for Isdn<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Isdn<Other>) -> bool {
+        self.address.eq(&other.address)
+            && match (self.subaddress.as_ref(), other.subaddress.as_ref()) {
+                (Some(left), Some(right)) => left.eq(right),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Isdn<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Isdn<Other>> for Isdn<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Isdn<Other>) -> Option<Ordering> {
+        Some(self.canonical_cmp(other))
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Isdn<Other>> for Isdn<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Isdn<Other>) -> Ordering {
+        match self.address.canonical_cmp(&other.address) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        // A missing subaddress is shorter on the wire and therefore
+        // sorts first.
+        match (self.subaddress.as_ref(), other.subaddress.as_ref()) {
+            (Some(left), Some(right)) => left.canonical_cmp(right),
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Isdn<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Isdn<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.address.hash(state);
+        self.subaddress.hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Isdn<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let address = CharStr::parse(parser)?;
+        let subaddress = if parser.remaining() > 0 {
+            Some(CharStr::parse(parser)?)
+        } else {
+            None
+        };
+        Ok(Self::new(address, subaddress))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        CharStr::skip(parser)?;
+        if parser.remaining() > 0 {
+            CharStr::skip(parser)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Isdn<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.address.compose(target)?;
+            if let Some(ref subaddress) = self.subaddress {
+                subaddress.compose(target)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Isdn<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        let address = CharStr::scan(scanner)?;
+        let subaddress = if scanner.entry_ended()? {
+            None
+        } else {
+            Some(CharStr::scan(scanner)?)
+        };
+        Ok(Self::new(address, subaddress))
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Isdn<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\"", self.address)?;
+        if let Some(ref subaddress) = self.subaddress {
+            write!(f, " \"{}\"", subaddress)?;
+        }
+        Ok(())
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Isdn<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Isdn")
+            .field("address", &self.address)
+            .field("subaddress", &self.subaddress)
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Isdn<Octets> {
+    const RTYPE: Rtype = Rtype::Isdn;
+}
+
+//------------ Rt -----------------------------------------------------------
+
+/// Rt record data.
+///
+/// The Rt record specifies an intermediate host that provides routing to
+/// the owner name for hosts that are not directly connected to the
+/// internet.
+///
+/// The Rt record type is defined in RFC 1183, section 3.3.
+#[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rt<N> {
+    preference: u16,
+    intermediate: N,
+}
+
+impl<N> Rt<N> {
+    /// Creates a new Rt record data from the components.
+    pub fn new(preference: u16, intermediate: N) -> Self {
+        Rt {
+            preference,
+            intermediate,
+        }
+    }
+
+    /// The preference for this record.
+    ///
+    /// Defines an order if there are several Rt records for the same owner.
+    /// Lower values are preferred.
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// The name of the intermediate host.
+    pub fn intermediate(&self) -> &N {
+        &self.intermediate
+    }
+}
+
+impl<Ref> Rt<ParsedDname<Ref>>
+where
+    Ref: OctetsRef,
+{
+    pub fn flatten_into<Octets>(self) -> Result<Rt<Dname<Octets>>, PushError>
+    where
+        Octets: OctetsFrom<Ref::Range> + FromBuilder,
+        <Octets as FromBuilder>::Builder: EmptyBuilder,
+    {
+        let Self {
+            preference,
+            intermediate,
+        } = self;
+        Ok(Rt::new(preference, intermediate.flatten_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Name, SrcName> OctetsFrom<Rt<SrcName>> for Rt<Name>
+where
+    Name: OctetsFrom<SrcName>,
+{
+    fn octets_from(source: Rt<SrcName>) -> Result<Self, ShortBuf> {
+        Ok(Rt::new(
+            source.preference,
+            Name::octets_from(source.intermediate)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<N, NN> PartialEq<Rt<NN>> for Rt<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn eq(&self, other: &Rt<NN>) -> bool {
+        self.preference == other.preference
+            && self.intermediate.name_eq(&other.intermediate)
+    }
+}
+
+impl<N: ToDname> Eq for Rt<N> {}
+
+//--- PartialOrd, Ord, and CanonicalOrd
+
+impl<N, NN> PartialOrd<Rt<NN>> for Rt<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn partial_cmp(&self, other: &Rt<NN>) -> Option<Ordering> {
+        match self.preference.partial_cmp(&other.preference) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        Some(self.intermediate.name_cmp(&other.intermediate))
+    }
+}
+
+impl<N: ToDname> Ord for Rt<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.preference.cmp(&other.preference) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.intermediate.name_cmp(&other.intermediate)
+    }
+}
+
+impl<N: ToDname, NN: ToDname> CanonicalOrd<Rt<NN>> for Rt<N> {
+    fn canonical_cmp(&self, other: &Rt<NN>) -> Ordering {
+        match self.preference.cmp(&other.preference) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.intermediate
+            .lowercase_composed_cmp(&other.intermediate)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Rt<ParsedDname<Ref>> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Ok(Self::new(u16::parse(parser)?, ParsedDname::parse(parser)?))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        u16::skip(parser)?;
+        ParsedDname::skip(parser)
+    }
+}
+
+impl<N: ToDname> Compose for Rt<N> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.preference.compose(target)?;
+            self.intermediate.compose(target)
+        })
+    }
+
+    fn compose_canonical<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.preference.compose(target)?;
+            self.intermediate.compose_canonical(target)
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl<N: Scan> Scan for Rt<N> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(u16::scan(scanner)?, N::scan(scanner)?))
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Rt<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}.", self.preference, self.intermediate)
+    }
+}
+
+//--- RtypeRecordData
+
+impl<N> RtypeRecordData for Rt<N> {
+    const RTYPE: Rtype = Rtype::Rt;
+}
+
+//============ Testing ======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn rp_parse_compose_display() {
+        // The names must not be compressed, so a name pointing back into
+        // the record data must be expanded when composing.
+        let rdata = b"\x04mbox\x07example\x00\x03txt\xc0\x05";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let rp = Rp::parse(&mut parser).unwrap();
+        assert_eq!(rp.to_string(), "mbox.example. txt.example.");
+
+        let mut buf = Vec::new();
+        rp.compose(&mut buf).unwrap();
+        assert_eq!(
+            buf.as_slice(),
+            b"\x04mbox\x07example\x00\x03txt\x07example\x00".as_ref()
+        );
+    }
+
+    #[test]
+    fn afsdb_rt_canonical() {
+        let afsdb: Afsdb<Dname<Vec<u8>>> =
+            Afsdb::new(1, "AFS.Example.".parse().unwrap());
+        let mut buf = Vec::new();
+        afsdb.compose_canonical(&mut buf).unwrap();
+        assert_eq!(buf.as_slice(), b"\x00\x01\x03afs\x07example\x00");
+        assert_eq!(afsdb.to_string(), "1 AFS.Example.");
+
+        let rt: Rt<Dname<Vec<u8>>> =
+            Rt::new(10, "Relay.Example.".parse().unwrap());
+        let mut buf = Vec::new();
+        rt.compose(&mut buf).unwrap();
+        assert_eq!(buf.as_slice(), b"\x00\x0a\x05Relay\x07Example\x00");
+        assert_eq!(rt.to_string(), "10 Relay.Example.");
+    }
+
+    #[test]
+    fn isdn_optional_subaddress() {
+        let rdata = b"\x0f150862028003217\x03004";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let isdn = Isdn::parse(&mut parser).unwrap();
+        assert_eq!(isdn.to_string(), "\"150862028003217\" \"004\"");
+        let mut buf = Vec::new();
+        isdn.compose(&mut buf).unwrap();
+        assert_eq!(buf.as_slice(), rdata.as_ref());
+
+        let rdata = b"\x0f150862028003217";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let isdn = Isdn::parse(&mut parser).unwrap();
+        assert!(isdn.subaddress().is_none());
+        assert_eq!(isdn.to_string(), "\"150862028003217\"");
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new(
+            "louie.trantor.umd.edu. lam1.people.umd.edu.\n\
+             311061700956\n\
+             150862028003217\n\
+             150862028003217 004\n",
+        );
+        let rp: Rp<Dname<Bytes>> = Rp::scan(&mut scanner).unwrap();
+        assert_eq!(
+            rp.to_string(),
+            "louie.trantor.umd.edu. lam1.people.umd.edu."
+        );
+        scanner.scan_newline().unwrap();
+        let x25 = X25::scan(&mut scanner).unwrap();
+        assert_eq!(x25.psdn_address().as_slice(), b"311061700956");
+        scanner.scan_newline().unwrap();
+        let isdn = Isdn::scan(&mut scanner).unwrap();
+        assert!(isdn.subaddress().is_none());
+        scanner.scan_newline().unwrap();
+        let isdn = Isdn::scan(&mut scanner).unwrap();
+        assert_eq!(isdn.subaddress().unwrap().as_slice(), b"004");
+    }
+}
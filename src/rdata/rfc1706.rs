@@ -0,0 +1,252 @@
+//! Record data from [RFC 1706]: NSAP records.
+//!
+//! This RFC defines the Nsap record type used to map domain names to OSI
+//! network service access point addresses.
+//!
+//! [RFC 1706]: https://tools.ietf.org/html/rfc1706
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{
+    CharSource, Scan, ScanError, Scanner, SyntaxError,
+};
+use crate::utils::base16;
+#[cfg(feature = "master")]
+use bytes::{Bytes, BytesMut};
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Nsap ---------------------------------------------------------
+
+/// Nsap record data.
+///
+/// The Nsap record contains the NSAP address of the owner name as a
+/// sequence of octets.
+///
+/// In presentation format, the address is given in Base 16 with a leading
+/// `0x`. When scanning, the digits may be separated by dots for
+/// readability.
+///
+/// The Nsap record type is defined in RFC 1706, section 5.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>
+        ",
+        deserialize = "
+            Octets:
+                crate::base::octets::FromBuilder
+                + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as crate::base::octets::FromBuilder>::Builder:
+                OctetsBuilder<Octets = Octets>
+                + crate::base::octets::EmptyBuilder,
+        ",
+    ))
+)]
+pub struct Nsap<Octets> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base16::serde")
+    )]
+    address: Octets,
+}
+
+impl<Octets> Nsap<Octets> {
+    /// Creates a new Nsap record data from the address octets.
+    pub fn new(address: Octets) -> Self {
+        Nsap { address }
+    }
+
+    /// The NSAP address.
+    pub fn address(&self) -> &Octets {
+        &self.address
+    }
+
+    pub fn into_address(self) -> Octets {
+        self.address
+    }
+}
+
+impl<SrcOctets> Nsap<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Nsap<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        Ok(Nsap::new(self.address.octets_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Nsap<SrcOctets>> for Nsap<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Nsap<SrcOctets>) -> Result<Self, ShortBuf> {
+        Octets::octets_from(source.address).map(Self::new)
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Nsap<Other>> for Nsap<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Nsap<Other>) -> bool {
+        self.address.as_ref().eq(other.address.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Nsap<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Nsap<Other>> for Nsap<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Nsap<Other>) -> Option<Ordering> {
+        self.address.as_ref().partial_cmp(other.address.as_ref())
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Nsap<Other>> for Nsap<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Nsap<Other>) -> Ordering {
+        self.address.as_ref().cmp(other.address.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Nsap<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.address.as_ref().cmp(other.address.as_ref())
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Nsap<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.address.as_ref().hash(state)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Nsap<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let len = parser.remaining();
+        parser.parse_octets(len).map(Self::new)
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Nsap<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_slice(self.address.as_ref())
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Nsap<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        scanner.scan_string_word(|word| {
+            let digits = match word.get(..2) {
+                Some("0x") | Some("0X") => &word[2..],
+                _ => return Err(SyntaxError::Expected("0x".into())),
+            };
+            let mut decoder = base16::Decoder::<BytesMut>::new();
+            for ch in digits.chars().filter(|&ch| ch != '.') {
+                decoder.push(ch).map_err(SyntaxError::content)?;
+            }
+            decoder
+                .finalize()
+                .map(Self::new)
+                .map_err(SyntaxError::content)
+        })
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Nsap<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("0x")?;
+        base16::display(&self.address, f)
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Nsap<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Nsap(")?;
+        fmt::Display::fmt(self, f)?;
+        f.write_str(")")
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Nsap<Octets> {
+    const RTYPE: Rtype = Rtype::Nsap;
+}
+
+//============ Testing ======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x47\x00\x05\x80\x00\x5a\x00";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let nsap = Nsap::parse(&mut parser).unwrap();
+        assert_eq!(nsap.to_string(), "0x47000580005A00");
+
+        let mut buf = Vec::new();
+        nsap.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new("0x47.0005.80.005a00\n");
+        assert_eq!(
+            Nsap::scan(&mut scanner).unwrap().address().as_ref(),
+            b"\x47\x00\x05\x80\x00\x5a\x00"
+        );
+        assert!(Nsap::scan(&mut Scanner::new("470005\n")).is_err());
+        assert!(Nsap::scan(&mut Scanner::new("0x4700a\n")).is_err());
+    }
+}
@@ -0,0 +1,667 @@
+//! Record data from [RFC 1876]: LOC records.
+//!
+//! This RFC defines the LOC record type that expresses the geographical
+//! location of a host, network, or subnet.
+//!
+//! [RFC 1876]: https://tools.ietf.org/html/rfc1876
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsRef, Parse, ParseError, Parser,
+    ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{
+    CharSource, Scan, ScanError, Scanner, SyntaxError,
+};
+use core::cmp::Ordering;
+use core::fmt;
+
+//------------ Loc -----------------------------------------------------------
+
+/// LOC record data.
+///
+/// A LOC record provides the location of the entity named by its owner as
+/// latitude, longitude, and altitude together with the diameter of a
+/// sphere enclosing the entity and the horizontal and vertical precision
+/// of the location.
+///
+/// In wire format, latitude and longitude are given in thousandths of a
+/// second of arc offset by 2<sup>31</sup> so that the equator and the prime
+/// meridian are at 2<sup>31</sup>, with larger values to the north and
+/// east, respectively. The altitude is given in centimetres above a base of
+/// 100,000 metres below the WGS 84 reference spheroid. The raw values are
+/// available via [`raw_latitude`][Self::raw_latitude] and friends while
+/// [`latitude`][Self::latitude], [`longitude`][Self::longitude], and
+/// [`altitude`][Self::altitude] convert them into degrees and metres.
+///
+/// In presentation format, latitude and longitude are given in degrees,
+/// minutes, and seconds followed by a hemisphere letter and all other
+/// values in metres, optionally followed by the letter `m`. Minutes,
+/// seconds, and the last three values can be left out. The record data is
+/// always written in full.
+///
+/// The LOC record type is defined in RFC 1876, section 2.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Loc {
+    size: LocPrecision,
+    horiz_pre: LocPrecision,
+    vert_pre: LocPrecision,
+    latitude: u32,
+    longitude: u32,
+    altitude: u32,
+}
+
+impl Loc {
+    /// The raw value of the equator and the prime meridian.
+    pub const EQUATOR: u32 = 1 << 31;
+
+    /// The raw value of an altitude of zero.
+    pub const ZERO_ALTITUDE: u32 = 10_000_000;
+
+    /// Creates new LOC record data from raw wire format values.
+    pub fn new(
+        latitude: u32,
+        longitude: u32,
+        altitude: u32,
+        size: LocPrecision,
+        horiz_pre: LocPrecision,
+        vert_pre: LocPrecision,
+    ) -> Self {
+        Loc {
+            size,
+            horiz_pre,
+            vert_pre,
+            latitude,
+            longitude,
+            altitude,
+        }
+    }
+
+    /// Creates new LOC record data from decimal coordinates.
+    ///
+    /// Latitude and longitude are given in degrees with positive values
+    /// to the north and east, respectively. The altitude is given in metres.
+    /// The values are rounded to the resolution of the wire format. The
+    /// size and precision values are set to their defaults.
+    ///
+    /// Returns an error if any of the values is out of range.
+    pub fn from_degrees(
+        latitude: f64,
+        longitude: f64,
+        altitude: f64,
+    ) -> Result<Self, LocError> {
+        if !(-90.0..=90.0).contains(&latitude)
+            || !(-180.0..=180.0).contains(&longitude)
+        {
+            return Err(LocError);
+        }
+        let altitude =
+            round(altitude * 100.) + i64::from(Self::ZERO_ALTITUDE);
+        if !(0..=i64::from(u32::MAX)).contains(&altitude) {
+            return Err(LocError);
+        }
+        Ok(Loc::new(
+            angle_from_degrees(latitude),
+            angle_from_degrees(longitude),
+            altitude as u32,
+            LocPrecision::DEFAULT_SIZE,
+            LocPrecision::DEFAULT_HORIZ_PRE,
+            LocPrecision::DEFAULT_VERT_PRE,
+        ))
+    }
+
+    /// Returns the latitude in degrees.
+    ///
+    /// Positive values are to the north of the equator.
+    pub fn latitude(&self) -> f64 {
+        angle_to_degrees(self.latitude)
+    }
+
+    /// Returns the longitude in degrees.
+    ///
+    /// Positive values are to the east of the prime meridian.
+    pub fn longitude(&self) -> f64 {
+        angle_to_degrees(self.longitude)
+    }
+
+    /// Returns the altitude in metres.
+    pub fn altitude(&self) -> f64 {
+        (i64::from(self.altitude) - i64::from(Self::ZERO_ALTITUDE)) as f64
+            / 100.
+    }
+
+    /// Returns the raw wire format value of the latitude.
+    pub fn raw_latitude(&self) -> u32 {
+        self.latitude
+    }
+
+    /// Returns the raw wire format value of the longitude.
+    pub fn raw_longitude(&self) -> u32 {
+        self.longitude
+    }
+
+    /// Returns the raw wire format value of the altitude.
+    pub fn raw_altitude(&self) -> u32 {
+        self.altitude
+    }
+
+    /// Returns the diameter of a sphere enclosing the entity.
+    pub fn size(&self) -> LocPrecision {
+        self.size
+    }
+
+    /// Returns the horizontal precision of the location.
+    pub fn horiz_pre(&self) -> LocPrecision {
+        self.horiz_pre
+    }
+
+    /// Returns the vertical precision of the location.
+    pub fn vert_pre(&self) -> LocPrecision {
+        self.vert_pre
+    }
+
+    /// Sets the size and the horizontal and vertical precision.
+    pub fn set_precision(
+        &mut self,
+        size: LocPrecision,
+        horiz_pre: LocPrecision,
+        vert_pre: LocPrecision,
+    ) {
+        self.size = size;
+        self.horiz_pre = horiz_pre;
+        self.vert_pre = vert_pre;
+    }
+
+    pub fn flatten_into(self) -> Result<Loc, PushError> {
+        Ok(self)
+    }
+}
+
+//--- OctetsFrom
+
+impl OctetsFrom<Loc> for Loc {
+    fn octets_from(source: Loc) -> Result<Self, ShortBuf> {
+        Ok(source)
+    }
+}
+
+//--- CanonicalOrd
+
+impl CanonicalOrd for Loc {
+    fn canonical_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Loc {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        if u8::parse(parser)? != 0 {
+            return Err(ParseError::form_error("unknown LOC version"));
+        }
+        Ok(Self {
+            size: LocPrecision::parse(parser)?,
+            horiz_pre: LocPrecision::parse(parser)?,
+            vert_pre: LocPrecision::parse(parser)?,
+            latitude: u32::parse(parser)?,
+            longitude: u32::parse(parser)?,
+            altitude: u32::parse(parser)?,
+        })
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        Self::parse(parser).map(|_| ())
+    }
+}
+
+impl Compose for Loc {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|buf| {
+            0u8.compose(buf)?;
+            self.size.compose(buf)?;
+            self.horiz_pre.compose(buf)?;
+            self.vert_pre.compose(buf)?;
+            self.latitude.compose(buf)?;
+            self.longitude.compose(buf)?;
+            self.altitude.compose(buf)
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Loc {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        let latitude = scan_angle(scanner, 90, b'N', b'S')?;
+        let longitude = scan_angle(scanner, 180, b'E', b'W')?;
+        let altitude = scanner.scan_string_word(|word| {
+            parse_metres(&word)
+                .map(|alt| alt + i64::from(Self::ZERO_ALTITUDE))
+                .filter(|alt| (0..=i64::from(u32::MAX)).contains(alt))
+                .map(|alt| alt as u32)
+                .ok_or_else(|| SyntaxError::content(LocError))
+        })?;
+        let mut res = Loc::new(
+            latitude,
+            longitude,
+            altitude,
+            LocPrecision::DEFAULT_SIZE,
+            LocPrecision::DEFAULT_HORIZ_PRE,
+            LocPrecision::DEFAULT_VERT_PRE,
+        );
+        for field in
+            [&mut res.size, &mut res.horiz_pre, &mut res.vert_pre].iter_mut()
+        {
+            if scanner.entry_ended()? {
+                break;
+            }
+            **field = LocPrecision::scan(scanner)?;
+        }
+        Ok(res)
+    }
+}
+
+impl fmt::Display for Loc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_angle(self.latitude, b'N', b'S', f)?;
+        f.write_str(" ")?;
+        fmt_angle(self.longitude, b'E', b'W', f)?;
+        let altitude =
+            i64::from(self.altitude) - i64::from(Self::ZERO_ALTITUDE);
+        write!(
+            f,
+            " {}{}.{:02}m {} {} {}",
+            if altitude < 0 { "-" } else { "" },
+            altitude.abs() / 100,
+            altitude.abs() % 100,
+            self.size,
+            self.horiz_pre,
+            self.vert_pre
+        )
+    }
+}
+
+//--- RtypeRecordData
+
+impl RtypeRecordData for Loc {
+    const RTYPE: Rtype = Rtype::Loc;
+}
+
+//------------ LocPrecision --------------------------------------------------
+
+/// The size or precision value of a LOC record.
+///
+/// The value is a length in centimetres encoded in a single octet. The
+/// upper four bits contain the base and the lower four bits contain the
+/// power of ten the base is multiplied with. Both need to be between 0 and
+/// 9.
+///
+/// In presentation format, the value is given in metres.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocPrecision(u8);
+
+impl LocPrecision {
+    /// The default size of one metre.
+    pub const DEFAULT_SIZE: Self = LocPrecision(0x12);
+
+    /// The default horizontal precision of 10,000 metres.
+    pub const DEFAULT_HORIZ_PRE: Self = LocPrecision(0x16);
+
+    /// The default vertical precision of 10 metres.
+    pub const DEFAULT_VERT_PRE: Self = LocPrecision(0x13);
+
+    /// Creates a value from its encoded form.
+    ///
+    /// Returns `None` if the base or exponent is larger than 9.
+    pub fn from_int(value: u8) -> Option<Self> {
+        if value >> 4 > 9 || value & 0x0F > 9 {
+            None
+        } else {
+            Some(LocPrecision(value))
+        }
+    }
+
+    /// Creates a value from a length in centimetres.
+    ///
+    /// Lengths that cannot be represented exactly are rounded down to the
+    /// next smaller value that can. Returns `None` if the length is larger
+    /// than 9 · 10<sup>9</sup> centimetres.
+    pub fn from_centimetres(mut value: u64) -> Option<Self> {
+        let mut exp = 0;
+        while value > 9 {
+            value /= 10;
+            exp += 1;
+        }
+        if exp > 9 {
+            None
+        } else {
+            Some(LocPrecision((value as u8) << 4 | exp))
+        }
+    }
+
+    /// Returns the encoded form of the value.
+    pub fn to_int(self) -> u8 {
+        self.0
+    }
+
+    /// Returns the length in centimetres.
+    pub fn to_centimetres(self) -> u64 {
+        u64::from(self.0 >> 4) * 10u64.pow(u32::from(self.0 & 0x0F))
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: AsRef<[u8]>> Parse<Ref> for LocPrecision {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Self::from_int(u8::parse(parser)?)
+            .ok_or_else(|| ParseError::form_error("invalid LOC precision"))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        Self::parse(parser).map(|_| ())
+    }
+}
+
+impl Compose for LocPrecision {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        self.0.compose(target)
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for LocPrecision {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        scanner.scan_string_word(|word| {
+            parse_metres(&word)
+                .filter(|value| *value >= 0)
+                .and_then(|value| Self::from_centimetres(value as u64))
+                .ok_or_else(|| SyntaxError::content(LocError))
+        })
+    }
+}
+
+impl fmt::Display for LocPrecision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.to_centimetres();
+        if value % 100 == 0 {
+            write!(f, "{}m", value / 100)
+        } else {
+            write!(f, "{}.{:02}m", value / 100, value % 100)
+        }
+    }
+}
+
+//------------ Helper Functions ----------------------------------------------
+
+/// The number of thousandths of a second of arc in a degree.
+const DEGREE: i64 = 3_600_000;
+
+/// Rounds a floating point value to the nearest integer.
+fn round(value: f64) -> i64 {
+    if value < 0. {
+        -((-value + 0.5) as i64)
+    } else {
+        (value + 0.5) as i64
+    }
+}
+
+/// Converts an angle in degrees into its wire format value.
+///
+/// The angle must be between -180 and 180 degrees.
+fn angle_from_degrees(value: f64) -> u32 {
+    (round(value * DEGREE as f64) + i64::from(Loc::EQUATOR)) as u32
+}
+
+/// Converts a wire format angle into degrees.
+fn angle_to_degrees(value: u32) -> f64 {
+    (i64::from(value) - i64::from(Loc::EQUATOR)) as f64 / DEGREE as f64
+}
+
+/// Writes a wire format angle in presentation format.
+fn fmt_angle(
+    value: u32,
+    positive: u8,
+    negative: u8,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    let value = i64::from(value) - i64::from(Loc::EQUATOR);
+    let hemisphere = if value < 0 { negative } else { positive };
+    let value = value.abs();
+    write!(
+        f,
+        "{} {:02} {:02}.{:03} {}",
+        value / DEGREE,
+        value % DEGREE / 60_000,
+        value % 60_000 / 1000,
+        value % 1000,
+        hemisphere as char
+    )
+}
+
+/// Scans an angle in degrees, minutes, and seconds.
+#[cfg(feature = "master")]
+fn scan_angle<C: CharSource>(
+    scanner: &mut Scanner<C>,
+    max_degrees: i64,
+    positive: u8,
+    negative: u8,
+) -> Result<u32, ScanError> {
+    let hemisphere = |word: &str| match word.as_bytes() {
+        [ch] if ch.eq_ignore_ascii_case(&positive) => Some(1),
+        [ch] if ch.eq_ignore_ascii_case(&negative) => Some(-1),
+        _ => None,
+    };
+
+    // Degrees, minutes, and seconds are followed by either the next
+    // component or the hemisphere.
+    let mut value = 0;
+    let mut sign = None;
+    for (unit, max, frac) in [(DEGREE, max_degrees, 0), (60_000, 59, 0)]
+        .iter()
+        .chain([(1, 59_999, 3)].iter())
+    {
+        let word = scanner.scan_string_word(Ok)?;
+        if *unit != DEGREE {
+            if let Some(found) = hemisphere(&word) {
+                sign = Some(found);
+                break;
+            }
+        }
+        match parse_decimal(&word, *frac) {
+            Some(part) if (0..=*max).contains(&part) => value += part * unit,
+            _ => {
+                return Err(ScanError::Syntax(
+                    SyntaxError::content(LocError),
+                    scanner.pos(),
+                ))
+            }
+        }
+    }
+    let sign = match sign {
+        Some(sign) => sign,
+        None => scanner.scan_string_word(|word| {
+            hemisphere(&word).ok_or_else(|| {
+                SyntaxError::Expected(
+                    [positive as char, '/', negative as char]
+                        .iter()
+                        .collect(),
+                )
+            })
+        })?,
+    };
+    if value > max_degrees * DEGREE {
+        return Err(ScanError::Syntax(
+            SyntaxError::content(LocError),
+            scanner.pos(),
+        ));
+    }
+    Ok((sign * value + i64::from(Loc::EQUATOR)) as u32)
+}
+
+/// Parses a length in metres into centimetres.
+///
+/// The length may be followed by the letter `m`.
+#[cfg(feature = "master")]
+fn parse_metres(word: &str) -> Option<i64> {
+    let word = word
+        .strip_suffix(|ch| ch == 'm' || ch == 'M')
+        .unwrap_or(word);
+    match word.strip_prefix('-') {
+        Some(word) => parse_decimal(word, 2).map(|value| -value),
+        None => parse_decimal(word, 2),
+    }
+}
+
+/// Parses a non-negative decimal number into an integer.
+///
+/// The number may have up to `frac` decimal places. The result is the
+/// number multiplied by 10<sup>`frac`</sup>.
+#[cfg(feature = "master")]
+fn parse_decimal(word: &str, frac: u32) -> Option<i64> {
+    let (int, fraction) = match word.find('.') {
+        Some(pos) => (&word[..pos], &word[pos + 1..]),
+        None => (word, ""),
+    };
+    if int.is_empty()
+        || fraction.len() > frac as usize
+        || !int
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|ch| ch.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut res: i64 = 0;
+    for ch in int.bytes().chain(fraction.bytes()) {
+        res = res.checked_mul(10)?.checked_add(i64::from(ch - b'0'))?;
+    }
+    res.checked_mul(10i64.pow(frac - fraction.len() as u32))
+}
+
+//------------ LocError ------------------------------------------------------
+
+/// A LOC value was out of range.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LocError;
+
+impl fmt::Display for LocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("LOC value out of range")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LocError {}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x00\x33\x16\x13\x89\x17\x2d\xd0\
+            \x70\xbe\x15\xf0\x00\x98\x8d\x20";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let loc = Loc::parse(&mut parser).unwrap();
+        assert_eq!(
+            loc.to_string(),
+            "42 21 54.000 N 71 06 18.000 W -24.00m 30m 10000m 10m"
+        );
+
+        let mut buf = Vec::new();
+        loc.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let mut parser = Parser::from_ref(b"\x01\x33\x16\x13".as_ref());
+        assert!(Loc::parse(&mut parser).is_err());
+    }
+
+    #[test]
+    fn degrees() {
+        let loc = Loc::from_degrees(42.365, -71.105, -24.).unwrap();
+        assert_eq!(
+            loc.to_string(),
+            "42 21 54.000 N 71 06 18.000 W -24.00m 1m 10000m 10m"
+        );
+        assert!((loc.latitude() - 42.365).abs() < 1e-9);
+        assert!((loc.longitude() + 71.105).abs() < 1e-9);
+        assert!((loc.altitude() + 24.).abs() < 1e-9);
+        assert!(Loc::from_degrees(91., 0., 0.).is_err());
+        assert!(Loc::from_degrees(0., 0., -100_000.01).is_err());
+    }
+
+    #[test]
+    fn precision() {
+        assert_eq!(LocPrecision::from_centimetres(0).unwrap().to_int(), 0);
+        assert_eq!(
+            LocPrecision::from_centimetres(3000).unwrap().to_int(),
+            0x33
+        );
+        assert_eq!(
+            LocPrecision::from_centimetres(1234)
+                .unwrap()
+                .to_centimetres(),
+            1000
+        );
+        assert!(LocPrecision::from_centimetres(10_000_000_000).is_none());
+        assert!(LocPrecision::from_int(0xA0).is_none());
+        assert_eq!(
+            LocPrecision::from_int(0x51).unwrap().to_string(),
+            "0.50m"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new(
+            "42 21 54 N 71 06 18 W -24m 30m\n\
+             52 22 23.123 n 4 53 e 2.5 1 2.00m 0.01m\n\
+             0 S 0 E 0\n\
+             42 61 N 0 E 0\n",
+        );
+        let loc = Loc::scan(&mut scanner).unwrap();
+        assert_eq!(
+            loc.to_string(),
+            "42 21 54.000 N 71 06 18.000 W -24.00m 30m 10000m 10m"
+        );
+        scanner.scan_newline().unwrap();
+        let loc = Loc::scan(&mut scanner).unwrap();
+        assert_eq!(
+            loc.to_string(),
+            "52 22 23.123 N 4 53 00.000 E 2.50m 1m 2m 0.01m"
+        );
+        scanner.scan_newline().unwrap();
+        let loc = Loc::scan(&mut scanner).unwrap();
+        assert_eq!(loc.raw_latitude(), Loc::EQUATOR);
+        assert_eq!(loc.raw_altitude(), Loc::ZERO_ALTITUDE);
+        scanner.scan_newline().unwrap();
+        assert!(Loc::scan(&mut scanner).is_err());
+    }
+}
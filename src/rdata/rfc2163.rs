@@ -0,0 +1,284 @@
+//! Record data from [RFC 2163]: PX records.
+//!
+//! This RFC defines the Px record type used to map between RFC 822 and
+//! X.400 mail addresses.
+//!
+//! The domain names in the record data are not compressed when composing
+//! as required by [RFC 3597] but lowercased in the canonical form per
+//! [RFC 4034].
+//!
+//! [RFC 2163]: https://tools.ietf.org/html/rfc2163
+//! [RFC 3597]: https://tools.ietf.org/html/rfc3597
+//! [RFC 4034]: https://tools.ietf.org/html/rfc4034
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::{Dname, ParsedDname, PushError, ToDname};
+use crate::base::octets::{
+    Compose, EmptyBuilder, FromBuilder, OctetsBuilder, OctetsFrom, OctetsRef,
+    Parse, ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+use core::cmp::Ordering;
+use core::fmt;
+
+//------------ Px -----------------------------------------------------------
+
+/// Px record data.
+///
+/// The Px record maps the RFC 822 mail domain given by `map822` to the
+/// X.400 mail domain encoded as a domain name in `mapx400` or vice versa.
+///
+/// The Px record type is defined in RFC 2163, section 4.
+#[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Px<N> {
+    preference: u16,
+    map822: N,
+    mapx400: N,
+}
+
+impl<N> Px<N> {
+    /// Creates a new Px record data from the components.
+    pub fn new(preference: u16, map822: N, mapx400: N) -> Self {
+        Px {
+            preference,
+            map822,
+            mapx400,
+        }
+    }
+
+    /// The preference for this record.
+    ///
+    /// Defines an order if there are several Px records for the same owner.
+    /// Lower values are preferred.
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// The RFC 822 part of the mapping.
+    pub fn map822(&self) -> &N {
+        &self.map822
+    }
+
+    /// The X.400 part of the mapping.
+    pub fn mapx400(&self) -> &N {
+        &self.mapx400
+    }
+}
+
+impl<Ref> Px<ParsedDname<Ref>>
+where
+    Ref: OctetsRef,
+{
+    pub fn flatten_into<Octets>(self) -> Result<Px<Dname<Octets>>, PushError>
+    where
+        Octets: OctetsFrom<Ref::Range> + FromBuilder,
+        <Octets as FromBuilder>::Builder: EmptyBuilder,
+    {
+        let Self {
+            preference,
+            map822,
+            mapx400,
+        } = self;
+        Ok(Px::new(
+            preference,
+            map822.flatten_into()?,
+            mapx400.flatten_into()?,
+        ))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Name, SrcName> OctetsFrom<Px<SrcName>> for Px<Name>
+where
+    Name: OctetsFrom<SrcName>,
+{
+    fn octets_from(source: Px<SrcName>) -> Result<Self, ShortBuf> {
+        Ok(Px::new(
+            source.preference,
+            Name::octets_from(source.map822)?,
+            Name::octets_from(source.mapx400)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<N, NN> PartialEq<Px<NN>> for Px<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn eq(&self, other: &Px<NN>) -> bool {
+        self.preference == other.preference
+            && self.map822.name_eq(&other.map822)
+            && self.mapx400.name_eq(&other.mapx400)
+    }
+}
+
+impl<N: ToDname> Eq for Px<N> {}
+
+//--- PartialOrd, Ord, and CanonicalOrd
+
+impl<N, NN> PartialOrd<Px<NN>> for Px<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn partial_cmp(&self, other: &Px<NN>) -> Option<Ordering> {
+        match self.preference.partial_cmp(&other.preference) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        match self.map822.name_cmp(&other.map822) {
+            Ordering::Equal => {}
+            other => return Some(other),
+        }
+        Some(self.mapx400.name_cmp(&other.mapx400))
+    }
+}
+
+impl<N: ToDname> Ord for Px<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.preference.cmp(&other.preference) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.map822.name_cmp(&other.map822) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.mapx400.name_cmp(&other.mapx400)
+    }
+}
+
+impl<N: ToDname, NN: ToDname> CanonicalOrd<Px<NN>> for Px<N> {
+    fn canonical_cmp(&self, other: &Px<NN>) -> Ordering {
+        match self.preference.cmp(&other.preference) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.map822.lowercase_composed_cmp(&other.map822) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.mapx400.lowercase_composed_cmp(&other.mapx400)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Px<ParsedDname<Ref>> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Ok(Self::new(
+            u16::parse(parser)?,
+            ParsedDname::parse(parser)?,
+            ParsedDname::parse(parser)?,
+        ))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        u16::skip(parser)?;
+        ParsedDname::skip(parser)?;
+        ParsedDname::skip(parser)
+    }
+}
+
+impl<N: ToDname> Compose for Px<N> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.preference.compose(target)?;
+            self.map822.compose(target)?;
+            self.mapx400.compose(target)
+        })
+    }
+
+    fn compose_canonical<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.preference.compose(target)?;
+            self.map822.compose_canonical(target)?;
+            self.mapx400.compose_canonical(target)
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl<N: Scan> Scan for Px<N> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(
+            u16::scan(scanner)?,
+            N::scan(scanner)?,
+            N::scan(scanner)?,
+        ))
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Px<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}. {}.", self.preference, self.map822, self.mapx400)
+    }
+}
+
+//--- RtypeRecordData
+
+impl<N> RtypeRecordData for Px<N> {
+    const RTYPE: Rtype = Rtype::Px;
+}
+
+//============ Testing ======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x00\x0a\x03ab1\x02it\x00\
+            \x03ab1\x04ADMD\x04PRMD\x01C\x02it\x00";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let px = Px::parse(&mut parser).unwrap();
+        assert_eq!(px.to_string(), "10 ab1.it. ab1.ADMD.PRMD.C.it.");
+
+        let mut buf = Vec::new();
+        px.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let mut buf = Vec::new();
+        px.compose_canonical(&mut buf).unwrap();
+        assert_eq!(
+            buf.as_slice(),
+            b"\x00\x0a\x03ab1\x02it\x00\
+              \x03ab1\x04admd\x04prmd\x01c\x02it\x00"
+                .as_ref()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        use crate::base::name::Dname;
+        use bytes::Bytes;
+
+        let mut scanner = Scanner::new("10 ab1.it. ab1.ADMD.PRMD.C.it.\n");
+        let px: Px<Dname<Bytes>> = Px::scan(&mut scanner).unwrap();
+        assert_eq!(px.preference(), 10);
+        assert_eq!(px.to_string(), "10 ab1.it. ab1.ADMD.PRMD.C.it.");
+    }
+}
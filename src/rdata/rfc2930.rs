@@ -0,0 +1,520 @@
+//! Record data from [RFC 2930]: TKEY records.
+//!
+//! This RFC defines the TKEY record type used for establishing and deleting
+//! the shared secret keys used by TSIG.
+//!
+//! [RFC 2930]: https://tools.ietf.org/html/rfc2930
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::{Rtype, TkeyMode, TsigRcode};
+use crate::base::name::{Dname, ParsedDname, PushError, ToDname};
+use crate::base::octets::{
+    Compose, EmptyBuilder, FromBuilder, OctetsBuilder, OctetsFrom,
+    OctetsInto, OctetsRef, Parse, ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+use crate::base::serial::Serial;
+use crate::utils::base64;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Tkey ----------------------------------------------------------
+
+/// TKEY record data.
+///
+/// A TKEY record is used in requests and responses to establish or delete
+/// a shared secret key. It names the algorithm the key is to be used with,
+/// the period during which the key is valid, the mode of establishing the
+/// key, and carries key data specific to that mode.
+///
+/// The inception and expiration times are Unix timestamps of 32 bits that
+/// are interpreted using serial number arithmetic, just like the
+/// signature times of an RRSIG record.
+///
+/// The TKEY record type is defined in RFC 2930, section 2.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tkey<Octets, Name> {
+    /// The algorithm of the key as a domain name.
+    algorithm: Name,
+
+    /// The start of the validity period of the key.
+    inception: Serial,
+
+    /// The end of the validity period of the key.
+    expiration: Serial,
+
+    /// The scheme used for establishing the key.
+    mode: TkeyMode,
+
+    /// The error that happened while processing the request.
+    error: TsigRcode,
+
+    /// The mode specific key data.
+    ///
+    /// In wire format, consists of a unsigned 16 bit integer containing the
+    /// length followed by that many octets of actual key data.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "crate::base::octets::SerializeOctets::serialize_octets",
+            deserialize_with = "crate::base::octets::DeserializeOctets::deserialize_octets",
+            bound(
+                serialize = "Octets: crate::base::octets::SerializeOctets",
+                deserialize = "Octets: crate::base::octets::DeserializeOctets<'de>",
+            )
+        )
+    )]
+    key: Octets,
+
+    /// Other data.
+    ///
+    /// This field is not currently used by any mode. In wire format, it is
+    /// encoded as a unsigned 16 bit integer followed by that many octets.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "crate::base::octets::SerializeOctets::serialize_octets",
+            deserialize_with = "crate::base::octets::DeserializeOctets::deserialize_octets",
+            bound(
+                serialize = "Octets: crate::base::octets::SerializeOctets",
+                deserialize = "Octets: crate::base::octets::DeserializeOctets<'de>",
+            )
+        )
+    )]
+    other: Octets,
+}
+
+impl<O, N> Tkey<O, N> {
+    /// Creates a new TKEY record from its components.
+    ///
+    /// See the access methods for an explanation of these components.
+    pub fn new(
+        algorithm: N,
+        inception: Serial,
+        expiration: Serial,
+        mode: TkeyMode,
+        error: TsigRcode,
+        key: O,
+        other: O,
+    ) -> Self {
+        Tkey {
+            algorithm,
+            inception,
+            expiration,
+            mode,
+            error,
+            key,
+            other,
+        }
+    }
+
+    /// Returns a reference to the algorithm name.
+    ///
+    /// This is the name of the TSIG algorithm the key is to be used with.
+    pub fn algorithm(&self) -> &N {
+        &self.algorithm
+    }
+
+    /// Returns the Unix time when the key starts being valid.
+    pub fn inception(&self) -> Serial {
+        self.inception
+    }
+
+    /// Returns the Unix time when the key stops being valid.
+    pub fn expiration(&self) -> Serial {
+        self.expiration
+    }
+
+    /// Returns the scheme used for establishing or deleting the key.
+    pub fn mode(&self) -> TkeyMode {
+        self.mode
+    }
+
+    /// Returns the error.
+    ///
+    /// This is always `TsigRcode::NoError` in requests.
+    pub fn error(&self) -> TsigRcode {
+        self.error
+    }
+
+    /// Returns a reference to the key data.
+    ///
+    /// The meaning of the key data depends on the mode. With the
+    /// Diffie-Hellman mode, for instance, it is a nonce.
+    pub fn key(&self) -> &O {
+        &self.key
+    }
+
+    /// Returns an octet slice containing the key data.
+    pub fn key_slice(&self) -> &[u8]
+    where
+        O: AsRef<[u8]>,
+    {
+        self.key.as_ref()
+    }
+
+    /// Returns a reference to the other data.
+    pub fn other(&self) -> &O {
+        &self.other
+    }
+
+    /// Returns whether the key is valid at the given time.
+    ///
+    /// The method checks whether `now` is between inception and expiration
+    /// using serial number arithmetic.
+    pub fn is_valid_at(&self, now: Serial) -> bool {
+        self.inception <= now && now <= self.expiration
+    }
+}
+
+impl<Ref> Tkey<Ref::Range, ParsedDname<Ref>>
+where
+    Ref: OctetsRef,
+{
+    pub fn flatten_into<Octets>(
+        self,
+    ) -> Result<Tkey<Octets, Dname<Octets>>, PushError>
+    where
+        Octets: OctetsFrom<Ref::Range> + FromBuilder,
+        <Octets as FromBuilder>::Builder: EmptyBuilder,
+    {
+        let Self {
+            algorithm,
+            inception,
+            expiration,
+            mode,
+            error,
+            key,
+            other,
+        } = self;
+
+        Ok(Tkey::new(
+            algorithm.flatten_into()?,
+            inception,
+            expiration,
+            mode,
+            error,
+            key.octets_into()?,
+            other.octets_into()?,
+        ))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets, Name, SrcName> OctetsFrom<Tkey<SrcOctets, SrcName>>
+    for Tkey<Octets, Name>
+where
+    Octets: OctetsFrom<SrcOctets>,
+    Name: OctetsFrom<SrcName>,
+{
+    fn octets_from(
+        source: Tkey<SrcOctets, SrcName>,
+    ) -> Result<Self, ShortBuf> {
+        Ok(Tkey::new(
+            Name::octets_from(source.algorithm)?,
+            source.inception,
+            source.expiration,
+            source.mode,
+            source.error,
+            Octets::octets_from(source.key)?,
+            Octets::octets_from(source.other)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<O, OO, N, NN> PartialEq<Tkey<OO, NN>> for Tkey<O, N>
+where
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+    N: ToDname,
+    NN: ToDname,
+{
+    fn eq(&self, other: &Tkey<OO, NN>) -> bool {
+        self.algorithm.name_eq(&other.algorithm)
+            && self.inception == other.inception
+            && self.expiration == other.expiration
+            && self.mode == other.mode
+            && self.error == other.error
+            && self.key.as_ref().eq(other.key.as_ref())
+            && self.other.as_ref().eq(other.other.as_ref())
+    }
+}
+
+impl<O: AsRef<[u8]>, N: ToDname> Eq for Tkey<O, N> {}
+
+//--- PartialOrd, Ord, and CanonicalOrd
+
+impl<O, OO, N, NN> PartialOrd<Tkey<OO, NN>> for Tkey<O, N>
+where
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+    N: ToDname,
+    NN: ToDname,
+{
+    fn partial_cmp(&self, other: &Tkey<OO, NN>) -> Option<Ordering> {
+        match self.algorithm.name_cmp(&other.algorithm) {
+            Ordering::Equal => {}
+            other => return Some(other),
+        }
+        match self
+            .inception
+            .into_int()
+            .partial_cmp(&other.inception.into_int())
+        {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        match self
+            .expiration
+            .into_int()
+            .partial_cmp(&other.expiration.into_int())
+        {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        match self.mode.partial_cmp(&other.mode) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        match self.error.partial_cmp(&other.error) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        match self.key.as_ref().partial_cmp(other.key.as_ref()) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        self.other.as_ref().partial_cmp(other.other.as_ref())
+    }
+}
+
+impl<O: AsRef<[u8]>, N: ToDname> Ord for Tkey<O, N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.algorithm.name_cmp(&other.algorithm) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.inception.into_int().cmp(&other.inception.into_int()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.expiration.into_int().cmp(&other.expiration.into_int()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.mode.cmp(&other.mode) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.error.cmp(&other.error) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.key.as_ref().cmp(other.key.as_ref()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.other.as_ref().cmp(other.other.as_ref())
+    }
+}
+
+impl<O, OO, N, NN> CanonicalOrd<Tkey<OO, NN>> for Tkey<O, N>
+where
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+    N: ToDname,
+    NN: ToDname,
+{
+    fn canonical_cmp(&self, other: &Tkey<OO, NN>) -> Ordering {
+        match self.algorithm.composed_cmp(&other.algorithm) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.inception.into_int().cmp(&other.inception.into_int()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.expiration.into_int().cmp(&other.expiration.into_int()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.mode.cmp(&other.mode) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.error.cmp(&other.error) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.key.as_ref().len().cmp(&other.key.as_ref().len()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.key.as_ref().cmp(other.key.as_ref()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.other.as_ref().len().cmp(&other.other.as_ref().len()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.other.as_ref().cmp(other.other.as_ref())
+    }
+}
+
+//--- Hash
+
+impl<O: AsRef<[u8]>, N: hash::Hash> hash::Hash for Tkey<O, N> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.algorithm.hash(state);
+        self.inception.hash(state);
+        self.expiration.hash(state);
+        self.mode.hash(state);
+        self.error.hash(state);
+        self.key.as_ref().hash(state);
+        self.other.as_ref().hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Tkey<Ref::Range, ParsedDname<Ref>> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let algorithm = ParsedDname::parse(parser)?;
+        let inception = Serial::parse(parser)?;
+        let expiration = Serial::parse(parser)?;
+        let mode = TkeyMode::parse(parser)?;
+        let error = TsigRcode::parse(parser)?;
+        let key_size = u16::parse(parser)?;
+        let key = parser.parse_octets(key_size as usize)?;
+        let other_len = u16::parse(parser)?;
+        let other = parser.parse_octets(other_len as usize)?;
+        Ok(Tkey {
+            algorithm,
+            inception,
+            expiration,
+            mode,
+            error,
+            key,
+            other,
+        })
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        ParsedDname::skip(parser)?;
+        Serial::skip(parser)?;
+        Serial::skip(parser)?;
+        TkeyMode::skip(parser)?;
+        TsigRcode::skip(parser)?;
+        let key_size = u16::parse(parser)?;
+        parser.advance(key_size as usize)?;
+        let other_len = u16::parse(parser)?;
+        parser.advance(other_len as usize)?;
+        Ok(())
+    }
+}
+
+impl<O: AsRef<[u8]>, N: Compose> Compose for Tkey<O, N> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|buf| {
+            self.algorithm.compose(buf)?;
+            self.inception.compose(buf)?;
+            self.expiration.compose(buf)?;
+            self.mode.compose(buf)?;
+            self.error.compose(buf)?;
+            (self.key.as_ref().len() as u16).compose(buf)?;
+            buf.append_slice(self.key.as_ref())?;
+            (self.other.as_ref().len() as u16).compose(buf)?;
+            buf.append_slice(self.other.as_ref())
+        })
+    }
+}
+
+//--- Display and Debug
+
+impl<O: AsRef<[u8]>, N: fmt::Display> fmt::Display for Tkey<O, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}. {} {} {} {} \"",
+            self.algorithm,
+            self.inception,
+            self.expiration,
+            self.mode,
+            self.error
+        )?;
+        base64::display(&self.key, f)?;
+        write!(f, "\" \"")?;
+        base64::display(&self.other, f)?;
+        write!(f, "\"")
+    }
+}
+
+impl<O: AsRef<[u8]>, N: fmt::Debug> fmt::Debug for Tkey<O, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Tkey")
+            .field("algorithm", &self.algorithm)
+            .field("inception", &self.inception)
+            .field("expiration", &self.expiration)
+            .field("mode", &self.mode)
+            .field("error", &self.error)
+            .field("key", &self.key.as_ref())
+            .field("other", &self.other.as_ref())
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<O, N> RtypeRecordData for Tkey<O, N> {
+    const RTYPE: Rtype = Rtype::Tkey;
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x0bhmac-sha256\x00\
+                      \x5f\x5e\x10\x00\x5f\x5f\x61\x80\
+                      \x00\x02\x00\x00\
+                      \x00\x04\x01\x02\x03\x04\
+                      \x00\x00";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let tkey = Tkey::parse(&mut parser).unwrap();
+        assert_eq!(parser.remaining(), 0);
+        assert_eq!(tkey.inception(), Serial(0x5f5e_1000));
+        assert_eq!(tkey.expiration(), Serial(0x5f5f_6180));
+        assert_eq!(tkey.mode(), TkeyMode::DiffieHellman);
+        assert_eq!(tkey.error(), TsigRcode::NoError);
+        assert_eq!(tkey.key_slice(), b"\x01\x02\x03\x04");
+        assert_eq!(
+            tkey.to_string(),
+            "hmac-sha256. 1600000000 1600086400 2 NOERROR \"AQIDBA==\" \"\""
+        );
+        assert!(tkey.is_valid_at(Serial(1600000000)));
+        assert!(tkey.is_valid_at(Serial(1600086400)));
+        assert!(!tkey.is_valid_at(Serial(1600086401)));
+
+        let mut buf = Vec::new();
+        tkey.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        Tkey::<&[u8], ParsedDname<&[u8]>>::skip(&mut parser).unwrap();
+        assert_eq!(parser.remaining(), 0);
+    }
+}
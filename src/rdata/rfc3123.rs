@@ -0,0 +1,637 @@
+//! Record data from [RFC 3123]: APL records.
+//!
+//! This RFC defines the Apl record type that stores lists of address
+//! prefixes.
+//!
+//! [RFC 3123]: https://tools.ietf.org/html/rfc3123
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::PushError;
+use crate::base::net::IpAddr;
+#[cfg(feature = "master")]
+use crate::base::net::{Ipv4Addr, Ipv6Addr};
+use crate::base::octets::{
+    Compose, EmptyBuilder, FormError, OctetsBuilder, OctetsFrom, OctetsInto,
+    OctetsRef, Parse, ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{
+    CharSource, Scan, ScanError, Scanner, SyntaxError,
+};
+#[cfg(feature = "master")]
+use bytes::{Bytes, BytesMut};
+use core::cmp::Ordering;
+#[cfg(feature = "master")]
+use core::str::FromStr;
+use core::{fmt, hash};
+
+//------------ Apl -----------------------------------------------------------
+
+/// Apl record data.
+///
+/// The record data is a list of address prefixes. Each item in the list
+/// consists of an address family, a prefix length, a negation flag, and
+/// the significant octets of the address with trailing zero octets
+/// removed. Only the address families 1 for IPv4 and 2 for IPv6 have a
+/// defined presentation format.
+///
+/// In presentation format, each item is given as the address family, a
+/// colon, the address, a slash, and the prefix length, with an exclamation
+/// mark in front if the negation flag is set, e.g., `!1:192.0.2.0/24`.
+///
+/// The Apl record type is defined in RFC 3123, section 4.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>
+        ",
+        deserialize = "
+            Octets:
+                crate::base::octets::FromBuilder
+                + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as crate::base::octets::FromBuilder>::Builder:
+                OctetsBuilder<Octets = Octets>
+                + crate::base::octets::EmptyBuilder,
+        ",
+    ))
+)]
+pub struct Apl<Octets> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base16::serde")
+    )]
+    items: Octets,
+}
+
+impl<Octets: AsRef<[u8]>> Apl<Octets> {
+    /// Creates new Apl record data from its encoded content.
+    ///
+    /// Returns an error if the octets aren’t a valid sequence of items.
+    pub fn from_octets(items: Octets) -> Result<Self, AplError> {
+        let mut tmp = items.as_ref();
+        while !tmp.is_empty() {
+            tmp = AplItem::split_from(tmp)?.1;
+        }
+        Ok(Apl { items })
+    }
+
+    /// Returns an iterator over the items of the list.
+    pub fn iter(&self) -> AplIter<'_> {
+        AplIter(self.items.as_ref())
+    }
+
+    /// The encoded content of the record data.
+    pub fn items(&self) -> &Octets {
+        &self.items
+    }
+
+    pub fn into_items(self) -> Octets {
+        self.items
+    }
+}
+
+impl<SrcOctets> Apl<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Apl<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        Ok(Apl {
+            items: self.items.octets_into()?,
+        })
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Apl<SrcOctets>> for Apl<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Apl<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(Apl {
+            items: Octets::octets_from(source.items)?,
+        })
+    }
+}
+
+//--- IntoIterator
+
+impl<'a, Octets: AsRef<[u8]>> IntoIterator for &'a Apl<Octets> {
+    type Item = AplItem<'a>;
+    type IntoIter = AplIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Apl<Other>> for Apl<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Apl<Other>) -> bool {
+        self.items.as_ref().eq(other.items.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Apl<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Apl<Other>> for Apl<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Apl<Other>) -> Option<Ordering> {
+        self.items.as_ref().partial_cmp(other.items.as_ref())
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Apl<Other>> for Apl<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Apl<Other>) -> Ordering {
+        self.items.as_ref().cmp(other.items.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Apl<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.items.as_ref().cmp(other.items.as_ref())
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Apl<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.items.as_ref().hash(state)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Apl<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let len = parser.remaining();
+        let items = parser.parse_octets(len)?;
+        Apl::from_octets(items).map_err(Into::into)
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Apl<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_slice(self.items.as_ref())
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Apl<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        let mut builder = AplBuilder::<BytesMut>::new();
+        while !scanner.entry_ended()? {
+            scanner.scan_string_word(|word| {
+                let (negation, addr, prefix) = scan_item(&word)?;
+                builder
+                    .push(negation, addr, prefix)
+                    .map_err(SyntaxError::content)
+            })?;
+        }
+        Ok(builder.finish().freeze())
+    }
+}
+
+/// Converts a single item in presentation format.
+#[cfg(feature = "master")]
+fn scan_item(word: &str) -> Result<(bool, IpAddr, u8), SyntaxError> {
+    let (negation, word) = match word.strip_prefix('!') {
+        Some(word) => (true, word),
+        None => (false, word),
+    };
+    let colon = word
+        .find(':')
+        .ok_or_else(|| SyntaxError::Expected(":".into()))?;
+    let slash = word
+        .rfind('/')
+        .ok_or_else(|| SyntaxError::Expected("/".into()))?;
+    if slash < colon {
+        return Err(SyntaxError::Expected("/".into()));
+    }
+    let addr = match &word[..colon] {
+        "1" => IpAddr::V4(Ipv4Addr::from_str(&word[colon + 1..slash])?),
+        "2" => IpAddr::V6(Ipv6Addr::from_str(&word[colon + 1..slash])?),
+        _ => return Err(SyntaxError::Expected("1 or 2".into())),
+    };
+    let prefix = u8::from_str(&word[slash + 1..])
+        .map_err(|_| SyntaxError::IllegalInteger)?;
+    Ok((negation, addr, prefix))
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Apl<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Items of other address families don’t have a presentation
+        // format, so we fall back to the generic format of RFC 3597.
+        if self.iter().any(|item| item.addr().is_none()) {
+            let items = self.items.as_ref();
+            write!(f, "\\# {}", items.len())?;
+            for ch in items {
+                write!(f, " {:02x}", *ch)?
+            }
+            return Ok(());
+        }
+        let mut first = true;
+        for item in self.iter() {
+            if first {
+                first = false;
+            } else {
+                f.write_str(" ")?;
+            }
+            fmt::Display::fmt(&item, f)?;
+        }
+        Ok(())
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Apl<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Apl(")?;
+        fmt::Display::fmt(self, f)?;
+        f.write_str(")")
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Apl<Octets> {
+    const RTYPE: Rtype = Rtype::Apl;
+}
+
+//------------ AplItem -------------------------------------------------------
+
+/// A single item of an Apl record.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AplItem<'a> {
+    family: u16,
+    prefix: u8,
+    negation: bool,
+    afdpart: &'a [u8],
+}
+
+impl<'a> AplItem<'a> {
+    /// Splits an item from the beginning of a slice.
+    ///
+    /// Returns the item and the remaining slice.
+    fn split_from(slice: &'a [u8]) -> Result<(Self, &'a [u8]), AplError> {
+        if slice.len() < 4 {
+            return Err(AplError::ShortInput);
+        }
+        let family = u16::from_be_bytes([slice[0], slice[1]]);
+        let prefix = slice[2];
+        let negation = slice[3] & 0x80 != 0;
+        let len = usize::from(slice[3] & 0x7F);
+        let slice = &slice[4..];
+        if slice.len() < len {
+            return Err(AplError::ShortInput);
+        }
+        let (afdpart, slice) = slice.split_at(len);
+        let max_prefix = match family {
+            1 => Some(32),
+            2 => Some(128),
+            _ => None,
+        };
+        if let Some(max_prefix) = max_prefix {
+            if prefix > max_prefix || len * 8 > usize::from(max_prefix) {
+                return Err(AplError::BadItem);
+            }
+        }
+        Ok((
+            AplItem {
+                family,
+                prefix,
+                negation,
+                afdpart,
+            },
+            slice,
+        ))
+    }
+
+    /// The address family of the item.
+    ///
+    /// The value is taken from the IANA Address Family Numbers registry.
+    pub fn family(&self) -> u16 {
+        self.family
+    }
+
+    /// The prefix length in bits.
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// Whether the item excludes the prefix rather than including it.
+    pub fn negation(&self) -> bool {
+        self.negation
+    }
+
+    /// The address octets with trailing zero octets removed.
+    pub fn afdpart(&self) -> &'a [u8] {
+        self.afdpart
+    }
+
+    /// Returns the address if the item is of family 1 or 2.
+    pub fn addr(&self) -> Option<IpAddr> {
+        match self.family {
+            1 => {
+                let mut addr = [0; 4];
+                addr[..self.afdpart.len()].copy_from_slice(self.afdpart);
+                Some(addr.into())
+            }
+            2 => {
+                let mut addr = [0; 16];
+                addr[..self.afdpart.len()].copy_from_slice(self.afdpart);
+                Some(addr.into())
+            }
+            _ => None,
+        }
+    }
+}
+
+//--- Display
+
+impl<'a> fmt::Display for AplItem<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.negation {
+            f.write_str("!")?;
+        }
+        match self.addr() {
+            Some(IpAddr::V4(addr)) => {
+                write!(f, "{}:{}/{}", self.family, addr, self.prefix)
+            }
+            Some(IpAddr::V6(addr)) => {
+                write!(f, "{}:{}/{}", self.family, addr, self.prefix)
+            }
+            None => {
+                write!(f, "{}:", self.family)?;
+                for ch in self.afdpart {
+                    write!(f, "{:02x}", *ch)?
+                }
+                write!(f, "/{}", self.prefix)
+            }
+        }
+    }
+}
+
+//------------ AplIter -------------------------------------------------------
+
+/// An iterator over the items of an Apl record.
+#[derive(Clone, Debug)]
+pub struct AplIter<'a>(&'a [u8]);
+
+impl<'a> Iterator for AplIter<'a> {
+    type Item = AplItem<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The content was checked when creating the record data, so
+        // splitting can only fail if we are at the end.
+        let (item, tail) = AplItem::split_from(self.0).ok()?;
+        self.0 = tail;
+        Some(item)
+    }
+}
+
+//------------ AplBuilder ----------------------------------------------------
+
+/// A builder for Apl record data.
+#[derive(Clone, Debug)]
+pub struct AplBuilder<Builder> {
+    builder: Builder,
+}
+
+impl<Builder: OctetsBuilder + EmptyBuilder> AplBuilder<Builder> {
+    pub fn new() -> Self {
+        AplBuilder {
+            builder: Builder::empty(),
+        }
+    }
+}
+
+impl<Builder: OctetsBuilder + AsMut<[u8]>> AplBuilder<Builder> {
+    /// Appends an item for an IPv4 or IPv6 prefix.
+    ///
+    /// The address octets beyond the prefix length are ignored and
+    /// trailing zero octets are removed as required by the RFC.
+    pub fn push(
+        &mut self,
+        negation: bool,
+        addr: IpAddr,
+        prefix: u8,
+    ) -> Result<(), AplError> {
+        let (family, octets, max_prefix) = match addr {
+            IpAddr::V4(addr) => {
+                let mut octets = [0; 16];
+                octets[..4].copy_from_slice(&addr.octets());
+                (1u16, octets, 32u8)
+            }
+            IpAddr::V6(addr) => (2u16, addr.octets(), 128u8),
+        };
+        if prefix > max_prefix {
+            return Err(AplError::BadItem);
+        }
+        let mut len = (usize::from(prefix) + 7) / 8;
+        let mut octets = octets;
+        if prefix % 8 != 0 {
+            octets[len - 1] &= 0xFFu8 << (8 - prefix % 8);
+        }
+        while len > 0 && octets[len - 1] == 0 {
+            len -= 1;
+        }
+        self.builder
+            .append_all(|target| {
+                family.compose(target)?;
+                prefix.compose(target)?;
+                let flags = if negation { 0x80 } else { 0 } | len as u8;
+                flags.compose(target)?;
+                target.append_slice(&octets[..len])
+            })
+            .map_err(|_| AplError::ShortBuf)
+    }
+
+    /// Returns the record data.
+    pub fn finish(self) -> Apl<Builder> {
+        Apl {
+            items: self.builder,
+        }
+    }
+}
+
+impl<Builder: OctetsBuilder + EmptyBuilder> Default for AplBuilder<Builder> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "master")]
+impl Apl<BytesMut> {
+    fn freeze(self) -> Apl<Bytes> {
+        Apl {
+            items: self.items.freeze(),
+        }
+    }
+}
+
+//------------ AplError ------------------------------------------------------
+
+/// Apl record data was invalid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AplError {
+    /// The data ended in the middle of an item.
+    ShortInput,
+
+    /// An item had a prefix or address too long for its address family.
+    BadItem,
+
+    /// The builder ran out of space.
+    ShortBuf,
+}
+
+//--- From
+
+impl From<AplError> for ParseError {
+    fn from(err: AplError) -> ParseError {
+        match err {
+            AplError::ShortInput => ParseError::ShortInput,
+            AplError::BadItem => {
+                ParseError::Form(FormError::new("invalid APL item"))
+            }
+            AplError::ShortBuf => {
+                ParseError::Form(FormError::new("short buffer"))
+            }
+        }
+    }
+}
+
+//--- Display and Error
+
+impl fmt::Display for AplError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AplError::ShortInput => ParseError::ShortInput.fmt(f),
+            AplError::BadItem => f.write_str("invalid APL item"),
+            AplError::ShortBuf => ShortBuf.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AplError {}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use crate::base::net::{Ipv4Addr, Ipv6Addr};
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x00\x01\x15\x03\xc0\xa8\x20\
+                      \x00\x01\x1c\x84\xc0\xa8\x26\x10";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let apl = Apl::parse(&mut parser).unwrap();
+        assert_eq!(apl.to_string(), "1:192.168.32.0/21 !1:192.168.38.16/28");
+        let items: Vec<_> = apl.iter().collect();
+        assert_eq!(items.len(), 2);
+        assert!(!items[0].negation());
+        assert!(items[1].negation());
+        assert_eq!(items[1].prefix(), 28);
+
+        let mut buf = Vec::new();
+        apl.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let apl = Apl::from_octets(b"\x00\x03\x08\x01\xff".as_ref()).unwrap();
+        assert_eq!(apl.to_string(), "\\# 5 00 03 08 01 ff");
+
+        assert!(Apl::from_octets(b"\x00\x01\x15\x03\xc0".as_ref()).is_err());
+        assert!(Apl::from_octets(b"\x00\x01\x21\x00".as_ref()).is_err());
+        assert!(Apl::from_octets(b"\x00\x01".as_ref()).is_err());
+    }
+
+    #[test]
+    fn builder() {
+        let mut builder = AplBuilder::<Vec<u8>>::new();
+        builder
+            .push(false, Ipv4Addr::new(192, 168, 32, 255).into(), 21)
+            .unwrap();
+        builder
+            .push(
+                true,
+                Ipv6Addr::from([0xff00, 0, 0, 0, 0, 0, 0, 0]).into(),
+                8,
+            )
+            .unwrap();
+        builder
+            .push(false, Ipv4Addr::new(0, 0, 0, 0).into(), 0)
+            .unwrap();
+        assert!(builder
+            .push(false, Ipv4Addr::new(0, 0, 0, 0).into(), 33)
+            .is_err());
+        let apl = builder.finish();
+        assert_eq!(
+            apl.items().as_slice(),
+            b"\x00\x01\x15\x03\xc0\xa8\x20\
+              \x00\x02\x08\x81\xff\
+              \x00\x01\x00\x00"
+                .as_ref()
+        );
+        assert_eq!(
+            apl.to_string(),
+            "1:192.168.32.0/21 !2:ff00::/8 1:0.0.0.0/0"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner =
+            Scanner::new("1:192.168.32.0/21 !1:192.168.38.0/28 2:ff00::/8\n");
+        let apl = Apl::scan(&mut scanner).unwrap();
+        assert_eq!(
+            apl.to_string(),
+            "1:192.168.32.0/21 !1:192.168.38.0/28 2:ff00::/8"
+        );
+        let mut scanner = Scanner::new("\n");
+        assert!(Apl::scan(&mut scanner).unwrap().iter().next().is_none());
+        assert!(Apl::scan(&mut Scanner::new("3:192.0.2.0/24\n")).is_err());
+        assert!(Apl::scan(&mut Scanner::new("1:192.0.2.0/33\n")).is_err());
+        assert!(Apl::scan(&mut Scanner::new("1:192.0.2.0\n")).is_err());
+        assert!(Apl::scan(&mut Scanner::new("1/192.0.2.0:24\n")).is_err());
+    }
+}
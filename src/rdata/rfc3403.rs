@@ -0,0 +1,459 @@
+//! Record data from [RFC 3403]: NAPTR records.
+//!
+//! This RFC defines the NAPTR record type used as the DNS database of the
+//! Dynamic Delegation Discovery System (DDDS).
+//!
+//! [RFC 3403]: https://tools.ietf.org/html/rfc3403
+
+use crate::base::charstr::CharStr;
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::{Dname, ParsedDname, PushError, ToDname};
+use crate::base::octets::{
+    Compose, EmptyBuilder, FromBuilder, OctetsBuilder, OctetsFrom,
+    OctetsInto, OctetsRef, Parse, ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Naptr ---------------------------------------------------------
+
+/// NAPTR record data.
+///
+/// A NAPTR record contains a rule for rewriting an application’s string
+/// into a new domain name or a URI. The order and preference fields
+/// determine the sequence in which the rules of a domain are applied. The
+/// flags control the rewriting process, the services field describes the
+/// protocols available via the result, and the rule itself is given either
+/// by the substitution expression in the regexp field or by the replacement
+/// domain name.
+///
+/// The replacement name is never compressed.
+///
+/// The NAPTR record type is defined in RFC 3403, section 4.1.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>,
+            Name: serde::Serialize,
+        ",
+        deserialize = "
+            Octets: FromBuilder + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as FromBuilder>::Builder: EmptyBuilder,
+            Name: serde::Deserialize<'de>,
+        ",
+    ))
+)]
+pub struct Naptr<Octets, Name> {
+    order: u16,
+    preference: u16,
+    flags: CharStr<Octets>,
+    services: CharStr<Octets>,
+    regexp: CharStr<Octets>,
+    replacement: Name,
+}
+
+impl<Octets, Name> Naptr<Octets, Name> {
+    pub fn new(
+        order: u16,
+        preference: u16,
+        flags: CharStr<Octets>,
+        services: CharStr<Octets>,
+        regexp: CharStr<Octets>,
+        replacement: Name,
+    ) -> Self {
+        Naptr {
+            order,
+            preference,
+            flags,
+            services,
+            regexp,
+            replacement,
+        }
+    }
+
+    /// Returns the order in which the record must be processed.
+    pub fn order(&self) -> u16 {
+        self.order
+    }
+
+    /// Returns the preference among records with the same order.
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// Returns the flags controlling the rewriting process.
+    pub fn flags(&self) -> &CharStr<Octets> {
+        &self.flags
+    }
+
+    /// Returns the services available via the rewritten result.
+    pub fn services(&self) -> &CharStr<Octets> {
+        &self.services
+    }
+
+    /// Returns the substitution expression.
+    pub fn regexp(&self) -> &CharStr<Octets> {
+        &self.regexp
+    }
+
+    /// Returns the replacement domain name.
+    pub fn replacement(&self) -> &Name {
+        &self.replacement
+    }
+}
+
+impl<SrcOctets, Ref> Naptr<SrcOctets, ParsedDname<Ref>>
+where
+    SrcOctets: AsRef<[u8]>,
+    Ref: OctetsRef,
+{
+    pub fn flatten_into<Octets>(
+        self,
+    ) -> Result<Naptr<Octets, Dname<Octets>>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets> + FromBuilder,
+        <Octets as FromBuilder>::Builder: EmptyBuilder,
+    {
+        let Self {
+            order,
+            preference,
+            flags,
+            services,
+            regexp,
+            replacement,
+        } = self;
+        Ok(Naptr::new(
+            order,
+            preference,
+            flags.octets_into()?,
+            services.octets_into()?,
+            regexp.octets_into()?,
+            replacement.to_dname()?,
+        ))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets, Name, SrcName> OctetsFrom<Naptr<SrcOctets, SrcName>>
+    for Naptr<Octets, Name>
+where
+    Octets: OctetsFrom<SrcOctets>,
+    Name: OctetsFrom<SrcName>,
+{
+    fn octets_from(
+        source: Naptr<SrcOctets, SrcName>,
+    ) -> Result<Self, ShortBuf> {
+        Ok(Naptr::new(
+            source.order,
+            source.preference,
+            CharStr::octets_from(source.flags)?,
+            CharStr::octets_from(source.services)?,
+            CharStr::octets_from(source.regexp)?,
+            Name::octets_from(source.replacement)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<N, NN, O, OO> PartialEq<Naptr<OO, NN>> for Naptr<O, N>
+where
+    N: ToDname,
+    NN: ToDname,
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Naptr<OO, NN>) -> bool {
+        self.order == other.order
+            && self.preference == other.preference
+            && self.flags.eq(&other.flags)
+            && self.services.eq(&other.services)
+            && self.regexp.eq(&other.regexp)
+            && self.replacement.name_eq(&other.replacement)
+    }
+}
+
+impl<Octets, Name> Eq for Naptr<Octets, Name>
+where
+    Octets: AsRef<[u8]>,
+    Name: ToDname,
+{
+}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<N, NN, O, OO> PartialOrd<Naptr<OO, NN>> for Naptr<O, N>
+where
+    N: ToDname,
+    NN: ToDname,
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Naptr<OO, NN>) -> Option<Ordering> {
+        match self.order.partial_cmp(&other.order) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        match self.preference.partial_cmp(&other.preference) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        match self.flags.partial_cmp(&other.flags) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        match self.services.partial_cmp(&other.services) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        match self.regexp.partial_cmp(&other.regexp) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        Some(self.replacement.name_cmp(&other.replacement))
+    }
+}
+
+impl<N, NN, O, OO> CanonicalOrd<Naptr<OO, NN>> for Naptr<O, N>
+where
+    N: ToDname,
+    NN: ToDname,
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Naptr<OO, NN>) -> Ordering {
+        match self.order.cmp(&other.order) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.preference.cmp(&other.preference) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.flags.canonical_cmp(&other.flags) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.services.canonical_cmp(&other.services) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.regexp.canonical_cmp(&other.regexp) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.replacement.lowercase_composed_cmp(&other.replacement)
+    }
+}
+
+impl<O: AsRef<[u8]>, N: ToDname> Ord for Naptr<O, N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.order.cmp(&other.order) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.preference.cmp(&other.preference) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.flags.cmp(&other.flags) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.services.cmp(&other.services) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.regexp.cmp(&other.regexp) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.replacement.name_cmp(&other.replacement)
+    }
+}
+
+//--- Hash
+
+impl<O: AsRef<[u8]>, N: hash::Hash> hash::Hash for Naptr<O, N> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.order.hash(state);
+        self.preference.hash(state);
+        self.flags.hash(state);
+        self.services.hash(state);
+        self.regexp.hash(state);
+        self.replacement.hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Naptr<Ref::Range, ParsedDname<Ref>> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Ok(Self::new(
+            u16::parse(parser)?,
+            u16::parse(parser)?,
+            CharStr::parse(parser)?,
+            CharStr::parse(parser)?,
+            CharStr::parse(parser)?,
+            ParsedDname::parse(parser)?,
+        ))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        u16::skip(parser)?;
+        u16::skip(parser)?;
+        CharStr::skip(parser)?;
+        CharStr::skip(parser)?;
+        CharStr::skip(parser)?;
+        ParsedDname::skip(parser)
+    }
+}
+
+impl<Octets: AsRef<[u8]>, Name: Compose> Compose for Naptr<Octets, Name> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|buf| {
+            self.order.compose(buf)?;
+            self.preference.compose(buf)?;
+            self.flags.compose(buf)?;
+            self.services.compose(buf)?;
+            self.regexp.compose(buf)?;
+            self.replacement.compose(buf)
+        })
+    }
+
+    fn compose_canonical<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|buf| {
+            self.order.compose(buf)?;
+            self.preference.compose(buf)?;
+            self.flags.compose(buf)?;
+            self.services.compose(buf)?;
+            self.regexp.compose(buf)?;
+            self.replacement.compose_canonical(buf)
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl<N: Scan> Scan for Naptr<Bytes, N> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(
+            u16::scan(scanner)?,
+            u16::scan(scanner)?,
+            CharStr::scan(scanner)?,
+            CharStr::scan(scanner)?,
+            CharStr::scan(scanner)?,
+            N::scan(scanner)?,
+        ))
+    }
+}
+
+impl<Octets, Name> fmt::Display for Naptr<Octets, Name>
+where
+    Octets: AsRef<[u8]>,
+    Name: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} \"{}\" \"{}\" \"{}\" {}.",
+            self.order,
+            self.preference,
+            self.flags,
+            self.services,
+            self.regexp,
+            self.replacement
+        )
+    }
+}
+
+//--- Debug
+
+impl<Octets, Name> fmt::Debug for Naptr<Octets, Name>
+where
+    Octets: AsRef<[u8]>,
+    Name: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Naptr")
+            .field("order", &self.order)
+            .field("preference", &self.preference)
+            .field("flags", &self.flags)
+            .field("services", &self.services)
+            .field("regexp", &self.regexp)
+            .field("replacement", &self.replacement)
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets, Name> RtypeRecordData for Naptr<Octets, Name> {
+    const RTYPE: Rtype = Rtype::Naptr;
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x00\x64\x00\x0a\x01u\x07E2U+sip\
+            \x1b!^.*$!sip:info@example.com!\x00";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let naptr = Naptr::parse(&mut parser).unwrap();
+        assert_eq!(naptr.order(), 100);
+        assert_eq!(naptr.preference(), 10);
+        assert_eq!(naptr.flags().as_slice(), b"u");
+        assert_eq!(naptr.services().as_slice(), b"E2U+sip");
+        assert!(naptr.replacement().is_root());
+        assert_eq!(
+            naptr.to_string(),
+            "100 10 \"u\" \"E2U+sip\" \"!^.*$!sip:info@example.com!\" ."
+        );
+
+        let mut buf = Vec::new();
+        naptr.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new(
+            "100 50 \"s\" \"SIP+D2U\" \"\" _sip._udp.example.com.\n",
+        );
+        let naptr: Naptr<Bytes, Dname<Bytes>> =
+            Naptr::scan(&mut scanner).unwrap();
+        assert_eq!(naptr.flags().as_slice(), b"s");
+        assert!(naptr.regexp().is_empty());
+        assert_eq!(
+            naptr.to_string(),
+            "100 50 \"s\" \"SIP+D2U\" \"\" _sip._udp.example.com."
+        );
+    }
+}
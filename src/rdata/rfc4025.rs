@@ -0,0 +1,612 @@
+//! Record data from [RFC 4025]: IPSECKEY records.
+//!
+//! This RFC defines the IPSECKEY record type for storing keying material
+//! for IPsec.
+//!
+//! [RFC 4025]: https://tools.ietf.org/html/rfc4025
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::{IpseckeyAlg, Rtype};
+use crate::base::name::{Dname, ParsedDname, PushError, ToDname};
+use crate::base::net::{Ipv4Addr, Ipv6Addr};
+use crate::base::octets::{
+    Compose, EmptyBuilder, FromBuilder, OctetsBuilder, OctetsFrom, OctetsRef,
+    Parse, ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{
+    CharSource, Scan, ScanError, Scanner, SyntaxError,
+};
+use crate::utils::base64;
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+#[cfg(feature = "master")]
+use core::str::FromStr;
+use core::{fmt, hash};
+
+//------------ Ipseckey ------------------------------------------------------
+
+/// IPSECKEY record data.
+///
+/// An IPSECKEY record provides a public key and a gateway for establishing
+/// an IPsec connection with the host the record is found for. The record
+/// data consists of a precedence for selecting between several records,
+/// the algorithm of the public key, the gateway, and the public key
+/// itself.
+///
+/// The gateway can be absent, an IPv4 or IPv6 address, or a domain name.
+/// Its kind is encoded in the gateway type field that is not kept
+/// separately but derived from the [`IpseckeyGateway`] value. The public
+/// key can be empty.
+///
+/// In presentation format, an absent gateway is given as a single dot and
+/// the public key is given in Base 64. If the key is empty, it is left out.
+///
+/// The IPSECKEY record type is defined in RFC 4025, section 2.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>,
+            Name: serde::Serialize,
+        ",
+        deserialize = "
+            Octets: FromBuilder + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as FromBuilder>::Builder:
+                OctetsBuilder<Octets = Octets> + EmptyBuilder,
+            Name: serde::Deserialize<'de>,
+        ",
+    ))
+)]
+pub struct Ipseckey<Octets, Name> {
+    precedence: u8,
+    algorithm: IpseckeyAlg,
+    gateway: IpseckeyGateway<Name>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base64::serde")
+    )]
+    public_key: Octets,
+}
+
+impl<Octets, Name> Ipseckey<Octets, Name> {
+    pub fn new(
+        precedence: u8,
+        algorithm: IpseckeyAlg,
+        gateway: IpseckeyGateway<Name>,
+        public_key: Octets,
+    ) -> Self {
+        Ipseckey {
+            precedence,
+            algorithm,
+            gateway,
+            public_key,
+        }
+    }
+
+    pub fn precedence(&self) -> u8 {
+        self.precedence
+    }
+
+    pub fn algorithm(&self) -> IpseckeyAlg {
+        self.algorithm
+    }
+
+    pub fn gateway(&self) -> &IpseckeyGateway<Name> {
+        &self.gateway
+    }
+
+    pub fn public_key(&self) -> &Octets {
+        &self.public_key
+    }
+
+    pub fn into_public_key(self) -> Octets {
+        self.public_key
+    }
+}
+
+impl<SrcOctets, Ref> Ipseckey<SrcOctets, ParsedDname<Ref>>
+where
+    SrcOctets: AsRef<[u8]>,
+    Ref: OctetsRef,
+{
+    pub fn flatten_into<Octets>(
+        self,
+    ) -> Result<Ipseckey<Octets, Dname<Octets>>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets> + FromBuilder,
+        <Octets as FromBuilder>::Builder: EmptyBuilder,
+    {
+        let Self {
+            precedence,
+            algorithm,
+            gateway,
+            public_key,
+        } = self;
+        let gateway = match gateway {
+            IpseckeyGateway::None => IpseckeyGateway::None,
+            IpseckeyGateway::Ipv4(addr) => IpseckeyGateway::Ipv4(addr),
+            IpseckeyGateway::Ipv6(addr) => IpseckeyGateway::Ipv6(addr),
+            IpseckeyGateway::Name(name) => {
+                IpseckeyGateway::Name(name.to_dname()?)
+            }
+        };
+        Ok(Ipseckey::new(
+            precedence,
+            algorithm,
+            gateway,
+            Octets::octets_from(public_key)?,
+        ))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets, Name, SrcName>
+    OctetsFrom<Ipseckey<SrcOctets, SrcName>> for Ipseckey<Octets, Name>
+where
+    Octets: OctetsFrom<SrcOctets>,
+    Name: OctetsFrom<SrcName>,
+{
+    fn octets_from(
+        source: Ipseckey<SrcOctets, SrcName>,
+    ) -> Result<Self, ShortBuf> {
+        Ok(Ipseckey::new(
+            source.precedence,
+            source.algorithm,
+            IpseckeyGateway::octets_from(source.gateway)?,
+            Octets::octets_from(source.public_key)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<N, NN, O, OO> PartialEq<Ipseckey<OO, NN>> for Ipseckey<O, N>
+where
+    N: ToDname,
+    NN: ToDname,
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Ipseckey<OO, NN>) -> bool {
+        self.precedence == other.precedence
+            && self.algorithm == other.algorithm
+            && self.gateway == other.gateway
+            && self.public_key.as_ref() == other.public_key.as_ref()
+    }
+}
+
+impl<Octets, Name> Eq for Ipseckey<Octets, Name>
+where
+    Octets: AsRef<[u8]>,
+    Name: ToDname,
+{
+}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<N, NN, O, OO> PartialOrd<Ipseckey<OO, NN>> for Ipseckey<O, N>
+where
+    N: ToDname,
+    NN: ToDname,
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Ipseckey<OO, NN>) -> Option<Ordering> {
+        Some(self.canonical_cmp(other))
+    }
+}
+
+impl<N, NN, O, OO> CanonicalOrd<Ipseckey<OO, NN>> for Ipseckey<O, N>
+where
+    N: ToDname,
+    NN: ToDname,
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Ipseckey<OO, NN>) -> Ordering {
+        match self.precedence.cmp(&other.precedence) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self
+            .gateway
+            .gateway_type()
+            .cmp(&other.gateway.gateway_type())
+        {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.algorithm.cmp(&other.algorithm) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.gateway.canonical_cmp(&other.gateway) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.public_key.as_ref().cmp(other.public_key.as_ref())
+    }
+}
+
+impl<O: AsRef<[u8]>, N: ToDname> Ord for Ipseckey<O, N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
+//--- Hash
+
+impl<O: AsRef<[u8]>, N: hash::Hash> hash::Hash for Ipseckey<O, N> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.precedence.hash(state);
+        self.algorithm.hash(state);
+        self.gateway.hash(state);
+        self.public_key.as_ref().hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Ipseckey<Ref::Range, ParsedDname<Ref>> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let precedence = u8::parse(parser)?;
+        let gateway_type = u8::parse(parser)?;
+        let algorithm = IpseckeyAlg::parse(parser)?;
+        let gateway = IpseckeyGateway::parse(gateway_type, parser)?;
+        let len = parser.remaining();
+        let public_key = parser.parse_octets(len)?;
+        Ok(Self::new(precedence, algorithm, gateway, public_key))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        u8::skip(parser)?;
+        let gateway_type = u8::parse(parser)?;
+        IpseckeyAlg::skip(parser)?;
+        IpseckeyGateway::parse(gateway_type, parser)?;
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>, Name: Compose> Compose for Ipseckey<Octets, Name> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|buf| {
+            self.precedence.compose(buf)?;
+            self.gateway.gateway_type().compose(buf)?;
+            self.algorithm.compose(buf)?;
+            self.gateway.compose(buf)?;
+            buf.append_slice(self.public_key.as_ref())
+        })
+    }
+
+    // Default compose_canonical is correct as we keep the case.
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl<N: Scan> Scan for Ipseckey<Bytes, N> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        let precedence = u8::scan(scanner)?;
+        let gateway_type =
+            scanner.scan_string_word(|word| match u8::from_str(&word) {
+                Ok(value) if value <= 3 => Ok(value),
+                _ => Err(SyntaxError::Expected("0, 1, 2, or 3".into())),
+            })?;
+        let algorithm = IpseckeyAlg::scan(scanner)?;
+        let gateway = IpseckeyGateway::scan(gateway_type, scanner)?;
+        let public_key = if scanner.entry_ended()? {
+            Bytes::new()
+        } else {
+            scanner.scan_base64_phrases(Ok)?
+        };
+        Ok(Self::new(precedence, algorithm, gateway, public_key))
+    }
+}
+
+impl<Octets, Name> fmt::Display for Ipseckey<Octets, Name>
+where
+    Octets: AsRef<[u8]>,
+    Name: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.precedence,
+            self.gateway.gateway_type(),
+            self.algorithm,
+            self.gateway
+        )?;
+        if !self.public_key.as_ref().is_empty() {
+            f.write_str(" ")?;
+            base64::display(&self.public_key, f)?;
+        }
+        Ok(())
+    }
+}
+
+//--- Debug
+
+impl<Octets, Name> fmt::Debug for Ipseckey<Octets, Name>
+where
+    Octets: AsRef<[u8]>,
+    Name: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Ipseckey")
+            .field("precedence", &self.precedence)
+            .field("algorithm", &self.algorithm)
+            .field("gateway", &self.gateway)
+            .field("public_key", &self.public_key.as_ref())
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets, Name> RtypeRecordData for Ipseckey<Octets, Name> {
+    const RTYPE: Rtype = Rtype::Ipseckey;
+}
+
+//------------ IpseckeyGateway -----------------------------------------------
+
+/// The gateway of an IPSECKEY record.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IpseckeyGateway<Name> {
+    /// There is no gateway.
+    None,
+
+    /// The gateway is given by its IPv4 address.
+    Ipv4(Ipv4Addr),
+
+    /// The gateway is given by its IPv6 address.
+    Ipv6(Ipv6Addr),
+
+    /// The gateway is given by its domain name.
+    Name(Name),
+}
+
+impl<Name> IpseckeyGateway<Name> {
+    /// Returns the value of the gateway type field for the gateway.
+    pub fn gateway_type(&self) -> u8 {
+        match *self {
+            IpseckeyGateway::None => 0,
+            IpseckeyGateway::Ipv4(_) => 1,
+            IpseckeyGateway::Ipv6(_) => 2,
+            IpseckeyGateway::Name(_) => 3,
+        }
+    }
+}
+
+impl<Ref: OctetsRef> IpseckeyGateway<ParsedDname<Ref>> {
+    /// Parses a gateway of the given gateway type.
+    pub fn parse(
+        gateway_type: u8,
+        parser: &mut Parser<Ref>,
+    ) -> Result<Self, ParseError> {
+        match gateway_type {
+            0 => Ok(IpseckeyGateway::None),
+            1 => Ipv4Addr::parse(parser).map(IpseckeyGateway::Ipv4),
+            2 => Ipv6Addr::parse(parser).map(IpseckeyGateway::Ipv6),
+            3 => ParsedDname::parse(parser).map(IpseckeyGateway::Name),
+            _ => Err(ParseError::form_error("unknown IPSECKEY gateway type")),
+        }
+    }
+}
+
+#[cfg(feature = "master")]
+impl<N: Scan> IpseckeyGateway<N> {
+    /// Scans a gateway of the given gateway type.
+    pub fn scan<C: CharSource>(
+        gateway_type: u8,
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        match gateway_type {
+            0 => scanner.scan_string_word(|word| {
+                if word == "." {
+                    Ok(IpseckeyGateway::None)
+                } else {
+                    Err(SyntaxError::Expected(".".into()))
+                }
+            }),
+            1 => scanner.scan_string_phrase(|res| {
+                Ipv4Addr::from_str(&res)
+                    .map(IpseckeyGateway::Ipv4)
+                    .map_err(Into::into)
+            }),
+            2 => scanner.scan_string_phrase(|res| {
+                Ipv6Addr::from_str(&res)
+                    .map(IpseckeyGateway::Ipv6)
+                    .map_err(Into::into)
+            }),
+            _ => N::scan(scanner).map(IpseckeyGateway::Name),
+        }
+    }
+}
+
+//--- OctetsFrom
+
+impl<Name, SrcName> OctetsFrom<IpseckeyGateway<SrcName>>
+    for IpseckeyGateway<Name>
+where
+    Name: OctetsFrom<SrcName>,
+{
+    fn octets_from(
+        source: IpseckeyGateway<SrcName>,
+    ) -> Result<Self, ShortBuf> {
+        Ok(match source {
+            IpseckeyGateway::None => IpseckeyGateway::None,
+            IpseckeyGateway::Ipv4(addr) => IpseckeyGateway::Ipv4(addr),
+            IpseckeyGateway::Ipv6(addr) => IpseckeyGateway::Ipv6(addr),
+            IpseckeyGateway::Name(name) => {
+                IpseckeyGateway::Name(Name::octets_from(name)?)
+            }
+        })
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<N, NN> PartialEq<IpseckeyGateway<NN>> for IpseckeyGateway<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn eq(&self, other: &IpseckeyGateway<NN>) -> bool {
+        match (self, other) {
+            (IpseckeyGateway::None, IpseckeyGateway::None) => true,
+            (IpseckeyGateway::Ipv4(left), IpseckeyGateway::Ipv4(right)) => {
+                left == right
+            }
+            (IpseckeyGateway::Ipv6(left), IpseckeyGateway::Ipv6(right)) => {
+                left == right
+            }
+            (IpseckeyGateway::Name(left), IpseckeyGateway::Name(right)) => {
+                left.name_eq(right)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<Name: ToDname> Eq for IpseckeyGateway<Name> {}
+
+//--- CanonicalOrd
+
+impl<N, NN> CanonicalOrd<IpseckeyGateway<NN>> for IpseckeyGateway<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn canonical_cmp(&self, other: &IpseckeyGateway<NN>) -> Ordering {
+        match (self, other) {
+            (IpseckeyGateway::Ipv4(left), IpseckeyGateway::Ipv4(right)) => {
+                left.octets().cmp(&right.octets())
+            }
+            (IpseckeyGateway::Ipv6(left), IpseckeyGateway::Ipv6(right)) => {
+                left.octets().cmp(&right.octets())
+            }
+            (IpseckeyGateway::Name(left), IpseckeyGateway::Name(right)) => {
+                left.composed_cmp(right)
+            }
+            _ => self.gateway_type().cmp(&other.gateway_type()),
+        }
+    }
+}
+
+//--- Hash
+
+impl<Name: hash::Hash> hash::Hash for IpseckeyGateway<Name> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.gateway_type().hash(state);
+        match *self {
+            IpseckeyGateway::None => {}
+            IpseckeyGateway::Ipv4(ref addr) => addr.hash(state),
+            IpseckeyGateway::Ipv6(ref addr) => addr.hash(state),
+            IpseckeyGateway::Name(ref name) => name.hash(state),
+        }
+    }
+}
+
+//--- Compose
+
+impl<Name: Compose> Compose for IpseckeyGateway<Name> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        match *self {
+            IpseckeyGateway::None => Ok(()),
+            IpseckeyGateway::Ipv4(ref addr) => addr.compose(target),
+            IpseckeyGateway::Ipv6(ref addr) => addr.compose(target),
+            IpseckeyGateway::Name(ref name) => name.compose(target),
+        }
+    }
+}
+
+//--- Display
+
+impl<Name: fmt::Display> fmt::Display for IpseckeyGateway<Name> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IpseckeyGateway::None => f.write_str("."),
+            IpseckeyGateway::Ipv4(ref addr) => addr.fmt(f),
+            IpseckeyGateway::Ipv6(ref addr) => addr.fmt(f),
+            IpseckeyGateway::Name(ref name) => write!(f, "{}.", name),
+        }
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x0a\x03\x02\x07example\x03com\x00\x99\x01";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let key = Ipseckey::parse(&mut parser).unwrap();
+        assert_eq!(key.precedence(), 10);
+        assert_eq!(key.algorithm(), IpseckeyAlg::Rsa);
+        assert_eq!(key.gateway().gateway_type(), 3);
+        assert_eq!(key.to_string(), "10 3 2 example.com. mQE=");
+
+        let mut buf = Vec::new();
+        key.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let rdata = b"\x0a\x00\x00";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let key = Ipseckey::parse(&mut parser).unwrap();
+        assert_eq!(key.to_string(), "10 0 0 .");
+
+        let mut parser = Parser::from_ref(b"\x0a\x04\x00".as_ref());
+        assert!(Ipseckey::parse(&mut parser).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new(
+            "10 1 2 192.0.2.38 mQE=\n10 2 0 2001:db8::1\n10 0 2 . mQE=\n",
+        );
+        let first: Ipseckey<Bytes, Dname<Bytes>> =
+            Ipseckey::scan(&mut scanner).unwrap();
+        assert_eq!(
+            first.gateway(),
+            &IpseckeyGateway::<Dname<Bytes>>::Ipv4(Ipv4Addr::new(
+                192, 0, 2, 38
+            ))
+        );
+        assert_eq!(first.public_key().as_ref(), b"\x99\x01");
+        scanner.scan_newline().unwrap();
+        let second: Ipseckey<Bytes, Dname<Bytes>> =
+            Ipseckey::scan(&mut scanner).unwrap();
+        assert_eq!(second.to_string(), "10 2 0 2001:db8::1");
+        scanner.scan_newline().unwrap();
+        let third: Ipseckey<Bytes, Dname<Bytes>> =
+            Ipseckey::scan(&mut scanner).unwrap();
+        assert_eq!(third.gateway(), &IpseckeyGateway::<Dname<Bytes>>::None);
+
+        let mut scanner = Scanner::new("10 4 2 . mQE=\n");
+        assert!(Ipseckey::<Bytes, Dname<Bytes>>::scan(&mut scanner).is_err());
+    }
+}
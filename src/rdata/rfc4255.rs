@@ -0,0 +1,296 @@
+//! Record data from [RFC 4255]: SSHFP records.
+//!
+//! This RFC defines the SSHFP record type that publishes fingerprints of
+//! SSH host keys. Additional algorithms and fingerprint types have been
+//! added by [RFC 6594] and later documents.
+//!
+//! [RFC 4255]: https://tools.ietf.org/html/rfc4255
+//! [RFC 6594]: https://tools.ietf.org/html/rfc6594
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::{Rtype, SshfpAlg, SshfpType};
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+use crate::utils::base16;
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Sshfp ---------------------------------------------------------
+
+/// SSHFP record data.
+///
+/// An SSHFP record contains the fingerprint of an SSH host key of the host
+/// with the domain name where the record is found. The record data consists
+/// of the algorithm of the key, the type of the fingerprint, and the
+/// fingerprint itself.
+///
+/// In presentation format, the fingerprint is given in Base 16.
+///
+/// The SSHFP record type is defined in RFC 4255, section 3.1.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>
+        ",
+        deserialize = "
+            Octets:
+                crate::base::octets::FromBuilder
+                + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as crate::base::octets::FromBuilder>::Builder:
+                OctetsBuilder<Octets = Octets>
+                + crate::base::octets::EmptyBuilder,
+        ",
+    ))
+)]
+pub struct Sshfp<Octets> {
+    algorithm: SshfpAlg,
+    fp_type: SshfpType,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base16::serde")
+    )]
+    fingerprint: Octets,
+}
+
+impl<Octets> Sshfp<Octets> {
+    pub fn new(
+        algorithm: SshfpAlg,
+        fp_type: SshfpType,
+        fingerprint: Octets,
+    ) -> Self {
+        Sshfp {
+            algorithm,
+            fp_type,
+            fingerprint,
+        }
+    }
+
+    pub fn algorithm(&self) -> SshfpAlg {
+        self.algorithm
+    }
+
+    pub fn fp_type(&self) -> SshfpType {
+        self.fp_type
+    }
+
+    pub fn fingerprint(&self) -> &Octets {
+        &self.fingerprint
+    }
+
+    pub fn into_fingerprint(self) -> Octets {
+        self.fingerprint
+    }
+}
+
+impl<SrcOctets> Sshfp<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Sshfp<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        let Self {
+            algorithm,
+            fp_type,
+            fingerprint,
+        } = self;
+        Ok(Sshfp::new(algorithm, fp_type, fingerprint.octets_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Sshfp<SrcOctets>> for Sshfp<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Sshfp<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(Sshfp::new(
+            source.algorithm,
+            source.fp_type,
+            Octets::octets_from(source.fingerprint)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Sshfp<Other>> for Sshfp<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Sshfp<Other>) -> bool {
+        self.algorithm == other.algorithm
+            && self.fp_type == other.fp_type
+            && self.fingerprint.as_ref().eq(other.fingerprint.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Sshfp<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Sshfp<Other>> for Sshfp<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Sshfp<Other>) -> Option<Ordering> {
+        Some(self.canonical_cmp(other))
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Sshfp<Other>> for Sshfp<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Sshfp<Other>) -> Ordering {
+        match self.algorithm.cmp(&other.algorithm) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.fp_type.cmp(&other.fp_type) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.fingerprint.as_ref().cmp(other.fingerprint.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Sshfp<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Sshfp<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.algorithm.hash(state);
+        self.fp_type.hash(state);
+        self.fingerprint.as_ref().hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Sshfp<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let len = match parser.remaining().checked_sub(2) {
+            Some(len) => len,
+            None => return Err(ParseError::ShortInput),
+        };
+        Ok(Self::new(
+            SshfpAlg::parse(parser)?,
+            SshfpType::parse(parser)?,
+            parser.parse_octets(len)?,
+        ))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        if parser.remaining() < 2 {
+            return Err(ParseError::ShortInput);
+        }
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Sshfp<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|buf| {
+            self.algorithm.compose(buf)?;
+            self.fp_type.compose(buf)?;
+            buf.append_slice(self.fingerprint.as_ref())
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Sshfp<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(
+            SshfpAlg::scan(scanner)?,
+            SshfpType::scan(scanner)?,
+            scanner.scan_hex_words(Ok)?,
+        ))
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Sshfp<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} ", self.algorithm, self.fp_type)?;
+        base16::display(&self.fingerprint, f)
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Sshfp<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sshfp")
+            .field("algorithm", &self.algorithm)
+            .field("fp_type", &self.fp_type)
+            .field("fingerprint", &self.fingerprint.as_ref())
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Sshfp<Octets> {
+    const RTYPE: Rtype = Rtype::Sshfp;
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x04\x02\x12\x34\xab";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let sshfp = Sshfp::parse(&mut parser).unwrap();
+        assert_eq!(sshfp.algorithm(), SshfpAlg::Ed25519);
+        assert_eq!(sshfp.fp_type(), SshfpType::Sha256);
+        assert_eq!(sshfp.to_string(), "4 2 1234AB");
+
+        let mut buf = Vec::new();
+        sshfp.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new("1 1 1234 ab\n");
+        assert_eq!(
+            Sshfp::scan(&mut scanner).unwrap(),
+            Sshfp::new(SshfpAlg::Rsa, SshfpType::Sha1, b"\x12\x34\xab")
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Record data from [RFC 4398]: CERT records.
+//!
+//! This RFC defines the CERT record type for storing certificates and
+//! certificate revocation lists in the DNS.
+//!
+//! [RFC 4398]: https://tools.ietf.org/html/rfc4398
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::{CertType, Rtype, SecAlg};
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+use crate::utils::base64;
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Cert ----------------------------------------------------------
+
+/// CERT record data.
+///
+/// A CERT record contains a certificate or certificate revocation list.
+/// The record data consists of the type of the certificate, the key tag
+/// and algorithm of the key the certificate refers to, and the certificate
+/// itself.
+///
+/// In presentation format, the certificate type and algorithm can be given
+/// as mnemonics or decimal numbers and the certificate is given in Base 64.
+///
+/// The CERT record type is defined in RFC 4398, section 2.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>
+        ",
+        deserialize = "
+            Octets:
+                crate::base::octets::FromBuilder
+                + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as crate::base::octets::FromBuilder>::Builder:
+                OctetsBuilder<Octets = Octets>
+                + crate::base::octets::EmptyBuilder,
+        ",
+    ))
+)]
+pub struct Cert<Octets> {
+    cert_type: CertType,
+    key_tag: u16,
+    algorithm: SecAlg,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base64::serde")
+    )]
+    certificate: Octets,
+}
+
+impl<Octets> Cert<Octets> {
+    pub fn new(
+        cert_type: CertType,
+        key_tag: u16,
+        algorithm: SecAlg,
+        certificate: Octets,
+    ) -> Self {
+        Cert {
+            cert_type,
+            key_tag,
+            algorithm,
+            certificate,
+        }
+    }
+
+    pub fn cert_type(&self) -> CertType {
+        self.cert_type
+    }
+
+    pub fn key_tag(&self) -> u16 {
+        self.key_tag
+    }
+
+    pub fn algorithm(&self) -> SecAlg {
+        self.algorithm
+    }
+
+    pub fn certificate(&self) -> &Octets {
+        &self.certificate
+    }
+
+    pub fn into_certificate(self) -> Octets {
+        self.certificate
+    }
+}
+
+impl<SrcOctets> Cert<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Cert<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        let Self {
+            cert_type,
+            key_tag,
+            algorithm,
+            certificate,
+        } = self;
+        Ok(Cert::new(
+            cert_type,
+            key_tag,
+            algorithm,
+            certificate.octets_into()?,
+        ))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Cert<SrcOctets>> for Cert<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Cert<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(Cert::new(
+            source.cert_type,
+            source.key_tag,
+            source.algorithm,
+            Octets::octets_from(source.certificate)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Cert<Other>> for Cert<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Cert<Other>) -> bool {
+        self.cert_type == other.cert_type
+            && self.key_tag == other.key_tag
+            && self.algorithm == other.algorithm
+            && self.certificate.as_ref().eq(other.certificate.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Cert<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Cert<Other>> for Cert<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Cert<Other>) -> Option<Ordering> {
+        Some(self.canonical_cmp(other))
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Cert<Other>> for Cert<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Cert<Other>) -> Ordering {
+        match self.cert_type.cmp(&other.cert_type) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.key_tag.cmp(&other.key_tag) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.algorithm.cmp(&other.algorithm) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.certificate.as_ref().cmp(other.certificate.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Cert<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Cert<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.cert_type.hash(state);
+        self.key_tag.hash(state);
+        self.algorithm.hash(state);
+        self.certificate.as_ref().hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Cert<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let len = match parser.remaining().checked_sub(5) {
+            Some(len) => len,
+            None => return Err(ParseError::ShortInput),
+        };
+        Ok(Self::new(
+            CertType::parse(parser)?,
+            u16::parse(parser)?,
+            SecAlg::parse(parser)?,
+            parser.parse_octets(len)?,
+        ))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        if parser.remaining() < 5 {
+            return Err(ParseError::ShortInput);
+        }
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Cert<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|buf| {
+            self.cert_type.compose(buf)?;
+            self.key_tag.compose(buf)?;
+            self.algorithm.compose(buf)?;
+            buf.append_slice(self.certificate.as_ref())
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Cert<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(
+            CertType::scan(scanner)?,
+            u16::scan(scanner)?,
+            SecAlg::scan(scanner)?,
+            scanner.scan_base64_phrases(Ok)?,
+        ))
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Cert<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} ", self.cert_type, self.key_tag, self.algorithm)?;
+        base64::display(&self.certificate, f)
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Cert<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Cert")
+            .field("cert_type", &self.cert_type)
+            .field("key_tag", &self.key_tag)
+            .field("algorithm", &self.algorithm)
+            .field("certificate", &self.certificate.as_ref())
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Cert<Octets> {
+    const RTYPE: Rtype = Rtype::Cert;
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x00\x03\x30\x39\x08\x99\x01\x0d\x04";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let cert = Cert::parse(&mut parser).unwrap();
+        assert_eq!(cert.cert_type(), CertType::Pgp);
+        assert_eq!(cert.key_tag(), 12345);
+        assert_eq!(cert.algorithm(), SecAlg::RsaSha256);
+        assert_eq!(cert.to_string(), "PGP 12345 RSASHA256 mQENBA==");
+
+        let mut buf = Vec::new();
+        cert.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new("PKIX 0 0 mQEN BA==\n254 1 8 AA==\n");
+        assert_eq!(
+            Cert::scan(&mut scanner).unwrap(),
+            Cert::new(
+                CertType::Pkix,
+                0,
+                SecAlg::DeleteDs,
+                b"\x99\x01\x0d\x04"
+            )
+        );
+        scanner.scan_newline().unwrap();
+        assert_eq!(
+            Cert::scan(&mut scanner).unwrap(),
+            Cert::new(CertType::Oid, 1, SecAlg::RsaSha256, b"\x00")
+        );
+    }
+}
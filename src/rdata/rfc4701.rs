@@ -0,0 +1,259 @@
+//! Record data from [RFC 4701]: DHCID records.
+//!
+//! This RFC defines the DHCID record type used by DHCP servers and clients
+//! to associate DNS updates with the DHCP client that caused them.
+//!
+//! [RFC 4701]: https://tools.ietf.org/html/rfc4701
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+use crate::utils::base64;
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Dhcid ---------------------------------------------------------
+
+/// DHCID record data.
+///
+/// The record data is an opaque identifier of the DHCP client that owns
+/// the domain name. It consists of an identifier type code, a digest type
+/// code, and a digest computed over the client identity and the domain
+/// name. In presentation format, the data is given in Base 64.
+///
+/// The DHCID record type is defined in RFC 4701, section 3.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>
+        ",
+        deserialize = "
+            Octets:
+                crate::base::octets::FromBuilder
+                + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as crate::base::octets::FromBuilder>::Builder:
+                OctetsBuilder<Octets = Octets>
+                + crate::base::octets::EmptyBuilder,
+        ",
+    ))
+)]
+pub struct Dhcid<Octets> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base64::serde")
+    )]
+    data: Octets,
+}
+
+impl<Octets> Dhcid<Octets> {
+    pub fn new(data: Octets) -> Self {
+        Dhcid { data }
+    }
+
+    /// Returns the raw record data.
+    pub fn data(&self) -> &Octets {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Octets {
+        self.data
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Dhcid<Octets> {
+    /// Returns the identifier type code if present.
+    ///
+    /// The code describes the kind of client identity used for
+    /// calculating the digest.
+    pub fn identifier_type(&self) -> Option<u16> {
+        match self.data.as_ref() {
+            [hi, lo, ..] => Some(u16::from_be_bytes([*hi, *lo])),
+            _ => None,
+        }
+    }
+
+    /// Returns the digest type code if present.
+    pub fn digest_type(&self) -> Option<u8> {
+        self.data.as_ref().get(2).copied()
+    }
+
+    /// Returns the digest if present.
+    pub fn digest(&self) -> &[u8] {
+        self.data.as_ref().get(3..).unwrap_or_default()
+    }
+}
+
+impl<SrcOctets> Dhcid<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Dhcid<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        Ok(Dhcid::new(self.data.octets_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Dhcid<SrcOctets>> for Dhcid<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Dhcid<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(Dhcid::new(Octets::octets_from(source.data)?))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Dhcid<Other>> for Dhcid<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Dhcid<Other>) -> bool {
+        self.data.as_ref().eq(other.data.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Dhcid<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Dhcid<Other>> for Dhcid<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Dhcid<Other>) -> Option<Ordering> {
+        self.data.as_ref().partial_cmp(other.data.as_ref())
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Dhcid<Other>> for Dhcid<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Dhcid<Other>) -> Ordering {
+        self.data.as_ref().cmp(other.data.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Dhcid<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Dhcid<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.data.as_ref().hash(state)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Dhcid<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let len = parser.remaining();
+        parser.parse_octets(len).map(Self::new)
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Dhcid<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_slice(self.data.as_ref())
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Dhcid<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        scanner.scan_base64_phrases(Ok).map(Self::new)
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Dhcid<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        base64::display(&self.data, f)
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Dhcid<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Dhcid").field(&self.data.as_ref()).finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Dhcid<Octets> {
+    const RTYPE: Rtype = Rtype::Dhcid;
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x00\x02\x01\x63\x6f\xc0\xb8\x27\x1c\x82\x82\x5b\
+            \xb1\xac\x5c\x41\xcf\x53\x51\xaa\x69\xb4\xfe\xbd\x94\xe8\
+            \xf1\x7c\xdb\x95\x00\x0d\xa4\x8c\x40";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let dhcid = Dhcid::parse(&mut parser).unwrap();
+        assert_eq!(dhcid.identifier_type(), Some(2));
+        assert_eq!(dhcid.digest_type(), Some(1));
+        assert_eq!(dhcid.digest().len(), 32);
+        assert_eq!(
+            dhcid.to_string(),
+            "AAIBY2/AuCccgoJbsaxcQc9TUapptP69lOjxfNuVAA2kjEA="
+        );
+
+        let mut buf = Vec::new();
+        dhcid.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new("AAIB Y2/A\n");
+        let dhcid = Dhcid::scan(&mut scanner).unwrap();
+        assert_eq!(dhcid.data().as_ref(), b"\x00\x02\x01\x63\x6f\xc0");
+        assert_eq!(dhcid.digest(), b"\x63\x6f\xc0");
+    }
+}
@@ -0,0 +1,114 @@
+//! Record data from [RFC 6698]: TLSA records.
+//!
+//! This RFC defines the TLSA record type used by DANE, the DNS-based
+//! Authentication of Named Entities, to associate TLS certificates with
+//! domain names.
+//!
+//! [RFC 6698]: https://tools.ietf.org/html/rfc6698
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::{
+    Rtype, TlsaCertUsage, TlsaMatchingType, TlsaSelector,
+};
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+use crate::utils::base16;
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Tlsa ----------------------------------------------------------
+
+tlsa_type! {
+    /// TLSA record data.
+    ///
+    /// A TLSA record associates a TLS server certificate or public key with
+    /// the domain name where the record is found. The record data consists
+    /// of the certificate usage, the selector, and the matching type which
+    /// together describe how the certificate association data is to be
+    /// matched against the certificate chain presented by the server.
+    ///
+    /// In presentation format, the association data is given in Base 16 or
+    /// as a single `-` if it is empty.
+    ///
+    /// The TLSA record type is defined in RFC 6698, section 2.
+    (Tlsa, Tlsa)
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x03\x01\x01\x0d\x2a\x45\x9f";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let tlsa = Tlsa::parse(&mut parser).unwrap();
+        assert_eq!(tlsa.usage(), TlsaCertUsage::DaneEe);
+        assert_eq!(tlsa.selector(), TlsaSelector::Spki);
+        assert_eq!(tlsa.matching_type(), TlsaMatchingType::Sha256);
+        assert_eq!(tlsa.data(), &b"\x0d\x2a\x45\x9f".as_ref());
+        assert_eq!(tlsa.to_string(), "3 1 1 0D2A459F");
+
+        let mut buf = Vec::new();
+        tlsa.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let mut parser = Parser::from_ref(b"\x03\x01".as_ref());
+        assert!(Tlsa::parse(&mut parser).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new("3 1 1 0d2a 459F\n");
+        assert_eq!(
+            Tlsa::scan(&mut scanner).unwrap(),
+            Tlsa::new(
+                TlsaCertUsage::DaneEe,
+                TlsaSelector::Spki,
+                TlsaMatchingType::Sha256,
+                b"\x0d\x2a\x45\x9f".as_ref()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn display_scan_round_trip() {
+        for data in [b"".as_ref(), b"\x0d\x2a\x45\x9f".as_ref()].iter() {
+            let tlsa = Tlsa::new(
+                TlsaCertUsage::DaneEe,
+                TlsaSelector::Spki,
+                TlsaMatchingType::Sha256,
+                Bytes::copy_from_slice(data),
+            );
+            let text = tlsa.to_string();
+            assert!(!text.ends_with(' '));
+            let mut scanner = Scanner::new(text.as_str());
+            assert_eq!(Tlsa::scan(&mut scanner).unwrap(), tlsa);
+        }
+        assert_eq!(
+            Tlsa::new(
+                TlsaCertUsage::DaneEe,
+                TlsaSelector::Spki,
+                TlsaMatchingType::Sha256,
+                b"".as_ref()
+            )
+            .to_string(),
+            "3 1 1 -"
+        );
+    }
+}
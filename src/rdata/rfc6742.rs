@@ -0,0 +1,626 @@
+//! Record data from [RFC 6742]: ILNP records.
+//!
+//! This RFC defines the Nid, L32, L64, and Lp record types used by the
+//! Identifier-Locator Network Protocol (ILNP).
+//!
+//! The 64 bit node identifiers and locators of the Nid and L64 record types
+//! are written in presentation format as four groups of up to four
+//! hexadecimal digits separated by colons, e.g., `0014:4fff:ff20:ee64`.
+//!
+//! [RFC 6742]: https://tools.ietf.org/html/rfc6742
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::{Dname, ParsedDname, PushError, ToDname};
+use crate::base::net::Ipv4Addr;
+use crate::base::octets::{
+    Compose, EmptyBuilder, FromBuilder, OctetsBuilder, OctetsFrom, OctetsRef,
+    Parse, ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{
+    CharSource, Scan, ScanError, Scanner, SyntaxError,
+};
+use core::cmp::Ordering;
+use core::fmt;
+#[cfg(feature = "master")]
+use core::str::FromStr;
+
+//------------ Nid -----------------------------------------------------------
+
+/// Nid record data.
+///
+/// The Nid record holds a 64 bit ILNP node identifier for the owner name.
+///
+/// The Nid record type is defined in RFC 6742, section 2.1.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Nid {
+    preference: u16,
+    node_id: u64,
+}
+
+impl Nid {
+    /// Creates new Nid record data from the components.
+    pub fn new(preference: u16, node_id: u64) -> Self {
+        Nid {
+            preference,
+            node_id,
+        }
+    }
+
+    /// The preference for this record.
+    ///
+    /// Lower values are preferred.
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// The node identifier.
+    pub fn node_id(&self) -> u64 {
+        self.node_id
+    }
+
+    pub fn flatten_into(self) -> Result<Nid, PushError> {
+        Ok(self)
+    }
+}
+
+//--- OctetsFrom
+
+impl OctetsFrom<Nid> for Nid {
+    fn octets_from(source: Nid) -> Result<Self, ShortBuf> {
+        Ok(source)
+    }
+}
+
+//--- CanonicalOrd
+
+impl CanonicalOrd for Nid {
+    fn canonical_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: AsRef<[u8]>> Parse<Ref> for Nid {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Ok(Self::new(u16::parse(parser)?, parse_u64(parser)?))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance(10)
+    }
+}
+
+impl Compose for Nid {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.preference.compose(target)?;
+            target.append_slice(&self.node_id.to_be_bytes())
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Nid {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(u16::scan(scanner)?, scan_u64(scanner)?))
+    }
+}
+
+impl fmt::Display for Nid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ", self.preference)?;
+        fmt_u64(self.node_id, f)
+    }
+}
+
+//--- RtypeRecordData
+
+impl RtypeRecordData for Nid {
+    const RTYPE: Rtype = Rtype::Nid;
+}
+
+//------------ L32 -----------------------------------------------------------
+
+/// L32 record data.
+///
+/// The L32 record holds a 32 bit ILNP locator for the owner name. The
+/// locator has the form of an IPv4 address and is given as such in
+/// presentation format.
+///
+/// The L32 record type is defined in RFC 6742, section 2.2.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct L32 {
+    preference: u16,
+    locator32: Ipv4Addr,
+}
+
+impl L32 {
+    /// Creates new L32 record data from the components.
+    pub fn new(preference: u16, locator32: Ipv4Addr) -> Self {
+        L32 {
+            preference,
+            locator32,
+        }
+    }
+
+    /// The preference for this record.
+    ///
+    /// Lower values are preferred.
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// The 32 bit locator.
+    pub fn locator32(&self) -> Ipv4Addr {
+        self.locator32
+    }
+
+    pub fn flatten_into(self) -> Result<L32, PushError> {
+        Ok(self)
+    }
+}
+
+//--- OctetsFrom
+
+impl OctetsFrom<L32> for L32 {
+    fn octets_from(source: L32) -> Result<Self, ShortBuf> {
+        Ok(source)
+    }
+}
+
+//--- CanonicalOrd
+
+impl CanonicalOrd for L32 {
+    fn canonical_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: AsRef<[u8]>> Parse<Ref> for L32 {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Ok(Self::new(u16::parse(parser)?, Ipv4Addr::parse(parser)?))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance(6)
+    }
+}
+
+impl Compose for L32 {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.preference.compose(target)?;
+            self.locator32.compose(target)
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for L32 {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(
+            u16::scan(scanner)?,
+            scanner.scan_string_word(|word| {
+                Ipv4Addr::from_str(&word).map_err(Into::into)
+            })?,
+        ))
+    }
+}
+
+impl fmt::Display for L32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.preference, self.locator32)
+    }
+}
+
+//--- RtypeRecordData
+
+impl RtypeRecordData for L32 {
+    const RTYPE: Rtype = Rtype::L32;
+}
+
+//------------ L64 -----------------------------------------------------------
+
+/// L64 record data.
+///
+/// The L64 record holds a 64 bit ILNP locator for the owner name.
+///
+/// The L64 record type is defined in RFC 6742, section 2.3.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct L64 {
+    preference: u16,
+    locator64: u64,
+}
+
+impl L64 {
+    /// Creates new L64 record data from the components.
+    pub fn new(preference: u16, locator64: u64) -> Self {
+        L64 {
+            preference,
+            locator64,
+        }
+    }
+
+    /// The preference for this record.
+    ///
+    /// Lower values are preferred.
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// The 64 bit locator.
+    pub fn locator64(&self) -> u64 {
+        self.locator64
+    }
+
+    pub fn flatten_into(self) -> Result<L64, PushError> {
+        Ok(self)
+    }
+}
+
+//--- OctetsFrom
+
+impl OctetsFrom<L64> for L64 {
+    fn octets_from(source: L64) -> Result<Self, ShortBuf> {
+        Ok(source)
+    }
+}
+
+//--- CanonicalOrd
+
+impl CanonicalOrd for L64 {
+    fn canonical_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: AsRef<[u8]>> Parse<Ref> for L64 {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Ok(Self::new(u16::parse(parser)?, parse_u64(parser)?))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance(10)
+    }
+}
+
+impl Compose for L64 {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.preference.compose(target)?;
+            target.append_slice(&self.locator64.to_be_bytes())
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for L64 {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(u16::scan(scanner)?, scan_u64(scanner)?))
+    }
+}
+
+impl fmt::Display for L64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ", self.preference)?;
+        fmt_u64(self.locator64, f)
+    }
+}
+
+//--- RtypeRecordData
+
+impl RtypeRecordData for L64 {
+    const RTYPE: Rtype = Rtype::L64;
+}
+
+//------------ Lp ------------------------------------------------------------
+
+/// Lp record data.
+///
+/// The Lp record names a host that has L32 or L64 records which provide
+/// locators for the owner name. The name is never compressed.
+///
+/// The Lp record type is defined in RFC 6742, section 2.4.
+#[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lp<N> {
+    preference: u16,
+    fqdn: N,
+}
+
+impl<N> Lp<N> {
+    /// Creates new Lp record data from the components.
+    pub fn new(preference: u16, fqdn: N) -> Self {
+        Lp { preference, fqdn }
+    }
+
+    /// The preference for this record.
+    ///
+    /// Lower values are preferred.
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// The name of the host with the locator records.
+    pub fn fqdn(&self) -> &N {
+        &self.fqdn
+    }
+}
+
+impl<Ref> Lp<ParsedDname<Ref>>
+where
+    Ref: OctetsRef,
+{
+    pub fn flatten_into<Octets>(self) -> Result<Lp<Dname<Octets>>, PushError>
+    where
+        Octets: OctetsFrom<Ref::Range> + FromBuilder,
+        <Octets as FromBuilder>::Builder: EmptyBuilder,
+    {
+        let Self { preference, fqdn } = self;
+        Ok(Lp::new(preference, fqdn.flatten_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Name, SrcName> OctetsFrom<Lp<SrcName>> for Lp<Name>
+where
+    Name: OctetsFrom<SrcName>,
+{
+    fn octets_from(source: Lp<SrcName>) -> Result<Self, ShortBuf> {
+        Ok(Lp::new(source.preference, Name::octets_from(source.fqdn)?))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<N, NN> PartialEq<Lp<NN>> for Lp<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn eq(&self, other: &Lp<NN>) -> bool {
+        self.preference == other.preference && self.fqdn.name_eq(&other.fqdn)
+    }
+}
+
+impl<N: ToDname> Eq for Lp<N> {}
+
+//--- PartialOrd, Ord, and CanonicalOrd
+
+impl<N, NN> PartialOrd<Lp<NN>> for Lp<N>
+where
+    N: ToDname,
+    NN: ToDname,
+{
+    fn partial_cmp(&self, other: &Lp<NN>) -> Option<Ordering> {
+        match self.preference.partial_cmp(&other.preference) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        Some(self.fqdn.name_cmp(&other.fqdn))
+    }
+}
+
+impl<N: ToDname> Ord for Lp<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.preference.cmp(&other.preference) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.fqdn.name_cmp(&other.fqdn)
+    }
+}
+
+impl<N: ToDname, NN: ToDname> CanonicalOrd<Lp<NN>> for Lp<N> {
+    fn canonical_cmp(&self, other: &Lp<NN>) -> Ordering {
+        match self.preference.cmp(&other.preference) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        // Lp isn’t in the list of RFC 4034, so the name keeps its case.
+        self.fqdn.composed_cmp(&other.fqdn)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Lp<ParsedDname<Ref>> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Ok(Self::new(u16::parse(parser)?, ParsedDname::parse(parser)?))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        u16::skip(parser)?;
+        ParsedDname::skip(parser)
+    }
+}
+
+impl<N: ToDname> Compose for Lp<N> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            self.preference.compose(target)?;
+            self.fqdn.compose(target)
+        })
+    }
+
+    // Default compose_canonical is correct as we keep the case.
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl<N: Scan> Scan for Lp<N> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(u16::scan(scanner)?, N::scan(scanner)?))
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Lp<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}.", self.preference, self.fqdn)
+    }
+}
+
+//--- RtypeRecordData
+
+impl<N> RtypeRecordData for Lp<N> {
+    const RTYPE: Rtype = Rtype::Lp;
+}
+
+//------------ Helper Functions ----------------------------------------------
+
+fn parse_u64<Ref: AsRef<[u8]>>(
+    parser: &mut Parser<Ref>,
+) -> Result<u64, ParseError> {
+    let mut buf = [0; 8];
+    parser.parse_buf(&mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+}
+
+/// Scans a 64 bit value given as four colon separated groups of hex digits.
+#[cfg(feature = "master")]
+fn scan_u64<C: CharSource>(
+    scanner: &mut Scanner<C>,
+) -> Result<u64, ScanError> {
+    scanner.scan_string_word(|word| {
+        let mut res = 0u64;
+        let mut groups = word.split(':');
+        for _ in 0..4 {
+            let group = groups
+                .next()
+                .ok_or_else(|| SyntaxError::Expected(":".into()))?;
+            if group.is_empty()
+                || group.len() > 4
+                || !group.bytes().all(|ch| ch.is_ascii_hexdigit())
+            {
+                return Err(SyntaxError::IllegalInteger);
+            }
+            let group = u16::from_str_radix(group, 16)
+                .map_err(|_| SyntaxError::IllegalInteger)?;
+            res = res << 16 | u64::from(group);
+        }
+        if groups.next().is_some() {
+            return Err(SyntaxError::IllegalInteger);
+        }
+        Ok(res)
+    })
+}
+
+/// Writes a 64 bit value as four colon separated groups of hex digits.
+fn fmt_u64(value: u64, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+        f,
+        "{:04x}:{:04x}:{:04x}:{:04x}",
+        value >> 48,
+        (value >> 32) & 0xFFFF,
+        (value >> 16) & 0xFFFF,
+        value & 0xFFFF
+    )
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x00\x0a\x00\x14\x4f\xff\xff\x20\xee\x64";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let nid = Nid::parse(&mut parser).unwrap();
+        assert_eq!(nid.node_id(), 0x0014_4fff_ff20_ee64);
+        assert_eq!(nid.to_string(), "10 0014:4fff:ff20:ee64");
+        let mut buf = Vec::new();
+        nid.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let rdata = b"\x00\x0a\x0a\x01\x02\x00";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let l32 = L32::parse(&mut parser).unwrap();
+        assert_eq!(l32.to_string(), "10 10.1.2.0");
+        let mut buf = Vec::new();
+        l32.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let rdata = b"\x00\x14\x20\x01\x0d\xb8\x14\x0b\xf0\x01";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let l64 = L64::parse(&mut parser).unwrap();
+        assert_eq!(l64.to_string(), "20 2001:0db8:140b:f001");
+        let mut buf = Vec::new();
+        l64.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let rdata = b"\x00\x0a\x0bl64-subnet1\x07example\x03com\x00";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let lp = Lp::parse(&mut parser).unwrap();
+        assert_eq!(lp.to_string(), "10 l64-subnet1.example.com.");
+        let mut buf = Vec::new();
+        lp.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new("10 14:4fff:ff20:ee64\n");
+        assert_eq!(
+            Nid::scan(&mut scanner).unwrap(),
+            Nid::new(10, 0x0014_4fff_ff20_ee64)
+        );
+        let mut scanner = Scanner::new("10 2001:0DB8:1140:1000\n");
+        assert_eq!(
+            L64::scan(&mut scanner).unwrap(),
+            L64::new(10, 0x2001_0db8_1140_1000)
+        );
+        let mut scanner = Scanner::new("10 10.1.2.0\n");
+        assert_eq!(
+            L32::scan(&mut scanner).unwrap(),
+            L32::new(10, Ipv4Addr::new(10, 1, 2, 0))
+        );
+        assert!(Nid::scan(&mut Scanner::new("10 14:4fff:ff20\n")).is_err());
+        assert!(L64::scan(&mut Scanner::new("10 1:2:3:4:5\n")).is_err());
+        assert!(L64::scan(&mut Scanner::new("10 1:2:3:12345\n")).is_err());
+    }
+}
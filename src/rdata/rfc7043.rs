@@ -0,0 +1,342 @@
+//! Record data from [RFC 7043]: EUI48 and EUI64 records.
+//!
+//! This RFC defines the Eui48 and Eui64 record types that store IEEE
+//! extended unique identifiers, i.e., MAC addresses, in the DNS. Their
+//! presentation format is the hexadecimal octets separated by hyphens.
+//!
+//! [RFC 7043]: https://tools.ietf.org/html/rfc7043
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, Parse, ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{
+    CharSource, Scan, ScanError, Scanner, SyntaxError,
+};
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
+
+//------------ Eui48 ---------------------------------------------------------
+
+/// Eui48 record data.
+///
+/// The record data is a 48 bit IEEE extended unique identifier. In
+/// presentation format, it is written as six two-digit hexadecimal octets
+/// separated by hyphens, e.g., `00-00-5e-00-53-2a`.
+///
+/// The Eui48 record type is defined in RFC 7043, section 3.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Eui48 {
+    addr: [u8; 6],
+}
+
+impl Eui48 {
+    /// Creates new Eui48 record data from the address octets.
+    pub fn new(addr: [u8; 6]) -> Self {
+        Eui48 { addr }
+    }
+
+    /// The address octets.
+    pub fn addr(&self) -> [u8; 6] {
+        self.addr
+    }
+
+    pub fn flatten_into(self) -> Result<Eui48, PushError> {
+        Ok(self)
+    }
+}
+
+//--- OctetsFrom
+
+impl OctetsFrom<Eui48> for Eui48 {
+    fn octets_from(source: Eui48) -> Result<Self, ShortBuf> {
+        Ok(source)
+    }
+}
+
+//--- From and FromStr
+
+impl From<[u8; 6]> for Eui48 {
+    fn from(addr: [u8; 6]) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl FromStr for Eui48 {
+    type Err = FromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut addr = [0; 6];
+        parse_eui(s, &mut addr)?;
+        Ok(Self::new(addr))
+    }
+}
+
+//--- CanonicalOrd
+
+impl CanonicalOrd for Eui48 {
+    fn canonical_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: AsRef<[u8]>> Parse<Ref> for Eui48 {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let mut addr = [0; 6];
+        parser.parse_buf(&mut addr)?;
+        Ok(Self::new(addr))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance(6)
+    }
+}
+
+impl Compose for Eui48 {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_slice(&self.addr)
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Eui48 {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        scanner.scan_string_word(|word| {
+            Self::from_str(&word).map_err(SyntaxError::content)
+        })
+    }
+}
+
+impl fmt::Display for Eui48 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_eui(&self.addr, f)
+    }
+}
+
+//--- RtypeRecordData
+
+impl RtypeRecordData for Eui48 {
+    const RTYPE: Rtype = Rtype::Eui48;
+}
+
+//------------ Eui64 ---------------------------------------------------------
+
+/// Eui64 record data.
+///
+/// The record data is a 64 bit IEEE extended unique identifier. In
+/// presentation format, it is written as eight two-digit hexadecimal
+/// octets separated by hyphens, e.g., `00-00-5e-ef-10-00-00-2a`.
+///
+/// The Eui64 record type is defined in RFC 7043, section 4.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Eui64 {
+    addr: [u8; 8],
+}
+
+impl Eui64 {
+    /// Creates new Eui64 record data from the address octets.
+    pub fn new(addr: [u8; 8]) -> Self {
+        Eui64 { addr }
+    }
+
+    /// The address octets.
+    pub fn addr(&self) -> [u8; 8] {
+        self.addr
+    }
+
+    pub fn flatten_into(self) -> Result<Eui64, PushError> {
+        Ok(self)
+    }
+}
+
+//--- OctetsFrom
+
+impl OctetsFrom<Eui64> for Eui64 {
+    fn octets_from(source: Eui64) -> Result<Self, ShortBuf> {
+        Ok(source)
+    }
+}
+
+//--- From and FromStr
+
+impl From<[u8; 8]> for Eui64 {
+    fn from(addr: [u8; 8]) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl FromStr for Eui64 {
+    type Err = FromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut addr = [0; 8];
+        parse_eui(s, &mut addr)?;
+        Ok(Self::new(addr))
+    }
+}
+
+//--- CanonicalOrd
+
+impl CanonicalOrd for Eui64 {
+    fn canonical_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: AsRef<[u8]>> Parse<Ref> for Eui64 {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let mut addr = [0; 8];
+        parser.parse_buf(&mut addr)?;
+        Ok(Self::new(addr))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance(8)
+    }
+}
+
+impl Compose for Eui64 {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_slice(&self.addr)
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Eui64 {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        scanner.scan_string_word(|word| {
+            Self::from_str(&word).map_err(SyntaxError::content)
+        })
+    }
+}
+
+impl fmt::Display for Eui64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_eui(&self.addr, f)
+    }
+}
+
+//--- RtypeRecordData
+
+impl RtypeRecordData for Eui64 {
+    const RTYPE: Rtype = Rtype::Eui64;
+}
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Parses hyphen separated hex octets into `addr`.
+///
+/// Each octet has to be given as exactly two hex digits and there have to
+/// be exactly as many octets as `addr` is long.
+fn parse_eui(s: &str, addr: &mut [u8]) -> Result<(), FromStrError> {
+    let mut parts = s.split('-');
+    for octet in addr.iter_mut() {
+        let part = parts.next().ok_or(FromStrError)?;
+        if part.len() != 2 || !part.bytes().all(|ch| ch.is_ascii_hexdigit()) {
+            return Err(FromStrError);
+        }
+        *octet = u8::from_str_radix(part, 16).map_err(|_| FromStrError)?;
+    }
+    if parts.next().is_some() {
+        return Err(FromStrError);
+    }
+    Ok(())
+}
+
+/// Writes the octets as hyphen separated lowercase hex digits.
+fn fmt_eui(addr: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
+    let mut first = true;
+    for octet in addr {
+        if first {
+            first = false;
+        } else {
+            f.write_str("-")?;
+        }
+        write!(f, "{:02x}", octet)?;
+    }
+    Ok(())
+}
+
+//------------ FromStrError --------------------------------------------------
+
+/// A string could not be converted into an EUI address.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FromStrError;
+
+impl fmt::Display for FromStrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid EUI address")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromStrError {}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x00\x00\x5e\x00\x53\x2a";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let eui = Eui48::parse(&mut parser).unwrap();
+        assert_eq!(eui.to_string(), "00-00-5e-00-53-2a");
+        let mut buf = Vec::new();
+        eui.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let rdata = b"\x00\x00\x5e\xef\x10\x00\x00\x2a";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let eui = Eui64::parse(&mut parser).unwrap();
+        assert_eq!(eui.to_string(), "00-00-5e-ef-10-00-00-2a");
+        let mut buf = Vec::new();
+        eui.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            Eui48::from_str("00-00-5E-00-53-2a").unwrap().addr(),
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x2a]
+        );
+        assert!(Eui48::from_str("00-00-5e-00-53").is_err());
+        assert!(Eui48::from_str("00-00-5e-00-53-2a-00").is_err());
+        assert!(Eui48::from_str("00:00:5e:00:53:2a").is_err());
+        assert!(Eui48::from_str("0-00-5e-00-53-2a").is_err());
+        assert!(Eui48::from_str("+0-00-5e-00-53-2a").is_err());
+        assert!(Eui64::from_str("00-00-5e-ef-10-00-00-2a").is_ok());
+        assert!(Eui64::from_str("00-00-5e-00-53-2a").is_err());
+    }
+}
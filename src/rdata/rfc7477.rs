@@ -0,0 +1,701 @@
+//! Record data from [RFC 7477]: CSYNC records.
+//!
+//! This RFC defines the Csync record type which a child zone uses to ask
+//! its parent to synchronize the delegation, i.e., the NS records and the
+//! glue address records, with the authoritative data in the child.
+//!
+//! Besides the record data type, the module provides a helper for the
+//! parental agent: [`Csync::delegation_changes`] takes the delegation as
+//! currently present in the parent and the data fetched from the child and
+//! determines which records need to be removed from and added to the
+//! parent zone.
+//!
+//! [RFC 7477]: https://tools.ietf.org/html/rfc7477
+
+use super::rfc4034::RtypeBitmap;
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::PushError;
+#[cfg(feature = "std")]
+use crate::base::name::ToDname;
+#[cfg(feature = "std")]
+use crate::base::net::IpAddr;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+use crate::base::serial::Serial;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+//------------ Csync ---------------------------------------------------------
+
+/// Csync record data.
+///
+/// The Csync record lists the record types the parent should copy from
+/// the child apex into the delegation. It also carries the SOA serial of
+/// the child zone the record refers to and two flags that tell the
+/// parental agent under which conditions it may act on the record.
+///
+/// The Csync record type is defined in RFC 7477, section 2.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>
+        ",
+        deserialize = "
+            Octets:
+                crate::base::octets::FromBuilder
+                + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as crate::base::octets::FromBuilder>::Builder:
+                OctetsBuilder<Octets = Octets>
+                + crate::base::octets::EmptyBuilder
+                + AsRef<[u8]> + AsMut<[u8]>,
+        ",
+    ))
+)]
+pub struct Csync<Octets> {
+    serial: Serial,
+    flags: u16,
+    types: RtypeBitmap<Octets>,
+}
+
+impl<Octets> Csync<Octets> {
+    /// The flag allowing the parent to process the record immediately.
+    pub const IMMEDIATE: u16 = 0x0001;
+
+    /// The flag requiring the child’s SOA serial to be at least `serial`.
+    pub const SOA_MINIMUM: u16 = 0x0002;
+
+    /// Creates a new Csync record data from the components.
+    pub fn new(
+        serial: Serial,
+        flags: u16,
+        types: RtypeBitmap<Octets>,
+    ) -> Self {
+        Csync {
+            serial,
+            flags,
+            types,
+        }
+    }
+
+    /// The SOA serial of the child zone the record refers to.
+    pub fn serial(&self) -> Serial {
+        self.serial
+    }
+
+    /// The raw flags field.
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    /// Returns whether the immediate flag is set.
+    ///
+    /// If it is not set, the parental agent must not act on the record
+    /// until the change has been approved through some other means.
+    pub fn immediate(&self) -> bool {
+        self.flags & Self::IMMEDIATE != 0
+    }
+
+    /// Returns whether the soaminimum flag is set.
+    ///
+    /// If it is set, the parental agent must only act on the record if the
+    /// SOA serial of the child zone it fetched the data from is at least
+    /// the serial given in the record.
+    pub fn soa_minimum(&self) -> bool {
+        self.flags & Self::SOA_MINIMUM != 0
+    }
+
+    /// The record types that should be synchronized.
+    pub fn types(&self) -> &RtypeBitmap<Octets> {
+        &self.types
+    }
+}
+
+impl<SrcOctets> Csync<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Csync<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        let Self {
+            serial,
+            flags,
+            types,
+        } = self;
+        Ok(Csync::new(serial, flags, types.octets_into()?))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Octets: AsRef<[u8]>> Csync<Octets> {
+    /// Determines the changes to a delegation requested by the record.
+    ///
+    /// The `apex` is the name of the child zone and `child_serial` the SOA
+    /// serial of the child zone that `child` was fetched from. `current`
+    /// is the delegation as present in the parent zone, while `child`
+    /// contains the NS and address records fetched from the child zone.
+    /// Only address records of in-bailiwick name servers are considered
+    /// glue and only record types present in the record’s type bitmap are
+    /// synchronized.
+    ///
+    /// The method fails if the soaminimum flag is set and `child_serial`
+    /// is older than the serial of the record, if the type bitmap contains
+    /// types other than NS, A, or AAAA, or if the synchronization would
+    /// leave the delegation without name servers.
+    ///
+    /// The method does not check the immediate flag. The caller has to
+    /// make sure the change has been approved if it isn’t set.
+    pub fn delegation_changes<Apex, Name>(
+        &self,
+        apex: &Apex,
+        child_serial: Serial,
+        current: &Delegation<Name>,
+        child: &Delegation<Name>,
+    ) -> Result<DelegationChanges<Name>, CsyncError>
+    where
+        Apex: ToDname + ?Sized,
+        Name: ToDname + Clone,
+    {
+        if self.soa_minimum()
+            && !matches!(
+                child_serial.partial_cmp(&self.serial),
+                Some(Ordering::Equal) | Some(Ordering::Greater)
+            )
+        {
+            return Err(CsyncError::OldSerial);
+        }
+        for rtype in self.types.iter() {
+            if !matches!(rtype, Rtype::Ns | Rtype::A | Rtype::Aaaa) {
+                return Err(CsyncError::UnsupportedType(rtype));
+            }
+        }
+
+        let mut res = DelegationChanges::default();
+
+        // Determine the new name servers first since they decide which
+        // address records are glue.
+        let nameservers = if self.types.contains(Rtype::Ns) {
+            if child.nameservers.is_empty() {
+                return Err(CsyncError::NoNameservers);
+            }
+            for name in &current.nameservers {
+                if !contains_name(&child.nameservers, name) {
+                    res.remove_nameservers.push(name.clone());
+                }
+            }
+            for name in &child.nameservers {
+                if !contains_name(&current.nameservers, name)
+                    && !contains_name(&res.add_nameservers, name)
+                {
+                    res.add_nameservers.push(name.clone());
+                }
+            }
+            &child.nameservers
+        } else {
+            &current.nameservers
+        };
+
+        let sync_v4 = self.types.contains(Rtype::A);
+        let sync_v6 = self.types.contains(Rtype::Aaaa);
+        let synced = |addr: &IpAddr| match addr {
+            IpAddr::V4(_) => sync_v4,
+            IpAddr::V6(_) => sync_v6,
+        };
+        let is_glue = |name: &Name| {
+            contains_name(nameservers, name) && name.ends_with(apex)
+        };
+
+        // Glue of synchronized address families is replaced by the child’s
+        // data. Glue of name servers that are gone is removed regardless.
+        for (name, addr) in &current.addresses {
+            let keep = if synced(addr) {
+                is_glue(name) && contains_addr(&child.addresses, name, *addr)
+            } else {
+                contains_name(nameservers, name)
+            };
+            if !keep {
+                res.remove_addresses.push((name.clone(), *addr));
+            }
+        }
+        for (name, addr) in &child.addresses {
+            if synced(addr)
+                && is_glue(name)
+                && !contains_addr(&current.addresses, name, *addr)
+                && !contains_addr(&res.add_addresses, name, *addr)
+            {
+                res.add_addresses.push((name.clone(), *addr));
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Csync<SrcOctets>> for Csync<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Csync<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(Csync::new(
+            source.serial,
+            source.flags,
+            RtypeBitmap::octets_from(source.types)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Csync<Other>> for Csync<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Csync<Other>) -> bool {
+        self.serial == other.serial
+            && self.flags == other.flags
+            && self.types == other.types
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Csync<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Csync<Other>> for Csync<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Csync<Other>) -> Option<Ordering> {
+        match self.serial.partial_cmp(&other.serial) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        match self.flags.partial_cmp(&other.flags) {
+            Some(Ordering::Equal) => {}
+            other => return other,
+        }
+        self.types.partial_cmp(&other.types)
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Csync<Other>> for Csync<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Csync<Other>) -> Ordering {
+        match self.serial.canonical_cmp(&other.serial) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.flags.cmp(&other.flags) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.types.canonical_cmp(&other.types)
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Csync<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.serial.0.cmp(&other.serial.0) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.flags.cmp(&other.flags) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.types.cmp(&other.types)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Csync<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.serial.hash(state);
+        self.flags.hash(state);
+        self.types.hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Csync<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        Ok(Self::new(
+            Serial::parse(parser)?,
+            u16::parse(parser)?,
+            RtypeBitmap::parse(parser)?,
+        ))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        Serial::skip(parser)?;
+        u16::skip(parser)?;
+        RtypeBitmap::skip(parser)
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Csync<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|buf| {
+            self.serial.compose(buf)?;
+            self.flags.compose(buf)?;
+            self.types.compose(buf)
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Csync<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(
+            Serial::scan(scanner)?,
+            u16::scan(scanner)?,
+            RtypeBitmap::scan(scanner)?,
+        ))
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Csync<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.serial, self.flags, self.types)
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Csync<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Csync")
+            .field("serial", &self.serial)
+            .field("flags", &self.flags)
+            .field("types", &self.types)
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Csync<Octets> {
+    const RTYPE: Rtype = Rtype::Csync;
+}
+
+//------------ Delegation ----------------------------------------------------
+
+/// The NS and address records making up a delegation.
+///
+/// This type is used as input to [`Csync::delegation_changes`] both for the
+/// delegation as currently present in the parent zone and for the data
+/// fetched from the apex of the child zone.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct Delegation<Name> {
+    nameservers: Vec<Name>,
+    addresses: Vec<(Name, IpAddr)>,
+}
+
+#[cfg(feature = "std")]
+impl<Name> Delegation<Name> {
+    /// Creates a new, empty delegation.
+    pub fn new() -> Self {
+        Delegation {
+            nameservers: Vec::new(),
+            addresses: Vec::new(),
+        }
+    }
+
+    /// Adds the name of a name server, i.e., the content of an NS record.
+    pub fn add_nameserver(&mut self, name: Name) {
+        self.nameservers.push(name)
+    }
+
+    /// Adds an address of a host, i.e., the content of an A or AAAA record.
+    pub fn add_address(&mut self, name: Name, addr: IpAddr) {
+        self.addresses.push((name, addr))
+    }
+
+    /// Returns the names of the name servers.
+    pub fn nameservers(&self) -> &[Name] {
+        &self.nameservers
+    }
+
+    /// Returns the host addresses.
+    pub fn addresses(&self) -> &[(Name, IpAddr)] {
+        &self.addresses
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Name> Default for Delegation<Name> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//------------ DelegationChanges ---------------------------------------------
+
+/// The changes to a delegation requested by a Csync record.
+///
+/// Values of this type are returned by [`Csync::delegation_changes`]. The
+/// NS records and glue address records listed for removal should be
+/// deleted from the parent zone and those listed for addition be added.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct DelegationChanges<Name> {
+    remove_nameservers: Vec<Name>,
+    add_nameservers: Vec<Name>,
+    remove_addresses: Vec<(Name, IpAddr)>,
+    add_addresses: Vec<(Name, IpAddr)>,
+}
+
+#[cfg(feature = "std")]
+impl<Name> DelegationChanges<Name> {
+    /// Returns whether the delegation stays unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.remove_nameservers.is_empty()
+            && self.add_nameservers.is_empty()
+            && self.remove_addresses.is_empty()
+            && self.add_addresses.is_empty()
+    }
+
+    /// Returns the name servers whose NS records should be removed.
+    pub fn remove_nameservers(&self) -> &[Name] {
+        &self.remove_nameservers
+    }
+
+    /// Returns the name servers for which NS records should be added.
+    pub fn add_nameservers(&self) -> &[Name] {
+        &self.add_nameservers
+    }
+
+    /// Returns the glue address records that should be removed.
+    pub fn remove_addresses(&self) -> &[(Name, IpAddr)] {
+        &self.remove_addresses
+    }
+
+    /// Returns the glue address records that should be added.
+    pub fn add_addresses(&self) -> &[(Name, IpAddr)] {
+        &self.add_addresses
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Name> Default for DelegationChanges<Name> {
+    fn default() -> Self {
+        DelegationChanges {
+            remove_nameservers: Vec::new(),
+            add_nameservers: Vec::new(),
+            remove_addresses: Vec::new(),
+            add_addresses: Vec::new(),
+        }
+    }
+}
+
+//------------ CsyncError ----------------------------------------------------
+
+/// A Csync record could not be processed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CsyncError {
+    /// The soaminimum flag is set and the child’s serial is too old.
+    OldSerial,
+
+    /// The type bitmap contains a type that can’t be synchronized.
+    UnsupportedType(Rtype),
+
+    /// The child zone doesn’t have any NS records.
+    NoNameservers,
+}
+
+impl fmt::Display for CsyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CsyncError::OldSerial => {
+                f.write_str("child SOA serial older than CSYNC serial")
+            }
+            CsyncError::UnsupportedType(rtype) => {
+                write!(f, "unsupported record type {} in CSYNC", rtype)
+            }
+            CsyncError::NoNameservers => {
+                f.write_str("child zone has no NS records")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CsyncError {}
+
+//------------ Helper Functions ----------------------------------------------
+
+#[cfg(feature = "std")]
+fn contains_name<Name: ToDname>(names: &[Name], name: &Name) -> bool {
+    names.iter().any(|item| item.name_eq(name))
+}
+
+#[cfg(feature = "std")]
+fn contains_addr<Name: ToDname>(
+    addrs: &[(Name, IpAddr)],
+    name: &Name,
+    addr: IpAddr,
+) -> bool {
+    addrs
+        .iter()
+        .any(|(item, item_addr)| *item_addr == addr && item.name_eq(name))
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use crate::base::name::Dname;
+    use crate::rdata::rfc4034::RtypeBitmapBuilder;
+    use std::string::ToString;
+
+    fn csync(flags: u16, types: &[Rtype]) -> Csync<Vec<u8>> {
+        let mut builder = RtypeBitmapBuilder::<Vec<u8>>::new();
+        for rtype in types {
+            builder.add(*rtype).unwrap();
+        }
+        Csync::new(Serial(66), flags, builder.finalize())
+    }
+
+    fn name(s: &str) -> Dname<Vec<u8>> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x00\x00\x00\x42\x00\x03\x00\x04\x60\x00\x00\x08";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let csync = Csync::parse(&mut parser).unwrap();
+        assert_eq!(csync.serial(), Serial(66));
+        assert!(csync.immediate());
+        assert!(csync.soa_minimum());
+        assert_eq!(csync.to_string(), "66 3 A NS AAAA");
+
+        let mut buf = Vec::new();
+        csync.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new("66 1 A NS AAAA\n");
+        let csync = Csync::scan(&mut scanner).unwrap();
+        assert!(csync.immediate());
+        assert!(!csync.soa_minimum());
+        assert_eq!(
+            csync,
+            self::csync(1, &[Rtype::A, Rtype::Ns, Rtype::Aaaa])
+        );
+    }
+
+    #[test]
+    fn delegation_changes() {
+        let apex = name("example.com.");
+        let mut current = Delegation::new();
+        current.add_nameserver(name("ns1.example.com."));
+        current.add_nameserver(name("ns.example.net."));
+        current.add_address(name("ns1.example.com."), [192, 0, 2, 1].into());
+        current.add_address(
+            name("ns1.example.com."),
+            [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+                .into(),
+        );
+
+        let mut child = Delegation::new();
+        child.add_nameserver(name("NS2.example.com."));
+        child.add_nameserver(name("ns.example.net."));
+        child.add_address(name("ns2.example.com."), [192, 0, 2, 2].into());
+        child.add_address(name("ns.example.net."), [198, 51, 100, 1].into());
+
+        // NS and A: ns1 and both its addresses go, ns2 with its v4
+        // address comes. The out-of-bailiwick address is not glue.
+        let changes = csync(1, &[Rtype::Ns, Rtype::A])
+            .delegation_changes(&apex, Serial(66), &current, &child)
+            .unwrap();
+        assert_eq!(changes.remove_nameservers(), [name("ns1.example.com.")]);
+        assert_eq!(changes.add_nameservers(), [name("NS2.example.com.")]);
+        assert_eq!(changes.remove_addresses().len(), 2);
+        assert_eq!(
+            changes.add_addresses(),
+            [(name("ns2.example.com."), [192, 0, 2, 2].into())]
+        );
+
+        // Only A: the v4 glue of ns1 is dropped since the child has none.
+        let changes = csync(1, &[Rtype::A])
+            .delegation_changes(&apex, Serial(66), &current, &child)
+            .unwrap();
+        assert!(changes.remove_nameservers().is_empty());
+        assert!(changes.add_nameservers().is_empty());
+        assert_eq!(
+            changes.remove_addresses(),
+            [(name("ns1.example.com."), [192, 0, 2, 1].into())]
+        );
+        assert!(changes.add_addresses().is_empty());
+
+        // Nothing to do if the child has the same data.
+        assert!(csync(3, &[Rtype::Ns, Rtype::A, Rtype::Aaaa])
+            .delegation_changes(&apex, Serial(67), &current, &current)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn delegation_changes_errors() {
+        let apex = name("example.com.");
+        let mut current = Delegation::new();
+        current.add_nameserver(name("ns1.example.com."));
+        let empty = Delegation::new();
+
+        assert_eq!(
+            csync(3, &[Rtype::Ns])
+                .delegation_changes(&apex, Serial(65), &current, &current)
+                .unwrap_err(),
+            CsyncError::OldSerial
+        );
+        assert!(csync(1, &[Rtype::Ns])
+            .delegation_changes(&apex, Serial(65), &current, &current)
+            .is_ok());
+        assert_eq!(
+            csync(1, &[Rtype::Ns, Rtype::Mx])
+                .delegation_changes(&apex, Serial(66), &current, &current)
+                .unwrap_err(),
+            CsyncError::UnsupportedType(Rtype::Mx)
+        );
+        assert_eq!(
+            csync(1, &[Rtype::Ns])
+                .delegation_changes(&apex, Serial(66), &current, &empty)
+                .unwrap_err(),
+            CsyncError::NoNameservers
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Record data from [RFC 7553]: URI records.
+//!
+//! This RFC defines the URI record type that maps a domain name to one or
+//! more URIs.
+//!
+//! [RFC 7553]: https://tools.ietf.org/html/rfc7553
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+use crate::base::str::Symbol;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Uri -----------------------------------------------------------
+
+/// URI record data.
+///
+/// A URI record provides a URI for the domain name where it is found. Like
+/// with SRV records, the priority and weight fields allow selecting between
+/// several records. The target is the URI itself and extends to the end of
+/// the record data.
+///
+/// In presentation format, the target is given as a quoted string.
+///
+/// The URI record type is defined in RFC 7553, section 4.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>
+        ",
+        deserialize = "
+            Octets:
+                crate::base::octets::FromBuilder
+                + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as crate::base::octets::FromBuilder>::Builder:
+                crate::base::octets::EmptyBuilder,
+        ",
+    ))
+)]
+pub struct Uri<Octets> {
+    priority: u16,
+    weight: u16,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "crate::base::octets::SerializeOctets::serialize_octets",
+            deserialize_with = "crate::base::octets::DeserializeOctets::deserialize_octets",
+        )
+    )]
+    target: Octets,
+}
+
+impl<Octets> Uri<Octets> {
+    pub fn new(priority: u16, weight: u16, target: Octets) -> Self {
+        Uri {
+            priority,
+            weight,
+            target,
+        }
+    }
+
+    /// Returns the priority of the target.
+    ///
+    /// Clients should use the target with the lowest priority first.
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// Returns the weight for selecting among targets of equal priority.
+    pub fn weight(&self) -> u16 {
+        self.weight
+    }
+
+    /// Returns the target URI.
+    pub fn target(&self) -> &Octets {
+        &self.target
+    }
+
+    pub fn into_target(self) -> Octets {
+        self.target
+    }
+}
+
+impl<SrcOctets> Uri<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Uri<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        let Self {
+            priority,
+            weight,
+            target,
+        } = self;
+        Ok(Uri::new(priority, weight, target.octets_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Uri<SrcOctets>> for Uri<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Uri<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(Uri::new(
+            source.priority,
+            source.weight,
+            Octets::octets_from(source.target)?,
+        ))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Uri<Other>> for Uri<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Uri<Other>) -> bool {
+        self.priority == other.priority
+            && self.weight == other.weight
+            && self.target.as_ref().eq(other.target.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Uri<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Uri<Other>> for Uri<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Uri<Other>) -> Option<Ordering> {
+        Some(self.canonical_cmp(other))
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Uri<Other>> for Uri<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Uri<Other>) -> Ordering {
+        match self.priority.cmp(&other.priority) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.weight.cmp(&other.weight) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.target.as_ref().cmp(other.target.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Uri<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Uri<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.priority.hash(state);
+        self.weight.hash(state);
+        self.target.as_ref().hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Uri<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let priority = u16::parse(parser)?;
+        let weight = u16::parse(parser)?;
+        let len = parser.remaining();
+        Ok(Self::new(priority, weight, parser.parse_octets(len)?))
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        u16::skip(parser)?;
+        u16::skip(parser)?;
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Uri<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|buf| {
+            self.priority.compose(buf)?;
+            self.weight.compose(buf)?;
+            buf.append_slice(self.target.as_ref())
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Uri<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        Ok(Self::new(
+            u16::scan(scanner)?,
+            u16::scan(scanner)?,
+            scanner.scan_byte_phrase(Ok)?,
+        ))
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Uri<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} \"", self.priority, self.weight)?;
+        for &ch in self.target.as_ref() {
+            fmt::Display::fmt(&Symbol::from_octet(ch), f)?;
+        }
+        f.write_str("\"")
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Uri<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Uri")
+            .field("priority", &self.priority)
+            .field("weight", &self.weight)
+            .field("target", &self.target.as_ref())
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Uri<Octets> {
+    const RTYPE: Rtype = Rtype::Uri;
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x00\x0a\x00\x01ftp://ftp1.example.com/public";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let uri = Uri::parse(&mut parser).unwrap();
+        assert_eq!(uri.priority(), 10);
+        assert_eq!(uri.weight(), 1);
+        assert_eq!(uri.to_string(), "10 1 \"ftp://ftp1.example.com/public\"");
+
+        let mut buf = Vec::new();
+        uri.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner =
+            Scanner::new("10 1 \"https://www.example.com/path\"\n");
+        assert_eq!(
+            Uri::scan(&mut scanner).unwrap(),
+            Uri::new(10, 1, b"https://www.example.com/path")
+        );
+    }
+}
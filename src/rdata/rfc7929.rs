@@ -0,0 +1,232 @@
+//! Record data from [RFC 7929]: OPENPGPKEY records.
+//!
+//! This RFC defines the OPENPGPKEY record type that publishes OpenPGP
+//! public keys for email addresses.
+//!
+//! [RFC 7929]: https://tools.ietf.org/html/rfc7929
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+use crate::utils::base64;
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Openpgpkey ----------------------------------------------------
+
+/// OPENPGPKEY record data.
+///
+/// The record data consists of a single OpenPGP transferable public key
+/// in binary form that extends to the end of the record data. In
+/// presentation format, the key is given in Base 64.
+///
+/// The OPENPGPKEY record type is defined in RFC 7929, section 2.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>
+        ",
+        deserialize = "
+            Octets:
+                crate::base::octets::FromBuilder
+                + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as crate::base::octets::FromBuilder>::Builder:
+                OctetsBuilder<Octets = Octets>
+                + crate::base::octets::EmptyBuilder,
+        ",
+    ))
+)]
+pub struct Openpgpkey<Octets> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base64::serde")
+    )]
+    key: Octets,
+}
+
+impl<Octets> Openpgpkey<Octets> {
+    pub fn new(key: Octets) -> Self {
+        Openpgpkey { key }
+    }
+
+    /// Returns the OpenPGP transferable public key.
+    pub fn key(&self) -> &Octets {
+        &self.key
+    }
+
+    pub fn into_key(self) -> Octets {
+        self.key
+    }
+}
+
+impl<SrcOctets> Openpgpkey<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Openpgpkey<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        Ok(Openpgpkey::new(self.key.octets_into()?))
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Openpgpkey<SrcOctets>>
+    for Openpgpkey<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Openpgpkey<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(Openpgpkey::new(Octets::octets_from(source.key)?))
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Openpgpkey<Other>> for Openpgpkey<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Openpgpkey<Other>) -> bool {
+        self.key.as_ref().eq(other.key.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Openpgpkey<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Openpgpkey<Other>> for Openpgpkey<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Openpgpkey<Other>) -> Option<Ordering> {
+        self.key.as_ref().partial_cmp(other.key.as_ref())
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Openpgpkey<Other>> for Openpgpkey<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Openpgpkey<Other>) -> Ordering {
+        self.key.as_ref().cmp(other.key.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Openpgpkey<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Openpgpkey<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.key.as_ref().hash(state)
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Openpgpkey<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let len = parser.remaining();
+        parser.parse_octets(len).map(Self::new)
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Openpgpkey<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_slice(self.key.as_ref())
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Openpgpkey<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        scanner.scan_base64_phrases(Ok).map(Self::new)
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Openpgpkey<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        base64::display(&self.key, f)
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Openpgpkey<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Openpgpkey")
+            .field(&self.key.as_ref())
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Openpgpkey<Octets> {
+    const RTYPE: Rtype = Rtype::Openpgpkey;
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    #[test]
+    fn parse_compose_display() {
+        let rdata = b"\x99\x01\x0d\x04";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let key = Openpgpkey::parse(&mut parser).unwrap();
+        assert_eq!(key.key(), &rdata.as_ref());
+        assert_eq!(key.to_string(), "mQENBA==");
+
+        let mut buf = Vec::new();
+        key.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new("mQEN BA==\n");
+        assert_eq!(
+            Openpgpkey::scan(&mut scanner).unwrap(),
+            Openpgpkey::new(b"\x99\x01\x0d\x04".as_ref())
+        );
+    }
+}
@@ -0,0 +1,486 @@
+//! Record data from [RFC 8005]: HIP records.
+//!
+//! This RFC defines the Hip record type that stores the Host Identity Tag
+//! and Host Identity of a Host Identity Protocol node together with the
+//! names of its rendezvous servers.
+//!
+//! [RFC 8005]: https://tools.ietf.org/html/rfc8005
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::{IpseckeyAlg, Rtype};
+use crate::base::name::{Dname, Label, PushError};
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{
+    CharSource, Scan, ScanError, Scanner, SyntaxError,
+};
+use crate::utils::{base16, base64};
+#[cfg(feature = "master")]
+use bytes::{Bytes, BytesMut};
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Hip -----------------------------------------------------------
+
+/// Hip record data.
+///
+/// The record data consists of the Host Identity Tag (HIT), the algorithm
+/// and content of the public key forming the Host Identity, and an
+/// optional list of rendezvous servers. The algorithm values are those
+/// used by the IPSECKEY record type.
+///
+/// The rendezvous servers are kept as their uncompressed wire format,
+/// one name after another, and can be accessed via
+/// [`rendezvous_servers`][Self::rendezvous_servers].
+///
+/// In presentation format, the HIT is given in Base 16 and the public key
+/// in Base 64, followed by the rendezvous servers.
+///
+/// The Hip record type is defined in RFC 8005, section 5.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>
+        ",
+        deserialize = "
+            Octets:
+                crate::base::octets::FromBuilder
+                + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as crate::base::octets::FromBuilder>::Builder:
+                OctetsBuilder<Octets = Octets>
+                + crate::base::octets::EmptyBuilder,
+        ",
+    ))
+)]
+pub struct Hip<Octets> {
+    algorithm: IpseckeyAlg,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base16::serde")
+    )]
+    hit: Octets,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base64::serde")
+    )]
+    public_key: Octets,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base16::serde")
+    )]
+    rendezvous: Octets,
+}
+
+impl<Octets: AsRef<[u8]>> Hip<Octets> {
+    /// Creates new Hip record data from its components.
+    ///
+    /// The `rendezvous` octets must contain a sequence of uncompressed
+    /// absolute domain names. Returns an error if they don’t or if the HIT
+    /// or public key are too long to be encoded.
+    pub fn new(
+        algorithm: IpseckeyAlg,
+        hit: Octets,
+        public_key: Octets,
+        rendezvous: Octets,
+    ) -> Result<Self, HipError> {
+        if hit.as_ref().len() > usize::from(u8::MAX) {
+            return Err(HipError::LongHit);
+        }
+        if public_key.as_ref().len() > usize::from(u16::MAX) {
+            return Err(HipError::LongPublicKey);
+        }
+        let mut tmp = rendezvous.as_ref();
+        while !tmp.is_empty() {
+            tmp = split_dname(tmp)?.1;
+        }
+        Ok(Hip {
+            algorithm,
+            hit,
+            public_key,
+            rendezvous,
+        })
+    }
+
+    /// The algorithm of the public key.
+    pub fn algorithm(&self) -> IpseckeyAlg {
+        self.algorithm
+    }
+
+    /// The Host Identity Tag.
+    pub fn hit(&self) -> &Octets {
+        &self.hit
+    }
+
+    /// The public key forming the Host Identity.
+    pub fn public_key(&self) -> &Octets {
+        &self.public_key
+    }
+
+    /// The wire format of the rendezvous server names.
+    pub fn rendezvous(&self) -> &Octets {
+        &self.rendezvous
+    }
+
+    /// Returns an iterator over the rendezvous server names.
+    pub fn rendezvous_servers(&self) -> RendezvousServers<'_> {
+        RendezvousServers(self.rendezvous.as_ref())
+    }
+}
+
+impl<SrcOctets> Hip<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Hip<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        Ok(Hip {
+            algorithm: self.algorithm,
+            hit: self.hit.octets_into()?,
+            public_key: self.public_key.octets_into()?,
+            rendezvous: self.rendezvous.octets_into()?,
+        })
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Hip<SrcOctets>> for Hip<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Hip<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(Hip {
+            algorithm: source.algorithm,
+            hit: Octets::octets_from(source.hit)?,
+            public_key: Octets::octets_from(source.public_key)?,
+            rendezvous: Octets::octets_from(source.rendezvous)?,
+        })
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Hip<Other>> for Hip<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Hip<Other>) -> bool {
+        self.algorithm == other.algorithm
+            && self.hit.as_ref().eq(other.hit.as_ref())
+            && self.public_key.as_ref().eq(other.public_key.as_ref())
+            && self.rendezvous.as_ref().eq(other.rendezvous.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Hip<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Hip<Other>> for Hip<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Hip<Other>) -> Option<Ordering> {
+        Some(self.canonical_cmp(other))
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Hip<Other>> for Hip<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Hip<Other>) -> Ordering {
+        // Rendezvous server names are compared as is since Hip isn’t
+        // among the types whose names are lowercased by RFC 4034.
+        match self.hit.as_ref().len().cmp(&other.hit.as_ref().len()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.algorithm.to_int().cmp(&other.algorithm.to_int()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self
+            .public_key
+            .as_ref()
+            .len()
+            .cmp(&other.public_key.as_ref().len())
+        {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.hit.as_ref().cmp(other.hit.as_ref()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.public_key.as_ref().cmp(other.public_key.as_ref()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.rendezvous.as_ref().cmp(other.rendezvous.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Hip<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Hip<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.algorithm.hash(state);
+        self.hit.as_ref().hash(state);
+        self.public_key.as_ref().hash(state);
+        self.rendezvous.as_ref().hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Hip<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let hit_len = u8::parse(parser)?;
+        let algorithm = IpseckeyAlg::parse(parser)?;
+        let pk_len = u16::parse(parser)?;
+        let hit = parser.parse_octets(usize::from(hit_len))?;
+        let public_key = parser.parse_octets(usize::from(pk_len))?;
+        let len = parser.remaining();
+        let rendezvous = parser.parse_octets(len)?;
+        Hip::new(algorithm, hit, public_key, rendezvous).map_err(Into::into)
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Hip<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|target| {
+            (self.hit.as_ref().len() as u8).compose(target)?;
+            self.algorithm.compose(target)?;
+            (self.public_key.as_ref().len() as u16).compose(target)?;
+            target.append_slice(self.hit.as_ref())?;
+            target.append_slice(self.public_key.as_ref())?;
+            target.append_slice(self.rendezvous.as_ref())
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Hip<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        let algorithm = IpseckeyAlg::scan(scanner)?;
+        let hit = scanner.scan_string_word(|word| {
+            base16::decode::<Bytes>(&word).map_err(SyntaxError::content)
+        })?;
+        let public_key = scanner.scan_string_word(|word| {
+            base64::decode::<Bytes>(&word).map_err(SyntaxError::content)
+        })?;
+        let mut rendezvous = BytesMut::new();
+        while !scanner.entry_ended()? {
+            let name = Dname::<Bytes>::scan(scanner)?;
+            rendezvous.extend_from_slice(name.as_slice());
+        }
+        Hip::new(algorithm, hit, public_key, rendezvous.freeze()).map_err(
+            |err| ScanError::Syntax(SyntaxError::content(err), scanner.pos()),
+        )
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Hip<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ", self.algorithm)?;
+        base16::display(&self.hit, f)?;
+        f.write_str(" ")?;
+        base64::display(&self.public_key, f)?;
+        for name in self.rendezvous_servers() {
+            write!(f, " {}.", name)?;
+        }
+        Ok(())
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Hip<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Hip(")?;
+        fmt::Display::fmt(self, f)?;
+        f.write_str(")")
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Hip<Octets> {
+    const RTYPE: Rtype = Rtype::Hip;
+}
+
+//------------ RendezvousServers ---------------------------------------------
+
+/// An iterator over the rendezvous servers of a Hip record.
+#[derive(Clone, Debug)]
+pub struct RendezvousServers<'a>(&'a [u8]);
+
+impl<'a> Iterator for RendezvousServers<'a> {
+    type Item = &'a Dname<[u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The names were checked when creating the record data, so
+        // splitting can only fail if we are at the end.
+        let (name, tail) = split_dname(self.0).ok()?;
+        self.0 = tail;
+        Some(name)
+    }
+}
+
+/// Splits an uncompressed domain name from the beginning of a slice.
+fn split_dname(slice: &[u8]) -> Result<(&Dname<[u8]>, &[u8]), HipError> {
+    let mut tmp = slice;
+    loop {
+        let (label, tail) =
+            Label::split_from(tmp).map_err(|_| HipError::BadRendezvous)?;
+        tmp = tail;
+        if label.is_root() {
+            break;
+        }
+    }
+    let (name, tail) = slice.split_at(slice.len() - tmp.len());
+    let name =
+        Dname::from_slice(name).map_err(|_| HipError::BadRendezvous)?;
+    Ok((name, tail))
+}
+
+//------------ HipError ------------------------------------------------------
+
+/// Hip record data was invalid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HipError {
+    /// The HIT was longer than 255 octets.
+    LongHit,
+
+    /// The public key was longer than 65535 octets.
+    LongPublicKey,
+
+    /// The rendezvous servers weren’t a sequence of uncompressed names.
+    BadRendezvous,
+}
+
+//--- From
+
+impl From<HipError> for ParseError {
+    fn from(err: HipError) -> ParseError {
+        match err {
+            HipError::LongHit => ParseError::form_error("long HIT"),
+            HipError::LongPublicKey => {
+                ParseError::form_error("long public key")
+            }
+            HipError::BadRendezvous => {
+                ParseError::form_error("invalid rendezvous server")
+            }
+        }
+    }
+}
+
+//--- Display and Error
+
+impl fmt::Display for HipError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            HipError::LongHit => "long HIT",
+            HipError::LongPublicKey => "long public key",
+            HipError::BadRendezvous => "invalid rendezvous server",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HipError {}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    const HIT: &[u8] = b"\x20\x01\x00\x10\x7b\x1a\x74\xdf\
+                         \x36\x56\x39\xcc\x39\xf1\xd5\x78";
+
+    #[test]
+    fn parse_compose_display() {
+        let mut rdata = Vec::new();
+        rdata.extend_from_slice(b"\x10\x02\x00\x04");
+        rdata.extend_from_slice(HIT);
+        rdata.extend_from_slice(b"\x03\x01\x00\x01");
+        rdata.extend_from_slice(b"\x04rvs1\x07example\x03com\x00");
+        rdata.extend_from_slice(b"\x04rvs2\x07example\x03com\x00");
+        let mut parser = Parser::from_ref(rdata.as_slice());
+        let hip = Hip::parse(&mut parser).unwrap();
+        assert_eq!(hip.algorithm(), IpseckeyAlg::Rsa);
+        assert_eq!(hip.hit(), &HIT);
+        assert_eq!(hip.rendezvous_servers().count(), 2);
+        assert_eq!(
+            hip.to_string(),
+            "2 200100107B1A74DF365639CC39F1D578 AwEAAQ== \
+             rvs1.example.com. rvs2.example.com."
+        );
+
+        let mut buf = Vec::new();
+        hip.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata);
+
+        // Compressed rendezvous server.
+        let mut parser =
+            Parser::from_ref(b"\x01\x02\x00\x01\x00\x00\xc0\x0c".as_ref());
+        assert!(Hip::parse(&mut parser).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner = Scanner::new(
+            "2 200100107B1A74DF365639CC39F1D578 AwEAAQ== \
+             rvs1.example.com.\n",
+        );
+        let hip = Hip::scan(&mut scanner).unwrap();
+        assert_eq!(hip.hit().as_ref(), HIT);
+        assert_eq!(hip.public_key().as_ref(), b"\x03\x01\x00\x01");
+        assert_eq!(
+            hip.rendezvous_servers()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>(),
+            ["rvs1.example.com"]
+        );
+
+        let mut scanner =
+            Scanner::new("2 200100107B1A74DF365639CC39F1D578 AwEAAQ==\n");
+        let hip = Hip::scan(&mut scanner).unwrap();
+        assert_eq!(hip.rendezvous_servers().count(), 0);
+
+        assert!(Hip::scan(&mut Scanner::new("2 2001001 AwEAAQ==\n")).is_err());
+    }
+}
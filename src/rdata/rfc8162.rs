@@ -0,0 +1,43 @@
+//! Record data from [RFC 8162]: SMIMEA records.
+//!
+//! This RFC defines the SMIMEA record type that associates S/MIME
+//! certificates with email addresses.
+//!
+//! [RFC 8162]: https://tools.ietf.org/html/rfc8162
+
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::{
+    Rtype, TlsaCertUsage, TlsaMatchingType, TlsaSelector,
+};
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{CharSource, Scan, ScanError, Scanner};
+use crate::utils::base16;
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash};
+
+//------------ Smimea --------------------------------------------------------
+
+tlsa_type! {
+    /// SMIMEA record data.
+    ///
+    /// An SMIMEA record associates an S/MIME certificate with the email
+    /// address encoded in the domain name where the record is found. The
+    /// record data has the same format and semantics as that of a TLSA
+    /// record: the certificate usage, the selector, and the matching type
+    /// describe how the certificate association data is to be matched
+    /// against a certificate.
+    ///
+    /// In presentation format, the association data is given in Base 16 or
+    /// as a single `-` if it is empty.
+    ///
+    /// The SMIMEA record type is defined in RFC 8162, section 2.
+    (Smimea, Smimea)
+}
@@ -0,0 +1,634 @@
+//! Record data from [RFC 8659]: CAA records.
+//!
+//! This RFC defines the Certification Authority Authorization record type
+//! that allows domain name holders to specify which certification
+//! authorities are allowed to issue certificates for their domain names.
+//!
+//! [RFC 8659]: https://tools.ietf.org/html/rfc8659
+
+use crate::base::charstr::CharStr;
+use crate::base::cmp::CanonicalOrd;
+use crate::base::iana::Rtype;
+use crate::base::name::PushError;
+use crate::base::octets::{
+    Compose, OctetsBuilder, OctetsFrom, OctetsInto, OctetsRef, Parse,
+    ParseError, Parser, ShortBuf,
+};
+use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{
+    CharSource, Scan, ScanError, Scanner, SyntaxError,
+};
+#[cfg(feature = "master")]
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::{fmt, hash, str};
+
+//------------ Caa -----------------------------------------------------------
+
+/// CAA record data.
+///
+/// A CAA record holds a single property of the domain name consisting of a
+/// tag and a value. The tag determines the meaning of the value. RFC 8659
+/// defines the tags `issue` and `issuewild` which authorize a certification
+/// authority to issue certificates and wildcard certificates, respectively,
+/// and the tag `iodef` which provides a URL for reporting invalid
+/// certificate requests. These properties can be interpreted via
+/// [`property`][Self::property].
+///
+/// The flags contain the issuer critical flag. If it is set, a
+/// certification authority must not issue a certificate if it doesn’t
+/// understand the property.
+///
+/// The tag is a non-empty sequence of ASCII letters and digits. Tags are
+/// compared ignoring case. The value is an octets sequence that extends to
+/// the end of the record data.
+///
+/// In presentation format, the record data consists of the flags as a
+/// decimal number, the tag, and the value as a quoted string.
+///
+/// The CAA record type is defined in RFC 8659, section 4.1.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            Octets: crate::base::octets::SerializeOctets + AsRef<[u8]>
+        ",
+        deserialize = "
+            Octets:
+                crate::base::octets::FromBuilder
+                + crate::base::octets::DeserializeOctets<'de>,
+            <Octets as crate::base::octets::FromBuilder>::Builder:
+                crate::base::octets::EmptyBuilder,
+        ",
+    ))
+)]
+pub struct Caa<Octets> {
+    flags: u8,
+    tag: CharStr<Octets>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "crate::base::octets::SerializeOctets::serialize_octets",
+            deserialize_with = "crate::base::octets::DeserializeOctets::deserialize_octets",
+        )
+    )]
+    value: Octets,
+}
+
+impl<Octets> Caa<Octets> {
+    /// The flag marking the property as critical.
+    pub const CRITICAL: u8 = 0x80;
+
+    /// Creates new CAA record data from its components.
+    ///
+    /// Returns an error if the tag is empty or contains anything other than
+    /// ASCII letters and digits.
+    pub fn new(
+        flags: u8,
+        tag: CharStr<Octets>,
+        value: Octets,
+    ) -> Result<Self, CaaTagError>
+    where
+        Octets: AsRef<[u8]>,
+    {
+        check_tag(tag.as_ref())?;
+        Ok(unsafe { Self::new_unchecked(flags, tag, value) })
+    }
+
+    /// Creates new CAA record data without checking the tag.
+    ///
+    /// # Safety
+    ///
+    /// The caller has to make sure that the tag is not empty and only
+    /// contains ASCII letters and digits.
+    pub unsafe fn new_unchecked(
+        flags: u8,
+        tag: CharStr<Octets>,
+        value: Octets,
+    ) -> Self {
+        Caa { flags, tag, value }
+    }
+
+    /// Returns the flags.
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Returns whether the issuer critical flag is set.
+    pub fn is_critical(&self) -> bool {
+        self.flags & Self::CRITICAL != 0
+    }
+
+    /// Returns the tag of the property.
+    pub fn tag(&self) -> &CharStr<Octets> {
+        &self.tag
+    }
+
+    /// Returns the value of the property.
+    pub fn value(&self) -> &Octets {
+        &self.value
+    }
+
+    /// Converts the record data into its value.
+    pub fn into_value(self) -> Octets {
+        self.value
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Caa<Octets> {
+    /// Returns whether the tag equals `tag` ignoring ASCII case.
+    pub fn tag_eq(&self, tag: &str) -> bool {
+        self.tag.as_ref().eq_ignore_ascii_case(tag.as_bytes())
+    }
+
+    /// Interprets the property.
+    ///
+    /// Returns an error if the tag is one of those defined by RFC 8659 but
+    /// the value doesn’t follow the syntax defined for it.
+    pub fn property(&self) -> Result<CaaProperty<'_>, CaaValueError> {
+        if self.tag_eq("issue") {
+            CaaIssue::from_value(self.value.as_ref()).map(CaaProperty::Issue)
+        } else if self.tag_eq("issuewild") {
+            CaaIssue::from_value(self.value.as_ref())
+                .map(CaaProperty::IssueWild)
+        } else if self.tag_eq("iodef") {
+            iodef_url(self.value.as_ref()).map(CaaProperty::Iodef)
+        } else {
+            Ok(CaaProperty::Other)
+        }
+    }
+}
+
+impl<SrcOctets> Caa<SrcOctets> {
+    pub fn flatten_into<Octets>(self) -> Result<Caa<Octets>, PushError>
+    where
+        Octets: OctetsFrom<SrcOctets>,
+    {
+        let Self { flags, tag, value } = self;
+        Ok(unsafe {
+            Caa::new_unchecked(
+                flags,
+                tag.octets_into()?,
+                value.octets_into()?,
+            )
+        })
+    }
+}
+
+//--- OctetsFrom
+
+impl<Octets, SrcOctets> OctetsFrom<Caa<SrcOctets>> for Caa<Octets>
+where
+    Octets: OctetsFrom<SrcOctets>,
+{
+    fn octets_from(source: Caa<SrcOctets>) -> Result<Self, ShortBuf> {
+        Ok(unsafe {
+            Caa::new_unchecked(
+                source.flags,
+                CharStr::octets_from(source.tag)?,
+                Octets::octets_from(source.value)?,
+            )
+        })
+    }
+}
+
+//--- PartialEq and Eq
+
+impl<Octets, Other> PartialEq<Caa<Other>> for Caa<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn eq(&self, other: &Caa<Other>) -> bool {
+        self.flags == other.flags
+            && self.tag.eq(&other.tag)
+            && self.value.as_ref() == other.value.as_ref()
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Eq for Caa<Octets> {}
+
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<Octets, Other> PartialOrd<Caa<Other>> for Caa<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn partial_cmp(&self, other: &Caa<Other>) -> Option<Ordering> {
+        Some(self.canonical_cmp(other))
+    }
+}
+
+impl<Octets, Other> CanonicalOrd<Caa<Other>> for Caa<Octets>
+where
+    Octets: AsRef<[u8]>,
+    Other: AsRef<[u8]>,
+{
+    fn canonical_cmp(&self, other: &Caa<Other>) -> Ordering {
+        match self.flags.cmp(&other.flags) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.tag.canonical_cmp(&other.tag) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.value.as_ref().cmp(other.value.as_ref())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Ord for Caa<Octets> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
+//--- Hash
+
+impl<Octets: AsRef<[u8]>> hash::Hash for Caa<Octets> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.flags.hash(state);
+        self.tag.hash(state);
+        self.value.as_ref().hash(state);
+    }
+}
+
+//--- Parse and Compose
+
+impl<Ref: OctetsRef> Parse<Ref> for Caa<Ref::Range> {
+    fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
+        let flags = u8::parse(parser)?;
+        let tag = CharStr::parse(parser)?;
+        if check_tag(tag.as_ref()).is_err() {
+            return Err(ParseError::form_error("invalid CAA tag"));
+        }
+        let len = parser.remaining();
+        let value = parser.parse_octets(len)?;
+        Ok(unsafe { Self::new_unchecked(flags, tag, value) })
+    }
+
+    fn skip(parser: &mut Parser<Ref>) -> Result<(), ParseError> {
+        u8::skip(parser)?;
+        CharStr::skip(parser)?;
+        parser.advance_to_end();
+        Ok(())
+    }
+}
+
+impl<Octets: AsRef<[u8]>> Compose for Caa<Octets> {
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        &self,
+        target: &mut T,
+    ) -> Result<(), ShortBuf> {
+        target.append_all(|buf| {
+            self.flags.compose(buf)?;
+            self.tag.compose(buf)?;
+            buf.append_slice(self.value.as_ref())
+        })
+    }
+}
+
+//--- Scan and Display
+
+#[cfg(feature = "master")]
+impl Scan for Caa<Bytes> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        let flags = u8::scan(scanner)?;
+        let tag = scanner.scan_byte_phrase(|tag| {
+            check_tag(tag.as_ref()).map_err(SyntaxError::content)?;
+            CharStr::from_octets(tag).map_err(|_| SyntaxError::LongCharStr)
+        })?;
+        let value = scanner.scan_byte_phrase(Ok)?;
+        Ok(unsafe { Self::new_unchecked(flags, tag, value) })
+    }
+}
+
+impl<Octets: AsRef<[u8]>> fmt::Display for Caa<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} \"", self.flags, self.tag)?;
+        for &ch in self.value.as_ref() {
+            if ch == b'"' || ch == b'\\' {
+                write!(f, "\\{}", ch as char)?;
+            } else if (0x20..0x7F).contains(&ch) {
+                write!(f, "{}", ch as char)?;
+            } else {
+                write!(f, "\\{:03}", ch)?;
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+//--- Debug
+
+impl<Octets: AsRef<[u8]>> fmt::Debug for Caa<Octets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Caa")
+            .field("flags", &self.flags)
+            .field("tag", &self.tag)
+            .field("value", &self.value.as_ref())
+            .finish()
+    }
+}
+
+//--- RtypeRecordData
+
+impl<Octets> RtypeRecordData for Caa<Octets> {
+    const RTYPE: Rtype = Rtype::Caa;
+}
+
+//------------ CaaProperty ---------------------------------------------------
+
+/// The interpretation of the property of a CAA record.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CaaProperty<'a> {
+    /// The `issue` property authorizing certificate issuance.
+    Issue(CaaIssue<'a>),
+
+    /// The `issuewild` property authorizing wildcard certificate issuance.
+    IssueWild(CaaIssue<'a>),
+
+    /// The `iodef` property with the URL for reporting invalid requests.
+    Iodef(&'a str),
+
+    /// A property not defined by RFC 8659.
+    Other,
+}
+
+//------------ CaaIssue ------------------------------------------------------
+
+/// The value of an `issue` or `issuewild` property.
+///
+/// The value consists of the domain name of the issuer followed by a list
+/// of parameters as tag-value pairs. All of these are optional. The syntax
+/// of the value is defined in RFC 8659, section 4.2.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CaaIssue<'a> {
+    /// The domain name of the issuer, if present.
+    issuer: Option<&'a str>,
+
+    /// The parameters, already checked for syntax.
+    parameters: &'a str,
+}
+
+impl<'a> CaaIssue<'a> {
+    /// Interprets a property value.
+    pub fn from_value(value: &'a [u8]) -> Result<Self, CaaValueError> {
+        let value = str::from_utf8(value).map_err(|_| CaaValueError)?;
+        let (issuer, parameters) = match value.find(';') {
+            Some(pos) => (&value[..pos], trim_wsp(&value[pos + 1..])),
+            None => (value, ""),
+        };
+        let issuer = match trim_wsp(issuer) {
+            "" => None,
+            issuer => {
+                if !issuer.split('.').all(is_ldh) {
+                    return Err(CaaValueError);
+                }
+                Some(issuer)
+            }
+        };
+        if !parameters.is_empty() {
+            for parameter in parameters.split(';') {
+                let (tag, value) =
+                    split_parameter(parameter).ok_or(CaaValueError)?;
+                if !is_ldh(tag)
+                    || !value.bytes().all(|ch| {
+                        (0x21..=0x3A).contains(&ch)
+                            || (0x3C..=0x7E).contains(&ch)
+                    })
+                {
+                    return Err(CaaValueError);
+                }
+            }
+        }
+        Ok(CaaIssue { issuer, parameters })
+    }
+
+    /// Returns the domain name of the issuer.
+    ///
+    /// If this is `None`, no certification authority is authorized.
+    pub fn issuer(&self) -> Option<&'a str> {
+        self.issuer
+    }
+
+    /// Returns an iterator over the parameters as tag-value pairs.
+    pub fn parameters(&self) -> CaaParameters<'a> {
+        CaaParameters {
+            parameters: if self.parameters.is_empty() {
+                None
+            } else {
+                Some(self.parameters.split(';'))
+            },
+        }
+    }
+
+    /// Returns the value of the parameter with the given tag.
+    ///
+    /// Tags are compared ignoring ASCII case.
+    pub fn parameter(&self, tag: &str) -> Option<&'a str> {
+        self.parameters()
+            .find(|(item, _)| item.eq_ignore_ascii_case(tag))
+            .map(|(_, value)| value)
+    }
+}
+
+//------------ CaaParameters -------------------------------------------------
+
+/// An iterator over the parameters of an `issue` or `issuewild` property.
+#[derive(Clone, Debug)]
+pub struct CaaParameters<'a> {
+    parameters: Option<str::Split<'a, char>>,
+}
+
+impl<'a> Iterator for CaaParameters<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        split_parameter(self.parameters.as_mut()?.next()?)
+    }
+}
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Checks that a tag is non-empty and consists of letters and digits only.
+fn check_tag(tag: &[u8]) -> Result<(), CaaTagError> {
+    if tag.is_empty() || !tag.iter().all(u8::is_ascii_alphanumeric) {
+        Err(CaaTagError)
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the URL of an `iodef` property.
+///
+/// RFC 8659 only allows the `mailto:`, `http:`, and `https:` schemes.
+fn iodef_url(value: &[u8]) -> Result<&str, CaaValueError> {
+    let value = str::from_utf8(value).map_err(|_| CaaValueError)?;
+    let scheme = value.split(':').next().unwrap_or("");
+    if value.len() > scheme.len()
+        && ["mailto", "http", "https"]
+            .iter()
+            .any(|item| scheme.eq_ignore_ascii_case(item))
+    {
+        Ok(value)
+    } else {
+        Err(CaaValueError)
+    }
+}
+
+/// Removes leading and trailing spaces and tabs.
+fn trim_wsp(s: &str) -> &str {
+    s.trim_matches(|ch| ch == ' ' || ch == '\t')
+}
+
+/// Splits a parameter into its tag and value.
+fn split_parameter(parameter: &str) -> Option<(&str, &str)> {
+    let pos = parameter.find('=')?;
+    Some((trim_wsp(&parameter[..pos]), trim_wsp(&parameter[pos + 1..])))
+}
+
+/// Checks for letters and digits possibly with hyphens in between.
+fn is_ldh(s: &str) -> bool {
+    let s = s.as_bytes();
+    match (s.first(), s.last()) {
+        (Some(first), Some(last)) => {
+            first.is_ascii_alphanumeric()
+                && last.is_ascii_alphanumeric()
+                && s.iter()
+                    .all(|ch| ch.is_ascii_alphanumeric() || *ch == b'-')
+        }
+        _ => false,
+    }
+}
+
+//------------ CaaTagError ---------------------------------------------------
+
+/// A CAA tag was empty or contained characters other than letters and
+/// digits.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CaaTagError;
+
+impl fmt::Display for CaaTagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid CAA tag")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CaaTagError {}
+
+//------------ CaaValueError -------------------------------------------------
+
+/// The value of a CAA property didn’t follow the syntax for its tag.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CaaValueError;
+
+impl fmt::Display for CaaValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid CAA property value")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CaaValueError {}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    fn caa(flags: u8, tag: &str, value: &str) -> Caa<Vec<u8>> {
+        Caa::new(
+            flags,
+            CharStr::from_octets(tag.as_bytes().to_vec()).unwrap(),
+            value.as_bytes().to_vec(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_and_compose() {
+        let rdata = b"\x80\x05issueca.example.net";
+        let mut parser = Parser::from_ref(rdata.as_ref());
+        let parsed = Caa::parse(&mut parser).unwrap();
+        assert!(parsed.is_critical());
+        assert_eq!(parsed, caa(0x80, "issue", "ca.example.net"));
+
+        let mut buf = Vec::new();
+        parsed.compose(&mut buf).unwrap();
+        assert_eq!(buf, rdata.as_ref());
+
+        let mut parser = Parser::from_ref(b"\x00\x00".as_ref());
+        assert!(Caa::parse(&mut parser).is_err());
+        let mut parser = Parser::from_ref(b"\x00\x02i-x".as_ref());
+        assert!(Caa::parse(&mut parser).is_err());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            caa(0, "issue", "ca.example.net; account=\"1\"").to_string(),
+            "0 issue \"ca.example.net; account=\\\"1\\\"\""
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "master")]
+    fn scan() {
+        let mut scanner =
+            Scanner::new("128 tbs \"Unknown\"\n0 issue \";\"\n");
+        let first = Caa::scan(&mut scanner).unwrap();
+        assert_eq!(first, caa(128, "tbs", "Unknown"));
+        scanner.scan_newline().unwrap();
+        let second = Caa::scan(&mut scanner).unwrap();
+        assert_eq!(second, caa(0, "issue", ";"));
+
+        let mut scanner = Scanner::new("0 is-sue \"ca.example.net\"");
+        assert!(Caa::scan(&mut scanner).is_err());
+    }
+
+    #[test]
+    fn properties() {
+        let issue =
+            caa(0, "ISSUE", "ca.example.net; account = 230123; policy=ev");
+        let issue = match issue.property().unwrap() {
+            CaaProperty::Issue(issue) => issue,
+            _ => panic!("not an issue property"),
+        };
+        assert_eq!(issue.issuer(), Some("ca.example.net"));
+        assert_eq!(
+            issue.parameters().collect::<Vec<_>>(),
+            [("account", "230123"), ("policy", "ev")]
+        );
+        assert_eq!(issue.parameter("Policy"), Some("ev"));
+
+        match caa(0, "issuewild", ";").property().unwrap() {
+            CaaProperty::IssueWild(issue) => {
+                assert_eq!(issue.issuer(), None);
+                assert_eq!(issue.parameters().count(), 0);
+            }
+            _ => panic!("not an issuewild property"),
+        }
+        assert_eq!(
+            caa(0, "iodef", "mailto:security@example.com").property(),
+            Ok(CaaProperty::Iodef("mailto:security@example.com"))
+        );
+        assert_eq!(caa(0, "tbs", "x y").property(), Ok(CaaProperty::Other));
+
+        assert!(caa(0, "issue", "-ca.example.net").property().is_err());
+        assert!(caa(0, "issue", "ca.example.net; a=b;").property().is_err());
+        assert!(caa(0, "issue", "ca.example.net; a").property().is_err());
+        assert!(caa(0, "iodef", "ftp://example.com/").property().is_err());
+    }
+}
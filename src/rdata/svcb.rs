@@ -1,15 +1,27 @@
 // Implementation of SVCB RR type
-// https://datatracker.ietf.org/doc/html/draft-ietf-dnsop-svcb-https-08#section-8
+// https://tools.ietf.org/html/rfc9460
 
+use crate::base::cmp::CanonicalOrd;
 use crate::base::iana::{Rtype, SvcbParamKey};
 use crate::base::name::{Dname, ParsedDname, PushError, ToDname};
 use crate::base::octets::{
-    Compose, EmptyBuilder, FromBuilder, Octets512, OctetsBuilder, OctetsFrom,
+    Compose, EmptyBuilder, FromBuilder, OctetsBuilder, OctetsFrom,
     OctetsInto, OctetsRef, Parse, ParseError, Parser, ShortBuf,
 };
 use crate::base::rdata::RtypeRecordData;
+#[cfg(feature = "master")]
+use crate::master::scan::{
+    CharSource, Scan, ScanError, Scanner, SyntaxError,
+};
+#[cfg(feature = "master")]
+use bytes::{Bytes, BytesMut};
+use core::cmp::Ordering;
 use core::{fmt, hash};
-use param::{AllParams, SvcbParam};
+use param::AllParams;
+#[cfg(feature = "master")]
+use param::SvcbParam;
+#[cfg(feature = "master")]
+use std::vec::Vec;
 
 // Types in SVCB type group are based on the same format.
 macro_rules! svcb_types {
@@ -18,10 +30,31 @@ macro_rules! svcb_types {
 /// Struct has priority and target decoded, but not parameters.
 /// Provides a [`iter`](Svcb::iter) method to iterate through each parameter.
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "
+            O: crate::base::octets::SerializeOctets + AsRef<[u8]>,
+            N: serde::Serialize,
+        ",
+        deserialize = "
+            O: FromBuilder + crate::base::octets::DeserializeOctets<'de>,
+            <O as FromBuilder>::Builder:
+                OctetsBuilder<Octets = O> + EmptyBuilder,
+            N: serde::Deserialize<'de>,
+        ",
+    ))
+)]
 pub struct $name<O, N> {
     priority: u16,
     target: N,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::base16::serde")
+    )]
     params: O,
+    #[cfg_attr(feature = "serde", serde(skip))]
     sorter: Sorter,
 }
 
@@ -96,11 +129,12 @@ where
     NN: OctetsFrom<N>,
 {
     fn octets_from(source: $name<O, N>) -> Result<Self, ShortBuf> {
-        Ok($name::new(
-            source.priority,
-            NN::octets_from(source.target)?,
-            OO::octets_from(source.params)?,
-        ))
+        Ok($name {
+            priority: source.priority,
+            target: NN::octets_from(source.target)?,
+            params: OO::octets_from(source.params)?,
+            sorter: source.sorter,
+        })
     }
 }
 
@@ -122,11 +156,9 @@ impl<OB: OctetsBuilder + AsMut<[u8]>, N> $name<OB, N> {
         &mut self,
         param: AllParams<O>,
     ) -> Result<(), ShortBuf> {
-        let key = param.key().into();
-        let off = self.params.len();
         param.compose(&mut self.params)?;
-        let len = self.params.len() - off;
-        self.sorter.insert(key, off as u16, len as u16)
+        self.sorter.insert();
+        Ok(())
     }
 }
 
@@ -158,20 +190,13 @@ impl<O: AsRef<[u8]>, N: Compose> Compose for $name<O, N> {
             self.priority.compose(buf)?;
             self.target.compose(buf)?;
 
-            let view = self.sorter.buf.as_slice();
-            let mut bytes = [0u8; 2];
-            for chunk in view.chunks_exact(Sorter::CHUNK_SIZE) {
-                bytes[0] = chunk[2];
-                bytes[1] = chunk[3];
-                let off = u16::from_ne_bytes(bytes).into();
-                bytes[0] = chunk[4];
-                bytes[1] = chunk[5];
-                let len: usize = u16::from_ne_bytes(bytes).into();
-                let slice = &self.params.as_ref()[off..off + len];
-                buf.append_slice(slice)?;
+            // Parameters that weren’t pushed, e.g., because they were
+            // parsed, are already in wire format.
+            if self.sorter.n == 0 {
+                return buf.append_slice(self.params.as_ref());
             }
 
-            Ok(())
+            Sorter::compose(self.params.as_ref(), buf)
         })
     }
 }
@@ -203,14 +228,27 @@ impl<O: AsRef<[u8]>, N> $name<O, N> {
     }
 }
 
-//--- Display and Debug
+//--- Scan, Display, and Debug
+
+#[cfg(feature = "master")]
+impl<N: Scan> Scan for $name<Bytes, N> {
+    fn scan<C: CharSource>(
+        scanner: &mut Scanner<C>,
+    ) -> Result<Self, ScanError> {
+        let priority = u16::scan(scanner)?;
+        let target = N::scan(scanner)?;
+        let params = scan_params(scanner)?;
+        Ok(Self::new(priority, target, params))
+    }
+}
+
 impl<O, N> fmt::Display for $name<O, N>
 where
     O: AsRef<[u8]>,
     N: fmt::Display,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} {}", self.priority, self.target)?;
+        write!(f, "{} {}.", self.priority, self.target)?;
         for param in self.iter() {
             write!(f, " {}", param.map_err(|_| fmt::Error)?)?;
         }
@@ -250,6 +288,46 @@ where
 
 impl<O: AsRef<[u8]>, N: ToDname> Eq for $name<O, N> {}
 
+//--- PartialOrd, CanonicalOrd, and Ord
+
+impl<O, OO, N, NN> PartialOrd<$name<OO, NN>> for $name<O, N>
+where
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+    N: ToDname,
+    NN: ToDname,
+{
+    fn partial_cmp(&self, other: &$name<OO, NN>) -> Option<Ordering> {
+        Some(self.canonical_cmp(other))
+    }
+}
+
+impl<O, OO, N, NN> CanonicalOrd<$name<OO, NN>> for $name<O, N>
+where
+    O: AsRef<[u8]>,
+    OO: AsRef<[u8]>,
+    N: ToDname,
+    NN: ToDname,
+{
+    fn canonical_cmp(&self, other: &$name<OO, NN>) -> Ordering {
+        match self.priority.cmp(&other.priority) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        match self.target.composed_cmp(&other.target) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        self.params.as_ref().cmp(other.params.as_ref())
+    }
+}
+
+impl<O: AsRef<[u8]>, N: ToDname> Ord for $name<O, N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_cmp(other)
+    }
+}
+
 //--- Hash
 
 impl<O: AsRef<[u8]>, N: hash::Hash> hash::Hash for $name<O, N> {
@@ -265,64 +343,228 @@ impl<O: AsRef<[u8]>, N: hash::Hash> hash::Hash for $name<O, N> {
 
 svcb_types!(Svcb, Https,);
 
-// This is a helper type to sort parameters without allocation. It
-// uses internal buffer to keep parameter key sorted. With the
-// additional offset and length recorded when the parameter was
-// pushed, it reorder them during composing.
-//
-// FIXME: Using a fixed length buffer is not ideal, the size should be
-// big enough.
-#[derive(Clone, Default)]
+/// Scans the parameters of a SVCB record in presentation format.
+///
+/// The parameters are checked for consistency as required by section 2.1
+/// of RFC 9460 and returned in wire format sorted by key.
+#[cfg(feature = "master")]
+fn scan_params<C: CharSource>(
+    scanner: &mut Scanner<C>,
+) -> Result<Bytes, ScanError> {
+    let mut params = Vec::new();
+    while !scanner.entry_ended()? {
+        params.push(scanner.scan_key_value(|key, value| {
+            AllParams::scan(&key, value).map_err(SyntaxError::content)
+        })?);
+    }
+    compose_params(params).map_err(|err| {
+        ScanError::Syntax(SyntaxError::content(err), scanner.pos())
+    })
+}
+
+/// Checks and composes a list of parameters.
+#[cfg(feature = "master")]
+fn compose_params(
+    mut params: Vec<AllParams<Bytes>>,
+) -> Result<Bytes, SvcbParamError> {
+    params.sort_by_key(|param| u16::from(param.key()));
+    for pair in params.windows(2) {
+        if u16::from(pair[0].key()) == u16::from(pair[1].key()) {
+            return Err(SvcbParamError::DuplicateKey(pair[0].key()));
+        }
+    }
+    let has_key = |key: SvcbParamKey| {
+        params
+            .binary_search_by_key(&u16::from(key), |param| {
+                u16::from(param.key())
+            })
+            .is_ok()
+    };
+    if let Some(AllParams::Mandatory(mandatory)) = params.first() {
+        let mut keys = Vec::new();
+        for key in mandatory.for_slice().iter() {
+            let key =
+                u16::from(key.map_err(|_| SvcbParamError::InvalidMandatory)?);
+            if key == u16::from(SvcbParamKey::Mandatory)
+                || keys.contains(&key)
+            {
+                return Err(SvcbParamError::InvalidMandatory);
+            }
+            keys.push(key);
+        }
+        if keys.is_empty() {
+            return Err(SvcbParamError::InvalidMandatory);
+        }
+        for key in keys {
+            if !has_key(key.into()) {
+                return Err(SvcbParamError::MissingMandatory(key.into()));
+            }
+        }
+    }
+    if has_key(SvcbParamKey::NoDefaultAlpn) && !has_key(SvcbParamKey::Alpn) {
+        return Err(SvcbParamError::MissingAlpn);
+    }
+    let mut res = BytesMut::new();
+    for param in params {
+        param
+            .compose(&mut res)
+            .map_err(|_| SvcbParamError::LongValue(param.key()))?;
+    }
+    Ok(res.freeze())
+}
+
+/// Writes octets as a character string without enclosing quotes.
+///
+/// If `value_list` is `true`, commas and backslashes are escaped
+/// additionally as required for items of a value list per appendix A.1
+/// of RFC 9460.
+fn write_escaped(
+    octets: &[u8],
+    value_list: bool,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    use core::fmt::Write;
+
+    for &ch in octets {
+        if value_list && ch == b',' {
+            f.write_str("\\\\,")?;
+        } else if value_list && ch == b'\\' {
+            f.write_str("\\\\\\\\")?;
+        } else if ch == b'"'
+            || ch == b'\\'
+            || ch == b';'
+            || ch == b'('
+            || ch == b')'
+        {
+            f.write_char('\\')?;
+            f.write_char(ch as char)?;
+        } else if (0x21..0x7F).contains(&ch) {
+            f.write_char(ch as char)?;
+        } else {
+            write!(f, "\\{:03}", ch)?;
+        }
+    }
+    Ok(())
+}
+
+//------------ SvcbParamError ------------------------------------------------
+
+/// An error happened while reading the parameters of a SVCB record.
+#[derive(Clone, Copy, Debug)]
+pub enum SvcbParamError {
+    /// A parameter key was not recognized.
+    UnknownKey,
+
+    /// A key appeared more than once.
+    DuplicateKey(SvcbParamKey),
+
+    /// A parameter requires a value but none was given.
+    MissingValue(SvcbParamKey),
+
+    /// A parameter does not allow a value but one was given.
+    UnexpectedValue(SvcbParamKey),
+
+    /// The value of a parameter was malformed.
+    InvalidValue(SvcbParamKey),
+
+    /// The value of a parameter was too long.
+    LongValue(SvcbParamKey),
+
+    /// The list of mandatory keys was empty or contained invalid keys.
+    InvalidMandatory,
+
+    /// A key listed as mandatory was missing.
+    MissingMandatory(SvcbParamKey),
+
+    /// The no-default-alpn parameter was given without alpn.
+    MissingAlpn,
+}
+
+//--- Display and Error
+
+impl fmt::Display for SvcbParamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SvcbParamError::UnknownKey => f.write_str("unknown key"),
+            SvcbParamError::DuplicateKey(key) => {
+                write!(f, "duplicate key {}", key)
+            }
+            SvcbParamError::MissingValue(key) => {
+                write!(f, "missing value for key {}", key)
+            }
+            SvcbParamError::UnexpectedValue(key) => {
+                write!(f, "unexpected value for key {}", key)
+            }
+            SvcbParamError::InvalidValue(key) => {
+                write!(f, "invalid value for key {}", key)
+            }
+            SvcbParamError::LongValue(key) => {
+                write!(f, "value too long for key {}", key)
+            }
+            SvcbParamError::InvalidMandatory => {
+                f.write_str("invalid list of mandatory keys")
+            }
+            SvcbParamError::MissingMandatory(key) => {
+                write!(f, "missing mandatory key {}", key)
+            }
+            SvcbParamError::MissingAlpn => {
+                f.write_str("no-default-alpn without alpn")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SvcbParamError {}
+
+// This is a helper type to sort parameters without allocation. It only
+// records whether parameters were pushed. When composing, the pushed
+// parameters are written in order of their keys by repeatedly picking
+// the parameter following the one written last. Parameters with the same
+// key are kept in the order they were pushed.
+#[derive(Clone, Copy, Default)]
 struct Sorter {
     n: usize,
-    buf: Octets512,
 }
 
 impl Sorter {
-    // key + off + len
-    const CHUNK_SIZE: usize = 6;
-    fn insert(
-        &mut self,
-        key: u16,
-        off: u16,
-        len: u16,
+    fn insert(&mut self) {
+        self.n += 1;
+    }
+
+    fn compose<T: OctetsBuilder + AsMut<[u8]>>(
+        params: &[u8],
+        target: &mut T,
     ) -> Result<(), ShortBuf> {
-        let end = self.buf.len();
-
-        // allocate space by appending an empty chunck
-        self.buf.append_slice(&[0u8; Self::CHUNK_SIZE])?;
-
-        let view = &self.buf.as_slice()[..end];
-        let target =
-            view.chunks_exact(Self::CHUNK_SIZE)
-                .enumerate()
-                .find(|(_, c)| {
-                    let mut key_buf = [0u8; 2];
-                    key_buf[0] = c[0];
-                    key_buf[1] = c[1];
-                    key <= u16::from_ne_bytes(key_buf)
-                });
-
-        let buf = match target {
-            None => &mut self.buf.as_slice_mut()[end..end + Self::CHUNK_SIZE],
-            Some((i, _)) => {
-                let view = &mut self.buf.as_slice_mut()
-                    [i * Self::CHUNK_SIZE..end + Self::CHUNK_SIZE];
-                view.rotate_right(Self::CHUNK_SIZE);
-                &mut view[..Self::CHUNK_SIZE]
+        let mut last = None;
+        loop {
+            let mut next: Option<((u16, usize), &[u8])> = None;
+            let mut off = 0;
+            while params.len() - off >= 4 {
+                let key = u16::from_be_bytes([params[off], params[off + 1]]);
+                let len =
+                    u16::from_be_bytes([params[off + 2], params[off + 3]]);
+                let len = usize::from(len) + 4;
+                let param = match params.get(off..off + len) {
+                    Some(param) => param,
+                    None => break,
+                };
+                let pos = (key, off);
+                if last.map_or(true, |last| pos > last)
+                    && next.map_or(true, |(next, _)| pos < next)
+                {
+                    next = Some((pos, param));
+                }
+                off += len;
             }
-        };
-        let bytes = key.to_ne_bytes();
-        buf[0] = bytes[0];
-        buf[1] = bytes[1];
-        let bytes = off.to_ne_bytes();
-        buf[2] = bytes[0];
-        buf[3] = bytes[1];
-        let bytes = len.to_ne_bytes();
-        buf[4] = bytes[0];
-        buf[5] = bytes[1];
-        self.n += 1;
-        Ok(())
+            match next {
+                Some((pos, param)) => {
+                    target.append_slice(param)?;
+                    last = Some(pos);
+                }
+                None => return Ok(()),
+            }
+        }
     }
 }
 
@@ -347,9 +589,15 @@ where
 
 pub mod param {
     use super::*;
+    #[cfg(feature = "master")]
+    use crate::base::iana::svcb::SVCB_PARAM_KEY_INVALID;
     use crate::base::net::{Ipv4Addr, Ipv6Addr};
+    #[cfg(feature = "master")]
+    use crate::utils::base64;
     use core::convert::TryInto;
     use core::fmt::{self, Write};
+    #[cfg(feature = "master")]
+    use core::str::{self, FromStr};
 
     macro_rules! param_enum {
         ($($name:ident($type:ty),)+) => {
@@ -455,6 +703,143 @@ pub mod param {
         }
     }
 
+    #[cfg(feature = "master")]
+    impl AllParams<Bytes> {
+        /// Creates a parameter from its presentation format.
+        ///
+        /// The key is the key name and the value is the content of the
+        /// value with the character string escapes already resolved or
+        /// `None` if there was no value at all.
+        pub fn scan(
+            key: &str,
+            value: Option<Bytes>,
+        ) -> Result<Self, SvcbParamError> {
+            let key = match SvcbParamKey::from_str(key) {
+                Ok(key) if key != SVCB_PARAM_KEY_INVALID => key,
+                _ => return Err(SvcbParamError::UnknownKey),
+            };
+            let invalid = SvcbParamError::InvalidValue(key);
+            let value = match (key, value) {
+                (SvcbParamKey::NoDefaultAlpn, None) => {
+                    return Ok(NoDefaultAlpn.into())
+                }
+                (SvcbParamKey::NoDefaultAlpn, Some(value)) => {
+                    if value.is_empty() {
+                        return Ok(NoDefaultAlpn.into());
+                    }
+                    return Err(SvcbParamError::UnexpectedValue(key));
+                }
+                (SvcbParamKey::Int(_), value) => {
+                    return Ok(
+                        Unknown::new(key, value.unwrap_or_default()).into()
+                    )
+                }
+                (_, None) => return Err(SvcbParamError::MissingValue(key)),
+                (_, Some(value)) => value,
+            };
+            let res = match key {
+                SvcbParamKey::Mandatory => {
+                    // The keys are sorted in wire format.
+                    let mut keys = Vec::new();
+                    for item in scan_list(key, &value)? {
+                        keys.push(u16::from(
+                            SvcbParamKey::from_str(item).map_err(|_| {
+                                SvcbParamError::InvalidMandatory
+                            })?,
+                        ));
+                    }
+                    keys.sort_unstable();
+                    let mut res = Mandatory::new(BytesMut::new());
+                    for item in keys {
+                        res.push(item.into()).map_err(|_| invalid)?;
+                    }
+                    res.freeze().into()
+                }
+                SvcbParamKey::Alpn => {
+                    let mut res = Alpn::new(BytesMut::new());
+                    let mut item = Vec::new();
+                    let mut octets = value.iter();
+                    loop {
+                        let ch = octets.next();
+                        match ch {
+                            Some(b'\\') => match octets.next() {
+                                Some(ch) => item.push(*ch),
+                                None => return Err(invalid),
+                            },
+                            Some(b',') | None => {
+                                if item.is_empty() {
+                                    return Err(invalid);
+                                }
+                                res.push(&item).map_err(|_| invalid)?;
+                                item.clear();
+                                if ch.is_none() {
+                                    break;
+                                }
+                            }
+                            Some(ch) => item.push(*ch),
+                        }
+                    }
+                    res.freeze().into()
+                }
+                SvcbParamKey::Port => Port::new(
+                    str::from_utf8(&value)
+                        .ok()
+                        .and_then(|value| u16::from_str(value).ok())
+                        .ok_or(invalid)?,
+                )
+                .into(),
+                SvcbParamKey::Ipv4Hint => {
+                    let mut res = Ipv4Hint::new(BytesMut::new());
+                    for item in scan_list(key, &value)? {
+                        res.push(
+                            Ipv4Addr::from_str(item).map_err(|_| invalid)?,
+                        )
+                        .map_err(|_| invalid)?;
+                    }
+                    res.freeze().into()
+                }
+                SvcbParamKey::Ech => Ech::new(
+                    str::from_utf8(&value)
+                        .ok()
+                        .and_then(|value| base64::decode(value).ok())
+                        .ok_or(invalid)?,
+                )
+                .into(),
+                SvcbParamKey::Ipv6Hint => {
+                    let mut res = Ipv6Hint::new(BytesMut::new());
+                    for item in scan_list(key, &value)? {
+                        res.push(
+                            Ipv6Addr::from_str(item).map_err(|_| invalid)?,
+                        )
+                        .map_err(|_| invalid)?;
+                    }
+                    res.freeze().into()
+                }
+                SvcbParamKey::DohPath => {
+                    str::from_utf8(&value).map_err(|_| invalid)?;
+                    DohPath::new(value).into()
+                }
+                _ => Unknown::new(key, value).into(),
+            };
+            Ok(res)
+        }
+    }
+
+    /// Splits a comma-separated list of non-empty strings.
+    #[cfg(feature = "master")]
+    fn scan_list(
+        key: SvcbParamKey,
+        value: &[u8],
+    ) -> Result<Vec<&str>, SvcbParamError> {
+        let value = str::from_utf8(value)
+            .map_err(|_| SvcbParamError::InvalidValue(key))?;
+        let res: Vec<_> = value.split(',').collect();
+        if res.iter().any(|item| item.is_empty()) {
+            return Err(SvcbParamError::InvalidValue(key));
+        }
+        Ok(res)
+    }
+
     // for types wraps an octets
     macro_rules! octets_wrapper {
         ($name:ident) => {
@@ -585,13 +970,11 @@ pub mod param {
 
     impl<Ref: OctetsRef> fmt::Display for Mandatory<Ref> {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("mandatory")?;
             for (i, v) in self.iter().enumerate() {
                 let v = v.map_err(|_| fmt::Error)?;
-                if i == 0 {
-                    write!(f, "mandatory={}", v)?;
-                } else {
-                    write!(f, ",{}", v)?;
-                }
+                f.write_char(if i == 0 { '=' } else { ',' })?;
+                write!(f, "{}", v)?;
             }
             Ok(())
         }
@@ -634,16 +1017,11 @@ pub mod param {
 
     impl<Ref: OctetsRef> fmt::Display for Alpn<Ref> {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("alpn")?;
             for (i, v) in self.iter().enumerate() {
                 let v = v.map_err(|_| fmt::Error)?;
-                if i == 0 {
-                    f.write_str("alpn=")?;
-                } else {
-                    f.write_char(',')?;
-                }
-                for ch in v.as_ref() {
-                    f.write_char(*ch as char)?;
-                }
+                f.write_char(if i == 0 { '=' } else { ',' })?;
+                write_escaped(v.as_ref(), true, f)?;
             }
             Ok(())
         }
@@ -679,7 +1057,7 @@ pub mod param {
 
     impl fmt::Display for NoDefaultAlpn {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            f.write_str("nodefaultalpn")
+            f.write_str("no-default-alpn")
         }
     }
 
@@ -689,6 +1067,11 @@ pub mod param {
         pub fn new(port: u16) -> Self {
             Self(port)
         }
+
+        /// Returns the port number.
+        pub fn port(&self) -> u16 {
+            self.0
+        }
     }
     impl<Ref: OctetsRef> Parse<Ref> for Port {
         fn parse(parser: &mut Parser<Ref>) -> Result<Self, ParseError> {
@@ -726,9 +1109,13 @@ pub mod param {
     // ECHConfigList: https://datatracker.ietf.org/doc/draft-ietf-tls-esni/
     octets_wrapper!(Ech);
 
-    impl<Octets> fmt::Display for Ech<Octets> {
+    impl<O: AsRef<[u8]>> fmt::Display for Ech<O> {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            f.write_str("ech")
+            if self.0.as_ref().is_empty() {
+                return f.write_str("ech=\"\"");
+            }
+            f.write_str("ech=")?;
+            crate::utils::base64::display(self.0.as_ref(), f)
         }
     }
 
@@ -763,13 +1150,11 @@ pub mod param {
 
     impl<Ref: OctetsRef> fmt::Display for Ipv4Hint<Ref> {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("ipv4hint")?;
             for (i, v) in self.iter().enumerate() {
                 let v = v.map_err(|_| fmt::Error)?;
-                if i == 0 {
-                    write!(f, "ipv4hint={}", v)?;
-                } else {
-                    write!(f, ",{}", v)?;
-                }
+                f.write_char(if i == 0 { '=' } else { ',' })?;
+                write!(f, "{}", v)?;
             }
             Ok(())
         }
@@ -806,13 +1191,11 @@ pub mod param {
 
     impl<Ref: OctetsRef> fmt::Display for Ipv6Hint<Ref> {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("ipv6hint")?;
             for (i, v) in self.iter().enumerate() {
                 let v = v.map_err(|_| fmt::Error)?;
-                if i == 0 {
-                    write!(f, "ipv6hint={}", v)?;
-                } else {
-                    write!(f, ",{}", v)?;
-                }
+                f.write_char(if i == 0 { '=' } else { ',' })?;
+                write!(f, "{}", v)?;
             }
             Ok(())
         }
@@ -824,10 +1207,7 @@ pub mod param {
     impl<Ref: OctetsRef> fmt::Display for DohPath<Ref> {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             f.write_str("dohpath=")?;
-            for ch in self.0.as_ref() {
-                f.write_char(*ch as char)?;
-            }
-            Ok(())
+            write_escaped(self.0.as_ref(), false, f)
         }
     }
 
@@ -862,9 +1242,14 @@ pub mod param {
         }
     }
 
-    impl<Ref: OctetsRef> fmt::Display for Unknown<Ref> {
+    impl<O: AsRef<[u8]>> fmt::Display for Unknown<O> {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "{}", self.key())
+            write!(f, "{}", self.key())?;
+            if !self.val.as_ref().is_empty() {
+                f.write_char('=')?;
+                write_escaped(self.val.as_ref(), false, f)?;
+            }
+            Ok(())
         }
     }
 }
@@ -873,6 +1258,7 @@ pub mod param {
 mod test {
     use super::*;
     use crate::base::{octets::Octets512, Dname};
+    use param::SvcbParam;
 
     // Test parser and composer with test vectors from appendix D
     #[test]
@@ -1314,10 +1700,10 @@ mod test {
             format!("{}", alpn_builder.freeze().for_slice())
         );
 
-        assert_eq!("nodefaultalpn", format!("{}", param::NoDefaultAlpn));
+        assert_eq!("no-default-alpn", format!("{}", param::NoDefaultAlpn));
 
         assert_eq!(
-            "ech",
+            "ech=\"\"",
             format!(
                 "{}",
                 param::Ech::new(Octets512::new()).freeze().for_slice()
@@ -1334,6 +1720,126 @@ mod test {
         );
     }
 
+    #[cfg(feature = "master")]
+    #[test]
+    fn scan_and_display() {
+        use crate::master::scan::{Scan, Scanner};
+
+        fn check(zonefile: &str, wire: &[u8], display: &str) {
+            let mut scanner = Scanner::new(zonefile);
+            let rdata =
+                Https::<Bytes, Dname<Bytes>>::scan(&mut scanner).unwrap();
+            let mut buf = Vec::new();
+            rdata.compose(&mut buf).unwrap();
+            assert_eq!(wire, buf.as_slice());
+            assert_eq!(display, format!("{}", rdata));
+            let mut scanner = Scanner::new(display);
+            assert_eq!(
+                rdata,
+                Https::<Bytes, Dname<Bytes>>::scan(&mut scanner).unwrap()
+            );
+        }
+
+        check(
+            "0 foo.example.com.\n",
+            b"\x00\x00\x03foo\x07example\x03com\x00",
+            "0 foo.example.com.",
+        );
+        check("1 .\n", b"\x00\x01\x00", "1 .");
+        check(
+            "16 foo.example.com. port=53\n",
+            b"\x00\x10\x03foo\x07example\x03com\x00\
+              \x00\x03\x00\x02\x00\x35",
+            "16 foo.example.com. port=53",
+        );
+        check(
+            "1 foo.example.com. key667=\"hello\\210qoo\"\n",
+            b"\x00\x01\x03foo\x07example\x03com\x00\
+              \x02\x9b\x00\x09hello\xd2qoo",
+            "1 foo.example.com. key667=hello\\210qoo",
+        );
+        check(
+            "1 foo.example.com. ipv6hint=\"2001:db8::1,2001:db8::53:1\"\n",
+            b"\x00\x01\x03foo\x07example\x03com\x00\
+              \x00\x06\x00\x20\
+              \x20\x01\x0d\xb8\x00\x00\x00\x00\
+              \x00\x00\x00\x00\x00\x00\x00\x01\
+              \x20\x01\x0d\xb8\x00\x00\x00\x00\
+              \x00\x00\x00\x00\x00\x53\x00\x01",
+            "1 foo.example.com. ipv6hint=2001:db8::1,2001:db8::53:1",
+        );
+        check(
+            "16 foo.example.org. (alpn=h2,h3-19 mandatory=ipv4hint,alpn\n\
+             ipv4hint=192.0.2.1)\n",
+            b"\x00\x10\x03foo\x07example\x03org\x00\
+              \x00\x00\x00\x04\x00\x01\x00\x04\
+              \x00\x01\x00\x09\x02h2\x05h3-19\
+              \x00\x04\x00\x04\xc0\x00\x02\x01",
+            "16 foo.example.org. mandatory=alpn,ipv4hint alpn=h2,h3-19 \
+             ipv4hint=192.0.2.1",
+        );
+        let wire = b"\x00\x10\x03foo\x07example\x03org\x00\
+              \x00\x01\x00\x0c\x08f\\oo,bar\x02h2";
+        let display = "16 foo.example.org. alpn=f\\\\\\\\oo\\\\,bar,h2";
+        check(
+            "16 foo.example.org. alpn=\"f\\\\\\\\oo\\\\,bar,h2\"\n",
+            wire,
+            display,
+        );
+        check(
+            "16 foo.example.org. alpn=f\\\\\\092oo\\092,bar,h2\n",
+            wire,
+            display,
+        );
+        check(
+            "1 . alpn=h2 no-default-alpn ech=AEj+DQBEAQ== dohpath=/q{?dns}\n",
+            b"\x00\x01\x00\
+              \x00\x01\x00\x03\x02h2\
+              \x00\x02\x00\x00\
+              \x00\x05\x00\x07\x00\x48\xfe\x0d\x00\x44\x01\
+              \x00\x07\x00\x08/q{?dns}",
+            "1 . alpn=h2 no-default-alpn ech=AEj+DQBEAQ== dohpath=/q{?dns}",
+        );
+        check(
+            "1 . key65333 alpn=h2 key2=\n",
+            b"\x00\x01\x00\x00\x01\x00\x03\x02h2\
+              \x00\x02\x00\x00\xff\x35\x00\x00",
+            "1 . alpn=h2 no-default-alpn key65333",
+        );
+    }
+
+    #[cfg(feature = "master")]
+    #[test]
+    fn scan_failures() {
+        use crate::master::scan::{Scan, Scanner};
+
+        for zonefile in &[
+            "1 foo.example.com. (key123=abc key123=def)\n",
+            "1 foo.example.com. mandatory\n",
+            "1 foo.example.com. alpn\n",
+            "1 foo.example.com. port\n",
+            "1 foo.example.com. ipv4hint\n",
+            "1 foo.example.com. ipv6hint\n",
+            "1 foo.example.com. no-default-alpn=abc\n",
+            "1 foo.example.com. mandatory=key123\n",
+            "1 foo.example.com. mandatory=mandatory\n",
+            "1 foo.example.com. (mandatory=key123,key123 key123=abc)\n",
+            "1 foo.example.com. alpn=h2,,h3\n",
+            "1 foo.example.com. port=65536\n",
+            "1 foo.example.com. ipv4hint=2001:db8::1\n",
+            "1 foo.example.com. no-default-alpn\n",
+            "1 foo.example.com. key65535=abc\n",
+            "1 foo.example.com. foo=bar\n",
+        ] {
+            let mut scanner = Scanner::new(*zonefile);
+            assert!(
+                Svcb::<Bytes, Dname<Bytes>>::scan(&mut scanner).is_err(),
+                "{}",
+                zonefile
+            );
+        }
+    }
+
     #[test]
     fn test_param_order() {
         let mut mandatory_builder = param::Mandatory::new(Octets512::new());
@@ -0,0 +1,537 @@
+//! The hosts file.
+//!
+//! Before there was the DNS, host names were kept in a simple text file
+//! listing IP addresses and the names that belong to them. The file is still
+//! around as `/etc/hosts` on Unix-y systems and the C library consults it
+//! before asking the DNS. Container environments in particular rely on it
+//! for making services available under a name.
+//!
+//! This module provides a parser for this file in [`Hosts`] and, via
+//! [`HostsFile`], a way to keep the parsed content current if the file
+//! changes. The stub resolver can be configured to use a hosts file
+//! through the `hosts` field of its
+//! [`ResolvConf`][crate::resolv::stub::conf::ResolvConf]. In this case,
+//! it answers A, AAAA, and PTR queries for names listed in the file from
+//! the file before querying any servers, so that both
+//! [`lookup_host`][crate::resolv::lookup::lookup_host] and
+//! [`lookup_addr`][crate::resolv::lookup::lookup_addr] consider it.
+
+use crate::base::iana::{Class, Rcode, Rtype};
+use crate::base::message::Message;
+use crate::base::message_builder::MessageBuilder;
+use crate::base::name::{Dname, ToDname};
+use crate::base::octets::{Octets128, OctetsRef};
+use crate::rdata::{Aaaa, Cname, Ptr, A};
+use crate::resolv::lookup::addr::dname_from_addr;
+use bytes::Bytes;
+use std::io::{BufRead, Read};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use std::vec::Vec;
+use std::{fs, io};
+
+//------------ Module Configuration ------------------------------------------
+
+/// The path of the hosts file on Unix-y systems.
+pub const DEFAULT_PATH: &str = "/etc/hosts";
+
+/// The TTL used for records synthesized from the hosts file.
+const HOSTS_TTL: u32 = 0;
+
+/// How often a [`HostsFile`] is checked for changes.
+///
+/// The file is checked when its content is requested but at most once
+/// during this interval.
+const HOSTS_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+//------------ Hosts ---------------------------------------------------------
+
+/// The content of a hosts file.
+///
+/// The file consists of lines each containing an IP address followed by
+/// the canonical host name for this address and any number of aliases, all
+/// separated by white space. Everything following a hash sign is a comment.
+/// Lines that cannot be parsed – for instance, because they contain an
+/// IPv6 address with a zone index – are silently skipped, just like the C
+/// library does.
+///
+/// All names in the file are considered absolute.
+#[derive(Clone, Debug, Default)]
+pub struct Hosts {
+    /// The entries in the order they appeared in the file.
+    entries: Vec<HostsEntry>,
+}
+
+impl Hosts {
+    /// Creates a new, empty hosts database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the hosts database from a file.
+    pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
+        Self::parse(&mut fs::File::open(path)?)
+    }
+
+    /// Parses the hosts database from a reader.
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, io::Error> {
+        let mut res = Self::new();
+        for line in io::BufReader::new(reader).lines() {
+            if let Some(entry) = HostsEntry::parse_line(&line?) {
+                res.entries.push(entry)
+            }
+        }
+        Ok(res)
+    }
+
+    /// Returns whether the database is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds an entry to the end of the database.
+    pub fn push(&mut self, entry: HostsEntry) {
+        self.entries.push(entry)
+    }
+
+    /// Returns an iterator over all entries.
+    pub fn iter(&self) -> impl Iterator<Item = &HostsEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns the canonical name for a host name.
+    ///
+    /// This is the canonical name of the first entry that lists `name`
+    /// either as canonical name or as an alias.
+    pub fn canonical_name(&self, name: &impl ToDname) -> Option<&HostName> {
+        self.entries
+            .iter()
+            .find(|entry| entry.has_name(name))
+            .map(HostsEntry::canonical)
+    }
+
+    /// Returns an iterator over all addresses for the given host name.
+    ///
+    /// The addresses are collected from all entries listing `name` as
+    /// either canonical name or alias.
+    pub fn addrs<'a, N: ToDname>(
+        &'a self,
+        name: &'a N,
+    ) -> impl Iterator<Item = IpAddr> + 'a {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.has_name(name))
+            .map(HostsEntry::addr)
+    }
+
+    /// Returns an iterator over all host names for the given address.
+    ///
+    /// For each entry for the address, the iterator returns first its
+    /// canonical name and then its aliases.
+    pub fn names(&self, addr: IpAddr) -> impl Iterator<Item = &HostName> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.addr() == addr)
+            .flat_map(HostsEntry::names)
+    }
+
+    /// Answers a query from the database.
+    ///
+    /// Answers are only provided for queries of class IN and for record
+    /// types A, AAAA, and PTR. In addition, the queried name must appear
+    /// in the database. Otherwise, the method returns `None`.
+    ///
+    /// If the queried name is an alias, the answer contains a CNAME record
+    /// pointing to the canonical name followed by the addresses of all
+    /// entries listing the queried name. If the name appears in the database
+    /// but only with addresses of the other family, the answer will not
+    /// contain any address records. All records have a TTL of zero.
+    pub fn answer<Octets>(
+        &self,
+        query: &Message<Octets>,
+    ) -> Option<Message<Bytes>>
+    where
+        Octets: AsRef<[u8]>,
+        for<'a> &'a Octets: OctetsRef,
+    {
+        let question = query.sole_question().ok()?;
+        if question.qclass() != Class::In {
+            return None;
+        }
+        let qname = question.qname();
+        let mut builder = MessageBuilder::new_bytes()
+            .start_answer(query, Rcode::NoError)
+            .ok()?;
+        builder.as_builder_mut().header_mut().set_ra(true);
+        match question.qtype() {
+            Rtype::A | Rtype::Aaaa => {
+                let canonical = self.canonical_name(qname)?;
+                if !canonical.name_eq(qname) {
+                    builder
+                        .push((qname, HOSTS_TTL, Cname::new(canonical)))
+                        .ok()?;
+                }
+                for addr in self.addrs(&qname) {
+                    match (question.qtype(), addr) {
+                        (Rtype::A, IpAddr::V4(addr)) => builder
+                            .push((canonical, HOSTS_TTL, A::new(addr)))
+                            .ok()?,
+                        (Rtype::Aaaa, IpAddr::V6(addr)) => builder
+                            .push((canonical, HOSTS_TTL, Aaaa::new(addr)))
+                            .ok()?,
+                        _ => {}
+                    }
+                }
+            }
+            Rtype::Ptr => {
+                let entry = self
+                    .entries
+                    .iter()
+                    .find(|entry| entry.reverse.name_eq(qname))?;
+                for name in self.names(entry.addr()) {
+                    builder.push((qname, HOSTS_TTL, Ptr::new(name))).ok()?;
+                }
+            }
+            _ => return None,
+        }
+        Some(builder.into_message())
+    }
+}
+
+impl FromStr for Hosts {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(&mut s.as_bytes())
+    }
+}
+
+//------------ HostsEntry ----------------------------------------------------
+
+/// A host name as used in the hosts database.
+pub type HostName = Dname<Bytes>;
+
+/// A single entry of the hosts database.
+///
+/// An entry corresponds to one line of the hosts file. It contains an IP
+/// address, the canonical name of the host with that address, and a
+/// possibly empty list of aliases.
+#[derive(Clone, Debug)]
+pub struct HostsEntry {
+    /// The address of the host.
+    addr: IpAddr,
+
+    /// The canonical name of the host.
+    canonical: HostName,
+
+    /// Additional names of the host.
+    aliases: Vec<HostName>,
+
+    /// The reverse lookup name of the address.
+    reverse: Dname<Octets128>,
+}
+
+impl HostsEntry {
+    /// Creates a new entry from an address and a canonical name.
+    pub fn new(addr: IpAddr, canonical: HostName) -> Self {
+        HostsEntry {
+            addr,
+            canonical,
+            aliases: Vec::new(),
+            reverse: dname_from_addr(addr),
+        }
+    }
+
+    /// Adds an alias to the entry.
+    pub fn push_alias(&mut self, alias: HostName) {
+        self.aliases.push(alias)
+    }
+
+    /// Returns the address of the entry.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the canonical name of the entry.
+    pub fn canonical(&self) -> &HostName {
+        &self.canonical
+    }
+
+    /// Returns the aliases of the entry.
+    pub fn aliases(&self) -> &[HostName] {
+        &self.aliases
+    }
+
+    /// Returns an iterator over the canonical name and all aliases.
+    pub fn names(&self) -> impl Iterator<Item = &HostName> {
+        Some(&self.canonical).into_iter().chain(self.aliases.iter())
+    }
+
+    /// Returns whether the entry lists the given name.
+    pub fn has_name(&self, name: &impl ToDname) -> bool {
+        self.names().any(|item| item.name_eq(name))
+    }
+
+    /// Parses a single line of the hosts file.
+    ///
+    /// Returns `None` if the line is empty or if its address or canonical
+    /// name are broken. Malformed aliases are skipped.
+    fn parse_line(line: &str) -> Option<Self> {
+        let line = match line.find('#') {
+            Some(idx) => &line[..idx],
+            None => line,
+        };
+        let mut words = line.split_whitespace();
+        let addr = IpAddr::from_str(words.next()?).ok()?;
+        let mut res =
+            Self::new(addr, HostName::from_str(words.next()?).ok()?);
+        for word in words {
+            if let Ok(alias) = HostName::from_str(word) {
+                res.push_alias(alias)
+            }
+        }
+        Some(res)
+    }
+}
+
+//------------ HostsFile -----------------------------------------------------
+
+/// A hosts file that is reloaded when it changes.
+///
+/// The value keeps the parsed content of the file at the given path. When
+/// the content is requested via [`get`][Self::get], it checks whether the
+/// modification time or size of the file have changed and, if so, reloads
+/// the file. In order to not hit the file system for every lookup, this
+/// check happens at most once every five seconds. If the file cannot be
+/// read, the database is empty.
+#[derive(Debug)]
+pub struct HostsFile {
+    /// The path of the file.
+    path: PathBuf,
+
+    /// The current content and the file state it was read from.
+    state: Mutex<HostsFileState>,
+}
+
+#[derive(Debug)]
+struct HostsFileState {
+    /// The modification time and size of the file when last read.
+    stamp: Option<(SystemTime, u64)>,
+
+    /// The earliest time to check the file again.
+    next_check: Instant,
+
+    /// The content of the file.
+    hosts: Arc<Hosts>,
+}
+
+impl HostsFile {
+    /// Creates a new hosts file for the given path.
+    ///
+    /// The file is read for the first time right away.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        let res = HostsFile {
+            path: path.into(),
+            state: Mutex::new(HostsFileState {
+                stamp: None,
+                next_check: Instant::now(),
+                hosts: Arc::new(Hosts::new()),
+            }),
+        };
+        res.reload();
+        res
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the current content of the file.
+    ///
+    /// Reloads the file if it is due to be checked and has changed since
+    /// it was last read.
+    pub fn get(&self) -> Arc<Hosts> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        if now >= state.next_check {
+            state.next_check = now + HOSTS_CHECK_INTERVAL;
+            let stamp = self.stamp();
+            if stamp != state.stamp {
+                Self::load(&self.path, &mut state, stamp);
+            }
+        }
+        state.hosts.clone()
+    }
+
+    /// Unconditionally reloads the file.
+    pub fn reload(&self) {
+        let stamp = self.stamp();
+        let mut state = self.state.lock().unwrap();
+        state.next_check = Instant::now() + HOSTS_CHECK_INTERVAL;
+        Self::load(&self.path, &mut state, stamp)
+    }
+
+    fn stamp(&self) -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(&self.path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    fn load(
+        path: &Path,
+        state: &mut HostsFileState,
+        stamp: Option<(SystemTime, u64)>,
+    ) {
+        state.stamp = stamp;
+        state.hosts = Arc::new(Hosts::parse_file(path).unwrap_or_default());
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::message_builder::MessageBuilder;
+    use std::string::ToString;
+
+    const HOSTS: &str = "\
+        # The usual suspects\n\
+        127.0.0.1   localhost\n\
+        ::1         localhost ip6-localhost   # IPv6\n\
+        192.0.2.10  web.example.com web www.example.com\n\
+        2001:db8::10 web.example.com\n\
+        192.0.2.20  mail.example.com bad..alias mail\n\
+        fe80::1%lo  broken.example.com\n\
+        not-an-addr broken.example.com\n";
+
+    fn name(s: &str) -> HostName {
+        HostName::from_str(s).unwrap()
+    }
+
+    fn query(qname: &str, qtype: Rtype) -> Message<Bytes> {
+        let mut msg = MessageBuilder::new_bytes().question();
+        msg.push((name(qname), qtype)).unwrap();
+        msg.into_message()
+    }
+
+    #[test]
+    fn parse_and_lookup() {
+        let hosts = Hosts::from_str(HOSTS).unwrap();
+        assert_eq!(hosts.iter().count(), 5);
+        assert_eq!(
+            hosts.canonical_name(&name("www.example.com")),
+            Some(&name("web.example.com"))
+        );
+        assert_eq!(
+            hosts.addrs(&name("WEB.example.com")).collect::<Vec<_>>(),
+            [
+                IpAddr::from([192, 0, 2, 10]),
+                "2001:db8::10".parse().unwrap()
+            ]
+        );
+        assert_eq!(
+            hosts
+                .names([192, 0, 2, 10].into())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["web.example.com", "web", "www.example.com"]
+        );
+        assert!(hosts.canonical_name(&name("broken.example.com")).is_none());
+        assert_eq!(
+            hosts.canonical_name(&name("mail")),
+            Some(&name("mail.example.com"))
+        );
+        assert_eq!(
+            hosts
+                .names([192, 0, 2, 20].into())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["mail.example.com", "mail"]
+        );
+    }
+
+    #[test]
+    fn answer() {
+        let hosts = Hosts::from_str(HOSTS).unwrap();
+
+        let answer =
+            hosts.answer(&query("www.example.com", Rtype::A)).unwrap();
+        assert_eq!(
+            answer.canonical_name().unwrap().to_string(),
+            "web.example.com"
+        );
+        assert_eq!(answer.header_counts().ancount(), 2);
+
+        let answer = hosts.answer(&query("localhost", Rtype::Aaaa)).unwrap();
+        assert_eq!(answer.header_counts().ancount(), 1);
+
+        let answer = hosts.answer(&query("ip6-localhost", Rtype::A)).unwrap();
+        assert_eq!(answer.header_counts().ancount(), 1);
+        assert_eq!(answer.header().rcode(), Rcode::NoError);
+
+        let answer = hosts
+            .answer(&query("10.2.0.192.in-addr.arpa", Rtype::Ptr))
+            .unwrap();
+        assert_eq!(answer.header_counts().ancount(), 3);
+
+        assert!(hosts.answer(&query("example.com", Rtype::A)).is_none());
+        assert!(hosts.answer(&query("localhost", Rtype::Mx)).is_none());
+    }
+
+    #[test]
+    fn reload() {
+        let path = std::env::temp_dir()
+            .join(format!("domain-hosts-test-{}", std::process::id()));
+        fs::write(&path, "192.0.2.1 one.example.com\n").unwrap();
+        let file = HostsFile::new(&path);
+        assert!(file
+            .get()
+            .canonical_name(&name("one.example.com"))
+            .is_some());
+
+        // Changes are only noticed once the check interval has passed.
+        fs::write(&path, "192.0.2.2 two.example.com other\n").unwrap();
+        assert!(file
+            .get()
+            .canonical_name(&name("one.example.com"))
+            .is_some());
+        file.state.lock().unwrap().next_check = Instant::now();
+        let hosts = file.get();
+        assert!(hosts.canonical_name(&name("one.example.com")).is_none());
+        assert!(hosts.canonical_name(&name("other")).is_some());
+
+        fs::remove_file(&path).unwrap();
+        file.state.lock().unwrap().next_check = Instant::now();
+        assert!(file.get().is_empty());
+    }
+
+    #[tokio::test]
+    async fn stub_resolver() {
+        use crate::resolv::stub::conf::ResolvConf;
+        use crate::resolv::stub::upstream::MockUpstream;
+        use crate::resolv::stub::StubResolver;
+
+        let path = std::env::temp_dir()
+            .join(format!("domain-hosts-stub-test-{}", std::process::id()));
+        fs::write(&path, HOSTS).unwrap();
+        let mut conf = ResolvConf::new();
+        conf.hosts = Some(path.clone());
+        conf.finalize();
+        let resolver = StubResolver::with_upstream(conf, MockUpstream::new());
+
+        let found = resolver.lookup_host(name("web.example.com")).await;
+        assert_eq!(found.unwrap().iter().count(), 2);
+        let found = resolver.lookup_host(name("www.example.com")).await;
+        let found = found.unwrap();
+        assert_eq!(found.canonical_name().to_string(), "web.example.com");
+        assert_eq!(found.iter().count(), 1);
+        let found = resolver.lookup_addr([192, 0, 2, 10].into()).await;
+        assert_eq!(found.unwrap().iter().count(), 3);
+        assert!(resolver.lookup_host(name("example.com")).await.is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -2,21 +2,21 @@
 
 use crate::base::iana::Rtype;
 use crate::base::message::RecordIter;
-use crate::base::name::{Dname, DnameBuilder, ParsedDname};
+use crate::base::name::{self, Dname, ParsedDname};
 use crate::base::octets::{Octets128, OctetsRef};
 use crate::rdata::Ptr;
 use crate::resolv::resolver::Resolver;
 use std::io;
 use std::net::IpAddr;
-use std::str::FromStr;
 
 //------------ lookup_addr ---------------------------------------------------
 
 /// Resolves the host names of an IP address.
 ///
 /// The function will query DNS using the resolver represented by `resolv`.
-/// It will query DNS only and not consider any other database the system
-/// may have.
+/// It will not consider any other database the system may have unless the
+/// resolver itself does so. The stub resolver, for instance, can be
+/// configured to consult a [hosts file][crate::resolv::hosts] first.
 ///
 /// The value returned upon success can be turned into an iterator over
 /// host names via its `iter()` method. This is due to lifetime issues.
@@ -94,49 +94,8 @@ impl<Ref: OctetsRef> Iterator for FoundAddrsIter<Ref> {
 //------------ Helper Functions ---------------------------------------------
 
 /// Translates an IP address into a domain name.
-fn dname_from_addr(addr: IpAddr) -> Dname<Octets128> {
-    match addr {
-        IpAddr::V4(addr) => {
-            let octets = addr.octets();
-            Dname::from_str(&format!(
-                "{}.{}.{}.{}.in-addr.arpa.",
-                octets[3], octets[2], octets[1], octets[0]
-            ))
-            .unwrap()
-        }
-        IpAddr::V6(addr) => {
-            let mut res = DnameBuilder::<Octets128>::new();
-            for &item in addr.octets().iter().rev() {
-                res.append_label(&[hexdigit(item)]).unwrap();
-                res.append_label(&[hexdigit(item >> 4)]).unwrap();
-            }
-            res.append_label(b"ip6").unwrap();
-            res.append_label(b"arpa").unwrap();
-            res.into_dname().unwrap()
-        }
-    }
-}
-
-fn hexdigit(nibble: u8) -> u8 {
-    match nibble & 0x0F {
-        0 => b'0',
-        1 => b'1',
-        2 => b'2',
-        3 => b'3',
-        4 => b'4',
-        5 => b'5',
-        6 => b'6',
-        7 => b'7',
-        8 => b'8',
-        9 => b'9',
-        10 => b'A',
-        11 => b'B',
-        12 => b'C',
-        13 => b'D',
-        14 => b'E',
-        15 => b'F',
-        _ => unreachable!(),
-    }
+pub(crate) fn dname_from_addr(addr: IpAddr) -> Dname<Octets128> {
+    name::dname_from_addr(addr).unwrap()
 }
 
 //============ Tests =========================================================
@@ -0,0 +1,290 @@
+//! Looking up CAA records.
+
+use crate::base::iana::{Class, Rcode, Rtype};
+use crate::base::message::Message;
+use crate::base::name::{Dname, ToDname};
+use crate::base::octets::{OctetsFrom, OctetsVec, ParseError, ShortBuf};
+use crate::rdata::Caa;
+use crate::resolv::resolver::Resolver;
+use bytes::Bytes;
+use std::vec::Vec;
+use std::{io, slice, vec};
+
+//------------ lookup_caa ----------------------------------------------------
+
+/// Looks up the relevant CAA records for a domain.
+///
+/// The function determines the relevant resource record set as described
+/// in section 3 of [RFC 8659]: It queries the CAA records for `name` and,
+/// if there are none, for each of its parent domains in turn, stopping
+/// before the root. The result contains the records of the first domain
+/// that has any as well as the name of that domain.
+///
+/// If no domain up the tree has CAA records, the result is empty. In this
+/// case, any certification authority is allowed to issue certificates.
+///
+/// If any of the queries fails, i.e., the answer has a response code other
+/// than NOERROR or NXDOMAIN, the function returns an error. Per section 3
+/// of RFC 8659, a certification authority must not issue a certificate in
+/// this case.
+///
+/// [RFC 8659]: https://tools.ietf.org/html/rfc8659
+pub async fn lookup_caa(
+    resolver: &impl Resolver,
+    name: impl ToDname,
+) -> Result<FoundCaa, CaaError> {
+    let mut name: Dname<Bytes> =
+        name.to_dname().map_err(|_| CaaError::LongName)?;
+    while !name.is_root() {
+        let answer = resolver.query((&name, Rtype::Caa)).await?;
+        let found = FoundCaa::new(&answer.as_ref().for_slice())?;
+        if !found.is_empty() {
+            return Ok(found);
+        }
+        name.parent();
+    }
+    Ok(FoundCaa::default())
+}
+
+//------------ FoundCaa ------------------------------------------------------
+
+/// The relevant CAA records found by [`lookup_caa`].
+#[derive(Clone, Debug, Default)]
+pub struct FoundCaa {
+    /// The owner of the relevant records.
+    owner: Option<Dname<OctetsVec>>,
+
+    /// The records.
+    items: Vec<Caa<Bytes>>,
+}
+
+impl FoundCaa {
+    fn new(answer: &Message<&[u8]>) -> Result<Self, CaaError> {
+        let rcode = answer.header().rcode();
+        if rcode != Rcode::NoError && rcode != Rcode::NXDomain {
+            return Err(CaaError::Rcode(rcode));
+        }
+        let owner =
+            answer.canonical_name().ok_or(CaaError::MalformedAnswer)?;
+        let mut items = Vec::new();
+        for record in answer.answer()?.flatten() {
+            if record.rtype() != Rtype::Caa
+                || record.class() != Class::In
+                || *record.owner() != owner
+            {
+                continue;
+            }
+            if let Some(record) = record.to_record::<Caa<&[u8]>>()? {
+                // Bytes can’t be created from a borrowed slice directly.
+                let data = Caa::<Vec<u8>>::octets_from(record.into_data())?;
+                items.push(Caa::octets_from(data)?);
+            }
+        }
+        if items.is_empty() {
+            return Ok(FoundCaa::default());
+        }
+        Ok(FoundCaa {
+            owner: Some(
+                owner.to_dname().map_err(|_| CaaError::MalformedAnswer)?,
+            ),
+            items,
+        })
+    }
+
+    /// Returns whether there are no relevant CAA records.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the domain name the relevant records were found at.
+    pub fn owner(&self) -> Option<&Dname<OctetsVec>> {
+        self.owner.as_ref()
+    }
+
+    /// Returns an iterator over the records.
+    pub fn iter(&self) -> slice::Iter<'_, Caa<Bytes>> {
+        self.items.iter()
+    }
+
+    /// Returns an iterator over the values of all records with `tag`.
+    ///
+    /// Tags are compared ignoring ASCII case.
+    pub fn values<'a>(
+        &'a self,
+        tag: &'a str,
+    ) -> impl Iterator<Item = &'a [u8]> {
+        self.iter()
+            .filter(move |item| item.tag_eq(tag))
+            .map(|item| item.value().as_ref())
+    }
+
+    /// Returns whether there is a critical record with an unknown tag.
+    ///
+    /// The tags in `known` are the ones the caller understands. If this
+    /// method returns `true`, a certification authority must not issue a
+    /// certificate for the domain.
+    pub fn has_unknown_critical(&self, known: &[&str]) -> bool {
+        self.iter().any(|item| {
+            item.is_critical() && !known.iter().any(|tag| item.tag_eq(tag))
+        })
+    }
+}
+
+impl IntoIterator for FoundCaa {
+    type Item = Caa<Bytes>;
+    type IntoIter = vec::IntoIter<Caa<Bytes>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FoundCaa {
+    type Item = &'a Caa<Bytes>;
+    type IntoIter = slice::Iter<'a, Caa<Bytes>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+//------------ CaaError ------------------------------------------------------
+
+/// An error happened during a CAA lookup.
+#[derive(Debug)]
+pub enum CaaError {
+    /// The domain name was too long.
+    LongName,
+
+    /// An answer could not be parsed.
+    MalformedAnswer,
+
+    /// A server answered with the given error response code.
+    Rcode(Rcode),
+
+    /// A query failed.
+    Query(io::Error),
+}
+
+impl From<io::Error> for CaaError {
+    fn from(err: io::Error) -> CaaError {
+        CaaError::Query(err)
+    }
+}
+
+impl From<ParseError> for CaaError {
+    fn from(_: ParseError) -> CaaError {
+        CaaError::MalformedAnswer
+    }
+}
+
+impl From<ShortBuf> for CaaError {
+    fn from(_: ShortBuf) -> CaaError {
+        CaaError::MalformedAnswer
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::charstr::CharStr;
+    use crate::base::record::Record;
+    use crate::resolv::stub::conf::{ResolvConf, ServerConf, Transport};
+    use crate::resolv::stub::upstream::{
+        MockRecord, MockReply, MockUpstream,
+    };
+    use crate::resolv::stub::StubResolver;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    fn name(s: &str) -> Dname<Bytes> {
+        Dname::from_str(s).unwrap()
+    }
+
+    fn caa(owner: &str, flags: u8, tag: &str, value: &str) -> MockRecord {
+        Record::new(
+            name(owner),
+            Class::In,
+            3600,
+            Caa::new(
+                flags,
+                CharStr::from_octets(Bytes::copy_from_slice(tag.as_bytes()))
+                    .unwrap(),
+                Bytes::copy_from_slice(value.as_bytes()),
+            )
+            .unwrap()
+            .into(),
+        )
+    }
+
+    #[tokio::test]
+    async fn failure() {
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (name("www.example.com"), Rtype::Caa),
+            MockReply::new(Rcode::NoError),
+        );
+        mock.push(
+            (name("example.com"), Rtype::Caa),
+            MockReply::new(Rcode::ServFail),
+        );
+        let mut conf = ResolvConf::new();
+        conf.servers.push(ServerConf::new(
+            "192.0.2.53:53".parse().unwrap(),
+            Transport::Udp,
+        ));
+        conf.options.attempts = 1;
+        conf.finalize();
+        let resolver = StubResolver::with_upstream(conf, mock.clone());
+
+        assert!(matches!(
+            lookup_caa(&&resolver, name("www.example.com")).await,
+            Err(CaaError::Rcode(Rcode::ServFail))
+        ));
+        assert!(mock.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn climb_tree() {
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (name("www.sub.example.com"), Rtype::Caa),
+            MockReply::new(Rcode::NoError),
+        );
+        mock.push(
+            (name("sub.example.com"), Rtype::Caa),
+            MockReply::new(Rcode::NXDomain),
+        );
+        mock.push(
+            (name("example.com"), Rtype::Caa),
+            MockReply::new(Rcode::NoError)
+                .answer(caa("example.com", 0, "issue", "ca.example.net"))
+                .answer(caa("example.com", 0, "ISSUE", "ca.example.org"))
+                .answer(caa("example.com", 128, "tbs", "unknown")),
+        );
+        let mut conf = ResolvConf::new();
+        conf.servers.push(ServerConf::new(
+            "192.0.2.53:53".parse().unwrap(),
+            Transport::Udp,
+        ));
+        conf.finalize();
+        let resolver = StubResolver::with_upstream(conf, mock.clone());
+
+        let found = lookup_caa(&&resolver, name("www.sub.example.com"))
+            .await
+            .unwrap();
+        assert!(mock.is_exhausted());
+        assert_eq!(
+            found.owner(),
+            Some(&name("example.com").to_dname().unwrap())
+        );
+        assert_eq!(
+            found.values("issue").collect::<Vec<_>>(),
+            [b"ca.example.net".as_ref(), b"ca.example.org".as_ref()]
+        );
+        assert!(found.has_unknown_critical(&["issue", "issuewild"]));
+        assert!(!found.has_unknown_critical(&["issue", "tbs"]));
+    }
+}
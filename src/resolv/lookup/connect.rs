@@ -0,0 +1,540 @@
+//! Establishing TCP connections to hosts and services.
+//!
+//! Once the addresses of a host are known, connecting to them one after
+//! another in the order they were received stalls badly if one address
+//! family is broken – which often enough is the case for IPv6. The
+//! functions in this module instead implement the “Happy Eyeballs”
+//! algorithm defined in [RFC 8305]: They look up IPv6 and IPv4 addresses in
+//! parallel, interleave the two address families, and start a new
+//! connection attempt if the previous one hasn’t succeeded after a short
+//! delay without abandoning it. The first connection to be established
+//! wins.
+//!
+//! [`connect_host`] connects to a host name, [`connect_srv`] to the
+//! targets found via [`lookup_srv`][super::srv::lookup_srv], and
+//! [`connect_addrs`] to a list of socket addresses determined by other
+//! means. The behaviour of the algorithm can be tweaked via [`ConnectConf`].
+//!
+//! [RFC 8305]: https://tools.ietf.org/html/rfc8305
+
+use super::srv::FoundSrvs;
+use crate::base::iana::Rtype;
+use crate::base::name::ToDname;
+use crate::base::octets::OctetsRef;
+use crate::rdata::{Aaaa, A};
+use crate::resolv::resolver::Resolver;
+use futures::future::{
+    poll_fn, ready, select, BoxFuture, Either, Future, FutureExt,
+};
+use futures::pin_mut;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::boxed::Box;
+use std::collections::VecDeque;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::task::Poll;
+use std::time::Duration;
+use std::vec::Vec;
+use tokio::net::TcpStream;
+use tokio::time::{sleep_until, timeout, Instant};
+
+//------------ ConnectConf ---------------------------------------------------
+
+/// Configuration for establishing connections.
+///
+/// The default values are those recommended by RFC 8305.
+#[derive(Clone, Debug)]
+pub struct ConnectConf {
+    /// How long to wait for the AAAA answer if the A answer arrives first.
+    ///
+    /// This field defaults to 50 milliseconds.
+    pub resolution_delay: Duration,
+
+    /// How long to wait before starting the next connection attempt.
+    ///
+    /// This field defaults to 250 milliseconds.
+    pub connection_attempt_delay: Duration,
+
+    /// How many addresses of the preferred family to try first.
+    ///
+    /// After that many addresses, the families are alternated. This field
+    /// defaults to 1.
+    pub first_family_count: usize,
+
+    /// Whether to prefer IPv6 over IPv4.
+    ///
+    /// This field defaults to `true`.
+    pub prefer_ipv6: bool,
+}
+
+impl ConnectConf {
+    /// Creates a new default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for ConnectConf {
+    fn default() -> Self {
+        ConnectConf {
+            resolution_delay: Duration::from_millis(50),
+            connection_attempt_delay: Duration::from_millis(250),
+            first_family_count: 1,
+            prefer_ipv6: true,
+        }
+    }
+}
+
+//------------ connect_host --------------------------------------------------
+
+/// Connects to a host via TCP.
+///
+/// The function looks up the IPv6 and IPv4 addresses of `qname` in
+/// parallel using `resolver`. It starts connecting to `port` on the
+/// addresses as soon as they are available, racing connection attempts
+/// as described in the [module documentation][self]. It returns the first
+/// connection to be established.
+///
+/// If no connection can be established, the error of the last failed
+/// attempt is returned. If no addresses have been found, the function
+/// fails with an error of kind `NotFound` or, if both lookups failed, with
+/// the error of the lookup.
+pub async fn connect_host<R: Resolver>(
+    resolver: &R,
+    qname: impl ToDname,
+    port: u16,
+    conf: &ConnectConf,
+) -> Result<TcpStream, io::Error>
+where
+    for<'a> &'a R::Octets: OctetsRef,
+{
+    let (first, second) = if conf.prefer_ipv6 {
+        (Rtype::Aaaa, Rtype::A)
+    } else {
+        (Rtype::A, Rtype::Aaaa)
+    };
+    let first = resolver.query((&qname, first)).boxed();
+    let second = resolver.query((&qname, second)).boxed();
+
+    let mut addrs = PendingAddrs::new(conf);
+    let late: LateAddrs = match select(first, second).await {
+        Either::Left((res, second)) => {
+            // The preferred family arrived first, go ahead right away.
+            addrs.extend_answer::<R>(res, port);
+            second.map(move |res| late_addrs::<R>(res, port)).boxed()
+        }
+        Either::Right((res, mut first)) => {
+            // The other family arrived first. Give the preferred one a
+            // little more time.
+            let err = addrs.extend_answer::<R>(res, port);
+            match timeout(conf.resolution_delay, &mut first).await {
+                Ok(res) => {
+                    let res = late_addrs::<R>(res, port);
+                    ready(res.map_err(|first_err| err.unwrap_or(first_err)))
+                        .boxed()
+                }
+                Err(_) => {
+                    first.map(move |res| late_addrs::<R>(res, port)).boxed()
+                }
+            }
+        }
+    };
+    race(addrs, Some(late), conf.connection_attempt_delay).await
+}
+
+//------------ connect_srv ---------------------------------------------------
+
+/// Connects to a service found via an SRV lookup.
+///
+/// The function takes the result of a successful call to
+/// [`lookup_srv`][super::srv::lookup_srv]. It resolves the targets in the
+/// order provided by `found` and, for each target, tries to connect to its
+/// addresses as described in the [module documentation][self]. It returns
+/// the first connection to be established.
+pub async fn connect_srv<R: Resolver>(
+    resolver: &R,
+    found: FoundSrvs,
+    conf: &ConnectConf,
+) -> Result<TcpStream, io::Error>
+where
+    for<'a> &'a R::Octets: OctetsRef,
+{
+    let mut err = None;
+    let mut items = Box::pin(found.into_stream(resolver));
+    while let Some(item) = items.next().await {
+        let res = match item {
+            Ok(item) => {
+                connect_addrs(item.resolved().iter().copied(), conf).await
+            }
+            Err(item_err) => Err(item_err),
+        };
+        match res {
+            Ok(sock) => return Ok(sock),
+            Err(item_err) => err = Some(item_err),
+        }
+    }
+    Err(err.unwrap_or_else(no_addrs))
+}
+
+//------------ connect_addrs -------------------------------------------------
+
+/// Connects to one of a list of socket addresses.
+///
+/// The addresses are reordered so that the address families alternate
+/// and connections are attempted as described in the
+/// [module documentation][self]. The function returns the first connection
+/// to be established or the error of the last failed attempt.
+pub async fn connect_addrs(
+    addrs: impl IntoIterator<Item = SocketAddr>,
+    conf: &ConnectConf,
+) -> Result<TcpStream, io::Error> {
+    let mut pending = PendingAddrs::new(conf);
+    pending.extend(addrs);
+    race(pending, None, conf.connection_attempt_delay).await
+}
+
+//------------ race ----------------------------------------------------------
+
+/// A future resolving into addresses that become available later.
+type LateAddrs<'a> = BoxFuture<'a, Result<Vec<SocketAddr>, io::Error>>;
+
+/// Races connection attempts to the pending addresses.
+///
+/// If `late` is given, the addresses it resolves to are added to the
+/// pending addresses once it is ready.
+async fn race(
+    mut pending: PendingAddrs,
+    mut late: Option<LateAddrs<'_>>,
+    attempt_delay: Duration,
+) -> Result<TcpStream, io::Error> {
+    let mut attempts = FuturesUnordered::new();
+    let mut next_attempt = Instant::now();
+    let mut err = None;
+    loop {
+        // Start a new attempt right away if nothing is in flight.
+        if attempts.is_empty() {
+            if let Some(addr) = pending.next() {
+                attempts.push(TcpStream::connect(addr));
+                next_attempt = Instant::now() + attempt_delay;
+            } else if late.is_none() {
+                return Err(err.unwrap_or_else(no_addrs));
+            }
+        }
+
+        // Wait for whichever happens first: an attempt finishing, the
+        // delay before the next attempt passing if there is one, or the
+        // late addresses arriving.
+        let sleep = sleep_until(next_attempt);
+        pin_mut!(sleep);
+        let event = poll_fn(|cx| {
+            if !attempts.is_empty() {
+                if let Poll::Ready(res) = attempts.poll_next_unpin(cx) {
+                    return Poll::Ready(RaceEvent::Attempt(res));
+                }
+                if !pending.is_empty() && sleep.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(RaceEvent::Delay);
+                }
+            }
+            if let Some(fut) = late.as_mut() {
+                if let Poll::Ready(res) = fut.poll_unpin(cx) {
+                    return Poll::Ready(RaceEvent::Late(res));
+                }
+            }
+            Poll::Pending
+        })
+        .await;
+
+        match event {
+            RaceEvent::Attempt(Some(Ok(sock))) => return Ok(sock),
+            RaceEvent::Attempt(Some(Err(attempt_err))) => {
+                err = Some(attempt_err);
+                // A failed attempt allows the next one to start
+                // immediately.
+                next_attempt = Instant::now();
+            }
+            RaceEvent::Attempt(None) => {}
+            RaceEvent::Delay => {
+                if let Some(addr) = pending.next() {
+                    attempts.push(TcpStream::connect(addr));
+                    next_attempt = Instant::now() + attempt_delay;
+                }
+            }
+            RaceEvent::Late(res) => {
+                late = None;
+                match res {
+                    Ok(addrs) => pending.extend(addrs),
+                    Err(late_err) => {
+                        if err.is_none() {
+                            err = Some(late_err)
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What happened while racing connection attempts.
+enum RaceEvent {
+    /// A connection attempt has finished.
+    Attempt(Option<Result<TcpStream, io::Error>>),
+
+    /// The delay before starting the next attempt has passed.
+    Delay,
+
+    /// The late addresses have arrived.
+    Late(Result<Vec<SocketAddr>, io::Error>),
+}
+
+//------------ PendingAddrs --------------------------------------------------
+
+/// The addresses still waiting for a connection attempt.
+///
+/// The type keeps one queue per address family and hands out addresses
+/// alternating between the families, starting with a configurable number
+/// of addresses from the preferred family.
+#[derive(Clone, Debug)]
+struct PendingAddrs {
+    preferred: VecDeque<SocketAddr>,
+    other: VecDeque<SocketAddr>,
+    prefer_ipv6: bool,
+    first_family_count: usize,
+
+    /// The number of addresses handed out so far.
+    taken: usize,
+
+    /// Was the last address handed out of the preferred family?
+    last_preferred: bool,
+}
+
+impl PendingAddrs {
+    fn new(conf: &ConnectConf) -> Self {
+        PendingAddrs {
+            preferred: VecDeque::new(),
+            other: VecDeque::new(),
+            prefer_ipv6: conf.prefer_ipv6,
+            first_family_count: conf.first_family_count,
+            taken: 0,
+            last_preferred: false,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.preferred.is_empty() && self.other.is_empty()
+    }
+
+    fn push(&mut self, addr: SocketAddr) {
+        if addr.is_ipv6() == self.prefer_ipv6 {
+            self.preferred.push_back(addr)
+        } else {
+            self.other.push_back(addr)
+        }
+    }
+
+    fn extend(&mut self, addrs: impl IntoIterator<Item = SocketAddr>) {
+        addrs.into_iter().for_each(|addr| self.push(addr))
+    }
+
+    /// Adds the addresses from an answer.
+    ///
+    /// Returns the error if the query failed.
+    fn extend_answer<R: Resolver>(
+        &mut self,
+        answer: Result<R::Answer, io::Error>,
+        port: u16,
+    ) -> Option<io::Error>
+    where
+        for<'a> &'a R::Octets: OctetsRef,
+    {
+        match answer {
+            Ok(answer) => {
+                self.extend(answer_addrs::<R>(&answer, port));
+                None
+            }
+            Err(err) => Some(err),
+        }
+    }
+
+    fn next(&mut self) -> Option<SocketAddr> {
+        let preferred = if self.taken < self.first_family_count {
+            !self.preferred.is_empty()
+        } else {
+            !self.last_preferred
+        };
+        let res = if preferred {
+            self.preferred.pop_front().map(|addr| (addr, true))
+        } else {
+            self.other.pop_front().map(|addr| (addr, false))
+        };
+        let res = res.or_else(|| {
+            self.preferred
+                .pop_front()
+                .map(|addr| (addr, true))
+                .or_else(|| self.other.pop_front().map(|addr| (addr, false)))
+        });
+        res.map(|(addr, preferred)| {
+            self.taken += 1;
+            self.last_preferred = preferred;
+            addr
+        })
+    }
+}
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Returns the socket addresses for the canonical name in an answer.
+fn answer_addrs<R: Resolver>(answer: &R::Answer, port: u16) -> Vec<SocketAddr>
+where
+    for<'a> &'a R::Octets: OctetsRef,
+{
+    let msg = answer.as_ref();
+    let (name, records) = match (msg.canonical_name(), msg.answer()) {
+        (Some(name), Ok(records)) => (name, records),
+        _ => return Vec::new(),
+    };
+    let mut res = Vec::new();
+    for record in records.limit_to::<A>().flatten() {
+        if *record.owner() == name {
+            res.push(SocketAddr::new(IpAddr::V4(record.data().addr()), port))
+        }
+    }
+    for record in msg.answer().unwrap().limit_to::<Aaaa>().flatten() {
+        if *record.owner() == name {
+            res.push(SocketAddr::new(IpAddr::V6(record.data().addr()), port))
+        }
+    }
+    res
+}
+
+/// Converts the result of a query into the result of a late lookup.
+fn late_addrs<R: Resolver>(
+    answer: Result<R::Answer, io::Error>,
+    port: u16,
+) -> Result<Vec<SocketAddr>, io::Error>
+where
+    for<'a> &'a R::Octets: OctetsRef,
+{
+    answer.map(|answer| answer_addrs::<R>(&answer, port))
+}
+
+fn no_addrs() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "no addresses found")
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::{Class, Rcode};
+    use crate::base::name::Dname;
+    use crate::base::record::Record;
+    use crate::resolv::stub::conf::{ResolvConf, ServerConf, Transport};
+    use crate::resolv::stub::upstream::{MockReply, MockUpstream};
+    use crate::resolv::stub::StubResolver;
+    use bytes::Bytes;
+    use std::str::FromStr;
+    use tokio::net::TcpListener;
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn interleave() {
+        let mut pending = PendingAddrs::new(&ConnectConf::default());
+        pending.extend(vec![
+            addr("192.0.2.1:80"),
+            addr("192.0.2.2:80"),
+            addr("192.0.2.3:80"),
+            addr("[2001:db8::1]:80"),
+            addr("[2001:db8::2]:80"),
+        ]);
+        let order: Vec<_> = std::iter::from_fn(|| pending.next()).collect();
+        assert_eq!(
+            order,
+            [
+                addr("[2001:db8::1]:80"),
+                addr("192.0.2.1:80"),
+                addr("[2001:db8::2]:80"),
+                addr("192.0.2.2:80"),
+                addr("192.0.2.3:80"),
+            ]
+        );
+
+        let mut pending = PendingAddrs::new(&ConnectConf {
+            first_family_count: 2,
+            prefer_ipv6: false,
+            ..Default::default()
+        });
+        pending.extend(vec![
+            addr("[2001:db8::1]:80"),
+            addr("[2001:db8::2]:80"),
+            addr("192.0.2.1:80"),
+            addr("192.0.2.2:80"),
+            addr("192.0.2.3:80"),
+        ]);
+        let order: Vec<_> = std::iter::from_fn(|| pending.next()).collect();
+        assert_eq!(
+            order,
+            [
+                addr("192.0.2.1:80"),
+                addr("192.0.2.2:80"),
+                addr("[2001:db8::1]:80"),
+                addr("192.0.2.3:80"),
+                addr("[2001:db8::2]:80"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn connect_to_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // The first address refuses, the second one accepts.
+        let sock = connect_addrs(
+            vec![
+                SocketAddr::new([127, 0, 0, 1].into(), 0),
+                listener.local_addr().unwrap(),
+            ],
+            &ConnectConf::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(sock.peer_addr().unwrap().port(), port);
+
+        let name = Dname::<Bytes>::from_str("example.com").unwrap();
+        let mock = MockUpstream::new();
+        mock.push(
+            (name.clone(), Rtype::A),
+            MockReply::new(Rcode::NoError).answer(Record::new(
+                name.clone(),
+                Class::In,
+                3600,
+                A::from_octets(127, 0, 0, 1).into(),
+            )),
+        );
+        mock.push(
+            (name.clone(), Rtype::Aaaa),
+            MockReply::new(Rcode::NoError),
+        );
+        let mut conf = ResolvConf::new();
+        conf.servers
+            .push(ServerConf::new(addr("192.0.2.53:53"), Transport::Udp));
+        conf.finalize();
+        let resolver = StubResolver::with_upstream(conf, mock);
+        let sock = connect_host(&&resolver, &name, port, &ConnectConf::new())
+            .await
+            .unwrap();
+        assert_eq!(sock.peer_addr().unwrap().port(), port);
+    }
+
+    #[tokio::test]
+    async fn nothing_found() {
+        let err = connect_addrs(None, &ConnectConf::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
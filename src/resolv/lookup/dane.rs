@@ -0,0 +1,574 @@
+//! Verifying TLS server certificates via DANE.
+//!
+//! DNS-Based Authentication of Named Entities (DANE), defined in
+//! [RFC 6698] and updated by [RFC 7671], allows a domain to publish which
+//! certificates its TLS servers use via TLSA records. This module provides
+//! the client side: [`lookup_dane`] fetches the TLSA records for a service
+//! endpoint and insists that they were validated via DNSSEC, and
+//! [`Dane::verify`] matches them against the certificate chain presented
+//! by the server.
+//!
+//! The module only checks the TLSA records. Depending on the certificate
+//! usage that matched, the caller still has to perform additional checks
+//! which are described with the variants of [`DaneVerdict`].
+//!
+//! This module is only available if the `ring` feature is enabled.
+//!
+//! [RFC 6698]: https://tools.ietf.org/html/rfc6698
+//! [RFC 7671]: https://tools.ietf.org/html/rfc7671
+#![cfg(feature = "ring")]
+#![cfg_attr(docsrs, doc(cfg(feature = "ring")))]
+
+use super::tlsa::{lookup_tlsa, FoundTlsa, TlsaError};
+use crate::base::iana::{TlsaCertUsage, TlsaMatchingType, TlsaSelector};
+use crate::base::name::ToDname;
+use crate::rdata::Tlsa;
+use crate::resolv::resolver::Resolver;
+use bytes::Bytes;
+use core::fmt;
+use ring::digest;
+
+//------------ lookup_dane ---------------------------------------------------
+
+/// Looks up the DANE information for a service endpoint.
+///
+/// The function queries the TLSA records for `port`, `protocol`, and
+/// `host` via [`lookup_tlsa`] which also describes how the query name is
+/// constructed.
+///
+/// If the server denies the existence of TLSA records, i.e., answers with
+/// NOERROR or NXDOMAIN and no records, DANE doesn’t apply to the endpoint
+/// and the function returns `Ok(None)`. This covers both an authenticated
+/// denial and a denial for an unsigned zone. Any other response code,
+/// including the SERVFAIL a validating resolver returns for bogus answers,
+/// results in an error so that a failed validation can’t be used to
+/// downgrade the connection.
+///
+/// If there are records but the answer was not validated via DNSSEC –
+/// which for the stub resolver means that the upstream server didn’t set
+/// the AD bit – the function returns [`DaneError::Insecure`]. Such records
+/// must not be used. The stub resolver always sets the AD bit in TLSA
+/// queries to ask the server for this information, independently of the
+/// `trust_ad` option.
+pub async fn lookup_dane(
+    resolver: &impl Resolver,
+    port: u16,
+    protocol: &str,
+    host: impl ToDname,
+) -> Result<Option<Dane>, DaneError> {
+    let tlsa = lookup_tlsa(resolver, port, protocol, host).await?;
+    if tlsa.is_empty() {
+        Ok(None)
+    } else if !tlsa.is_secure() {
+        Err(DaneError::Insecure)
+    } else {
+        Ok(Some(Dane { tlsa }))
+    }
+}
+
+//------------ Dane ----------------------------------------------------------
+
+/// The secure TLSA records of a service endpoint.
+///
+/// Values of this type are created by [`lookup_dane`]. Use
+/// [`verify`][Self::verify] to check the certificate chain presented by
+/// the server.
+#[derive(Clone, Debug)]
+pub struct Dane {
+    tlsa: FoundTlsa,
+}
+
+impl Dane {
+    /// Returns the TLSA records.
+    pub fn tlsa(&self) -> &FoundTlsa {
+        &self.tlsa
+    }
+
+    /// Returns whether any of the records is usable by this implementation.
+    ///
+    /// Records with unknown certificate usages, selectors, or matching
+    /// types are ignored. If this method returns `false`, DANE
+    /// authentication of the server is impossible.
+    pub fn is_usable(&self) -> bool {
+        self.tlsa.iter().any(is_usable)
+    }
+
+    /// Verifies a certificate chain against the TLSA records.
+    ///
+    /// The chain is given as a slice of DER-encoded certificates in the
+    /// order presented by the server, i.e., with the end entity certificate
+    /// first. Records with the DANE-EE and PKIX-EE usages are only matched
+    /// against the end entity certificate, records with DANE-TA and PKIX-TA
+    /// against the remaining certificates.
+    ///
+    /// If several records match, the verdict prefers DANE-EE over DANE-TA
+    /// over PKIX-EE over PKIX-TA, i.e., the verdict requiring the fewest
+    /// additional checks. If no record matches, returns
+    /// [`DaneError::NoMatch`]. A certificate that can’t be parsed doesn’t
+    /// match records with the SPKI selector.
+    pub fn verify<C: AsRef<[u8]>>(
+        &self,
+        chain: &[C],
+    ) -> Result<DaneVerdict, DaneError> {
+        let (ee, rest) = chain.split_first().ok_or(DaneError::NoMatch)?;
+        let ee = ee.as_ref();
+        let mut best: Option<DaneVerdict> = None;
+        for item in self.tlsa.iter().filter(|item| is_usable(item)) {
+            let verdict = match item.usage() {
+                TlsaCertUsage::DaneEe | TlsaCertUsage::PkixEe => {
+                    if !matches(item, ee) {
+                        continue;
+                    }
+                    if item.usage() == TlsaCertUsage::DaneEe {
+                        DaneVerdict::DaneEe
+                    } else {
+                        DaneVerdict::PkixEe
+                    }
+                }
+                _ => {
+                    let mut found = None;
+                    for (idx, cert) in rest.iter().enumerate() {
+                        if matches(item, cert.as_ref()) {
+                            found = Some(idx + 1);
+                            break;
+                        }
+                    }
+                    match (found, item.usage()) {
+                        (Some(cert), TlsaCertUsage::DaneTa) => {
+                            DaneVerdict::DaneTa { cert }
+                        }
+                        (Some(cert), _) => DaneVerdict::PkixTa { cert },
+                        (None, _) => continue,
+                    }
+                }
+            };
+            if best.map_or(true, |best| verdict.rank() < best.rank()) {
+                best = Some(verdict)
+            }
+        }
+        best.ok_or(DaneError::NoMatch)
+    }
+}
+
+/// Returns whether a record has only parameters we understand.
+fn is_usable(item: &Tlsa<Bytes>) -> bool {
+    matches!(
+        item.usage(),
+        TlsaCertUsage::PkixTa
+            | TlsaCertUsage::PkixEe
+            | TlsaCertUsage::DaneTa
+            | TlsaCertUsage::DaneEe
+    ) && matches!(item.selector(), TlsaSelector::Cert | TlsaSelector::Spki)
+        && matches!(
+            item.matching_type(),
+            TlsaMatchingType::Full
+                | TlsaMatchingType::Sha256
+                | TlsaMatchingType::Sha512
+        )
+}
+
+/// Returns whether a record matches a certificate.
+///
+/// If the public key can’t be extracted from the certificate, the record
+/// doesn’t match.
+fn matches(item: &Tlsa<Bytes>, cert: &[u8]) -> bool {
+    let selected = match item.selector() {
+        TlsaSelector::Spki => match spki(cert) {
+            Some(spki) => spki,
+            None => return false,
+        },
+        _ => cert,
+    };
+    match item.matching_type() {
+        TlsaMatchingType::Sha256 => {
+            digest::digest(&digest::SHA256, selected).as_ref()
+                == item.data().as_ref()
+        }
+        TlsaMatchingType::Sha512 => {
+            digest::digest(&digest::SHA512, selected).as_ref()
+                == item.data().as_ref()
+        }
+        _ => selected == item.data().as_ref(),
+    }
+}
+
+//------------ DaneVerdict ---------------------------------------------------
+
+/// The outcome of a successful DANE verification.
+///
+/// The variant tells which certificate usage matched and thus which checks
+/// the caller still has to perform.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DaneVerdict {
+    /// A PKIX-TA record matched a certificate in the chain.
+    ///
+    /// The chain must pass regular PKIX validation and contain the
+    /// certificate at index `cert` on the path to a trusted root. The
+    /// server name must be checked as usual.
+    PkixTa { cert: usize },
+
+    /// A PKIX-EE record matched the end entity certificate.
+    ///
+    /// The chain must pass regular PKIX validation and the server name
+    /// must be checked as usual.
+    PkixEe,
+
+    /// A DANE-TA record matched a certificate in the chain.
+    ///
+    /// The certificate at index `cert` is the trust anchor: the chain up to
+    /// it must be valid, but it need not be trusted by any root store. The
+    /// server name must be checked against the end entity certificate.
+    DaneTa { cert: usize },
+
+    /// A DANE-EE record matched the end entity certificate.
+    ///
+    /// No further checks are required. In particular, neither the validity
+    /// period nor the names of the certificate are relevant.
+    DaneEe,
+}
+
+impl DaneVerdict {
+    /// Returns the rank of the verdict with the preferred one lowest.
+    fn rank(self) -> u8 {
+        match self {
+            DaneVerdict::DaneEe => 0,
+            DaneVerdict::DaneTa { .. } => 1,
+            DaneVerdict::PkixEe => 2,
+            DaneVerdict::PkixTa { .. } => 3,
+        }
+    }
+
+    /// Returns the certificate usage that matched.
+    pub fn usage(self) -> TlsaCertUsage {
+        match self {
+            DaneVerdict::PkixTa { .. } => TlsaCertUsage::PkixTa,
+            DaneVerdict::PkixEe => TlsaCertUsage::PkixEe,
+            DaneVerdict::DaneTa { .. } => TlsaCertUsage::DaneTa,
+            DaneVerdict::DaneEe => TlsaCertUsage::DaneEe,
+        }
+    }
+
+    /// Returns whether the caller must also perform PKIX validation.
+    pub fn requires_pkix(self) -> bool {
+        matches!(self, DaneVerdict::PkixTa { .. } | DaneVerdict::PkixEe)
+    }
+}
+
+//------------ DER Parsing ---------------------------------------------------
+
+/// Returns the DER-encoded SubjectPublicKeyInfo of a certificate.
+///
+/// The certificate is only parsed as far as necessary to find the field.
+/// Returns `None` if the certificate is malformed.
+fn spki(cert: &[u8]) -> Option<&[u8]> {
+    let (cert, _) = der_content(cert, 0x30)?;
+    let (tbs, _) = der_content(cert, 0x30)?;
+
+    // Skip the optional version, then serialNumber, signature, issuer,
+    // validity, and subject.
+    let mut tbs = tbs;
+    if tbs.first() == Some(&0xa0) {
+        tbs = der_split(tbs)?.1;
+    }
+    for _ in 0..5 {
+        tbs = der_split(tbs)?.1;
+    }
+    let (spki, _) = der_split(tbs)?;
+    if spki.first() != Some(&0x30) {
+        return None;
+    }
+    Some(spki)
+}
+
+/// Splits off the first DER value including its tag and length.
+fn der_split(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let first = *data.get(1)?;
+    let (header, len) = if first < 0x80 {
+        (2, usize::from(first))
+    } else {
+        let count = usize::from(first & 0x7f);
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let bytes = data.get(2..2 + count)?;
+        let len = bytes
+            .iter()
+            .fold(0usize, |len, byte| (len << 8) | usize::from(*byte));
+        (2 + count, len)
+    };
+    let end = header.checked_add(len)?;
+    if end > data.len() {
+        return None;
+    }
+    Some(data.split_at(end))
+}
+
+/// Returns the content of the first DER value which must have `tag`.
+fn der_content(data: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    if data.first() != Some(&tag) {
+        return None;
+    }
+    let (value, rest) = der_split(data)?;
+    let header = if value[1] < 0x80 {
+        2
+    } else {
+        2 + usize::from(value[1] & 0x7f)
+    };
+    Some((&value[header..], rest))
+}
+
+//------------ DaneError -----------------------------------------------------
+
+/// An error happened during DANE lookup or verification.
+#[derive(Debug)]
+pub enum DaneError {
+    /// Looking up the TLSA records failed.
+    Lookup(TlsaError),
+
+    /// The TLSA records were not validated via DNSSEC.
+    Insecure,
+
+    /// None of the TLSA records matched the certificate chain.
+    NoMatch,
+}
+
+impl From<TlsaError> for DaneError {
+    fn from(err: TlsaError) -> DaneError {
+        DaneError::Lookup(err)
+    }
+}
+
+impl fmt::Display for DaneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DaneError::Lookup(ref err) => {
+                write!(f, "TLSA lookup failed: {:?}", err)
+            }
+            DaneError::Insecure => f.write_str("insecure TLSA records"),
+            DaneError::NoMatch => {
+                f.write_str("no TLSA record matches the certificate chain")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DaneError {}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::{Class, Rcode, Rtype};
+    use crate::base::name::Dname;
+    use crate::base::rdata::UnknownRecordData;
+    use crate::base::record::Record;
+    use crate::resolv::stub::conf::{ResolvConf, ServerConf, Transport};
+    use crate::resolv::stub::upstream::{
+        MockRecord, MockReply, MockUpstream,
+    };
+    use crate::resolv::stub::StubResolver;
+    use bytes::Bytes;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::vec::Vec;
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut res = vec![tag];
+        if content.len() < 0x80 {
+            res.push(content.len() as u8)
+        } else {
+            res.push(0x82);
+            res.extend_from_slice(&(content.len() as u16).to_be_bytes());
+        }
+        res.extend_from_slice(content);
+        res
+    }
+
+    /// Creates a fake certificate with just enough structure.
+    fn cert(key: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let spki = tlv(
+            0x30,
+            &[tlv(0x30, &tlv(0x06, b"\x2a\x03")), tlv(0x03, key)].concat(),
+        );
+        let tbs = tlv(
+            0x30,
+            &[
+                tlv(0xa0, &tlv(0x02, b"\x02")),
+                tlv(0x02, b"\x01"),
+                tlv(0x30, b""),
+                tlv(0x30, &[0x55; 200]),
+                tlv(0x30, b""),
+                tlv(0x30, b""),
+                spki.clone(),
+            ]
+            .concat(),
+        );
+        let cert =
+            tlv(0x30, &[tbs, tlv(0x30, b""), tlv(0x03, b"\0")].concat());
+        (cert, spki)
+    }
+
+    fn tlsa(
+        usage: u8,
+        selector: u8,
+        matching: u8,
+        data: &[u8],
+    ) -> MockRecord {
+        let mut rdata = vec![usage, selector, matching];
+        rdata.extend_from_slice(data);
+        Record::new(
+            Dname::from_str("_25._tcp.mx.example.com").unwrap(),
+            Class::In,
+            3600,
+            UnknownRecordData::from_octets(Rtype::Tlsa, Bytes::from(rdata))
+                .into(),
+        )
+    }
+
+    async fn lookup(reply: MockReply) -> Result<Option<Dane>, DaneError> {
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (
+                Dname::<Bytes>::from_str("_25._tcp.mx.example.com").unwrap(),
+                Rtype::Tlsa,
+            ),
+            reply,
+        );
+        let mut conf = ResolvConf::new();
+        conf.servers.push(ServerConf::new(
+            "192.0.2.53:53".parse().unwrap(),
+            Transport::Udp,
+        ));
+        conf.options.attempts = 1;
+        conf.finalize();
+        let resolver = StubResolver::with_upstream(conf, mock);
+        lookup_dane(
+            &&resolver,
+            25,
+            "tcp",
+            Dname::<Bytes>::from_str("mx.example.com").unwrap(),
+        )
+        .await
+    }
+
+    #[test]
+    fn extract_spki() {
+        let (cert, spki) = cert(b"\0key");
+        assert_eq!(super::spki(&cert).unwrap(), spki.as_slice());
+        assert!(super::spki(&cert[..cert.len() - 1]).is_none());
+    }
+
+    #[tokio::test]
+    async fn verify() {
+        let (ee, ee_spki) = cert(b"\0end entity");
+        let (ca, _) = cert(b"\0intermediate");
+        let (other, _) = cert(b"\0other");
+        let ee_hash = digest::digest(&digest::SHA256, &ee_spki);
+        let ca_hash = digest::digest(&digest::SHA512, &ca);
+
+        let dane = lookup(
+            MockReply::new(Rcode::NoError)
+                .authenticated()
+                .answer(tlsa(2, 0, 2, ca_hash.as_ref()))
+                .answer(tlsa(3, 1, 1, ee_hash.as_ref()))
+                .answer(tlsa(4, 0, 0, b"unknown usage")),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(dane.is_usable());
+        assert_eq!(dane.verify(&[&ee, &ca]).unwrap(), DaneVerdict::DaneEe);
+        assert_eq!(
+            dane.verify(&[&other, &ca]).unwrap(),
+            DaneVerdict::DaneTa { cert: 1 }
+        );
+        assert!(matches!(
+            dane.verify(&[&other, &other]),
+            Err(DaneError::NoMatch)
+        ));
+        assert!(!DaneVerdict::DaneTa { cert: 1 }.requires_pkix());
+
+        let dane = lookup(
+            MockReply::new(Rcode::NoError)
+                .authenticated()
+                .answer(tlsa(1, 0, 0, &ee)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let verdict = dane.verify(&[&ee]).unwrap();
+        assert_eq!(verdict, DaneVerdict::PkixEe);
+        assert!(verdict.requires_pkix());
+    }
+
+    #[tokio::test]
+    async fn verify_malformed() {
+        let (ee, _) = cert(b"\0end entity");
+        let (ca, ca_spki) = cert(b"\0intermediate");
+        let broken = &ca[..ca.len() - 1];
+        let ca_hash = digest::digest(&digest::SHA256, &ca_spki);
+
+        let dane = lookup(
+            MockReply::new(Rcode::NoError)
+                .authenticated()
+                .answer(tlsa(3, 1, 1, b"no match"))
+                .answer(tlsa(2, 1, 1, ca_hash.as_ref())),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            dane.verify(&[broken, &ee, broken, &ca]).unwrap(),
+            DaneVerdict::DaneTa { cert: 3 }
+        );
+        assert!(matches!(
+            dane.verify(&[broken, broken]),
+            Err(DaneError::NoMatch)
+        ));
+    }
+
+    #[tokio::test]
+    async fn insecure() {
+        assert!(matches!(
+            lookup(
+                MockReply::new(Rcode::NoError).answer(tlsa(3, 1, 1, b"x"))
+            )
+            .await,
+            Err(DaneError::Insecure)
+        ));
+        assert!(lookup(MockReply::new(Rcode::NoError).authenticated())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn denial() {
+        assert!(lookup(MockReply::new(Rcode::NoError))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(lookup(MockReply::new(Rcode::NXDomain))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn failure() {
+        for rcode in &[Rcode::ServFail, Rcode::Refused] {
+            let res = lookup(
+                MockReply::new(*rcode)
+                    .authenticated()
+                    .answer(tlsa(3, 1, 1, b"x")),
+            )
+            .await;
+            assert!(matches!(
+                res,
+                Err(DaneError::Lookup(TlsaError::Rcode(value)))
+                    if value == *rcode
+            ));
+        }
+    }
+}
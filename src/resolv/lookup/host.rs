@@ -20,11 +20,15 @@ use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 /// IP addresses or even socket addresses. Since the lookup may determine that
 /// the host name is in fact an alias for another name, the value will also
 /// return the canonical name.
+///
+/// If the resolver has been configured to use a
+/// [hosts file][crate::resolv::hosts], names listed there are answered
+/// from the file.
 pub async fn lookup_host<R: Resolver>(
     resolver: &R,
     qname: impl ToDname,
 ) -> Result<FoundHosts<R>, io::Error> {
-    let (a, aaaa) = tokio::join!(
+    let (a, aaaa) = futures::join!(
         resolver.query((&qname, Rtype::A)),
         resolver.query((&qname, Rtype::Aaaa)),
     );
@@ -4,9 +4,29 @@
 //! implement applications of the DNS.
 
 pub use self::addr::lookup_addr;
+pub use self::caa::lookup_caa;
+#[cfg(feature = "resolv-tokio")]
+pub use self::connect::{connect_addrs, connect_host, connect_srv};
+#[cfg(feature = "ring")]
+pub use self::dane::lookup_dane;
 pub use self::host::{lookup_host, search_host};
+pub use self::mx::lookup_mx;
+pub use self::naptr::lookup_naptr;
 pub use self::srv::lookup_srv;
+pub use self::svcb::{lookup_https, lookup_svcb};
+pub use self::tlsa::lookup_tlsa;
+pub use self::txt::lookup_txt;
 
 pub mod addr;
+pub mod caa;
+#[cfg(feature = "resolv-tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "resolv-tokio")))]
+pub mod connect;
+pub mod dane;
 pub mod host;
+pub mod mx;
+pub mod naptr;
 pub mod srv;
+pub mod svcb;
+pub mod tlsa;
+pub mod txt;
@@ -0,0 +1,309 @@
+//! Looking up MX records.
+
+use crate::base::iana::{Rcode, Rtype};
+use crate::base::message::Message;
+use crate::base::name::{Dname, ToDname};
+use crate::base::octets::{OctetsVec, ParseError};
+use crate::rdata::Mx;
+use crate::resolv::resolver::Resolver;
+use rand::seq::SliceRandom;
+use std::vec::Vec;
+use std::{io, slice, vec};
+
+//------------ lookup_mx -----------------------------------------------------
+
+/// Looks up the mail exchangers for a domain.
+///
+/// The function queries the MX records for `name` using `resolver`. The
+/// exchanges found are ordered by their preference, with exchanges of
+/// equal preference in random order as required by section 5.1 of
+/// [RFC 5321].
+///
+/// If there are no MX records, the result contains the implicit MX, i.e.,
+/// `name` itself with a preference of zero. You can check for this case
+/// via [`FoundMx::is_implicit`].
+///
+/// If the domain has a “null MX” record as defined in [RFC 7505], which
+/// declares that the domain doesn’t accept email, or if the domain doesn’t
+/// exist at all, the function returns `Ok(None)`. If the server answers
+/// with any other error response code, the function returns an error.
+///
+/// [RFC 5321]: https://tools.ietf.org/html/rfc5321
+/// [RFC 7505]: https://tools.ietf.org/html/rfc7505
+pub async fn lookup_mx(
+    resolver: &impl Resolver,
+    name: impl ToDname,
+) -> Result<Option<FoundMx>, MxError> {
+    let answer = resolver.query((&name, Rtype::Mx)).await?;
+    FoundMx::new(&answer.as_ref().for_slice(), name)
+}
+
+//------------ FoundMx -------------------------------------------------------
+
+/// The mail exchangers found by [`lookup_mx`].
+#[derive(Clone, Debug)]
+pub struct FoundMx {
+    /// The exchanges in the order they should be tried.
+    items: Vec<MxItem>,
+
+    /// Is the sole item the implicit MX?
+    implicit: bool,
+}
+
+impl FoundMx {
+    fn new(
+        answer: &Message<&[u8]>,
+        name: impl ToDname,
+    ) -> Result<Option<Self>, MxError> {
+        match answer.header().rcode() {
+            Rcode::NoError => {}
+            Rcode::NXDomain => return Ok(None),
+            rcode => return Err(MxError::Rcode(rcode)),
+        }
+        let owner =
+            answer.canonical_name().ok_or(MxError::MalformedAnswer)?;
+        let mut items = Vec::new();
+        for record in answer.answer()?.limit_to_in::<Mx<_>>().flatten() {
+            if *record.owner() == owner {
+                items.push(MxItem {
+                    preference: record.data().preference(),
+                    exchange: record
+                        .data()
+                        .exchange()
+                        .to_dname()
+                        .map_err(|_| MxError::MalformedAnswer)?,
+                })
+            }
+        }
+
+        if items.is_empty() {
+            return Ok(Some(FoundMx {
+                items: vec![MxItem {
+                    preference: 0,
+                    exchange: name
+                        .to_dname()
+                        .map_err(|_| MxError::LongName)?,
+                }],
+                implicit: true,
+            }));
+        }
+        if items.len() == 1
+            && items[0].preference == 0
+            && items[0].exchange.is_root()
+        {
+            // RFC 7505 null MX.
+            return Ok(None);
+        }
+
+        items.shuffle(&mut rand::thread_rng());
+        items.sort_by_key(|item| item.preference);
+        Ok(Some(FoundMx {
+            items,
+            implicit: false,
+        }))
+    }
+
+    /// Returns whether the result is the implicit MX.
+    pub fn is_implicit(&self) -> bool {
+        self.implicit
+    }
+
+    /// Returns a slice of the exchanges.
+    pub fn as_slice(&self) -> &[MxItem] {
+        &self.items
+    }
+
+    /// Returns an iterator over the exchanges.
+    pub fn iter(&self) -> slice::Iter<'_, MxItem> {
+        self.items.iter()
+    }
+}
+
+impl IntoIterator for FoundMx {
+    type Item = MxItem;
+    type IntoIter = vec::IntoIter<MxItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FoundMx {
+    type Item = &'a MxItem;
+    type IntoIter = slice::Iter<'a, MxItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+//------------ MxItem --------------------------------------------------------
+
+/// A single mail exchange.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MxItem {
+    /// The preference of the exchange.
+    preference: u16,
+
+    /// The host name of the exchange.
+    exchange: Dname<OctetsVec>,
+}
+
+impl MxItem {
+    /// Returns the preference of the exchange.
+    ///
+    /// Exchanges with lower values are to be preferred.
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// Returns the host name of the exchange.
+    pub fn exchange(&self) -> &Dname<OctetsVec> {
+        &self.exchange
+    }
+}
+
+//------------ MxError -------------------------------------------------------
+
+/// An error happened during an MX lookup.
+#[derive(Debug)]
+pub enum MxError {
+    /// The domain name was too long.
+    LongName,
+
+    /// The answer could not be parsed.
+    MalformedAnswer,
+
+    /// The server answered with the given error response code.
+    Rcode(Rcode),
+
+    /// The query failed.
+    Query(io::Error),
+}
+
+impl From<io::Error> for MxError {
+    fn from(err: io::Error) -> MxError {
+        MxError::Query(err)
+    }
+}
+
+impl From<ParseError> for MxError {
+    fn from(_: ParseError) -> MxError {
+        MxError::MalformedAnswer
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::Class;
+    use crate::base::record::Record;
+    use crate::resolv::stub::conf::{ResolvConf, ServerConf, Transport};
+    use crate::resolv::stub::upstream::{
+        MockRecord, MockReply, MockUpstream,
+    };
+    use crate::resolv::stub::StubResolver;
+    use bytes::Bytes;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    fn name(s: &str) -> Dname<Bytes> {
+        if s == "." {
+            Dname::root_bytes()
+        } else {
+            Dname::from_str(s).unwrap()
+        }
+    }
+
+    fn resolver(mock: &Arc<MockUpstream>) -> StubResolver {
+        let mut conf = ResolvConf::new();
+        conf.servers.push(ServerConf::new(
+            "192.0.2.53:53".parse().unwrap(),
+            Transport::Udp,
+        ));
+        conf.options.attempts = 1;
+        conf.finalize();
+        StubResolver::with_upstream(conf, mock.clone())
+    }
+
+    fn mx(owner: &str, preference: u16, exchange: &str) -> MockRecord {
+        Record::new(
+            name(owner),
+            Class::In,
+            3600,
+            Mx::new(preference, name(exchange)).into(),
+        )
+    }
+
+    async fn lookup(
+        mock: &Arc<MockUpstream>,
+        reply: MockReply,
+    ) -> Option<FoundMx> {
+        mock.push((name("example.com"), Rtype::Mx), reply);
+        lookup_mx(&&resolver(mock), name("example.com"))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn sorted() {
+        let mock = Arc::new(MockUpstream::new());
+        let found = lookup(
+            &mock,
+            MockReply::new(Rcode::NoError)
+                .answer(mx("example.com", 20, "mx3.example.com"))
+                .answer(mx("example.com", 10, "mx1.example.com"))
+                .answer(mx("example.com", 10, "mx2.example.com"))
+                .answer(mx("example.net", 5, "mx.example.net")),
+        )
+        .await
+        .unwrap();
+        assert!(!found.is_implicit());
+        let prefs: Vec<_> = found.iter().map(MxItem::preference).collect();
+        assert_eq!(prefs, [10, 10, 20]);
+        assert_eq!(found.as_slice()[2].exchange(), &name("mx3.example.com"));
+    }
+
+    #[tokio::test]
+    async fn implicit_and_null() {
+        let mock = Arc::new(MockUpstream::new());
+        let found =
+            lookup(&mock, MockReply::new(Rcode::NoError)).await.unwrap();
+        assert!(found.is_implicit());
+        assert_eq!(found.as_slice()[0].exchange(), &name("example.com"));
+
+        assert!(lookup(
+            &mock,
+            MockReply::new(Rcode::NoError).answer(mx("example.com", 0, "."))
+        )
+        .await
+        .is_none());
+    }
+
+    #[tokio::test]
+    async fn nxdomain() {
+        let mock = Arc::new(MockUpstream::new());
+        assert!(lookup(&mock, MockReply::new(Rcode::NXDomain))
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn failure() {
+        for rcode in &[Rcode::ServFail, Rcode::Refused] {
+            let mock = Arc::new(MockUpstream::new());
+            mock.push(
+                (name("example.com"), Rtype::Mx),
+                MockReply::new(*rcode),
+            );
+            let res = lookup_mx(&&resolver(&mock), name("example.com")).await;
+            assert!(
+                matches!(res, Err(MxError::Rcode(value)) if value == *rcode),
+                "{:?}",
+                res
+            );
+        }
+    }
+}
@@ -0,0 +1,999 @@
+//! Looking up NAPTR records.
+//!
+//! NAPTR records are the DNS database of the Dynamic Delegation Discovery
+//! System (DDDS) defined in [RFC 3401] and following. They are used, for
+//! instance, by ENUM to map telephone numbers to URIs and by SIP to find
+//! the transports of a service.
+//!
+//! Besides the lookup itself via [`lookup_naptr`], the module provides
+//! [`NaptrItem::rewrite`] which applies the substitution expression or
+//! replacement of a record to the application’s string.
+//!
+//! [RFC 3401]: https://tools.ietf.org/html/rfc3401
+
+use crate::base::iana::{Rcode, Rtype};
+use crate::base::message::Message;
+use crate::base::name::{Dname, ToDname};
+use crate::base::octets::{OctetsVec, ParseError};
+use crate::rdata::Naptr;
+use crate::resolv::resolver::Resolver;
+use core::cell::Cell;
+use core::fmt;
+use std::boxed::Box;
+use std::vec::Vec;
+use std::{io, slice, vec};
+
+//------------ lookup_naptr --------------------------------------------------
+
+/// Looks up the NAPTR records of a domain.
+///
+/// The function queries the NAPTR records for `name` using `resolver`. The
+/// records are returned ordered by their order and then preference fields
+/// as required by section 4.1 of [RFC 3403].
+///
+/// If the server answers with an error other than NXDOMAIN, the function
+/// returns [`NaptrError::Rcode`].
+///
+/// [RFC 3403]: https://tools.ietf.org/html/rfc3403
+pub async fn lookup_naptr(
+    resolver: &impl Resolver,
+    name: impl ToDname,
+) -> Result<FoundNaptr, NaptrError> {
+    let answer = resolver.query((name, Rtype::Naptr)).await?;
+    FoundNaptr::new(&answer.as_ref().for_slice())
+}
+
+//------------ FoundNaptr ----------------------------------------------------
+
+/// The NAPTR records found by [`lookup_naptr`].
+#[derive(Clone, Debug, Default)]
+pub struct FoundNaptr {
+    items: Vec<NaptrItem>,
+}
+
+impl FoundNaptr {
+    fn new(answer: &Message<&[u8]>) -> Result<Self, NaptrError> {
+        let rcode = answer.header().rcode();
+        if rcode != Rcode::NoError && rcode != Rcode::NXDomain {
+            return Err(NaptrError::Rcode(rcode));
+        }
+        let owner =
+            answer.canonical_name().ok_or(NaptrError::MalformedAnswer)?;
+        let mut items = Vec::new();
+        for record in answer.answer()?.limit_to_in::<Naptr<_, _>>().flatten()
+        {
+            if *record.owner() == owner {
+                items.push(NaptrItem::new(record.data())?);
+            }
+        }
+        items.sort_by_key(|item| (item.order, item.preference));
+        Ok(FoundNaptr { items })
+    }
+
+    /// Returns whether there were no NAPTR records.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns an iterator over the records in order.
+    pub fn iter(&self) -> slice::Iter<'_, NaptrItem> {
+        self.items.iter()
+    }
+
+    /// Returns an iterator over the records offering `service`.
+    ///
+    /// Services are compared ignoring ASCII case.
+    pub fn with_service<'a>(
+        &'a self,
+        service: &'a [u8],
+    ) -> impl Iterator<Item = &'a NaptrItem> {
+        self.iter()
+            .filter(move |item| item.services.eq_ignore_ascii_case(service))
+    }
+}
+
+impl IntoIterator for FoundNaptr {
+    type Item = NaptrItem;
+    type IntoIter = vec::IntoIter<NaptrItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FoundNaptr {
+    type Item = &'a NaptrItem;
+    type IntoIter = slice::Iter<'a, NaptrItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+//------------ NaptrItem -----------------------------------------------------
+
+/// The content of a single NAPTR record.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NaptrItem {
+    order: u16,
+    preference: u16,
+    flags: Vec<u8>,
+    services: Vec<u8>,
+    regexp: Vec<u8>,
+    replacement: Dname<OctetsVec>,
+}
+
+impl NaptrItem {
+    fn new<Octets, Name>(
+        naptr: &Naptr<Octets, Name>,
+    ) -> Result<Self, NaptrError>
+    where
+        Octets: AsRef<[u8]>,
+        Name: ToDname,
+    {
+        let replacement = naptr
+            .replacement()
+            .to_dname()
+            .map_err(|_| NaptrError::MalformedAnswer)?;
+        Ok(NaptrItem {
+            order: naptr.order(),
+            preference: naptr.preference(),
+            flags: naptr.flags().as_slice().into(),
+            services: naptr.services().as_slice().into(),
+            regexp: naptr.regexp().as_slice().into(),
+            replacement,
+        })
+    }
+
+    /// Returns the order field.
+    pub fn order(&self) -> u16 {
+        self.order
+    }
+
+    /// Returns the preference field.
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// Returns the flags.
+    pub fn flags(&self) -> &[u8] {
+        &self.flags
+    }
+
+    /// Returns whether the record has the given flag.
+    ///
+    /// Flags are single ASCII letters compared ignoring case.
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags.iter().any(|ch| ch.eq_ignore_ascii_case(&flag))
+    }
+
+    /// Returns whether the record is terminal.
+    ///
+    /// A record is terminal if it has one of the flags `S`, `A`, `U`, or
+    /// `P` which end the rewrite loop.
+    pub fn is_terminal(&self) -> bool {
+        self.flags.iter().any(|ch| b"SAUPsaup".contains(ch))
+    }
+
+    /// Returns the services field.
+    pub fn services(&self) -> &[u8] {
+        &self.services
+    }
+
+    /// Returns the substitution expression.
+    pub fn regexp(&self) -> &[u8] {
+        &self.regexp
+    }
+
+    /// Returns the replacement domain name.
+    pub fn replacement(&self) -> &Dname<OctetsVec> {
+        &self.replacement
+    }
+
+    /// Applies the record’s rewrite rule to the application string `input`.
+    ///
+    /// If the record has a substitution expression, it is applied to
+    /// `input`. If it matches, the resulting string is returned, otherwise
+    /// `Ok(None)`. If there is no substitution expression, the replacement
+    /// domain name is returned.
+    pub fn rewrite(
+        &self,
+        input: &[u8],
+    ) -> Result<Option<NaptrRewrite>, RegexpError> {
+        if self.regexp.is_empty() {
+            if self.replacement.is_root() {
+                return Ok(None);
+            }
+            return Ok(Some(NaptrRewrite::Name(self.replacement.clone())));
+        }
+        Ok(Regexp::parse(&self.regexp)?
+            .apply(input)?
+            .map(NaptrRewrite::Text))
+    }
+}
+
+//------------ NaptrRewrite --------------------------------------------------
+
+/// The result of rewriting a string via a NAPTR record.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NaptrRewrite {
+    /// The record’s replacement domain name.
+    Name(Dname<OctetsVec>),
+
+    /// The string produced by the substitution expression.
+    Text(Vec<u8>),
+}
+
+//------------ Regexp --------------------------------------------------------
+
+/// A substitution expression of a NAPTR record.
+///
+/// The expression has the form `delim ere delim repl delim flags` as
+/// described in section 3.2 of [RFC 3402]. The extended regular expression
+/// supports the usual POSIX syntax: literal characters, `.`, bracket
+/// expressions including character classes such as `[:digit:]`, the
+/// anchors `^` and `$`, groups, alternatives, and the quantifiers `*`,
+/// `+`, `?`, and `{m,n}`. The replacement may contain the back references
+/// `\1` to `\9` to the subexpressions of the match.  The only flag is `i`
+/// for case-insensitive matching.
+///
+/// As with the `s` command of `sed`, the matched part of the input is
+/// replaced by the replacement and the rest of the input kept.
+///
+/// Since substitution expressions are taken from DNS answers, matching is
+/// limited to [`MAX_MATCH_STEPS`] steps of the backtracking matcher. An
+/// expression that needs more is rejected.
+///
+/// [RFC 3402]: https://tools.ietf.org/html/rfc3402
+#[derive(Clone, Debug)]
+pub struct Regexp {
+    /// The parsed expression.
+    ere: Node,
+
+    /// The number of capturing groups.
+    groups: usize,
+
+    /// The replacement.
+    repl: Vec<u8>,
+
+    /// Match case-insensitively?
+    icase: bool,
+}
+
+impl Regexp {
+    /// Parses a substitution expression.
+    pub fn parse(expr: &[u8]) -> Result<Self, RegexpError> {
+        let (&delim, rest) = expr.split_first().ok_or(RegexpError)?;
+        if delim == b'\\' || delim.is_ascii_digit() || delim == b'i' {
+            return Err(RegexpError);
+        }
+        let (ere, rest) = Self::split_delim(rest, delim)?;
+        let (repl, flags) = Self::split_delim(rest, delim)?;
+        let icase = match flags {
+            b"" => false,
+            b"i" => true,
+            _ => return Err(RegexpError),
+        };
+        let mut parser = EreParser {
+            src: &ere,
+            pos: 0,
+            groups: 0,
+        };
+        let node = parser.parse_alt()?;
+        if parser.pos != ere.len() {
+            return Err(RegexpError);
+        }
+        Ok(Regexp {
+            ere: node,
+            groups: parser.groups,
+            repl,
+            icase,
+        })
+    }
+
+    /// Splits off everything up to the next unescaped delimiter.
+    ///
+    /// Escaped delimiters are unescaped, all other escapes are kept.
+    fn split_delim(
+        src: &[u8],
+        delim: u8,
+    ) -> Result<(Vec<u8>, &[u8]), RegexpError> {
+        let mut res = Vec::new();
+        let mut iter = src.iter().enumerate();
+        while let Some((pos, &ch)) = iter.next() {
+            if ch == delim {
+                return Ok((res, &src[pos + 1..]));
+            }
+            if ch == b'\\' {
+                let (_, &next) = iter.next().ok_or(RegexpError)?;
+                if next != delim {
+                    res.push(b'\\');
+                }
+                res.push(next);
+            } else {
+                res.push(ch)
+            }
+        }
+        Err(RegexpError)
+    }
+
+    /// Applies the expression to `input`.
+    ///
+    /// Returns the rewritten string if the expression matched or `None`
+    /// otherwise. Returns an error if matching takes more than
+    /// [`MAX_MATCH_STEPS`] steps.
+    pub fn apply(
+        &self,
+        input: &[u8],
+    ) -> Result<Option<Vec<u8>>, RegexpError> {
+        let steps = Cell::new(MAX_MATCH_STEPS);
+        let mut matcher = Matcher {
+            input,
+            icase: self.icase,
+            steps: &steps,
+        };
+        let mut caps = vec![None; self.groups + 1];
+        let found = (0..=input.len()).find_map(|start| {
+            let mut end = None;
+            let found =
+                matcher.node(&self.ere, start, &mut caps, &mut |pos, _| {
+                    end = Some(pos);
+                    true
+                });
+            if found {
+                end.map(|end| (start, end))
+            } else {
+                None
+            }
+        });
+        let (start, end) = match found {
+            Some(found) => found,
+            None if steps.get() == 0 => return Err(RegexpError),
+            None => return Ok(None),
+        };
+        caps[0] = Some((start, end));
+
+        let mut res = Vec::from(&input[..start]);
+        let mut repl = self.repl.iter();
+        while let Some(&ch) = repl.next() {
+            if ch != b'\\' {
+                res.push(ch);
+                continue;
+            }
+            match repl.next() {
+                Some(&digit) if digit.is_ascii_digit() => {
+                    let idx = usize::from(digit - b'0');
+                    if let Some(Some((from, to))) = caps.get(idx) {
+                        res.extend_from_slice(&input[*from..*to])
+                    }
+                }
+                Some(&other) => res.push(other),
+                None => res.push(b'\\'),
+            }
+        }
+        res.extend_from_slice(&input[end..]);
+        Ok(Some(res))
+    }
+}
+
+//------------ Node ----------------------------------------------------------
+
+/// A node of a parsed regular expression.
+#[derive(Clone, Debug)]
+enum Node {
+    /// A literal character.
+    Char(u8),
+
+    /// Any character.
+    Any,
+
+    /// A bracket expression.
+    Class {
+        negated: bool,
+        ranges: Vec<(u8, u8)>,
+    },
+
+    /// The start of the input.
+    Start,
+
+    /// The end of the input.
+    End,
+
+    /// A sequence of nodes.
+    Concat(Vec<Node>),
+
+    /// Alternative nodes.
+    Alt(Vec<Node>),
+
+    /// A capturing group with its index.
+    Group(Box<Node>, usize),
+
+    /// A repeated node.
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+//------------ EreParser -----------------------------------------------------
+
+/// A recursive descent parser for extended regular expressions.
+struct EreParser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    groups: usize,
+}
+
+impl<'a> EreParser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<u8, RegexpError> {
+        let res = self.peek().ok_or(RegexpError)?;
+        self.pos += 1;
+        Ok(res)
+    }
+
+    fn parse_alt(&mut self) -> Result<Node, RegexpError> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.peek() == Some(b'|') {
+            self.pos += 1;
+            branches.push(self.parse_concat()?);
+        }
+        if branches.len() == 1 {
+            Ok(branches.pop().unwrap())
+        } else {
+            Ok(Node::Alt(branches))
+        }
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, RegexpError> {
+        let mut nodes = Vec::new();
+        while let Some(ch) = self.peek() {
+            if ch == b'|' || ch == b')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifiers(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegexpError> {
+        match self.next()? {
+            b'(' => {
+                self.groups += 1;
+                let idx = self.groups;
+                let node = self.parse_alt()?;
+                if self.next()? != b')' {
+                    return Err(RegexpError);
+                }
+                Ok(Node::Group(Box::new(node), idx))
+            }
+            b'.' => Ok(Node::Any),
+            b'^' => Ok(Node::Start),
+            b'$' => Ok(Node::End),
+            b'[' => self.parse_class(),
+            b'\\' => Ok(Node::Char(self.next()?)),
+            b'*' | b'+' | b'?' | b'{' => Err(RegexpError),
+            ch => Ok(Node::Char(ch)),
+        }
+    }
+
+    fn parse_quantifiers(
+        &mut self,
+        mut node: Node,
+    ) -> Result<Node, RegexpError> {
+        loop {
+            let (min, max) = match self.peek() {
+                Some(b'*') => (0, None),
+                Some(b'+') => (1, None),
+                Some(b'?') => (0, Some(1)),
+                Some(b'{') => {
+                    self.pos += 1;
+                    let min = self.parse_number()?.ok_or(RegexpError)?;
+                    let max = if self.peek() == Some(b',') {
+                        self.pos += 1;
+                        self.parse_number()?
+                    } else {
+                        Some(min)
+                    };
+                    if self.peek() != Some(b'}') {
+                        return Err(RegexpError);
+                    }
+                    if max.map(|max| max < min).unwrap_or(false) {
+                        return Err(RegexpError);
+                    }
+                    (min, max)
+                }
+                _ => return Ok(node),
+            };
+            self.pos += 1;
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+            };
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Option<usize>, RegexpError> {
+        let mut res: Option<usize> = None;
+        while let Some(ch) = self.peek().filter(u8::is_ascii_digit) {
+            self.pos += 1;
+            res = Some(
+                res.unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|res| res.checked_add(usize::from(ch - b'0')))
+                    .filter(|res| *res <= 255)
+                    .ok_or(RegexpError)?,
+            );
+        }
+        Ok(res)
+    }
+
+    fn parse_class(&mut self) -> Result<Node, RegexpError> {
+        let negated = if self.peek() == Some(b'^') {
+            self.pos += 1;
+            true
+        } else {
+            false
+        };
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let ch = self.next()?;
+            if ch == b']' && !first {
+                break;
+            }
+            first = false;
+            if ch == b'[' && self.peek() == Some(b':') {
+                self.pos += 1;
+                let end = self.src[self.pos..]
+                    .windows(2)
+                    .position(|w| w == b":]")
+                    .ok_or(RegexpError)?;
+                let name = &self.src[self.pos..self.pos + end];
+                self.pos += end + 2;
+                ranges.extend_from_slice(Self::named_class(name)?);
+                continue;
+            }
+            if self.peek() == Some(b'-')
+                && self.src.get(self.pos + 1).map_or(false, |ch| *ch != b']')
+            {
+                self.pos += 1;
+                let end = self.next()?;
+                if end < ch {
+                    return Err(RegexpError);
+                }
+                ranges.push((ch, end));
+            } else {
+                ranges.push((ch, ch));
+            }
+        }
+        Ok(Node::Class { negated, ranges })
+    }
+
+    fn named_class(name: &[u8]) -> Result<&'static [(u8, u8)], RegexpError> {
+        Ok(match name {
+            b"alnum" => &[(b'0', b'9'), (b'A', b'Z'), (b'a', b'z')],
+            b"alpha" => &[(b'A', b'Z'), (b'a', b'z')],
+            b"digit" => &[(b'0', b'9')],
+            b"lower" => &[(b'a', b'z')],
+            b"upper" => &[(b'A', b'Z')],
+            b"space" => &[(b' ', b' '), (b'\t', b'\r')],
+            b"xdigit" => &[(b'0', b'9'), (b'A', b'F'), (b'a', b'f')],
+            b"punct" => {
+                &[(b'!', b'/'), (b':', b'@'), (b'[', b'`'), (b'{', b'~')]
+            }
+            _ => return Err(RegexpError),
+        })
+    }
+}
+
+//------------ Matcher -------------------------------------------------------
+
+/// The maximum number of steps for matching a regular expression.
+///
+/// Each node visited by the backtracking matcher counts as one step. The
+/// limit keeps pathological expressions such as `(a|aa)*$`, which need
+/// exponential time, from stalling the caller.
+pub const MAX_MATCH_STEPS: usize = 100_000;
+
+/// The capture positions of the groups.
+type Captures = Vec<Option<(usize, usize)>>;
+
+/// A backtracking matcher.
+///
+/// The matcher uses continuation passing: each node is matched at a
+/// position and, if successful, the continuation is called with the
+/// position after the match. If the continuation fails, the next
+/// alternative of the node is tried.
+///
+/// Once the remaining `steps` have run out, all further nodes fail.
+struct Matcher<'a> {
+    input: &'a [u8],
+    icase: bool,
+    steps: &'a Cell<usize>,
+}
+
+impl<'a> Matcher<'a> {
+    fn node(
+        &mut self,
+        node: &Node,
+        pos: usize,
+        caps: &mut Captures,
+        cont: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    ) -> bool {
+        match self.steps.get().checked_sub(1) {
+            Some(steps) => self.steps.set(steps),
+            None => return false,
+        }
+        match *node {
+            Node::Char(ch) => {
+                let matches = self.input.get(pos).map_or(false, |&input| {
+                    if self.icase {
+                        input.eq_ignore_ascii_case(&ch)
+                    } else {
+                        input == ch
+                    }
+                });
+                matches && cont(pos + 1, caps)
+            }
+            Node::Any => pos < self.input.len() && cont(pos + 1, caps),
+            Node::Class {
+                negated,
+                ref ranges,
+            } => {
+                let ch = match self.input.get(pos) {
+                    Some(ch) => *ch,
+                    None => return false,
+                };
+                let icase = self.icase;
+                let contains = ranges.iter().any(|&(from, to)| {
+                    (from..=to).contains(&ch)
+                        || (icase
+                            && ((from..=to)
+                                .contains(&ch.to_ascii_lowercase())
+                                || (from..=to)
+                                    .contains(&ch.to_ascii_uppercase())))
+                });
+                contains != negated && cont(pos + 1, caps)
+            }
+            Node::Start => pos == 0 && cont(pos, caps),
+            Node::End => pos == self.input.len() && cont(pos, caps),
+            Node::Concat(ref nodes) => self.seq(nodes, pos, caps, cont),
+            Node::Alt(ref branches) => {
+                for branch in branches {
+                    if self.node(branch, pos, caps, cont) {
+                        return true;
+                    }
+                }
+                false
+            }
+            Node::Group(ref inner, idx) => {
+                self.node(inner, pos, caps, &mut |end, caps| {
+                    let saved = caps[idx];
+                    caps[idx] = Some((pos, end));
+                    if cont(end, caps) {
+                        true
+                    } else {
+                        caps[idx] = saved;
+                        false
+                    }
+                })
+            }
+            Node::Repeat { ref node, min, max } => {
+                self.repeat(node, min, max, 0, pos, caps, cont)
+            }
+        }
+    }
+
+    fn seq(
+        &mut self,
+        nodes: &[Node],
+        pos: usize,
+        caps: &mut Captures,
+        cont: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    ) -> bool {
+        match nodes.split_first() {
+            None => cont(pos, caps),
+            Some((first, rest)) => {
+                let (input, icase, steps) =
+                    (self.input, self.icase, self.steps);
+                self.node(first, pos, caps, &mut |pos, caps| {
+                    Matcher {
+                        input,
+                        icase,
+                        steps,
+                    }
+                    .seq(rest, pos, caps, cont)
+                })
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn repeat(
+        &mut self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        count: usize,
+        pos: usize,
+        caps: &mut Captures,
+        cont: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    ) -> bool {
+        // Greedy: try one more repetition first.
+        if max.map_or(true, |max| count < max) {
+            let (input, icase, steps) = (self.input, self.icase, self.steps);
+            let more = self.node(node, pos, caps, &mut |end, caps| {
+                // Avoid endless loops on empty repetitions.
+                if end == pos && count >= min {
+                    return false;
+                }
+                Matcher {
+                    input,
+                    icase,
+                    steps,
+                }
+                .repeat(
+                    node,
+                    min,
+                    max,
+                    count + 1,
+                    end,
+                    caps,
+                    cont,
+                )
+            });
+            if more {
+                return true;
+            }
+        }
+        count >= min && cont(pos, caps)
+    }
+}
+
+//------------ RegexpError ---------------------------------------------------
+
+/// A substitution expression was invalid or too expensive to match.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RegexpError;
+
+impl fmt::Display for RegexpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid or too complex substitution expression")
+    }
+}
+
+impl std::error::Error for RegexpError {}
+
+//------------ NaptrError ----------------------------------------------------
+
+/// An error happened during a NAPTR lookup.
+#[derive(Debug)]
+pub enum NaptrError {
+    /// The answer could not be parsed.
+    MalformedAnswer,
+
+    /// A server answered with the given error response code.
+    Rcode(Rcode),
+
+    /// The query failed.
+    Query(io::Error),
+}
+
+impl From<io::Error> for NaptrError {
+    fn from(err: io::Error) -> NaptrError {
+        NaptrError::Query(err)
+    }
+}
+
+impl From<ParseError> for NaptrError {
+    fn from(_: ParseError) -> NaptrError {
+        NaptrError::MalformedAnswer
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::Class;
+    use crate::base::octets::Compose;
+    use crate::base::rdata::UnknownRecordData;
+    use crate::base::record::Record;
+    use crate::resolv::stub::conf::{ResolvConf, ServerConf, Transport};
+    use crate::resolv::stub::upstream::{
+        MockRecord, MockReply, MockUpstream,
+    };
+    use crate::resolv::stub::StubResolver;
+    use bytes::Bytes;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    fn apply(expr: &str, input: &str) -> Option<std::string::String> {
+        Regexp::parse(expr.as_bytes())
+            .unwrap()
+            .apply(input.as_bytes())
+            .unwrap()
+            .map(|res| std::string::String::from_utf8(res).unwrap())
+    }
+
+    #[test]
+    fn regexp() {
+        // RFC 3403 and RFC 6116 examples.
+        assert_eq!(
+            apply("!^.*$!sip:info@example.com!", "+4412345678").as_deref(),
+            Some("sip:info@example.com")
+        );
+        assert_eq!(
+            apply("!^\\+44(.*)$!sip:\\1@example.co.uk!", "+4412345678")
+                .as_deref(),
+            Some("sip:12345678@example.co.uk")
+        );
+        assert_eq!(
+            apply("!^([^:]+)://([^/]+)/.*$!\\2!", "http://www.example.com/x")
+                .as_deref(),
+            Some("www.example.com")
+        );
+        assert_eq!(apply("!^\\+1!x!", "+4412345678"), None);
+
+        // Partial matches keep the rest, sed style.
+        assert_eq!(apply("/b+/X/", "abbbc").as_deref(), Some("aXc"));
+        assert_eq!(apply("/a|bc/X/", "xbc").as_deref(), Some("xX"));
+        assert_eq!(
+            apply("/[[:digit:]]{2,3}/#/", "ab12345").as_deref(),
+            Some("ab#45")
+        );
+        assert_eq!(apply("/(a*)*b/<\\1>/", "aab").as_deref(), Some("<aa>"));
+        assert_eq!(apply("/ABC/x/i", "zabc").as_deref(), Some("zx"));
+        assert_eq!(apply("/ABC/x/", "zabc"), None);
+        assert_eq!(apply("#a\\#b#c#", "a#b").as_deref(), Some("c"));
+
+        assert!(Regexp::parse(b"!abc!").is_err());
+        assert!(Regexp::parse(b"!a(b!c!").is_err());
+        assert!(Regexp::parse(b"!*!c!").is_err());
+        assert!(Regexp::parse(b"!a!c!x").is_err());
+    }
+
+    #[test]
+    fn regexp_step_limit() {
+        let regexp = Regexp::parse(b"!^(a|aa)*$!x!").unwrap();
+        let mut input = vec![b'a'; 64];
+        assert_eq!(regexp.apply(&input), Ok(Some(b"x".to_vec())));
+
+        // Failing to match needs exponential time without the limit.
+        input.push(b'b');
+        assert_eq!(regexp.apply(&input), Err(RegexpError));
+    }
+
+    fn naptr(
+        order: u16,
+        preference: u16,
+        flags: &str,
+        services: &str,
+        regexp: &str,
+        replacement: &str,
+    ) -> MockRecord {
+        let mut data = Vec::new();
+        data.extend_from_slice(&order.to_be_bytes());
+        data.extend_from_slice(&preference.to_be_bytes());
+        for item in [flags, services, regexp] {
+            data.push(item.len() as u8);
+            data.extend_from_slice(item.as_bytes());
+        }
+        if replacement == "." {
+            data.push(0)
+        } else {
+            Dname::<Bytes>::from_str(replacement)
+                .unwrap()
+                .compose(&mut data)
+                .unwrap();
+        }
+        Record::new(
+            Dname::from_str("4.3.2.1.5.5.5.0.0.8.1.e164.arpa").unwrap(),
+            Class::In,
+            3600,
+            UnknownRecordData::from_octets(Rtype::Naptr, Bytes::from(data))
+                .into(),
+        )
+    }
+
+    #[tokio::test]
+    async fn lookup() {
+        let qname =
+            Dname::<Bytes>::from_str("4.3.2.1.5.5.5.0.0.8.1.e164.arpa")
+                .unwrap();
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (qname.clone(), Rtype::Naptr),
+            MockReply::new(Rcode::NoError)
+                .answer(naptr(
+                    100,
+                    20,
+                    "u",
+                    "E2U+mailto",
+                    "!^.*$!mailto:info@example.com!i",
+                    ".",
+                ))
+                .answer(naptr(
+                    100,
+                    10,
+                    "u",
+                    "E2U+sip",
+                    "!^\\+1800(.*)$!sip:\\1@example.com!",
+                    ".",
+                ))
+                .answer(naptr(
+                    50,
+                    10,
+                    "s",
+                    "SIP+D2U",
+                    "",
+                    "_sip._udp.example.com",
+                )),
+        );
+        let mut conf = ResolvConf::new();
+        conf.servers.push(ServerConf::new(
+            "192.0.2.53:53".parse().unwrap(),
+            Transport::Udp,
+        ));
+        conf.finalize();
+        let resolver = StubResolver::with_upstream(conf, mock);
+
+        let found = lookup_naptr(&&resolver, qname).await.unwrap();
+        let order: Vec<_> = found
+            .iter()
+            .map(|item| (item.order(), item.preference()))
+            .collect();
+        assert_eq!(order, [(50, 10), (100, 10), (100, 20)]);
+
+        let first = found.iter().next().unwrap();
+        assert!(first.is_terminal());
+        assert!(first.has_flag(b'S'));
+        assert_eq!(
+            first.rewrite(b"+18005551234").unwrap(),
+            Some(NaptrRewrite::Name(
+                Dname::from_str("_sip._udp.example.com").unwrap()
+            ))
+        );
+
+        let sip = found.with_service(b"e2u+sip").next().unwrap();
+        assert_eq!(
+            sip.rewrite(b"+18005551234").unwrap(),
+            Some(NaptrRewrite::Text(b"sip:5551234@example.com".to_vec()))
+        );
+        assert_eq!(sip.rewrite(b"+4412345").unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn failure() {
+        let qname =
+            Dname::<Bytes>::from_str("4.3.2.1.5.5.5.0.0.8.1.e164.arpa")
+                .unwrap();
+        for rcode in &[Rcode::ServFail, Rcode::Refused] {
+            let mock = Arc::new(MockUpstream::new());
+            mock.push((qname.clone(), Rtype::Naptr), MockReply::new(*rcode));
+            let mut conf = ResolvConf::new();
+            conf.servers.push(ServerConf::new(
+                "192.0.2.53:53".parse().unwrap(),
+                Transport::Udp,
+            ));
+            conf.options.attempts = 1;
+            conf.finalize();
+            let resolver = StubResolver::with_upstream(conf, mock);
+
+            let res = lookup_naptr(&&resolver, qname.clone()).await;
+            assert!(
+                matches!(res, Err(NaptrError::Rcode(value)) if value == *rcode),
+                "{:?}",
+                res
+            );
+        }
+    }
+}
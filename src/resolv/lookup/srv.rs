@@ -82,7 +82,7 @@ impl FoundSrvs {
         resolver: &R,
     ) -> impl Stream<Item = Result<ResolvedSrvItem, io::Error>> + '_
     where
-        R::Octets: OctetsRef,
+        for<'a> &'a R::Octets: OctetsRef,
     {
         // Let’s make a somewhat elaborate single iterator from self.items
         // that we can use as the base for the stream: We turn the result into
@@ -0,0 +1,790 @@
+//! Looking up SVCB and HTTPS records.
+//!
+//! Service binding records, defined in [RFC 9460], provide the endpoints
+//! of a service together with the parameters necessary to connect to
+//! them, such as the supported application protocols (ALPN), the port,
+//! or the configuration for encrypted client hellos (ECH). The HTTPS
+//! record type is a variant of SVCB specific to HTTP.
+//!
+//! The functions [`lookup_https`] and [`lookup_svcb`] implement the client
+//! procedure described in section 3 of the RFC: They follow AliasMode
+//! records, order ServiceMode records by priority, skip records with
+//! mandatory parameters that aren’t supported, and determine the addresses
+//! of each endpoint, using the address hints only if no addresses can be
+//! determined via A and AAAA lookups.
+//!
+//! [RFC 9460]: https://tools.ietf.org/html/rfc9460
+
+use super::host::lookup_host;
+use crate::base::iana::{Class, Rtype, SvcbParamKey};
+use crate::base::message::Message;
+use crate::base::name::{Dname, DnameBuilder, ToDname, ToRelativeDname};
+use crate::base::octets::{OctetsRef, OctetsVec, ParseError};
+use crate::rdata::svcb::param::{AllParams, SvcbParam};
+use crate::rdata::svcb::ParamIter;
+use crate::rdata::{Aaaa, Https, Svcb, A};
+use crate::resolv::resolver::Resolver;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::vec::Vec;
+use std::{io, slice, vec};
+
+//------------ Module Configuration ------------------------------------------
+
+/// The maximum number of AliasMode records followed.
+const MAX_ALIAS_CHAIN: usize = 8;
+
+/// The port used for HTTPS.
+const HTTPS_PORT: u16 = 443;
+
+/// The ALPN protocol ID HTTPS endpoints support by default.
+pub const HTTPS_DEFAULT_ALPN: &[u8] = b"http/1.1";
+
+//------------ lookup_https --------------------------------------------------
+
+/// Looks up the HTTPS endpoints for a host and port.
+///
+/// The function queries HTTPS records for `host` if `port` is 443 or for
+/// the name with the port prefix `_port._https` otherwise. It then follows
+/// the procedure described in the [module documentation][self].
+///
+/// If the host has no HTTPS records or none of them are usable, the result
+/// contains a single fallback endpoint for `host` itself and `port`. If the
+/// service is declared unavailable via an AliasMode record with the root
+/// as its target, the function returns `Ok(None)`.
+///
+/// When determining the ALPN protocols of the endpoints, don’t forget to
+/// include [`HTTPS_DEFAULT_ALPN`] via [`SvcbParams::alpn_with_default`].
+pub async fn lookup_https<R: Resolver>(
+    resolver: &R,
+    host: impl ToDname,
+    port: u16,
+) -> Result<Option<FoundSvcb>, SvcbError>
+where
+    for<'a> &'a R::Octets: OctetsRef,
+{
+    let origin: Dname<OctetsVec> =
+        host.to_dname().map_err(|_| SvcbError::LongName)?;
+    let qname = if port == HTTPS_PORT {
+        origin.clone()
+    } else {
+        let mut qname = DnameBuilder::<OctetsVec>::new();
+        qname
+            .append_label(format!("_{}", port).as_bytes())
+            .and_then(|_| qname.append_label(b"_https"))
+            .map_err(|_| SvcbError::LongName)?;
+        qname
+            .append_origin(&origin)
+            .map_err(|_| SvcbError::LongName)?
+    };
+    lookup(resolver, Rtype::Https, qname, origin, port).await
+}
+
+//------------ lookup_svcb ---------------------------------------------------
+
+/// Looks up the SVCB endpoints of a service.
+///
+/// The function queries SVCB records for the name composed of `prefix`
+/// – such as `_dns` or `_8443._foo` – and `name` and then follows the
+/// procedure described in the [module documentation][self].
+///
+/// If there are no SVCB records or none of them are usable, the result
+/// contains a single fallback endpoint for `name` and `fallback_port`. If
+/// the service is declared unavailable via an AliasMode record with the
+/// root as its target, the function returns `Ok(None)`.
+pub async fn lookup_svcb<R: Resolver>(
+    resolver: &R,
+    prefix: impl ToRelativeDname,
+    name: impl ToDname,
+    fallback_port: u16,
+) -> Result<Option<FoundSvcb>, SvcbError>
+where
+    for<'a> &'a R::Octets: OctetsRef,
+{
+    let qname = (&prefix)
+        .chain(&name)
+        .map_err(|_| SvcbError::LongName)?
+        .to_dname()
+        .map_err(|_| SvcbError::LongName)?;
+    let origin = name.to_dname().map_err(|_| SvcbError::LongName)?;
+    lookup(resolver, Rtype::Svcb, qname, origin, fallback_port).await
+}
+
+/// Performs the actual lookup for both record types.
+async fn lookup<R: Resolver>(
+    resolver: &R,
+    rtype: Rtype,
+    qname: Dname<OctetsVec>,
+    origin: Dname<OctetsVec>,
+    fallback_port: u16,
+) -> Result<Option<FoundSvcb>, SvcbError>
+where
+    for<'a> &'a R::Octets: OctetsRef,
+{
+    let mut name = qname.clone();
+    let mut hops = 0;
+    loop {
+        let answer = resolver.query((&name, rtype)).await?;
+        let answer = answer.as_ref().for_slice();
+        let owner: Dname<OctetsVec> = answer
+            .canonical_name()
+            .ok_or(SvcbError::MalformedAnswer)?
+            .to_dname()
+            .map_err(|_| SvcbError::MalformedAnswer)?;
+        let records = SvcbRecord::collect(&answer, rtype, &owner)?;
+
+        // ServiceMode records take precedence over AliasMode records.
+        let (service, alias): (Vec<_>, Vec<_>) =
+            records.into_iter().partition(|rec| rec.priority != 0);
+        if !service.is_empty() {
+            // The root as target means the owner name, even if that is a
+            // name with a port prefix (RFC 9460, section 2.5.2).
+            let mut endpoints: Vec<_> = service
+                .into_iter()
+                .filter(|rec| rec.params.is_compatible())
+                .map(|rec| {
+                    let target = if rec.target.is_root() {
+                        owner.clone()
+                    } else {
+                        rec.target
+                    };
+                    SvcbEndpoint::new(
+                        rec.priority,
+                        target,
+                        fallback_port,
+                        rec.params,
+                    )
+                })
+                .collect();
+            if !endpoints.is_empty() {
+                endpoints.sort_by_key(|item| item.priority);
+                for endpoint in &mut endpoints {
+                    endpoint.process_additional(&answer)?;
+                    endpoint.resolve(resolver).await;
+                }
+                return Ok(Some(FoundSvcb {
+                    endpoints,
+                    fallback: false,
+                }));
+            }
+            break;
+        }
+        match alias.into_iter().next() {
+            Some(alias) => {
+                if alias.target.is_root() {
+                    return Ok(None);
+                }
+                hops += 1;
+                if hops > MAX_ALIAS_CHAIN || alias.target == qname {
+                    return Err(SvcbError::AliasLoop);
+                }
+                name = alias.target;
+            }
+            None => break,
+        }
+    }
+
+    // Without usable records, fall back to the last alias target or the
+    // origin if there wasn’t any.
+    let fallback = if hops == 0 { origin } else { name };
+    let mut endpoint =
+        SvcbEndpoint::new(0, fallback, fallback_port, SvcbParams::default());
+    endpoint.resolve(resolver).await;
+    Ok(Some(FoundSvcb {
+        endpoints: vec![endpoint],
+        fallback: true,
+    }))
+}
+
+//------------ FoundSvcb -----------------------------------------------------
+
+/// The endpoints found by a successful SVCB or HTTPS lookup.
+///
+/// The endpoints are ordered by priority, the most preferred first.
+#[derive(Clone, Debug)]
+pub struct FoundSvcb {
+    /// The endpoints.
+    endpoints: Vec<SvcbEndpoint>,
+
+    /// Is this the fallback endpoint because there were no records?
+    fallback: bool,
+}
+
+impl FoundSvcb {
+    /// Returns whether the endpoint is a fallback.
+    ///
+    /// This is the case if there were no usable SVCB or HTTPS records and
+    /// the result consists of a single endpoint for the origin host.
+    pub fn is_fallback(&self) -> bool {
+        self.fallback
+    }
+
+    /// Returns a slice of the endpoints.
+    pub fn endpoints(&self) -> &[SvcbEndpoint] {
+        &self.endpoints
+    }
+
+    /// Returns an iterator over the endpoints.
+    pub fn iter(&self) -> slice::Iter<'_, SvcbEndpoint> {
+        self.endpoints.iter()
+    }
+}
+
+impl IntoIterator for FoundSvcb {
+    type Item = SvcbEndpoint;
+    type IntoIter = vec::IntoIter<SvcbEndpoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.endpoints.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FoundSvcb {
+    type Item = &'a SvcbEndpoint;
+    type IntoIter = slice::Iter<'a, SvcbEndpoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+//------------ SvcbEndpoint --------------------------------------------------
+
+/// A single endpoint of a service.
+#[derive(Clone, Debug)]
+pub struct SvcbEndpoint {
+    /// The priority of the record this endpoint was derived from.
+    priority: u16,
+
+    /// The effective target name.
+    target: Dname<OctetsVec>,
+
+    /// The port to use if there is no port parameter.
+    fallback_port: u16,
+
+    /// The parameters of the endpoint.
+    params: SvcbParams,
+
+    /// The addresses of the endpoint.
+    addrs: Vec<IpAddr>,
+}
+
+impl SvcbEndpoint {
+    fn new(
+        priority: u16,
+        target: Dname<OctetsVec>,
+        fallback_port: u16,
+        params: SvcbParams,
+    ) -> Self {
+        SvcbEndpoint {
+            priority,
+            target,
+            fallback_port,
+            params,
+            addrs: Vec::new(),
+        }
+    }
+
+    /// Returns the priority of the endpoint.
+    ///
+    /// This is zero for a fallback endpoint.
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// Returns the effective target name of the endpoint.
+    pub fn target(&self) -> &Dname<OctetsVec> {
+        &self.target
+    }
+
+    /// Returns the port of the endpoint.
+    ///
+    /// This is the value of the port parameter if present or the port given
+    /// to the lookup function otherwise.
+    pub fn port(&self) -> u16 {
+        self.params.port().unwrap_or(self.fallback_port)
+    }
+
+    /// Returns the parameters of the endpoint.
+    pub fn params(&self) -> &SvcbParams {
+        &self.params
+    }
+
+    /// Returns the IP addresses of the endpoint.
+    ///
+    /// If the addresses couldn’t be determined via the DNS, these are the
+    /// address hints.
+    pub fn addrs(&self) -> &[IpAddr] {
+        &self.addrs
+    }
+
+    /// Returns an iterator over the socket addresses of the endpoint.
+    pub fn socket_addrs(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        let port = self.port();
+        self.addrs
+            .iter()
+            .map(move |addr| SocketAddr::new(*addr, port))
+    }
+
+    /// Takes addresses for the target from the additional section.
+    fn process_additional(
+        &mut self,
+        answer: &Message<&[u8]>,
+    ) -> Result<(), SvcbError> {
+        for record in answer.additional()?.flatten() {
+            if record.class() != Class::In || *record.owner() != self.target {
+                continue;
+            }
+            if let Ok(Some(record)) = record.to_record::<A>() {
+                self.addrs.push(record.data().addr().into())
+            }
+            if let Ok(Some(record)) = record.to_record::<Aaaa>() {
+                self.addrs.push(record.data().addr().into())
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolves the addresses if necessary, falling back to the hints.
+    async fn resolve<R: Resolver>(&mut self, resolver: &R)
+    where
+        for<'a> &'a R::Octets: OctetsRef,
+    {
+        if self.addrs.is_empty() {
+            if let Ok(found) = lookup_host(resolver, &self.target).await {
+                self.addrs.extend(found.iter())
+            }
+        }
+        if self.addrs.is_empty() {
+            self.addrs.extend(
+                self.params
+                    .ipv6hint()
+                    .iter()
+                    .copied()
+                    .map(IpAddr::from)
+                    .chain(
+                        self.params
+                            .ipv4hint()
+                            .iter()
+                            .copied()
+                            .map(Into::into),
+                    ),
+            )
+        }
+    }
+}
+
+//------------ SvcbParams ----------------------------------------------------
+
+/// The typed parameters of an SVCB or HTTPS record.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SvcbParams {
+    mandatory: Vec<SvcbParamKey>,
+    alpn: Vec<Vec<u8>>,
+    no_default_alpn: bool,
+    port: Option<u16>,
+    ech: Option<Vec<u8>>,
+    ipv4hint: Vec<Ipv4Addr>,
+    ipv6hint: Vec<Ipv6Addr>,
+    other: Vec<SvcbParamKey>,
+}
+
+impl SvcbParams {
+    /// Creates the parameters from an iterator over the record’s values.
+    pub fn from_params(iter: ParamIter<&[u8]>) -> Result<Self, ParseError> {
+        let mut res = Self::default();
+        for param in iter {
+            match param? {
+                AllParams::Mandatory(keys) => {
+                    for key in keys.iter() {
+                        res.mandatory.push(key?)
+                    }
+                }
+                AllParams::Alpn(ids) => {
+                    for id in ids.iter() {
+                        res.alpn.push(id?.into())
+                    }
+                }
+                AllParams::NoDefaultAlpn(_) => res.no_default_alpn = true,
+                AllParams::Port(port) => res.port = Some(port.port()),
+                AllParams::Ech(ech) => res.ech = Some(ech.as_slice().into()),
+                AllParams::Ipv4Hint(hint) => {
+                    for addr in hint.iter() {
+                        res.ipv4hint.push(addr?)
+                    }
+                }
+                AllParams::Ipv6Hint(hint) => {
+                    for addr in hint.iter() {
+                        res.ipv6hint.push(addr?)
+                    }
+                }
+                AllParams::DohPath(_) => {
+                    res.other.push(SvcbParamKey::DohPath)
+                }
+                AllParams::Unknown(unknown) => res.other.push(unknown.key()),
+            }
+        }
+        Ok(res)
+    }
+
+    /// Returns the keys listed as mandatory.
+    pub fn mandatory(&self) -> &[SvcbParamKey] {
+        &self.mandatory
+    }
+
+    /// Returns the ALPN protocol IDs listed in the alpn parameter.
+    pub fn alpn(&self) -> impl Iterator<Item = &[u8]> {
+        self.alpn.iter().map(AsRef::as_ref)
+    }
+
+    /// Returns the supported ALPN protocol IDs including the default.
+    ///
+    /// The default protocol `default` is added at the end unless the
+    /// no-default-alpn parameter is present.
+    pub fn alpn_with_default<'a>(
+        &'a self,
+        default: &'a [u8],
+    ) -> Vec<&'a [u8]> {
+        let mut res: Vec<_> = self.alpn().collect();
+        if !self.no_default_alpn && !res.contains(&default) {
+            res.push(default)
+        }
+        res
+    }
+
+    /// Returns whether the no-default-alpn parameter is present.
+    pub fn no_default_alpn(&self) -> bool {
+        self.no_default_alpn
+    }
+
+    /// Returns the value of the port parameter if present.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Returns the ECHConfigList from the ech parameter if present.
+    pub fn ech(&self) -> Option<&[u8]> {
+        self.ech.as_deref()
+    }
+
+    /// Returns the IPv4 address hints.
+    pub fn ipv4hint(&self) -> &[Ipv4Addr] {
+        &self.ipv4hint
+    }
+
+    /// Returns the IPv6 address hints.
+    pub fn ipv6hint(&self) -> &[Ipv6Addr] {
+        &self.ipv6hint
+    }
+
+    /// Returns the keys of any other parameters present.
+    pub fn other_keys(&self) -> &[SvcbParamKey] {
+        &self.other
+    }
+
+    /// Returns whether a client understanding these parameters can use them.
+    ///
+    /// This is not the case if a key listed as mandatory is not supported by
+    /// this implementation or isn’t actually present.
+    pub fn is_compatible(&self) -> bool {
+        self.mandatory.iter().all(|key| match *key {
+            SvcbParamKey::Alpn => !self.alpn.is_empty(),
+            SvcbParamKey::NoDefaultAlpn => self.no_default_alpn,
+            SvcbParamKey::Port => self.port.is_some(),
+            SvcbParamKey::Ech => self.ech.is_some(),
+            SvcbParamKey::Ipv4Hint => !self.ipv4hint.is_empty(),
+            SvcbParamKey::Ipv6Hint => !self.ipv6hint.is_empty(),
+            _ => false,
+        })
+    }
+}
+
+//------------ SvcbRecord ----------------------------------------------------
+
+/// The content of an SVCB or HTTPS record.
+struct SvcbRecord {
+    priority: u16,
+    target: Dname<OctetsVec>,
+    params: SvcbParams,
+}
+
+impl SvcbRecord {
+    /// Collects the records of the given type for `owner` from an answer.
+    ///
+    /// Records that cannot be parsed are skipped.
+    fn collect(
+        answer: &Message<&[u8]>,
+        rtype: Rtype,
+        owner: &impl ToDname,
+    ) -> Result<Vec<Self>, SvcbError> {
+        let mut res = Vec::new();
+        let section = answer.answer()?;
+        if rtype == Rtype::Https {
+            for record in section.limit_to_in::<Https<_, _>>().flatten() {
+                if record.owner().name_eq(owner) {
+                    let data = record.data();
+                    res.extend(Self::new(
+                        data.priority(),
+                        data.target(),
+                        data.iter(),
+                    ));
+                }
+            }
+        } else {
+            for record in section.limit_to_in::<Svcb<_, _>>().flatten() {
+                if record.owner().name_eq(owner) {
+                    let data = record.data();
+                    res.extend(Self::new(
+                        data.priority(),
+                        data.target(),
+                        data.iter(),
+                    ));
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    fn new(
+        priority: u16,
+        target: &impl ToDname,
+        params: ParamIter<&[u8]>,
+    ) -> Option<Self> {
+        Some(SvcbRecord {
+            priority,
+            target: target.to_dname().ok()?,
+            params: SvcbParams::from_params(params).ok()?,
+        })
+    }
+}
+
+//------------ SvcbError -----------------------------------------------------
+
+/// An error happened during an SVCB or HTTPS lookup.
+#[derive(Debug)]
+pub enum SvcbError {
+    /// The query name would be too long.
+    LongName,
+
+    /// An answer could not be parsed.
+    MalformedAnswer,
+
+    /// The AliasMode records form a loop or a chain that is too long.
+    AliasLoop,
+
+    /// A query failed.
+    Query(io::Error),
+}
+
+impl From<io::Error> for SvcbError {
+    fn from(err: io::Error) -> SvcbError {
+        SvcbError::Query(err)
+    }
+}
+
+impl From<ParseError> for SvcbError {
+    fn from(_: ParseError) -> SvcbError {
+        SvcbError::MalformedAnswer
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::Rcode;
+    use crate::base::octets::OctetsFrom;
+    use crate::base::record::Record;
+    use crate::rdata::svcb::param::{Alpn, Ipv4Hint, Port};
+    use crate::resolv::stub::conf::{ResolvConf, ServerConf, Transport};
+    use crate::resolv::stub::upstream::{
+        MockRecord, MockReply, MockUpstream,
+    };
+    use crate::resolv::stub::StubResolver;
+    use bytes::Bytes;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    fn name(s: &str) -> Dname<Bytes> {
+        if s == "." {
+            Dname::root_bytes()
+        } else {
+            Dname::from_str(s).unwrap()
+        }
+    }
+
+    fn resolver(mock: &Arc<MockUpstream>) -> StubResolver {
+        let mut conf = ResolvConf::new();
+        conf.servers.push(ServerConf::new(
+            "192.0.2.53:53".parse().unwrap(),
+            Transport::Udp,
+        ));
+        conf.finalize();
+        StubResolver::with_upstream(conf, mock.clone())
+    }
+
+    fn https(
+        owner: &str,
+        priority: u16,
+        target: &str,
+        params: Vec<AllParams<Vec<u8>>>,
+    ) -> MockRecord {
+        let mut data = Https::new(priority, name(target), Vec::new());
+        for param in params {
+            data.push(param).unwrap();
+        }
+        let data: Https<Bytes, _> =
+            OctetsFrom::octets_from(data.freeze()).unwrap();
+        Record::new(name(owner), Class::In, 3600, data.into())
+    }
+
+    fn a(owner: &str, addr: [u8; 4]) -> MockRecord {
+        Record::new(
+            name(owner),
+            Class::In,
+            3600,
+            A::from_octets(addr[0], addr[1], addr[2], addr[3]).into(),
+        )
+    }
+
+    #[tokio::test]
+    async fn alias_and_priority() {
+        let mut alpn = Alpn::new(Vec::new());
+        alpn.push(b"h2").unwrap();
+        let mut hint = Ipv4Hint::new(Vec::new());
+        hint.push(Ipv4Addr::new(198, 51, 100, 7)).unwrap();
+        let mut mandatory =
+            crate::rdata::svcb::param::Mandatory::new(Vec::new());
+        mandatory.push(SvcbParamKey::Int(0xff00)).unwrap();
+
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (name("example.com"), Rtype::Https),
+            MockReply::new(Rcode::NoError).answer(https(
+                "example.com",
+                0,
+                "svc.example.net",
+                vec![],
+            )),
+        );
+        mock.push(
+            (name("svc.example.net"), Rtype::Https),
+            MockReply::new(Rcode::NoError)
+                .answer(https(
+                    "svc.example.net",
+                    2,
+                    ".",
+                    vec![alpn.into(), Port::new(8443).into()],
+                ))
+                .answer(https(
+                    "svc.example.net",
+                    1,
+                    "pool.example.net",
+                    vec![hint.into()],
+                ))
+                .answer(https(
+                    "svc.example.net",
+                    1,
+                    "new.example.net",
+                    vec![mandatory.into()],
+                ))
+                .additional(a("svc.example.net", [192, 0, 2, 1])),
+        );
+        let resolver = resolver(&mock);
+        let found = lookup_https(&&resolver, name("example.com"), 443)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!found.is_fallback());
+        let endpoints = found.endpoints();
+        assert_eq!(endpoints.len(), 2);
+
+        assert_eq!(endpoints[0].priority(), 1);
+        assert_eq!(endpoints[0].target(), &name("pool.example.net"));
+        assert_eq!(endpoints[0].port(), 443);
+        assert_eq!(endpoints[0].addrs(), [IpAddr::from([198, 51, 100, 7])]);
+
+        assert_eq!(endpoints[1].priority(), 2);
+        assert_eq!(endpoints[1].target(), &name("svc.example.net"));
+        assert_eq!(
+            endpoints[1].socket_addrs().collect::<Vec<_>>(),
+            [SocketAddr::from(([192, 0, 2, 1], 8443))]
+        );
+        assert_eq!(
+            endpoints[1].params().alpn_with_default(HTTPS_DEFAULT_ALPN),
+            [b"h2".as_ref(), b"http/1.1".as_ref()]
+        );
+    }
+
+    #[tokio::test]
+    async fn fallback() {
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (name("_8080._https.example.com"), Rtype::Https),
+            MockReply::new(Rcode::NoError),
+        );
+        mock.push(
+            (name("example.com"), Rtype::A),
+            MockReply::new(Rcode::NoError)
+                .answer(a("example.com", [192, 0, 2, 8])),
+        );
+        mock.push(
+            (name("example.com"), Rtype::Aaaa),
+            MockReply::new(Rcode::NoError),
+        );
+        let resolver = resolver(&mock);
+        let found = lookup_https(&&resolver, name("example.com"), 8080)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(found.is_fallback());
+        let endpoint = found.iter().next().unwrap();
+        assert_eq!(endpoint.target(), &name("example.com"));
+        assert_eq!(
+            endpoint.socket_addrs().collect::<Vec<_>>(),
+            [SocketAddr::from(([192, 0, 2, 8], 8080))]
+        );
+        assert!(mock.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn root_target_with_port_prefix() {
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (name("_8443._https.example.com"), Rtype::Https),
+            MockReply::new(Rcode::NoError)
+                .answer(https("_8443._https.example.com", 1, ".", vec![]))
+                .additional(a("_8443._https.example.com", [192, 0, 2, 9])),
+        );
+        let resolver = resolver(&mock);
+        let found = lookup_https(&&resolver, name("example.com"), 8443)
+            .await
+            .unwrap()
+            .unwrap();
+        let endpoint = found.iter().next().unwrap();
+        assert_eq!(endpoint.target(), &name("_8443._https.example.com"));
+        assert_eq!(
+            endpoint.socket_addrs().collect::<Vec<_>>(),
+            [SocketAddr::from(([192, 0, 2, 9], 8443))]
+        );
+    }
+
+    #[tokio::test]
+    async fn unavailable() {
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (name("example.com"), Rtype::Https),
+            MockReply::new(Rcode::NoError).answer(https(
+                "example.com",
+                0,
+                ".",
+                vec![],
+            )),
+        );
+        let resolver = resolver(&mock);
+        assert!(lookup_https(&&resolver, name("example.com"), 443)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
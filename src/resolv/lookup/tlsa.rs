@@ -0,0 +1,251 @@
+//! Looking up TLSA records.
+
+use crate::base::iana::{Class, Rcode, Rtype};
+use crate::base::message::Message;
+use crate::base::name::{Dname, DnameBuilder, ToDname};
+use crate::base::octets::{OctetsFrom, OctetsVec, ParseError, ShortBuf};
+use crate::rdata::Tlsa;
+use crate::resolv::resolver::Resolver;
+use bytes::Bytes;
+use std::string::String;
+use std::vec::Vec;
+use std::{io, slice, vec};
+
+//------------ lookup_tlsa ---------------------------------------------------
+
+/// Looks up the TLSA records for a service endpoint.
+///
+/// The function queries the TLSA records for the name composed of the
+/// port, the protocol name, and the host name as described in section 3
+/// of [RFC 6698]. For instance, for port 443, protocol `"tcp"`, and host
+/// `www.example.com`, the query name is `_443._tcp.www.example.com`.
+///
+/// The result carries the port and protocol name it was looked up for, so
+/// results for several endpoints of a host can be kept apart. TLSA records
+/// should only be used if they were validated via DNSSEC which is reported
+/// by [`FoundTlsa::is_secure`].
+///
+/// If the server answers with an error other than NXDOMAIN, the function
+/// returns [`TlsaError::Rcode`]. A validating resolver answers with
+/// SERVFAIL if validation fails, so this must not be mistaken for the
+/// absence of records.
+///
+/// [RFC 6698]: https://tools.ietf.org/html/rfc6698
+pub async fn lookup_tlsa(
+    resolver: &impl Resolver,
+    port: u16,
+    protocol: &str,
+    host: impl ToDname,
+) -> Result<FoundTlsa, TlsaError> {
+    let qname = tlsa_name(port, protocol, &host)?;
+    let answer = resolver.query((&qname, Rtype::Tlsa)).await?;
+    FoundTlsa::new(&answer.as_ref().for_slice(), port, protocol)
+}
+
+/// Returns the query name for the TLSA records of a service endpoint.
+pub(crate) fn tlsa_name(
+    port: u16,
+    protocol: &str,
+    host: &impl ToDname,
+) -> Result<Dname<OctetsVec>, TlsaError> {
+    let mut res = DnameBuilder::<OctetsVec>::new();
+    res.append_label(format!("_{}", port).as_bytes())
+        .and_then(|_| res.append_label(format!("_{}", protocol).as_bytes()))
+        .map_err(|_| TlsaError::LongName)?;
+    res.append_origin(host).map_err(|_| TlsaError::LongName)
+}
+
+//------------ FoundTlsa -----------------------------------------------------
+
+/// The TLSA records found for a service endpoint.
+#[derive(Clone, Debug)]
+pub struct FoundTlsa {
+    /// The port the records were looked up for.
+    port: u16,
+
+    /// The protocol name the records were looked up for.
+    protocol: String,
+
+    /// Did the answer have the AD bit set?
+    secure: bool,
+
+    /// The records.
+    items: Vec<Tlsa<Bytes>>,
+}
+
+impl FoundTlsa {
+    fn new(
+        answer: &Message<&[u8]>,
+        port: u16,
+        protocol: &str,
+    ) -> Result<Self, TlsaError> {
+        let rcode = answer.header().rcode();
+        if rcode != Rcode::NoError && rcode != Rcode::NXDomain {
+            return Err(TlsaError::Rcode(rcode));
+        }
+        let owner =
+            answer.canonical_name().ok_or(TlsaError::MalformedAnswer)?;
+        let mut items = Vec::new();
+        for record in answer.answer()?.flatten() {
+            if record.rtype() != Rtype::Tlsa
+                || record.class() != Class::In
+                || *record.owner() != owner
+            {
+                continue;
+            }
+            if let Some(record) = record.to_record::<Tlsa<&[u8]>>()? {
+                // Bytes can’t be created from a borrowed slice directly.
+                let data = Tlsa::<Vec<u8>>::octets_from(record.into_data())?;
+                items.push(Tlsa::octets_from(data)?);
+            }
+        }
+        Ok(FoundTlsa {
+            port,
+            protocol: protocol.into(),
+            secure: answer.header().ad(),
+            items,
+        })
+    }
+
+    /// Returns the port the records were looked up for.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns the protocol name the records were looked up for.
+    pub fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    /// Returns whether the answer was validated via DNSSEC.
+    ///
+    /// This reflects the AD bit of the answer and can only be trusted if
+    /// the path to the validating resolver is secure.
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    /// Returns whether there were no TLSA records.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns an iterator over the records.
+    pub fn iter(&self) -> slice::Iter<'_, Tlsa<Bytes>> {
+        self.items.iter()
+    }
+}
+
+impl IntoIterator for FoundTlsa {
+    type Item = Tlsa<Bytes>;
+    type IntoIter = vec::IntoIter<Tlsa<Bytes>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FoundTlsa {
+    type Item = &'a Tlsa<Bytes>;
+    type IntoIter = slice::Iter<'a, Tlsa<Bytes>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+//------------ TlsaError -----------------------------------------------------
+
+/// An error happened during a TLSA lookup.
+#[derive(Debug)]
+pub enum TlsaError {
+    /// The query name would be too long.
+    LongName,
+
+    /// The answer could not be parsed.
+    MalformedAnswer,
+
+    /// A server answered with the given error response code.
+    Rcode(Rcode),
+
+    /// The query failed.
+    Query(io::Error),
+}
+
+impl From<io::Error> for TlsaError {
+    fn from(err: io::Error) -> TlsaError {
+        TlsaError::Query(err)
+    }
+}
+
+impl From<ParseError> for TlsaError {
+    fn from(_: ParseError) -> TlsaError {
+        TlsaError::MalformedAnswer
+    }
+}
+
+impl From<ShortBuf> for TlsaError {
+    fn from(_: ShortBuf) -> TlsaError {
+        TlsaError::MalformedAnswer
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::{
+        Rcode, TlsaCertUsage, TlsaMatchingType, TlsaSelector,
+    };
+    use crate::base::rdata::UnknownRecordData;
+    use crate::base::record::Record;
+    use crate::resolv::stub::conf::{ResolvConf, ServerConf, Transport};
+    use crate::resolv::stub::upstream::{MockReply, MockUpstream};
+    use crate::resolv::stub::StubResolver;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn lookup() {
+        let qname =
+            Dname::<Bytes>::from_str("_25._tcp.mx.example.com").unwrap();
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (qname.clone(), Rtype::Tlsa),
+            MockReply::new(Rcode::NoError).answer(Record::new(
+                qname,
+                Class::In,
+                3600,
+                UnknownRecordData::from_octets(
+                    Rtype::Tlsa,
+                    Bytes::from_static(b"\x03\x01\x01\xab\xcd"),
+                )
+                .into(),
+            )),
+        );
+        let mut conf = ResolvConf::new();
+        conf.servers.push(ServerConf::new(
+            "192.0.2.53:53".parse().unwrap(),
+            Transport::Udp,
+        ));
+        conf.finalize();
+        let resolver = StubResolver::with_upstream(conf, mock);
+
+        let host = Dname::<Bytes>::from_str("mx.example.com").unwrap();
+        let found = lookup_tlsa(&&resolver, 25, "tcp", host).await.unwrap();
+        assert_eq!(found.port(), 25);
+        assert_eq!(found.protocol(), "tcp");
+        assert!(!found.is_secure());
+        let item = found.iter().next().unwrap();
+        assert_eq!(
+            (item.usage(), item.selector(), item.matching_type()),
+            (
+                TlsaCertUsage::DaneEe,
+                TlsaSelector::Spki,
+                TlsaMatchingType::Sha256
+            )
+        );
+        assert_eq!(item.data().as_ref(), b"\xab\xcd");
+    }
+}
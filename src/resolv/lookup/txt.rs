@@ -0,0 +1,236 @@
+//! Looking up TXT records.
+
+use crate::base::iana::{Rcode, Rtype};
+use crate::base::message::Message;
+use crate::base::name::ToDname;
+use crate::base::octets::ParseError;
+use crate::rdata::Txt;
+use crate::resolv::resolver::Resolver;
+use std::vec::Vec;
+use std::{io, slice, str, vec};
+
+//------------ lookup_txt ----------------------------------------------------
+
+/// Looks up the TXT records of a domain.
+///
+/// The function queries the TXT records for `name` using `resolver`. The
+/// character strings of each record are joined into a single text as
+/// is the convention for protocols using TXT records such as SPF or DKIM.
+///
+/// The returned texts are in the order received. If there are no TXT
+/// records, the result is empty. If the server answers with an error other
+/// than NXDOMAIN, the function returns [`TxtError::Rcode`].
+pub async fn lookup_txt(
+    resolver: &impl Resolver,
+    name: impl ToDname,
+) -> Result<FoundTxt, TxtError> {
+    let answer = resolver.query((name, Rtype::Txt)).await?;
+    FoundTxt::new(&answer.as_ref().for_slice())
+}
+
+//------------ FoundTxt ------------------------------------------------------
+
+/// The texts found by [`lookup_txt`].
+#[derive(Clone, Debug, Default)]
+pub struct FoundTxt {
+    items: Vec<Vec<u8>>,
+}
+
+impl FoundTxt {
+    fn new(answer: &Message<&[u8]>) -> Result<Self, TxtError> {
+        let rcode = answer.header().rcode();
+        if rcode != Rcode::NoError && rcode != Rcode::NXDomain {
+            return Err(TxtError::Rcode(rcode));
+        }
+        let owner =
+            answer.canonical_name().ok_or(TxtError::MalformedAnswer)?;
+        let mut items = Vec::new();
+        for record in answer.answer()?.limit_to_in::<Txt<_>>().flatten() {
+            if *record.owner() == owner {
+                items.push(
+                    record
+                        .data()
+                        .text::<Vec<u8>>()
+                        .map_err(|_| TxtError::MalformedAnswer)?,
+                )
+            }
+        }
+        Ok(FoundTxt { items })
+    }
+
+    /// Returns whether there were no TXT records.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the number of TXT records.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns an iterator over the texts.
+    pub fn iter(&self) -> FoundTxtIter<'_> {
+        FoundTxtIter(self.items.iter())
+    }
+
+    /// Returns an iterator over the texts that are valid UTF-8.
+    pub fn iter_str(&self) -> impl Iterator<Item = &str> {
+        self.iter().filter_map(|item| str::from_utf8(item).ok())
+    }
+
+    /// Returns an iterator over the texts starting with `prefix`.
+    ///
+    /// This is useful for picking out records of protocols that mark their
+    /// records with a version tag, such as `v=spf1` for SPF.
+    pub fn iter_prefixed<'a>(
+        &'a self,
+        prefix: &'a [u8],
+    ) -> impl Iterator<Item = &'a [u8]> {
+        self.iter().filter(move |item| item.starts_with(prefix))
+    }
+}
+
+impl IntoIterator for FoundTxt {
+    type Item = Vec<u8>;
+    type IntoIter = vec::IntoIter<Vec<u8>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FoundTxt {
+    type Item = &'a [u8];
+    type IntoIter = FoundTxtIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+//------------ FoundTxtIter --------------------------------------------------
+
+/// An iterator over the texts of a [`FoundTxt`].
+#[derive(Clone, Debug)]
+pub struct FoundTxtIter<'a>(slice::Iter<'a, Vec<u8>>);
+
+impl<'a> Iterator for FoundTxtIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Vec::as_slice)
+    }
+}
+
+//------------ TxtError ------------------------------------------------------
+
+/// An error happened during a TXT lookup.
+#[derive(Debug)]
+pub enum TxtError {
+    /// The answer could not be parsed.
+    MalformedAnswer,
+
+    /// A server answered with the given error response code.
+    Rcode(Rcode),
+
+    /// The query failed.
+    Query(io::Error),
+}
+
+impl From<io::Error> for TxtError {
+    fn from(err: io::Error) -> TxtError {
+        TxtError::Query(err)
+    }
+}
+
+impl From<ParseError> for TxtError {
+    fn from(_: ParseError) -> TxtError {
+        TxtError::MalformedAnswer
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::Class;
+    use crate::base::name::Dname;
+    use crate::base::record::Record;
+    use crate::rdata::rfc1035::TxtBuilder;
+    use crate::resolv::stub::conf::{ResolvConf, ServerConf, Transport};
+    use crate::resolv::stub::upstream::{MockReply, MockUpstream};
+    use crate::resolv::stub::StubResolver;
+    use bytes::{Bytes, BytesMut};
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn joined() {
+        let owner = Dname::<Bytes>::from_str("example.com").unwrap();
+        let mut builder = TxtBuilder::<BytesMut>::new();
+        builder.append_slice(&[b'a'; 200]).unwrap();
+        builder.append_slice(b"bc").unwrap();
+        let long = builder.finish();
+
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (owner.clone(), Rtype::Txt),
+            MockReply::new(Rcode::NoError)
+                .answer(Record::new(
+                    owner.clone(),
+                    Class::In,
+                    3600,
+                    Txt::from_slice(b"v=spf1 -all").unwrap().into(),
+                ))
+                .answer(Record::new(
+                    owner.clone(),
+                    Class::In,
+                    3600,
+                    long.into(),
+                )),
+        );
+        let mut conf = ResolvConf::new();
+        conf.servers.push(ServerConf::new(
+            "192.0.2.53:53".parse().unwrap(),
+            Transport::Udp,
+        ));
+        conf.finalize();
+        let resolver = StubResolver::with_upstream(conf, mock);
+
+        let found = lookup_txt(&&resolver, owner).await.unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            found.iter_prefixed(b"v=spf1").collect::<Vec<_>>(),
+            [b"v=spf1 -all".as_ref()]
+        );
+        let long = found.iter().nth(1).unwrap();
+        assert_eq!(long.len(), 202);
+        assert!(long.ends_with(b"abc"));
+        assert_eq!(found.iter_str().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn failure() {
+        let owner = Dname::<Bytes>::from_str("example.com").unwrap();
+        for rcode in &[Rcode::ServFail, Rcode::Refused] {
+            let mock = Arc::new(MockUpstream::new());
+            mock.push((owner.clone(), Rtype::Txt), MockReply::new(*rcode));
+            let mut conf = ResolvConf::new();
+            conf.servers.push(ServerConf::new(
+                "192.0.2.53:53".parse().unwrap(),
+                Transport::Udp,
+            ));
+            conf.options.attempts = 1;
+            conf.finalize();
+            let resolver = StubResolver::with_upstream(conf, mock);
+
+            let res = lookup_txt(&&resolver, owner.clone()).await;
+            assert!(
+                matches!(res, Err(TxtError::Rcode(value)) if value == *rcode),
+                "{:?}",
+                res
+            );
+        }
+    }
+}
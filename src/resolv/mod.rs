@@ -19,6 +19,10 @@
 //!    See the [stub] module for more information on how to use the stub
 //!    resolver.
 //!
+//! The asynchronous resolvers are not tied to a specific async runtime.
+//! Which runtimes are available is determined by features with Tokio being
+//! the default. See the [runtime] module for details.
+//!
 //! The lookups implemented by the crate are generic over the particular
 //! resolver, so you can pick the resolver most suitable for your own
 //! application or even implement your own specialised resolver. All
@@ -27,15 +31,19 @@
 //! trait.
 //!
 //! [lookup]: lookup/index.html
+//! [runtime]: runtime/index.html
 //! [stub]: stub/index.html
 //! [`Resolver`]: resolver/trait.Resolver.html
 //! [`StubResolver`]: stub/struct.StubResolver.html
-#![cfg(feature = "resolv")]
-#![cfg_attr(docsrs, doc(cfg(feature = "resolv")))]
+#![cfg(feature = "resolv-core")]
+#![cfg_attr(docsrs, doc(cfg(feature = "resolv-core")))]
 
 pub use self::resolver::Resolver;
+pub use self::stub::sync::SyncStubResolver;
 pub use self::stub::StubResolver;
 
+pub mod hosts;
 pub mod lookup;
 pub mod resolver;
+pub mod runtime;
 pub mod stub;
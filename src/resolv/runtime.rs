@@ -0,0 +1,395 @@
+//! Abstracting over async runtimes.
+//!
+//! The resolver needs only a small set of operations from the async runtime
+//! it is running on: UDP sockets, TCP streams, and a timer. This module
+//! defines the [`Runtime`] trait that provides these operations as well
+//! as the [`DgramSocket`] and [`StreamSocket`] traits for the sockets it
+//! creates.
+//!
+//! Implementations are available for the following runtimes, each enabled
+//! through its own feature:
+//!
+//! * [`TokioRuntime`] for [Tokio](https://tokio.rs/) via the
+//!   `resolv-tokio` feature which is part of the `resolv` feature,
+//! * [`AsyncStdRuntime`] for [async-std](https://async.rs/) via the
+//!   `resolv-async-std` feature, and
+//! * [`SmolRuntime`] for [smol](https://github.com/smol-rs/smol) via the
+//!   `resolv-smol` feature.
+//!
+//! The type alias [`DefaultRuntime`] refers to the first of these that is
+//! enabled in the order given above. It is used by the resolver if you
+//! don’t explicitly pick a runtime.
+
+use futures::future::{select, Either};
+use std::boxed::Box;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::time::Duration;
+use std::{fmt, io};
+
+//------------ Runtime -------------------------------------------------------
+
+/// An async runtime.
+///
+/// The trait provides the means to create sockets and to wait for some
+/// time. All futures returned by the trait’s methods are boxed so they can
+/// be named without generic associated types.
+pub trait Runtime: fmt::Debug + Send + Sync {
+    /// The type of a UDP socket.
+    type DgramSocket: DgramSocket + 'static;
+
+    /// The type of a TCP stream.
+    type StreamSocket: StreamSocket + 'static;
+
+    /// Binds a UDP socket to the given local address.
+    fn bind_dgram(
+        &self,
+        addr: SocketAddr,
+    ) -> RuntimeFuture<'static, io::Result<Self::DgramSocket>>;
+
+    /// Connects a TCP stream to the given remote address.
+    fn connect_stream(
+        &self,
+        addr: SocketAddr,
+    ) -> RuntimeFuture<'static, io::Result<Self::StreamSocket>>;
+
+    /// Returns a future that resolves after `duration` has passed.
+    fn sleep(&self, duration: Duration) -> RuntimeFuture<'static, ()>;
+}
+
+/// The future returned by the methods of the runtime traits.
+pub type RuntimeFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+//------------ DgramSocket ---------------------------------------------------
+
+/// A UDP socket of a [`Runtime`].
+pub trait DgramSocket: Send + Sync {
+    /// Connects the socket to a remote address.
+    ///
+    /// Afterwards, only datagrams from this address will be received.
+    fn connect(&self, addr: SocketAddr) -> RuntimeFuture<'_, io::Result<()>>;
+
+    /// Sends a datagram to the connected address.
+    ///
+    /// Returns the number of octets sent.
+    fn send<'a>(
+        &'a self,
+        buf: &'a [u8],
+    ) -> RuntimeFuture<'a, io::Result<usize>>;
+
+    /// Receives a datagram from the connected address.
+    ///
+    /// Returns the number of octets received.
+    fn recv<'a>(
+        &'a self,
+        buf: &'a mut [u8],
+    ) -> RuntimeFuture<'a, io::Result<usize>>;
+}
+
+//------------ StreamSocket --------------------------------------------------
+
+/// A TCP stream of a [`Runtime`].
+pub trait StreamSocket: Send {
+    /// Writes all of `buf` to the stream.
+    fn write_all<'a>(
+        &'a mut self,
+        buf: &'a [u8],
+    ) -> RuntimeFuture<'a, io::Result<()>>;
+
+    /// Reads exactly enough octets to fill `buf` from the stream.
+    fn read_exact<'a>(
+        &'a mut self,
+        buf: &'a mut [u8],
+    ) -> RuntimeFuture<'a, io::Result<()>>;
+}
+
+//------------ timeout -------------------------------------------------------
+
+/// Runs a future with a time limit.
+///
+/// Returns the output of `fut` if it completes within `duration` or `None`
+/// otherwise.
+pub async fn timeout<R, F>(
+    runtime: &R,
+    duration: Duration,
+    fut: F,
+) -> Option<F::Output>
+where
+    R: Runtime + ?Sized,
+    F: Future,
+{
+    futures::pin_mut!(fut);
+    match select(fut, runtime.sleep(duration)).await {
+        Either::Left((res, _)) => Some(res),
+        Either::Right(_) => None,
+    }
+}
+
+//------------ DefaultRuntime ------------------------------------------------
+
+/// The runtime used if none is given explicitly.
+#[cfg(feature = "resolv-tokio")]
+pub type DefaultRuntime = TokioRuntime;
+
+/// The runtime used if none is given explicitly.
+#[cfg(all(not(feature = "resolv-tokio"), feature = "resolv-async-std"))]
+pub type DefaultRuntime = AsyncStdRuntime;
+
+/// The runtime used if none is given explicitly.
+#[cfg(all(
+    not(feature = "resolv-tokio"),
+    not(feature = "resolv-async-std"),
+    feature = "resolv-smol"
+))]
+pub type DefaultRuntime = SmolRuntime;
+
+//------------ TokioRuntime --------------------------------------------------
+
+#[cfg(feature = "resolv-tokio")]
+pub use self::tokio_runtime::TokioRuntime;
+
+#[cfg(feature = "resolv-tokio")]
+mod tokio_runtime {
+    use super::{DgramSocket, Runtime, RuntimeFuture, StreamSocket};
+    use std::boxed::Box;
+    use std::io;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpStream, UdpSocket};
+
+    /// The [Tokio](https://tokio.rs/) runtime.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct TokioRuntime;
+
+    impl Runtime for TokioRuntime {
+        type DgramSocket = UdpSocket;
+        type StreamSocket = TcpStream;
+
+        fn bind_dgram(
+            &self,
+            addr: SocketAddr,
+        ) -> RuntimeFuture<'static, io::Result<UdpSocket>> {
+            Box::pin(UdpSocket::bind(addr))
+        }
+
+        fn connect_stream(
+            &self,
+            addr: SocketAddr,
+        ) -> RuntimeFuture<'static, io::Result<TcpStream>> {
+            Box::pin(TcpStream::connect(addr))
+        }
+
+        fn sleep(&self, duration: Duration) -> RuntimeFuture<'static, ()> {
+            Box::pin(tokio::time::sleep(duration))
+        }
+    }
+
+    impl DgramSocket for UdpSocket {
+        fn connect(
+            &self,
+            addr: SocketAddr,
+        ) -> RuntimeFuture<'_, io::Result<()>> {
+            Box::pin(UdpSocket::connect(self, addr))
+        }
+
+        fn send<'a>(
+            &'a self,
+            buf: &'a [u8],
+        ) -> RuntimeFuture<'a, io::Result<usize>> {
+            Box::pin(UdpSocket::send(self, buf))
+        }
+
+        fn recv<'a>(
+            &'a self,
+            buf: &'a mut [u8],
+        ) -> RuntimeFuture<'a, io::Result<usize>> {
+            Box::pin(UdpSocket::recv(self, buf))
+        }
+    }
+
+    impl StreamSocket for TcpStream {
+        fn write_all<'a>(
+            &'a mut self,
+            buf: &'a [u8],
+        ) -> RuntimeFuture<'a, io::Result<()>> {
+            Box::pin(AsyncWriteExt::write_all(self, buf))
+        }
+
+        fn read_exact<'a>(
+            &'a mut self,
+            buf: &'a mut [u8],
+        ) -> RuntimeFuture<'a, io::Result<()>> {
+            Box::pin(async move {
+                AsyncReadExt::read_exact(self, buf).await.map(|_| ())
+            })
+        }
+    }
+}
+
+//------------ AsyncStdRuntime -----------------------------------------------
+
+#[cfg(feature = "resolv-async-std")]
+pub use self::async_std_runtime::AsyncStdRuntime;
+
+#[cfg(feature = "resolv-async-std")]
+mod async_std_runtime {
+    use super::{DgramSocket, Runtime, RuntimeFuture, StreamSocket};
+    use async_std::net::{TcpStream, UdpSocket};
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+    use std::boxed::Box;
+    use std::io;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    /// The [async-std](https://async.rs/) runtime.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct AsyncStdRuntime;
+
+    impl Runtime for AsyncStdRuntime {
+        type DgramSocket = UdpSocket;
+        type StreamSocket = TcpStream;
+
+        fn bind_dgram(
+            &self,
+            addr: SocketAddr,
+        ) -> RuntimeFuture<'static, io::Result<UdpSocket>> {
+            Box::pin(UdpSocket::bind(addr))
+        }
+
+        fn connect_stream(
+            &self,
+            addr: SocketAddr,
+        ) -> RuntimeFuture<'static, io::Result<TcpStream>> {
+            Box::pin(TcpStream::connect(addr))
+        }
+
+        fn sleep(&self, duration: Duration) -> RuntimeFuture<'static, ()> {
+            Box::pin(async_std::task::sleep(duration))
+        }
+    }
+
+    impl DgramSocket for UdpSocket {
+        fn connect(
+            &self,
+            addr: SocketAddr,
+        ) -> RuntimeFuture<'_, io::Result<()>> {
+            Box::pin(UdpSocket::connect(self, addr))
+        }
+
+        fn send<'a>(
+            &'a self,
+            buf: &'a [u8],
+        ) -> RuntimeFuture<'a, io::Result<usize>> {
+            Box::pin(UdpSocket::send(self, buf))
+        }
+
+        fn recv<'a>(
+            &'a self,
+            buf: &'a mut [u8],
+        ) -> RuntimeFuture<'a, io::Result<usize>> {
+            Box::pin(UdpSocket::recv(self, buf))
+        }
+    }
+
+    impl StreamSocket for TcpStream {
+        fn write_all<'a>(
+            &'a mut self,
+            buf: &'a [u8],
+        ) -> RuntimeFuture<'a, io::Result<()>> {
+            Box::pin(AsyncWriteExt::write_all(self, buf))
+        }
+
+        fn read_exact<'a>(
+            &'a mut self,
+            buf: &'a mut [u8],
+        ) -> RuntimeFuture<'a, io::Result<()>> {
+            Box::pin(AsyncReadExt::read_exact(self, buf))
+        }
+    }
+}
+
+//------------ SmolRuntime ---------------------------------------------------
+
+#[cfg(feature = "resolv-smol")]
+pub use self::smol_runtime::SmolRuntime;
+
+#[cfg(feature = "resolv-smol")]
+mod smol_runtime {
+    use super::{DgramSocket, Runtime, RuntimeFuture, StreamSocket};
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+    use smol::net::{TcpStream, UdpSocket};
+    use std::boxed::Box;
+    use std::io;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    /// The [smol](https://github.com/smol-rs/smol) runtime.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct SmolRuntime;
+
+    impl Runtime for SmolRuntime {
+        type DgramSocket = UdpSocket;
+        type StreamSocket = TcpStream;
+
+        fn bind_dgram(
+            &self,
+            addr: SocketAddr,
+        ) -> RuntimeFuture<'static, io::Result<UdpSocket>> {
+            Box::pin(UdpSocket::bind(addr))
+        }
+
+        fn connect_stream(
+            &self,
+            addr: SocketAddr,
+        ) -> RuntimeFuture<'static, io::Result<TcpStream>> {
+            Box::pin(TcpStream::connect(addr))
+        }
+
+        fn sleep(&self, duration: Duration) -> RuntimeFuture<'static, ()> {
+            Box::pin(async move {
+                smol::Timer::after(duration).await;
+            })
+        }
+    }
+
+    impl DgramSocket for UdpSocket {
+        fn connect(
+            &self,
+            addr: SocketAddr,
+        ) -> RuntimeFuture<'_, io::Result<()>> {
+            Box::pin(UdpSocket::connect(self, addr))
+        }
+
+        fn send<'a>(
+            &'a self,
+            buf: &'a [u8],
+        ) -> RuntimeFuture<'a, io::Result<usize>> {
+            Box::pin(UdpSocket::send(self, buf))
+        }
+
+        fn recv<'a>(
+            &'a self,
+            buf: &'a mut [u8],
+        ) -> RuntimeFuture<'a, io::Result<usize>> {
+            Box::pin(UdpSocket::recv(self, buf))
+        }
+    }
+
+    impl StreamSocket for TcpStream {
+        fn write_all<'a>(
+            &'a mut self,
+            buf: &'a [u8],
+        ) -> RuntimeFuture<'a, io::Result<()>> {
+            Box::pin(AsyncWriteExt::write_all(self, buf))
+        }
+
+        fn read_exact<'a>(
+            &'a mut self,
+            buf: &'a mut [u8],
+        ) -> RuntimeFuture<'a, io::Result<()>> {
+            Box::pin(AsyncReadExt::read_exact(self, buf))
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Caching answers.
+
+use super::{Answer, QueryMessage};
+use crate::base::iana::{Class, Rcode, Rtype};
+use crate::base::name::{Dname, ToDname};
+use crate::base::opt::rfc7871::{truncate_addr, ClientSubnet};
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+//------------ AnswerCache ---------------------------------------------------
+
+/// A cache for the answers received by a stub resolver.
+///
+/// Answers are kept per question for the smallest TTL of the records in
+/// their answer and authority sections. Only NOERROR and NXDOMAIN answers
+/// that aren’t truncated and contain at least one record are cached. The
+/// records are handed out as they were received, i.e., their TTLs are not
+/// decreased.
+///
+/// If a query carried an EDNS Client Subnet option and the response
+/// limited its answer to a scope, the answer is kept for the network
+/// given by the query’s address truncated to the scope prefix length as
+/// required by section 7.3.1 of RFC 7871. Such an answer is only used for
+/// later queries whose client subnet falls into that network and whose
+/// source prefix is at least as long. Answers without a scope are used for
+/// all queries. If several answers apply, the one with the longest scope
+/// prefix wins.
+pub(super) struct AnswerCache {
+    /// The maximum number of questions to keep answers for.
+    capacity: usize,
+
+    /// The answers per question.
+    entries: Mutex<HashMap<CacheKey, Vec<CacheEntry>>>,
+}
+
+/// The question an answer is for.
+type CacheKey = (Dname<Bytes>, Rtype, Class);
+
+impl AnswerCache {
+    /// Creates a new cache keeping answers for `capacity` questions.
+    pub fn new(capacity: usize) -> Self {
+        AnswerCache {
+            capacity,
+            entries: Default::default(),
+        }
+    }
+
+    /// Returns a cached answer for a query if there is one.
+    ///
+    /// The `subnet` argument is the client subnet option sent with the
+    /// query, if any.
+    pub fn get(
+        &self,
+        message: &QueryMessage,
+        subnet: Option<&ClientSubnet>,
+    ) -> Option<Answer> {
+        let key = Self::key(message)?;
+        let now = Instant::now();
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&key)?
+            .iter()
+            .filter(|entry| entry.expires > now && entry.applies(subnet))
+            .max_by_key(|entry| entry.prefix_len())
+            .map(|entry| entry.answer.clone())
+    }
+
+    /// Adds the answer to a query to the cache.
+    ///
+    /// The `subnet` argument is the client subnet option sent with the
+    /// query, if any. Answers that can’t be cached are quietly ignored as
+    /// are new questions if the cache is full.
+    pub fn insert(
+        &self,
+        message: &QueryMessage,
+        subnet: Option<&ClientSubnet>,
+        answer: &Answer,
+    ) {
+        let key = match Self::key(message) {
+            Some(key) => key,
+            None => return,
+        };
+        let ttl = match Self::ttl(answer) {
+            Some(ttl) if ttl > 0 => ttl,
+            _ => return,
+        };
+        let scope = Self::scope(answer, subnet);
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(&key) && entries.len() >= self.capacity {
+            entries.retain(|_, list| {
+                list.retain(|entry| entry.expires > now);
+                !list.is_empty()
+            });
+            if entries.len() >= self.capacity {
+                return;
+            }
+        }
+        let list = entries.entry(key).or_default();
+        list.retain(|entry| entry.scope != scope && entry.expires > now);
+        list.push(CacheEntry {
+            scope,
+            answer: answer.clone(),
+            expires: now + Duration::from_secs(ttl.into()),
+        });
+    }
+
+    /// Returns the key for a query.
+    fn key(message: &QueryMessage) -> Option<CacheKey> {
+        let message = message.as_message();
+        let question = message.sole_question().ok()?;
+        Some((
+            question.qname().to_dname().ok()?,
+            question.qtype(),
+            question.qclass(),
+        ))
+    }
+
+    /// Returns how long an answer can be cached in seconds.
+    ///
+    /// Returns `None` if the answer can’t be cached at all.
+    fn ttl(answer: &Answer) -> Option<u32> {
+        let rcode = answer.header().rcode();
+        if (rcode != Rcode::NoError && rcode != Rcode::NXDomain)
+            || answer.is_truncated()
+        {
+            return None;
+        }
+        let mut res = None;
+        for section in [answer.answer().ok()?, answer.authority().ok()?] {
+            for record in section {
+                let ttl = record.ok()?.ttl();
+                res = Some(res.map_or(ttl, |res: u32| res.min(ttl)));
+            }
+        }
+        res
+    }
+
+    /// Returns the network an answer applies to.
+    ///
+    /// Returns `None` if the answer applies to all clients.
+    fn scope(
+        answer: &Answer,
+        subnet: Option<&ClientSubnet>,
+    ) -> Option<(IpAddr, u8)> {
+        let subnet = subnet.filter(|subnet| !subnet.is_opt_out())?;
+        let response = answer.opt()?.iter::<ClientSubnet>().next()?.ok()?;
+        let len = response.scope_prefix_len().min(subnet.source_prefix_len());
+        if len == 0 {
+            None
+        } else {
+            Some((truncate_addr(subnet.addr(), len), len))
+        }
+    }
+}
+
+//--- Debug
+
+impl fmt::Debug for AnswerCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AnswerCache")
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+//------------ CacheEntry ----------------------------------------------------
+
+/// A single cached answer.
+#[derive(Clone)]
+struct CacheEntry {
+    /// The network the answer applies to or `None` for all clients.
+    scope: Option<(IpAddr, u8)>,
+
+    /// The answer.
+    answer: Answer,
+
+    /// When the answer expires.
+    expires: Instant,
+}
+
+impl CacheEntry {
+    /// Returns the scope prefix length of the entry.
+    fn prefix_len(&self) -> u8 {
+        self.scope.map_or(0, |(_, len)| len)
+    }
+
+    /// Returns whether the entry applies to a query with `subnet`.
+    fn applies(&self, subnet: Option<&ClientSubnet>) -> bool {
+        let (addr, len) = match self.scope {
+            Some(scope) => scope,
+            None => return true,
+        };
+        match subnet {
+            Some(subnet) => {
+                subnet.source_prefix_len() >= len
+                    && truncate_addr(subnet.addr(), len) == addr
+            }
+            None => false,
+        }
+    }
+}
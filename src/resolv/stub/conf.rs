@@ -9,16 +9,23 @@
 //! Both parts are modeled along the lines of glibc’s resolver.
 
 use crate::base::name::{self, Dname};
+use crate::base::opt::rfc7871::ClientSubnet;
 use smallvec::SmallVec;
 use std::cmp::Ordering;
 use std::default::Default;
 use std::io::Read;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::{self, FromStr, SplitWhitespace};
+use std::string::String;
 use std::time::Duration;
 use std::vec::Vec;
-use std::{convert, error, fmt, fs, io, ops};
+use std::{convert, env, error, fmt, fs, io, ops};
+
+//------------ Module Configuration ------------------------------------------
+
+/// The path of the resolver configuration file on Unix-y systems.
+pub const DEFAULT_PATH: &str = "/etc/resolv.conf";
 
 //------------ ResolvOptions ------------------------------------------------
 
@@ -125,9 +132,15 @@ pub struct ResolvOptions {
 
     /// Send each query simultaneously to all name servers.
     ///
-    /// This is not currently implemented. It would be a query option.
+    /// If set, this overrides `strategy` with [`QueryStrategy::Blast`].
     pub blast: bool,
 
+    /// How to distribute a query across the name servers.
+    ///
+    /// This option is implemented by the query. The default is
+    /// [`QueryStrategy::Sequential`].
+    pub strategy: QueryStrategy,
+
     /// Use bit-label format for IPv6 reverse lookups.
     ///
     /// Bit labels have been deprecated and consequently, this option is not
@@ -144,9 +157,60 @@ pub struct ResolvOptions {
 
     /// Use EDNS0.
     ///
-    /// EDNS is not yet supported.
+    /// The stub resolver always uses EDNS unless a server has answered
+    /// with FORMERR to a query with EDNS, so this option has no effect.
     pub use_edns0: bool,
 
+    /// Set the AD bit in queries.
+    ///
+    /// If enabled, queries ask the servers to indicate whether they have
+    /// validated the answer via the AD bit. This should only be enabled if
+    /// the servers and the path to them are trusted. This option is
+    /// implemented by the query. Unlike glibc, the resolver does not clear
+    /// the AD bit in responses if the option is disabled. Queries for TLSA
+    /// records always have the AD bit set since DANE requires validated
+    /// answers.
+    pub trust_ad: bool,
+
+    /// Do not reload the configuration file when it changes.
+    ///
+    /// This option is implemented by the stub resolver when it watches a
+    /// configuration file.
+    pub no_reload: bool,
+
+    /// Do not send AAAA queries.
+    ///
+    /// Like glibc, the stub resolver translates queries for AAAA records
+    /// into queries for A records so that a non-existing name is still
+    /// reported as such, and answers them with the response code of the A
+    /// query but without any records. The hosts file is still consulted
+    /// for AAAA queries. This option is implemented by the stub resolver.
+    pub no_aaaa: bool,
+
+    /// Send DNS cookies to the name servers.
+    ///
+    /// If enabled, the resolver adds a cookie option as defined in RFC 7873
+    /// to every query sent with EDNS, remembers the server cookies it
+    /// receives for each server, and drops responses carrying the wrong
+    /// client cookie. This is enabled by default.
+    pub use_cookies: bool,
+
+    /// The EDNS Client Subnet option to send with queries.
+    ///
+    /// If present, the option as defined in RFC 7871 is added to every
+    /// query sent with EDNS unless a different option is given for an
+    /// individual query. Use [`ClientSubnet::opt_out`] to ask the servers
+    /// not to use the client’s address. The default is not to send the
+    /// option.
+    pub client_subnet: Option<ClientSubnet>,
+
+    /// The number of questions to cache answers for.
+    ///
+    /// Answers are cached only if this is greater than zero. The cache
+    /// keeps answers given for a client subnet separately for each scope.
+    /// The default is zero.
+    pub cache_size: usize,
+
     /// Perform IPv4 and IPv6 lookups sequentially instead of in parallel.
     ///
     /// This is not yet implemented but would be an option for
@@ -180,6 +244,7 @@ impl Default for ResolvOptions {
             recurse: true,
             default_names: true,
             dn_search: true,
+            use_cookies: true,
 
             // everthing else is not:
             aa_only: false,
@@ -192,16 +257,76 @@ impl Default for ResolvOptions {
             no_check_name: false,
             keep_tsig: false,
             blast: false,
+            strategy: QueryStrategy::Sequential,
             use_bstring: false,
             use_ip6dotint: false,
             use_edns0: false,
+            trust_ad: false,
+            no_reload: false,
+            no_aaaa: false,
             single_request: false,
             single_request_reopen: false,
             no_tld_query: false,
+
+            // non-flags that are off:
+            client_subnet: None,
+            cache_size: 0,
+        }
+    }
+}
+
+impl ResolvOptions {
+    /// Returns the query strategy to use.
+    ///
+    /// This is the value of `strategy` unless `blast` is set in which case
+    /// it is [`QueryStrategy::Blast`].
+    pub fn query_strategy(&self) -> QueryStrategy {
+        if self.blast {
+            QueryStrategy::Blast
+        } else {
+            self.strategy
         }
     }
 }
 
+//------------ QueryStrategy -------------------------------------------------
+
+/// The strategy for distributing a query across the name servers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QueryStrategy {
+    /// Ask one server after the other.
+    ///
+    /// The next server is only asked once the previous one has failed or
+    /// its request has timed out. This is the traditional behaviour.
+    Sequential,
+
+    /// Ask all servers at once and take the first good answer.
+    Blast,
+
+    /// Start asking the next server after the given delay.
+    ///
+    /// The servers are asked in order. If a server hasn’t answered within
+    /// the delay or has failed, the next server is asked while the
+    /// requests to the earlier servers remain outstanding. The first good
+    /// answer is taken.
+    Staggered(Duration),
+
+    /// Ask one server after the other, fastest healthy servers first.
+    ///
+    /// The resolver keeps a smoothed round-trip time and a count of recent
+    /// failures for each server. Servers without recent failures are asked
+    /// first, ordered by their round-trip time. Servers that recently
+    /// failed are asked last, but are tried again once their failures are
+    /// old enough.
+    Adaptive,
+}
+
+impl Default for QueryStrategy {
+    fn default() -> Self {
+        QueryStrategy::Sequential
+    }
+}
+
 //------------ Transport -----------------------------------------------------
 
 /// The transport protocol to be used for a server.
@@ -314,8 +439,11 @@ impl ServerConf {
 ///
 /// The easiest way, however, to get the system resolver configuration is
 /// through `ResolvConf::default()`. This will parse the configuration file
-/// or return a default configuration if that fails.
+/// or return a default configuration if that fails and then apply the
+/// overrides given in the environment.
 ///
+/// The `Display` implementation writes the configuration in the format of
+/// the configuration file.
 #[derive(Clone, Debug)]
 pub struct ResolvConf {
     /// Addresses of servers to query.
@@ -323,6 +451,23 @@ pub struct ResolvConf {
 
     /// Default options.
     pub options: ResolvOptions,
+
+    /// The path of a hosts file to consult before querying the servers.
+    ///
+    /// If this is `None`, which is the default, no hosts file is used.
+    /// Set it to [`hosts::DEFAULT_PATH`] to use the system’s hosts file
+    /// like the C library does.
+    ///
+    /// [`hosts::DEFAULT_PATH`]: crate::resolv::hosts::DEFAULT_PATH
+    pub hosts: Option<PathBuf>,
+
+    /// The options that were not understood while parsing.
+    ///
+    /// Unknown options as well as options with a missing or malformed
+    /// value are ignored by the parser, just like glibc does. They are
+    /// collected here so they can be reported. The `Display`
+    /// implementation writes them back out unchanged.
+    pub unknown_options: Vec<String>,
 }
 
 /// # Management
@@ -336,6 +481,8 @@ impl ResolvConf {
         ResolvConf {
             servers: Vec::new(),
             options: ResolvOptions::default(),
+            hosts: None,
+            unknown_options: Vec::new(),
         }
     }
 
@@ -362,14 +509,45 @@ impl ResolvConf {
         }
     }
 
+    /// Takes the settings that can’t be given in a file from `base`.
+    ///
+    /// These are the hosts file, the query strategy, cookies, the client
+    /// subnet, and the cache size. This is used when a configuration file
+    /// is reloaded so that these settings given by the user stay in place.
+    pub(super) fn keep_programmatic(&mut self, base: &ResolvConf) {
+        self.hosts = base.hosts.clone();
+        self.options.strategy = base.options.strategy;
+        self.options.use_cookies = base.options.use_cookies;
+        self.options.client_subnet = base.options.client_subnet;
+        self.options.cache_size = base.options.cache_size;
+    }
+
     /// Creates a default configuration for this system.
     ///
+    /// The configuration is read from [`DEFAULT_PATH`] if possible.
+    /// Afterwards, the overrides given in the environment are applied via
+    /// [`apply_env`][Self::apply_env] and the configuration is finalized.
+    ///
     /// XXX This currently only works for Unix-y systems.
     pub fn default() -> Self {
+        Self::from_file(DEFAULT_PATH).unwrap_or_else(|_| {
+            let mut res = ResolvConf::new();
+            res.apply_env();
+            res.finalize();
+            res
+        })
+    }
+
+    /// Creates a configuration from a file the way the system would.
+    ///
+    /// Parses the file at `path`, applies the overrides given in the
+    /// environment, and finalizes the configuration.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
         let mut res = ResolvConf::new();
-        let _ = res.parse_file("/etc/resolv.conf");
+        res.parse_file(path)?;
+        res.apply_env();
         res.finalize();
-        res
+        Ok(res)
     }
 }
 
@@ -417,13 +595,22 @@ impl ResolvConf {
         Ok(())
     }
 
+    /// Parses a nameserver line.
+    ///
+    /// In addition to a plain address, we also accept an address and port
+    /// in the form produced by `SocketAddr`’s `Display` implementation.
     fn parse_nameserver(
         &mut self,
         mut words: SplitWhitespace,
     ) -> Result<(), Error> {
         use std::net::ToSocketAddrs;
 
-        for addr in (next_word(&mut words)?, 53).to_socket_addrs()? {
+        let word = next_word(&mut words)?;
+        let addrs = match SocketAddr::from_str(word) {
+            Ok(addr) => vec![addr],
+            Err(_) => (word, 53).to_socket_addrs()?.collect(),
+        };
+        for addr in addrs {
             self.servers.push(ServerConf::new(addr, Transport::Udp));
             self.servers.push(ServerConf::new(addr, Transport::Tcp));
         }
@@ -465,39 +652,99 @@ impl ResolvConf {
     }
     */
 
+    /// Parses the words of an options line or `RES_OPTIONS`.
+    ///
+    /// Besides the options supported by glibc, this also understands the
+    /// options for the flags of [`ResolvOptions`] that glibc only allows
+    /// to be set programmatically, so that everything the `Display`
+    /// implementation writes can be parsed again.
     #[allow(clippy::match_same_arms)]
     fn parse_options(&mut self, words: SplitWhitespace) -> Result<(), Error> {
         for word in words {
-            match split_arg(word)? {
+            let (name, arg) = match split_arg(word) {
+                Ok(res) => res,
+                Err(_) => {
+                    self.unknown_options.push(word.into());
+                    continue;
+                }
+            };
+            let options = &mut self.options;
+            match (name, arg) {
                 ("debug", None) => {}
-                ("ndots", Some(n)) => self.options.ndots = n,
+                ("ndots", Some(n)) => options.ndots = n,
                 ("timeout", Some(n)) => {
-                    self.options.timeout = Duration::new(n as u64, 0)
-                }
-                ("attempts", Some(n)) => self.options.attempts = n,
-                ("rotate", None) => self.options.rotate = true,
-                ("no-check-names", None) => self.options.no_check_name = true,
-                ("inet6", None) => self.options.use_inet6 = true,
-                ("ip6-bytestring", None) => self.options.use_bstring = true,
-                ("ip6-dotint", None) => self.options.use_ip6dotint = true,
-                ("no-ip6-dotint", None) => self.options.use_ip6dotint = false,
-                ("edns0", None) => self.options.use_edns0 = true,
-                ("single-request", None) => {
-                    self.options.single_request = true
+                    options.timeout = Duration::new(n as u64, 0)
                 }
+                ("attempts", Some(n)) => options.attempts = n,
+                ("rotate", None) => options.rotate = true,
+                ("no-check-names", None) => options.no_check_name = true,
+                ("inet6", None) => options.use_inet6 = true,
+                ("ip6-bytestring", None) => options.use_bstring = true,
+                ("ip6-dotint", None) => options.use_ip6dotint = true,
+                ("no-ip6-dotint", None) => options.use_ip6dotint = false,
+                ("edns0", None) => options.use_edns0 = true,
+                ("single-request", None) => options.single_request = true,
                 ("single-request-reopen", None) => {
-                    self.options.single_request_reopen = true
+                    options.single_request_reopen = true
                 }
-                ("no-tld-query", None) => self.options.no_tld_query = true,
-                ("use-vc", None) => self.options.use_vc = true,
-                // Ignore unknown or misformated options.
-                _ => {}
+                ("no-tld-query", None) => options.no_tld_query = true,
+                ("use-vc", None) => options.use_vc = true,
+                ("no-reload", None) => options.no_reload = true,
+                ("trust-ad", None) => options.trust_ad = true,
+                ("no-aaaa", None) => options.no_aaaa = true,
+
+                // Flags glibc only allows setting programmatically.
+                ("aa-only", None) => options.aa_only = true,
+                ("primary", None) => options.primary = true,
+                ("ign-tc", None) => options.ign_tc = true,
+                ("no-recurse", None) => options.recurse = false,
+                ("no-default-names", None) => options.default_names = false,
+                ("stay-open", None) => options.stay_open = true,
+                ("no-dn-search", None) => options.dn_search = false,
+                ("keep-tsig", None) => options.keep_tsig = true,
+                ("blast", None) => options.blast = true,
+
+                _ => self.unknown_options.push(word.into()),
             }
         }
         Ok(())
     }
 }
 
+/// # Environment
+///
+impl ResolvConf {
+    /// Applies the overrides given in the environment.
+    ///
+    /// Like glibc, this considers two environment variables. If
+    /// `LOCALDOMAIN` is set, its value is a whitespace separated list of
+    /// domain names that replaces the search list. If `RES_OPTIONS` is
+    /// set, its value is processed like the arguments of an `options`
+    /// line, overriding the options given in the configuration file.
+    pub fn apply_env(&mut self) {
+        self.apply_env_vars(
+            env::var("LOCALDOMAIN").ok().as_deref(),
+            env::var("RES_OPTIONS").ok().as_deref(),
+        )
+    }
+
+    /// Applies the values of the environment variables.
+    ///
+    /// A `LOCALDOMAIN` with an invalid domain name is ignored.
+    fn apply_env_vars(
+        &mut self,
+        localdomain: Option<&str>,
+        res_options: Option<&str>,
+    ) {
+        if let Some(localdomain) = localdomain {
+            let _ = self.parse_search(localdomain.split_whitespace());
+        }
+        if let Some(res_options) = res_options {
+            let _ = self.parse_options(res_options.split_whitespace());
+        }
+    }
+}
+
 //--- Default
 
 impl Default for ResolvConf {
@@ -511,8 +758,14 @@ impl Default for ResolvConf {
 impl fmt::Display for ResolvConf {
     #[allow(clippy::cognitive_complexity)]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Servers usually appear once per transport. Write them only once.
+        let mut servers = Vec::new();
         for server in &self.servers {
-            let server = server.addr;
+            if !servers.contains(&server.addr) {
+                servers.push(server.addr)
+            }
+        }
+        for server in servers {
             f.write_str("nameserver ")?;
             if server.port() == 53 {
                 server.ip().fmt(f)?;
@@ -521,13 +774,20 @@ impl fmt::Display for ResolvConf {
             }
             "\n".fmt(f)?;
         }
-        match self.options.search.len().cmp(&1) {
+        // The root is added back when parsing, so we can leave it out.
+        let search: Vec<_> = self
+            .options
+            .search
+            .iter()
+            .filter(|name| !name.is_root())
+            .collect();
+        match search.len().cmp(&1) {
             Ordering::Equal => {
-                writeln!(f, "domain {}", self.options.search[0])?;
+                writeln!(f, "domain {}", search[0])?;
             }
             Ordering::Greater => {
                 "search".fmt(f)?;
-                for name in self.options.search.as_slice() {
+                for name in search {
                     write!(f, " {}", name)?;
                 }
                 "\n".fmt(f)?;
@@ -575,13 +835,13 @@ impl fmt::Display for ResolvConf {
             options.push("no-dn-search".into())
         }
         if self.options.use_inet6 {
-            options.push("use-inet6".into())
+            options.push("inet6".into())
         }
         if self.options.rotate {
             options.push("rotate".into())
         }
         if self.options.no_check_name {
-            options.push("no-check-name".into())
+            options.push("no-check-names".into())
         }
         if self.options.keep_tsig {
             options.push("keep-tsig".into())
@@ -590,13 +850,22 @@ impl fmt::Display for ResolvConf {
             options.push("blast".into())
         }
         if self.options.use_bstring {
-            options.push("use-bstring".into())
+            options.push("ip6-bytestring".into())
         }
         if self.options.use_ip6dotint {
-            options.push("ip6dotint".into())
+            options.push("ip6-dotint".into())
         }
         if self.options.use_edns0 {
-            options.push("use-edns0".into())
+            options.push("edns0".into())
+        }
+        if self.options.trust_ad {
+            options.push("trust-ad".into())
+        }
+        if self.options.no_reload {
+            options.push("no-reload".into())
+        }
+        if self.options.no_aaaa {
+            options.push("no-aaaa".into())
         }
         if self.options.single_request {
             options.push("single-request".into())
@@ -607,6 +876,7 @@ impl fmt::Display for ResolvConf {
         if self.options.no_tld_query {
             options.push("no-tld-query".into())
         }
+        options.extend(self.unknown_options.iter().cloned());
 
         if !options.is_empty() {
             "options".fmt(f)?;
@@ -773,4 +1043,78 @@ mod test {
         assert!(conf.options.use_vc);
         assert_eq!(conf.options.ndots, 122);
     }
+
+    fn parse(data: &str) -> ResolvConf {
+        let mut conf = ResolvConf::new();
+        conf.parse(&mut io::Cursor::new(data)).unwrap();
+        conf
+    }
+
+    #[test]
+    fn parse_options() {
+        let conf = parse(
+            "nameserver 192.0.2.1\n\
+             nameserver [2001:db8::1]:5353\n\
+             options ndots:20 timeout:3 attempts:4 edns0 trust-ad\n\
+             options no-reload no-aaaa rotate bogus timeout:x\n",
+        );
+        assert_eq!(conf.servers.len(), 4);
+        assert_eq!(
+            conf.servers[2].addr,
+            "[2001:db8::1]:5353".parse().unwrap()
+        );
+        assert_eq!(conf.options.ndots, 20);
+        assert_eq!(conf.options.timeout, Duration::from_secs(3));
+        assert_eq!(conf.options.attempts, 4);
+        assert!(conf.options.use_edns0);
+        assert!(conf.options.trust_ad);
+        assert!(conf.options.no_reload);
+        assert!(conf.options.no_aaaa);
+        assert!(conf.options.rotate);
+        assert_eq!(conf.unknown_options, ["bogus", "timeout:x"]);
+    }
+
+    #[test]
+    fn env_overrides() {
+        let mut conf = parse(
+            "search example.com\n\
+             options ndots:2 attempts:3\n",
+        );
+        conf.apply_env_vars(
+            Some("example.net example.org"),
+            Some("ndots:4 use-vc"),
+        );
+        let search: Vec<_> = conf
+            .options
+            .search
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(search, ["example.net", "example.org", ""]);
+        assert_eq!(conf.options.ndots, 4);
+        assert_eq!(conf.options.attempts, 3);
+        assert!(conf.options.use_vc);
+    }
+
+    #[test]
+    fn display() {
+        let data = "nameserver 192.0.2.1\n\
+                    nameserver [2001:db8::1]:5353\n\
+                    search example.com example.net\n\
+                    options ndots:3 timeout:2 attempts:4 use-vc \
+                    no-recurse inet6 rotate no-check-names edns0 \
+                    trust-ad no-reload bogus\n";
+        let conf = parse(data);
+        let written = conf.to_string();
+        let reparsed = parse(&written);
+        assert_eq!(reparsed.to_string(), written);
+        assert_eq!(
+            written,
+            "nameserver 192.0.2.1\n\
+             nameserver [2001:db8::1]:5353\n\
+             search example.com example.net\n\
+             options ndots:3 timeout:2 attempts:4 use-vc no-recurse \
+             inet6 rotate no-check-names edns0 trust-ad no-reload bogus\n"
+        );
+    }
 }
@@ -9,44 +9,68 @@
 //! The main type is [`StubResolver`] that implements the [`Resolver`] trait
 //! and thus can be used with the various lookup functions.
 
+use self::cache::AnswerCache;
 use self::conf::{
-    ResolvConf, ResolvOptions, SearchSuffix, ServerConf, Transport,
+    QueryStrategy, ResolvConf, ResolvOptions, SearchSuffix, ServerConf,
+    Transport,
 };
-use crate::base::iana::Rcode;
+use self::observer::QueryObserver;
+use self::upstream::{NetUpstream, Upstream};
+use crate::base::iana::{OptRcode, Rcode, Rtype};
 use crate::base::message::Message;
 use crate::base::message_builder::{
     AdditionalBuilder, MessageBuilder, StreamTarget,
 };
 use crate::base::name::{ToDname, ToRelativeDname};
 use crate::base::octets::Octets512;
+use crate::base::opt::rfc7871::ClientSubnet;
+use crate::base::opt::rfc7873::{Cookie, ServerCookie};
+use crate::base::opt::rfc8914::ExtendedError;
 use crate::base::question::Question;
+use crate::resolv::hosts::HostsFile;
 use crate::resolv::lookup::addr::{lookup_addr, FoundAddrs};
 use crate::resolv::lookup::host::{lookup_host, search_host, FoundHosts};
 use crate::resolv::lookup::srv::{lookup_srv, FoundSrvs, SrvError};
 use crate::resolv::resolver::{Resolver, SearchNames};
+use crate::resolv::runtime::Runtime;
 use bytes::Bytes;
+use futures::future::{select, Either};
+use futures::stream::{FuturesUnordered, StreamExt};
 use std::boxed::Box;
 use std::future::Future;
 use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::string::{String, ToString};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use std::vec::Vec;
-use std::{io, ops};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpStream, UdpSocket};
+use std::{error, fmt, fs, io, ops};
 #[cfg(feature = "resolv-sync")]
 use tokio::runtime;
-use tokio::time::timeout;
 
-//------------ Sub-modules ---------------------------------------------------
+//------------ Module Configuration ------------------------------------------
 
-pub mod conf;
+/// How often a watched configuration file is checked for changes.
+///
+/// The file is checked when a query starts but at most once during this
+/// interval.
+const CONF_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 
-//------------ Module Configuration ------------------------------------------
+/// How long failures of a server are held against it.
+///
+/// In adaptive mode, servers that have failed within this time are asked
+/// only after all other servers.
+const FAILURE_HOLD_DOWN: Duration = Duration::from_secs(30);
+
+//------------ Sub-modules ---------------------------------------------------
 
-/// How many times do we try a new random port if we get ‘address in use.’
-const RETRY_RANDOM_PORT: usize = 10;
+mod cache;
+pub mod conf;
+pub mod observer;
+pub mod sync;
+pub mod upstream;
 
 //------------ StubResolver --------------------------------------------------
 
@@ -64,58 +88,188 @@ const RETRY_RANDOM_PORT: usize = 10;
 /// you can do so simply by using the [`run()`] or [`run_with_conf()`]
 /// associated functions.
 ///
+/// Requests are sent to the upstream servers over the network by default.
+/// The sockets and timers for this are provided by the [default
+/// runtime][DefaultRuntime] which is Tokio unless its feature has been
+/// disabled. You can use a different runtime through [`with_runtime()`].
+/// You can replace the code exchanging messages with the servers by
+/// providing your own [`Upstream`] to [`with_upstream()`]. In particular,
+/// this allows testing code using the resolver with a
+/// [`MockUpstream`][upstream::MockUpstream].
+///
 /// [`new()`]: #method.new
 /// [`from_conf()`]: #method.from_conf
+/// [`with_runtime()`]: #method.with_runtime
+/// [DefaultRuntime]: crate::resolv::runtime::DefaultRuntime
+/// [`with_upstream()`]: #method.with_upstream
 /// [`query()`]: #method.query
 /// [`run()`]: #method.run
 /// [`run_with_conf()`]: #method.run_with_conf
 #[derive(Clone, Debug)]
 pub struct StubResolver {
-    /// Preferred servers.
-    preferred: ServerList,
+    /// The servers, options, and hosts file shared by all clones.
+    core: Arc<SharedCore>,
 
-    /// Streaming servers.
-    stream: ServerList,
-
-    /// Resolver options.
-    options: ResolvOptions,
+    /// The upstream exchanging messages with the servers.
+    upstream: Arc<dyn Upstream>,
 }
 
 impl StubResolver {
     /// Creates a new resolver using the system’s default configuration.
+    ///
+    /// The resolver uses the [default
+    /// runtime][crate::resolv::runtime::DefaultRuntime].
+    #[cfg(any(
+        feature = "resolv-tokio",
+        feature = "resolv-async-std",
+        feature = "resolv-smol"
+    ))]
     pub fn new() -> Self {
-        Self::from_conf(ResolvConf::default())
+        let res = Self::from_conf(ResolvConf::default());
+        res.core.watch(conf::DEFAULT_PATH.into(), false);
+        res
     }
 
     /// Creates a new resolver using the given configuraiton.
+    ///
+    /// The resolver uses the [default
+    /// runtime][crate::resolv::runtime::DefaultRuntime].
+    #[cfg(any(
+        feature = "resolv-tokio",
+        feature = "resolv-async-std",
+        feature = "resolv-smol"
+    ))]
     pub fn from_conf(conf: ResolvConf) -> Self {
+        Self::with_upstream(conf, NetUpstream::new())
+    }
+
+    /// Creates a new resolver using the given configuration and runtime.
+    pub fn with_runtime(
+        conf: ResolvConf,
+        runtime: impl Runtime + 'static,
+    ) -> Self {
+        Self::with_upstream(conf, NetUpstream::with_runtime(runtime))
+    }
+
+    /// Creates a new resolver using the given configuration and upstream.
+    ///
+    /// All exchanges with the servers given in `conf` will be made through
+    /// `upstream`.
+    pub fn with_upstream(
+        conf: ResolvConf,
+        upstream: impl Upstream + 'static,
+    ) -> Self {
         StubResolver {
-            preferred: ServerList::from_conf(&conf, |s| {
-                s.transport.is_preferred()
-            }),
-            stream: ServerList::from_conf(&conf, |s| s.transport.is_stream()),
-            options: conf.options,
+            core: Arc::new(SharedCore::new(conf)),
+            upstream: Arc::new(upstream),
         }
     }
 
-    pub fn options(&self) -> &ResolvOptions {
-        &self.options
+    /// Returns the current options of the resolver.
+    pub fn options(&self) -> ResolvOptions {
+        self.core.get().options.clone()
+    }
+
+    /// Replaces the configuration of the resolver.
+    ///
+    /// The new configuration is used by this resolver and all its clones
+    /// for all queries started afterwards. Queries already in progress
+    /// finish with the old configuration. The state kept for the servers
+    /// and the cache are reset, the observer is kept.
+    pub fn set_conf(&self, conf: ResolvConf) {
+        self.core.set_conf(conf)
+    }
+
+    /// Reloads the configuration whenever the file at `path` changes.
+    ///
+    /// The file is read right away. Afterwards, it is checked at the start
+    /// of a query, but at most once every five seconds, and read again
+    /// whenever its modification time or size have changed. It is
+    /// processed like [`ResolvConf::from_file`] does, i.e., the overrides
+    /// from the environment are applied. The settings that can’t be given
+    /// in the file – the hosts file, the query strategy, cookies, the
+    /// client subnet, and the cache size – are kept from the configuration
+    /// the resolver was created with or last given via [`set_conf()`]. If
+    /// the file can’t be read or parsed, the resolver keeps its current
+    /// configuration. Once a configuration with the `no_reload` option is
+    /// in use, the file is not checked anymore.
+    ///
+    /// A resolver created via [`new()`] watches the system’s configuration
+    /// file already.
+    ///
+    /// [`new()`]: #method.new
+    /// [`set_conf()`]: #method.set_conf
+    pub fn watch_conf_file<P: Into<PathBuf>>(&self, path: P) {
+        self.core.watch(path.into(), true)
+    }
+
+    /// Sets the observer to report the resolver’s activity to.
+    ///
+    /// The observer is informed about every exchange with a server and
+    /// every answer taken from the cache. Like the configuration, it is
+    /// shared with all clones of the resolver, so setting it replaces the
+    /// observer of all clones. A
+    /// [`MetricsObserver`][observer::MetricsObserver] collects statistics
+    /// for each server.
+    pub fn set_observer(&self, observer: impl QueryObserver + 'static) {
+        self.core.set_observer(Arc::new(observer))
     }
 
     pub async fn query<N: ToDname, Q: Into<Question<N>>>(
         &self,
         question: Q,
     ) -> Result<Answer, io::Error> {
-        Query::new(self)?
-            .run(Query::create_message(question.into()))
+        self.query_message(Query::create_message(question.into()), None)
             .await
     }
 
+    /// Queries for `question` using the given EDNS Client Subnet option.
+    ///
+    /// The option replaces the `client_subnet` of the resolver’s options
+    /// for this query. Use [`ClientSubnet::from_addr`] to derive the option
+    /// from a client’s address or [`ClientSubnet::opt_out`] to ask the
+    /// servers not to use any address.
+    pub async fn query_with_subnet<N: ToDname, Q: Into<Question<N>>>(
+        &self,
+        question: Q,
+        subnet: ClientSubnet,
+    ) -> Result<Answer, io::Error> {
+        self.query_message(
+            Query::create_message(question.into()),
+            Some(subnet),
+        )
+        .await
+    }
+
+    /// Answers a query message.
+    ///
+    /// If `subnet` is `None`, the client subnet from the options is used.
     async fn query_message(
         &self,
         message: QueryMessage,
+        subnet: Option<ClientSubnet>,
     ) -> Result<Answer, io::Error> {
-        Query::new(self)?.run(message).await
+        let core = self.core.get();
+        let subnet = subnet.or(core.options.client_subnet);
+        if let Some(answer) = core.hosts_answer(&message) {
+            return Ok(answer);
+        }
+        let request = core.no_aaaa_request(&message);
+        let query = request.as_ref().unwrap_or(&message);
+        let answer = match core.cached_answer(query, subnet.as_ref()) {
+            Some(answer) => answer,
+            None => {
+                let answer = Query::with_core(self, core.clone(), subnet)
+                    .run(query.clone())
+                    .await?;
+                core.cache_answer(query, subnet.as_ref(), &answer);
+                answer
+            }
+        };
+        match request {
+            Some(_) => no_aaaa_answer(&message, answer),
+            None => Ok(answer),
+        }
     }
 }
 
@@ -164,6 +318,11 @@ impl StubResolver {
     /// The only argument is a closure taking a reference to a `StubResolver`
     /// and returning a future. Whatever that future resolves to will be
     /// returned.
+    ///
+    /// The function creates a new runtime and therefore panics if called
+    /// from within an async runtime. If you don’t need the asynchronous
+    /// resolver, the [`SyncStubResolver`][sync::SyncStubResolver] avoids
+    /// the runtime altogether.
     pub fn run<R, F>(op: F) -> R::Output
     where
         R: Future + Send + 'static,
@@ -194,6 +353,11 @@ impl StubResolver {
     }
 }
 
+#[cfg(any(
+    feature = "resolv-tokio",
+    feature = "resolv-async-std",
+    feature = "resolv-smol"
+))]
 impl Default for StubResolver {
     fn default() -> Self {
         Self::new()
@@ -212,7 +376,7 @@ impl<'a> Resolver for &'a StubResolver {
         Q: Into<Question<N>>,
     {
         let message = Query::create_message(question.into());
-        Box::pin(self.query_message(message))
+        Box::pin(self.query_message(message, None))
     }
 }
 
@@ -228,11 +392,496 @@ impl<'a> SearchNames for &'a StubResolver {
     }
 }
 
+//------------ ResolverCore --------------------------------------------------
+
+/// The parts of a resolver independent of how messages are exchanged.
+///
+/// This is shared by the asynchronous [`StubResolver`] and the blocking
+/// [`SyncStubResolver`][sync::SyncStubResolver].
+#[derive(Clone, Debug)]
+struct ResolverCore {
+    /// Preferred servers.
+    preferred: ServerList,
+
+    /// Streaming servers.
+    stream: ServerList,
+
+    /// Resolver options.
+    options: ResolvOptions,
+
+    /// The hosts file to consult before querying, if any.
+    hosts: Option<Arc<HostsFile>>,
+
+    /// The cache for answers, if enabled.
+    cache: Option<Arc<AnswerCache>>,
+
+    /// The observer to report to, if any.
+    observer: Option<Arc<dyn QueryObserver>>,
+}
+
+impl ResolverCore {
+    fn from_conf(conf: ResolvConf) -> Self {
+        ResolverCore {
+            preferred: ServerList::from_conf(&conf, |s| {
+                s.transport.is_preferred()
+            }),
+            stream: ServerList::from_conf(&conf, |s| s.transport.is_stream()),
+            cache: match conf.options.cache_size {
+                0 => None,
+                size => Some(Arc::new(AnswerCache::new(size))),
+            },
+            options: conf.options,
+            hosts: conf.hosts.map(|path| Arc::new(HostsFile::new(path))),
+            observer: None,
+        }
+    }
+
+    /// Tries to answer a query from the hosts file.
+    fn hosts_answer(&self, message: &QueryMessage) -> Option<Answer> {
+        self.hosts
+            .as_ref()?
+            .get()
+            .answer(&message.as_message())
+            .map(Into::into)
+    }
+
+    /// Tries to answer a query from the cache.
+    fn cached_answer(
+        &self,
+        message: &QueryMessage,
+        subnet: Option<&ClientSubnet>,
+    ) -> Option<Answer> {
+        let answer = self.cache.as_ref()?.get(message, subnet)?;
+        if let Some(observer) = self.observer.as_ref() {
+            if let Ok(question) = message.as_message().sole_question() {
+                if let Ok(qname) = question.qname().to_dname() {
+                    observer.cache_hit(&qname, question.qtype())
+                }
+            }
+        }
+        Some(answer)
+    }
+
+    /// Adds the answer to a query to the cache if it is enabled.
+    fn cache_answer(
+        &self,
+        message: &QueryMessage,
+        subnet: Option<&ClientSubnet>,
+        answer: &Answer,
+    ) {
+        if let Some(cache) = self.cache.as_ref() {
+            cache.insert(message, subnet, answer)
+        }
+    }
+
+    /// Returns the A query to send in place of an AAAA query.
+    ///
+    /// This implements the `no_aaaa` option. Returns `None` if the option
+    /// isn’t set or the query isn’t for AAAA records.
+    fn no_aaaa_request(
+        &self,
+        message: &QueryMessage,
+    ) -> Option<QueryMessage> {
+        if !self.options.no_aaaa {
+            return None;
+        }
+        let message = message.as_message();
+        let question = message.sole_question().ok()?;
+        if question.qtype() != Rtype::Aaaa {
+            return None;
+        }
+        Some(Query::create_message(Question::new(
+            question.qname(),
+            Rtype::A,
+            question.qclass(),
+        )))
+    }
+}
+
+/// Creates the answer to an AAAA query from the answer to its A query.
+///
+/// The answer keeps the response code and the flags of the A answer but
+/// contains no records.
+fn no_aaaa_answer(
+    message: &QueryMessage,
+    answer: Answer,
+) -> Result<Answer, io::Error> {
+    let header = answer.header();
+    let mut builder = MessageBuilder::new_bytes()
+        .start_answer(&message.as_message(), header.rcode())
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "short buf"))?;
+    builder.as_builder_mut().header_mut().set_aa(header.aa());
+    builder.as_builder_mut().header_mut().set_ra(header.ra());
+    builder.as_builder_mut().header_mut().set_ad(header.ad());
+    Ok(Answer {
+        message: builder.into_message(),
+        reports: answer.reports,
+    })
+}
+
+//------------ SharedCore ----------------------------------------------------
+
+/// The resolver core shared by all clones of a resolver.
+///
+/// The core can be replaced at any time. Queries keep using the core that
+/// was current when they started. If a configuration file is watched, the
+/// core is replaced whenever the file has changed.
+#[derive(Debug)]
+struct SharedCore {
+    /// The current core and the watched file.
+    state: Mutex<SharedState>,
+}
+
+#[derive(Debug)]
+struct SharedState {
+    /// The current core.
+    core: Arc<ResolverCore>,
+
+    /// The configuration given by the user.
+    ///
+    /// When the watched file is reloaded, the settings that can’t appear
+    /// in the file are taken from here.
+    conf: ResolvConf,
+
+    /// The configuration file to watch, if any.
+    file: Option<WatchedFile>,
+}
+
+#[derive(Debug)]
+struct WatchedFile {
+    /// The path of the file.
+    path: PathBuf,
+
+    /// The modification time and size of the file when last read.
+    stamp: Option<(SystemTime, u64)>,
+
+    /// The earliest time to check the file again.
+    next_check: Instant,
+}
+
+impl SharedCore {
+    fn new(conf: ResolvConf) -> Self {
+        SharedCore {
+            state: Mutex::new(SharedState {
+                core: Arc::new(ResolverCore::from_conf(conf.clone())),
+                conf,
+                file: None,
+            }),
+        }
+    }
+
+    /// Returns the current core.
+    ///
+    /// Reloads the configuration file first if it is due to be checked
+    /// and has changed.
+    fn get(&self) -> Arc<ResolverCore> {
+        let mut state = self.state.lock().unwrap();
+        state.check_file();
+        state.core.clone()
+    }
+
+    /// Replaces the core with one for the given configuration.
+    fn set_conf(&self, conf: ResolvConf) {
+        let mut state = self.state.lock().unwrap();
+        state.conf = conf.clone();
+        state.set_core(conf)
+    }
+
+    /// Sets the observer of the current core.
+    fn set_observer(&self, observer: Arc<dyn QueryObserver>) {
+        let mut state = self.state.lock().unwrap();
+        let mut core = ResolverCore::clone(&state.core);
+        core.observer = Some(observer);
+        state.core = Arc::new(core);
+    }
+
+    /// Starts watching the configuration file at `path`.
+    ///
+    /// If `load` is `false`, the current configuration is assumed to
+    /// reflect the current content of the file.
+    fn watch(&self, path: PathBuf, load: bool) {
+        let mut state = self.state.lock().unwrap();
+        let stamp = if load { None } else { file_stamp(&path) };
+        state.file = Some(WatchedFile {
+            path,
+            stamp,
+            next_check: Instant::now() + CONF_CHECK_INTERVAL,
+        });
+        if load {
+            state.reload()
+        }
+    }
+}
+
+impl SharedState {
+    /// Replaces the core with one for the given configuration.
+    fn set_core(&mut self, conf: ResolvConf) {
+        let mut core = ResolverCore::from_conf(conf);
+        core.observer = self.core.observer.clone();
+        self.core = Arc::new(core);
+    }
+
+    /// Reloads the watched file if it is due to be checked and has changed.
+    fn check_file(&mut self) {
+        if self.core.options.no_reload {
+            return;
+        }
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => return,
+        };
+        let now = Instant::now();
+        if now < file.next_check {
+            return;
+        }
+        file.next_check = now + CONF_CHECK_INTERVAL;
+        self.reload()
+    }
+
+    /// Reloads the watched file if it has changed, ignoring `no_reload`.
+    fn reload(&mut self) {
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => return,
+        };
+        let stamp = file_stamp(&file.path);
+        if stamp.is_none() || stamp == file.stamp {
+            return;
+        }
+        file.stamp = stamp;
+        if let Ok(mut conf) = ResolvConf::from_file(&file.path) {
+            conf.keep_programmatic(&self.conf);
+            self.set_core(conf)
+        }
+    }
+}
+
+/// Returns the modification time and size of a file.
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
 //------------ Query ---------------------------------------------------------
 
 pub struct Query<'a> {
-    /// The resolver whose configuration we are using.
-    resolver: &'a StubResolver,
+    /// The upstream to send requests to.
+    upstream: &'a dyn Upstream,
+
+    /// The resolver core to use.
+    core: Arc<ResolverCore>,
+
+    /// The EDNS Client Subnet option to send, if any.
+    subnet: Option<ClientSubnet>,
+}
+
+impl<'a> Query<'a> {
+    pub fn new(resolver: &'a StubResolver) -> Result<Self, io::Error> {
+        let core = resolver.core.get();
+        let subnet = core.options.client_subnet;
+        Ok(Self::with_core(resolver, core, subnet))
+    }
+
+    /// Creates a query sending the given EDNS Client Subnet option.
+    pub fn with_subnet(
+        resolver: &'a StubResolver,
+        subnet: Option<ClientSubnet>,
+    ) -> Result<Self, io::Error> {
+        Ok(Self::with_core(resolver, resolver.core.get(), subnet))
+    }
+
+    fn with_core(
+        resolver: &'a StubResolver,
+        core: Arc<ResolverCore>,
+        subnet: Option<ClientSubnet>,
+    ) -> Self {
+        Query {
+            upstream: resolver.upstream.as_ref(),
+            core,
+            subnet,
+        }
+    }
+
+    pub async fn run(
+        self,
+        message: QueryMessage,
+    ) -> Result<Answer, io::Error> {
+        let run = QueryRun {
+            upstream: self.upstream,
+            attempts: Attempts::new(&self.core, self.subnet)?,
+        };
+        match self.core.options.query_strategy() {
+            QueryStrategy::Sequential | QueryStrategy::Adaptive => {
+                run.run_sequential(message).await
+            }
+            QueryStrategy::Blast => run.run_parallel(message, None).await,
+            QueryStrategy::Staggered(delay) => {
+                run.run_parallel(message, Some(delay)).await
+            }
+        }
+    }
+
+    fn create_message(question: Question<impl ToDname>) -> QueryMessage {
+        let mut message = MessageBuilder::from_target(
+            StreamTarget::new(Octets512::new()).unwrap(),
+        )
+        .unwrap();
+        message.header_mut().set_rd(true);
+        let mut message = message.question();
+        message.push(question).unwrap();
+        message.additional()
+    }
+}
+
+//------------ QueryRun ------------------------------------------------------
+
+/// A query in progress.
+struct QueryRun<'a> {
+    /// The upstream to send requests to.
+    upstream: &'a dyn Upstream,
+
+    /// The state of trying the servers.
+    attempts: Attempts<'a>,
+}
+
+impl<'a> QueryRun<'a> {
+    /// Asks one server after the other until there is an answer.
+    async fn run_sequential(
+        mut self,
+        mut message: QueryMessage,
+    ) -> Result<Answer, io::Error> {
+        loop {
+            let server = self.attempts.prepare(&mut message);
+            let start = Instant::now();
+            let res = self.upstream.query(&server.conf, &message).await;
+            match self.attempts.process(res, start.elapsed()) {
+                Step::Done(res) => return res,
+                Step::GiveUp => return self.attempts.into_error(),
+                Step::Continue => {}
+            }
+        }
+    }
+
+    /// Asks several servers at the same time.
+    ///
+    /// If `delay` is `None`, all servers are asked at once. Otherwise, the
+    /// servers are asked in order, moving on to the next server whenever
+    /// `delay` has passed or a server has failed. The first good answer
+    /// wins. A truncated answer makes us continue sequentially with the
+    /// stream servers.
+    async fn run_parallel(
+        mut self,
+        message: QueryMessage,
+        delay: Option<Duration>,
+    ) -> Result<Answer, io::Error> {
+        loop {
+            let mut waiting = self.attempts.round().into_iter();
+            let mut inflight = FuturesUnordered::new();
+            let mut timer = None;
+            match delay {
+                None => {
+                    for server in waiting.by_ref() {
+                        inflight.push(self.exchange(server, &message));
+                    }
+                }
+                Some(delay) => {
+                    if let Some(server) = waiting.next() {
+                        inflight.push(self.exchange(server, &message));
+                    }
+                    timer = Some(self.upstream.sleep(delay));
+                }
+            }
+
+            loop {
+                // If there are servers waiting, we also wait for the timer
+                // to start the next one. Since a failed server immediately
+                // starts the next one, `inflight` is never empty while
+                // servers are still waiting.
+                let next = match timer.as_mut() {
+                    Some(sleep) if waiting.len() > 0 => {
+                        match select(inflight.next(), sleep).await {
+                            Either::Left((next, _)) => Some(next),
+                            Either::Right(_) => None,
+                        }
+                    }
+                    _ => Some(inflight.next().await),
+                };
+                let (server, res, elapsed) = match next {
+                    Some(Some(next)) => next,
+                    Some(None) => break,
+                    None => {
+                        if let Some(server) = waiting.next() {
+                            inflight.push(self.exchange(server, &message));
+                        }
+                        timer = delay.map(|delay| self.upstream.sleep(delay));
+                        continue;
+                    }
+                };
+                match self.attempts.evaluate(server, res, elapsed) {
+                    Outcome::Answer(answer) => return Ok(answer),
+                    Outcome::Retry => {
+                        inflight.push(self.exchange(server, &message));
+                    }
+                    Outcome::Truncated(answer) => {
+                        if self.attempts.switch_to_stream() {
+                            drop(inflight);
+                            return self.run_sequential(message).await;
+                        } else {
+                            return Ok(answer);
+                        }
+                    }
+                    Outcome::Failed => {
+                        if let Some(server) = waiting.next() {
+                            inflight.push(self.exchange(server, &message));
+                            timer =
+                                delay.map(|delay| self.upstream.sleep(delay));
+                        }
+                    }
+                }
+            }
+
+            if !self.attempts.next_round() {
+                return self.attempts.into_error();
+            }
+        }
+    }
+
+    /// Returns a future asking `server`.
+    fn exchange(
+        &self,
+        server: &'a ServerInfo,
+        message: &QueryMessage,
+    ) -> impl Future<Output = Exchange<'a>> + Send + 'a {
+        let mut message = message.clone();
+        server.prepare_message(
+            &mut message,
+            &self.attempts.core.options,
+            self.attempts.subnet.as_ref(),
+        );
+        let upstream = self.upstream;
+        async move {
+            let start = Instant::now();
+            let res = upstream.query(&server.conf, &message).await;
+            (server, res, start.elapsed())
+        }
+    }
+}
+
+/// The result of asking a server: the server, its response, and how long
+/// it took.
+type Exchange<'a> = (&'a ServerInfo, Result<Answer, io::Error>, Duration);
+
+//------------ Attempts ------------------------------------------------------
+
+/// The state of trying to get an answer from the servers.
+///
+/// This type contains the logic which server to ask next and when to give
+/// up. It is driven by the asynchronous [`Query`] as well as the
+/// [blocking resolver][sync::SyncStubResolver], which differ only in how
+/// they exchange messages with a server.
+struct Attempts<'a> {
+    /// The resolver core whose configuration we are using.
+    core: &'a ResolverCore,
 
     /// Are we still in the preferred server list or have gone streaming?
     preferred: bool,
@@ -243,6 +892,15 @@ pub struct Query<'a> {
     /// The index in the server list we currently trying.
     counter: ServerListCounter,
 
+    /// The EDNS Client Subnet option to send, if any.
+    subnet: Option<ClientSubnet>,
+
+    /// The reports of all servers asked so far.
+    reports: Vec<ServerReport>,
+
+    /// The servers we have already asked again after a BADCOOKIE response.
+    cookie_retries: Vec<(SocketAddr, Transport)>,
+
     /// The preferred error to return.
     ///
     /// Every time we finish a single query, we see if we can update this with
@@ -253,25 +911,31 @@ pub struct Query<'a> {
     error: Result<Answer, io::Error>,
 }
 
-impl<'a> Query<'a> {
-    pub fn new(resolver: &'a StubResolver) -> Result<Self, io::Error> {
+impl<'a> Attempts<'a> {
+    fn new(
+        core: &'a ResolverCore,
+        subnet: Option<ClientSubnet>,
+    ) -> Result<Self, io::Error> {
         let (preferred, counter) =
-            if resolver.options().use_vc || resolver.preferred.is_empty() {
-                if resolver.stream.is_empty() {
+            if core.options.use_vc || core.preferred.is_empty() {
+                if core.stream.is_empty() {
                     return Err(io::Error::new(
                         io::ErrorKind::NotFound,
                         "no servers available",
                     ));
                 }
-                (false, resolver.stream.counter(resolver.options().rotate))
+                (false, core.stream.counter(&core.options))
             } else {
-                (true, resolver.preferred.counter(resolver.options().rotate))
+                (true, core.preferred.counter(&core.options))
             };
-        Ok(Query {
-            resolver,
+        Ok(Attempts {
+            core,
             preferred,
             attempt: 0,
             counter,
+            subnet,
+            reports: Vec::new(),
+            cookie_retries: Vec::new(),
             error: Err(io::Error::new(
                 io::ErrorKind::TimedOut,
                 "all timed out",
@@ -279,77 +943,219 @@ impl<'a> Query<'a> {
         })
     }
 
-    pub async fn run(
-        mut self,
-        mut message: QueryMessage,
-    ) -> Result<Answer, io::Error> {
-        loop {
-            match self.run_query(&mut message).await {
-                Ok(answer) => {
-                    if answer.header().rcode() == Rcode::FormErr
-                        && self.current_server().does_edns()
-                    {
-                        // FORMERR with EDNS: turn off EDNS and try again.
-                        self.current_server().disable_edns();
-                        continue;
-                    } else if answer.header().rcode() == Rcode::ServFail {
-                        // SERVFAIL: go to next server.
-                        self.update_error_servfail(answer);
-                    } else if answer.header().tc()
-                        && self.preferred
-                        && !self.resolver.options().ign_tc
-                    {
-                        // Truncated. If we can, switch to stream transports
-                        // and try again. Otherwise return the truncated
-                        // answer.
-                        if self.switch_to_stream() {
-                            continue;
-                        } else {
-                            return Ok(answer);
-                        }
-                    } else {
-                        // I guess we have an answer ...
-                        return Ok(answer);
-                    }
+    /// Prepares the message for the current server and returns the server.
+    fn prepare(&self, message: &mut QueryMessage) -> &'a ServerInfo {
+        let server = self.current_server();
+        server.prepare_message(
+            message,
+            &self.core.options,
+            self.subnet.as_ref(),
+        );
+        server
+    }
+
+    /// Processes the result of asking the current server.
+    ///
+    /// The `elapsed` argument is the time the exchange took.
+    fn process(
+        &mut self,
+        res: Result<Answer, io::Error>,
+        elapsed: Duration,
+    ) -> Step {
+        match self.evaluate(self.current_server(), res, elapsed) {
+            Outcome::Answer(answer) => Step::Done(Ok(answer)),
+            Outcome::Retry => Step::Continue,
+            Outcome::Truncated(answer) => {
+                // If we can, switch to stream transports and try again.
+                // Otherwise return the truncated answer.
+                if self.switch_to_stream() {
+                    Step::Continue
+                } else {
+                    Step::Done(Ok(answer))
+                }
+            }
+            Outcome::Failed => {
+                if self.next_server() {
+                    Step::Continue
+                } else {
+                    Step::GiveUp
                 }
-                Err(err) => self.update_error(err),
             }
-            if !self.next_server() {
-                return self.error;
+        }
+    }
+
+    /// Evaluates the result of asking `server`.
+    ///
+    /// Updates the server’s statistics and the error to return.
+    fn evaluate(
+        &mut self,
+        server: &ServerInfo,
+        res: Result<Answer, io::Error>,
+        elapsed: Duration,
+    ) -> Outcome {
+        self.reports.push(ServerReport::new(server, &res, elapsed));
+        let outcome = self.judge(server, res, elapsed);
+        if let (Some(observer), Some(report)) =
+            (self.core.observer.as_ref(), self.reports.last())
+        {
+            observer.exchange(report)
+        }
+        outcome
+    }
+
+    /// Decides what the result of asking `server` means.
+    fn judge(
+        &mut self,
+        server: &ServerInfo,
+        res: Result<Answer, io::Error>,
+        elapsed: Duration,
+    ) -> Outcome {
+        let answer = match res {
+            Ok(answer) => answer,
+            Err(err) => {
+                server.record_failure();
+                self.update_error(err);
+                return Outcome::Failed;
+            }
+        };
+        let fresh_cookie = match server.process_cookie(&answer) {
+            Ok(fresh) => fresh,
+            Err(err) => {
+                // Someone else’s cookie: treat the response as spoofed.
+                server.record_failure();
+                self.update_error(err);
+                return Outcome::Failed;
+            }
+        };
+        if !self.subnet_matches(&answer) {
+            // The client subnet of the response doesn’t match the query.
+            server.record_failure();
+            self.update_error(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "client subnet mismatch",
+            ));
+            return Outcome::Failed;
+        }
+        let opt_rcode = answer.opt().map(|opt| opt.rcode(answer.header()));
+        if answer.header().rcode() == Rcode::FormErr && server.does_edns() {
+            // FORMERR with EDNS: turn off EDNS and try again.
+            server.disable_edns();
+            if let Some(report) = self.reports.last_mut() {
+                report.edns_fallback = true
+            }
+            Outcome::Retry
+        } else if opt_rcode == Some(OptRcode::BadCookie) {
+            // BADCOOKIE: try again once if we have learned a new server
+            // cookie, otherwise go to the next server. A server that keeps
+            // changing its cookie would otherwise keep us busy forever.
+            let key = (server.conf.addr, server.conf.transport);
+            if fresh_cookie && !self.cookie_retries.contains(&key) {
+                self.cookie_retries.push(key);
+                Outcome::Retry
+            } else {
+                server.record_failure();
+                self.update_error(io::Error::new(
+                    io::ErrorKind::Other,
+                    "bad cookie",
+                ));
+                Outcome::Failed
+            }
+        } else if answer.header().rcode() == Rcode::ServFail {
+            // SERVFAIL: go to next server.
+            server.record_failure();
+            self.update_error_servfail(answer);
+            Outcome::Failed
+        } else {
+            server.record_success(elapsed);
+            if answer.header().tc()
+                && self.preferred
+                && !self.core.options.ign_tc
+            {
+                Outcome::Truncated(self.with_reports(answer))
+            } else {
+                // I guess we have an answer ...
+                Outcome::Answer(self.with_reports(answer))
+            }
+        }
+    }
+
+    /// Returns whether the client subnet of a response matches the query.
+    ///
+    /// Section 7.3 of RFC 7871 requires the family, source prefix length,
+    /// and address of the option in a response to be equal to those sent.
+    fn subnet_matches(&self, answer: &Answer) -> bool {
+        let response = match answer.opt() {
+            Some(opt) => match opt.iter::<ClientSubnet>().next() {
+                Some(Ok(response)) => response,
+                _ => return true,
+            },
+            None => return true,
+        };
+        match self.subnet {
+            Some(subnet) => {
+                response.addr() == subnet.addr()
+                    && response.source_prefix_len()
+                        == subnet.source_prefix_len()
             }
+            None => true,
         }
     }
 
-    fn create_message(question: Question<impl ToDname>) -> QueryMessage {
-        let mut message = MessageBuilder::from_target(
-            StreamTarget::new(Octets512::new()).unwrap(),
-        )
-        .unwrap();
-        message.header_mut().set_rd(true);
-        let mut message = message.question();
-        message.push(question).unwrap();
-        message.additional()
+    /// Returns the best result after all servers have failed.
+    ///
+    /// An error contains a [`QueryError`] with the servers’ reports.
+    fn into_error(self) -> Result<Answer, io::Error> {
+        match self.error {
+            Ok(answer) => Ok(Answer {
+                reports: self.reports,
+                ..answer
+            }),
+            Err(err) => Err(io::Error::new(
+                err.kind(),
+                QueryError {
+                    message: err.to_string(),
+                    reports: self.reports,
+                },
+            )),
+        }
     }
 
-    async fn run_query(
-        &mut self,
-        message: &mut QueryMessage,
-    ) -> Result<Answer, io::Error> {
-        let server = self.current_server();
-        server.prepare_message(message);
-        server.query(message).await
+    /// Attaches the reports collected so far to an answer.
+    fn with_reports(&self, answer: Answer) -> Answer {
+        Answer {
+            reports: self.reports.clone(),
+            ..answer
+        }
     }
 
-    fn current_server(&self) -> &ServerInfo {
-        let list = if self.preferred {
-            &self.resolver.preferred
+    fn current_list(&self) -> &'a ServerList {
+        if self.preferred {
+            &self.core.preferred
         } else {
-            &self.resolver.stream
-        };
-        self.counter.info(list)
+            &self.core.stream
+        }
+    }
+
+    fn current_server(&self) -> &'a ServerInfo {
+        self.counter.info(self.current_list())
+    }
+
+    /// Returns the servers to ask in the current round in order.
+    fn round(&self) -> Vec<&'a ServerInfo> {
+        let list = self.current_list();
+        let mut counter = self.counter.clone();
+        let mut res = vec![counter.info(list)];
+        while counter.next() {
+            res.push(counter.info(list))
+        }
+        res
     }
 
     fn update_error(&mut self, err: io::Error) {
+        if let Some(report) = self.reports.last_mut() {
+            report.error_kind = Some(err.kind());
+            report.error = Some(err.to_string());
+        }
         // We keep the last error except for timeouts or if we have a servfail
         // answer already. Since we start with a timeout, we still get a that
         // if everything times out.
@@ -363,14 +1169,13 @@ impl<'a> Query<'a> {
     }
 
     fn switch_to_stream(&mut self) -> bool {
-        if !self.preferred {
-            // We already did this.
+        if !self.preferred || self.core.stream.is_empty() {
+            // We already did this or there is nothing to switch to.
             return false;
         }
         self.preferred = false;
         self.attempt = 0;
-        self.counter =
-            self.resolver.stream.counter(self.resolver.options().rotate);
+        self.counter = self.core.stream.counter(&self.core.options);
         true
     }
 
@@ -378,25 +1183,56 @@ impl<'a> Query<'a> {
         if self.counter.next() {
             return true;
         }
+        self.next_round()
+    }
+
+    /// Starts the next round of asking all servers if there is one.
+    fn next_round(&mut self) -> bool {
         self.attempt += 1;
-        if self.attempt >= self.resolver.options().attempts {
+        if self.attempt >= self.core.options.attempts {
             return false;
         }
-        self.counter = if self.preferred {
-            self.resolver
-                .preferred
-                .counter(self.resolver.options().rotate)
-        } else {
-            self.resolver.stream.counter(self.resolver.options().rotate)
-        };
+        self.counter = self.current_list().counter(&self.core.options);
         true
     }
 }
 
+//------------ Step ----------------------------------------------------------
+
+/// What to do after processing the result of asking a server.
+enum Step {
+    /// We have the final result.
+    Done(Result<Answer, io::Error>),
+
+    /// Ask the current or next server.
+    Continue,
+
+    /// All servers have failed, return the best error.
+    GiveUp,
+}
+
+//------------ Outcome -------------------------------------------------------
+
+/// The outcome of asking a single server.
+enum Outcome {
+    /// The server gave a good answer.
+    Answer(Answer),
+
+    /// The server should be asked again.
+    Retry,
+
+    /// The server gave a truncated answer.
+    Truncated(Answer),
+
+    /// The server failed.
+    Failed,
+}
+
 //------------ QueryMessage --------------------------------------------------
 
+/// The message type used for requests to upstream servers.
 // XXX This needs to be re-evaluated if we start adding OPTtions to the query.
-pub(super) type QueryMessage = AdditionalBuilder<StreamTarget<Octets512>>;
+pub type QueryMessage = AdditionalBuilder<StreamTarget<Octets512>>;
 
 //------------ Answer --------------------------------------------------------
 
@@ -407,6 +1243,9 @@ pub(super) type QueryMessage = AdditionalBuilder<StreamTarget<Octets512>>;
 #[derive(Clone)]
 pub struct Answer {
     message: Message<Bytes>,
+
+    /// The reports of all servers asked for this answer.
+    reports: Vec<ServerReport>,
 }
 
 impl Answer {
@@ -422,6 +1261,30 @@ impl Answer {
         self.message.header().tc()
     }
 
+    /// Returns the extended DNS errors contained in the answer.
+    ///
+    /// These are the Extended DNS Error options of RFC 8914 found in the
+    /// OPT record of the answer message.
+    pub fn extended_errors(&self) -> Vec<ExtendedError<Bytes>> {
+        match self.message.opt() {
+            Some(opt) => opt
+                .iter::<ExtendedError<Bytes>>()
+                .filter_map(Result::ok)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the reports of all servers asked for this answer.
+    ///
+    /// The reports are in the order the servers’ results were received,
+    /// so the last report is for the server that gave the answer. The list
+    /// is empty for answers that didn’t come from a server, such as
+    /// answers from the hosts file.
+    pub fn reports(&self) -> &[ServerReport] {
+        &self.reports
+    }
+
     pub fn into_message(self) -> Message<Bytes> {
         self.message
     }
@@ -429,10 +1292,224 @@ impl Answer {
 
 impl From<Message<Bytes>> for Answer {
     fn from(message: Message<Bytes>) -> Self {
-        Answer { message }
+        Answer {
+            message,
+            reports: Vec::new(),
+        }
+    }
+}
+
+//------------ ServerReport --------------------------------------------------
+
+/// What happened when asking a single server.
+///
+/// Reports are collected for every exchange with a server while answering
+/// a query. They are available via [`Answer::reports`] for an answer and
+/// via [`QueryError::reports`] if the query failed.
+#[derive(Clone, Debug)]
+pub struct ServerReport {
+    /// The address of the server.
+    server: SocketAddr,
+
+    /// The transport used.
+    transport: Transport,
+
+    /// When the exchange started.
+    start: Instant,
+
+    /// How long the exchange took.
+    duration: Duration,
+
+    /// The rcode of the response if there was one.
+    rcode: Option<OptRcode>,
+
+    /// Whether the response was truncated.
+    truncated: bool,
+
+    /// Whether the response made us stop using EDNS with the server.
+    edns_fallback: bool,
+
+    /// The extended DNS errors contained in the response.
+    extended_errors: Vec<ExtendedError<Bytes>>,
+
+    /// The kind of error if the exchange failed.
+    error_kind: Option<io::ErrorKind>,
+
+    /// Why the exchange failed if it did.
+    error: Option<String>,
+}
+
+impl ServerReport {
+    /// Creates a report for an exchange with `server`.
+    ///
+    /// The exchange took `elapsed` and has just finished.
+    fn new(
+        server: &ServerInfo,
+        res: &Result<Answer, io::Error>,
+        elapsed: Duration,
+    ) -> Self {
+        let now = Instant::now();
+        let (rcode, truncated, extended_errors) = match res {
+            Ok(answer) => (
+                Some(match answer.opt() {
+                    Some(opt) => opt.rcode(answer.header()),
+                    None => answer.header().rcode().into(),
+                }),
+                answer.header().tc(),
+                answer.extended_errors(),
+            ),
+            Err(_) => (None, false, Vec::new()),
+        };
+        ServerReport {
+            server: server.conf.addr,
+            transport: server.conf.transport,
+            start: now.checked_sub(elapsed).unwrap_or(now),
+            duration: elapsed,
+            rcode,
+            truncated,
+            edns_fallback: false,
+            extended_errors,
+            error_kind: None,
+            error: None,
+        }
+    }
+
+    /// Returns the address of the server.
+    pub fn server(&self) -> SocketAddr {
+        self.server
+    }
+
+    /// Returns the transport used to ask the server.
+    pub fn transport(&self) -> Transport {
+        self.transport
+    }
+
+    /// Returns when the exchange with the server started.
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+
+    /// Returns how long the exchange with the server took.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns the rcode of the server’s response.
+    ///
+    /// Returns `None` if no response was received.
+    pub fn rcode(&self) -> Option<OptRcode> {
+        self.rcode
+    }
+
+    /// Returns whether the server’s response was truncated.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Returns whether the response made the resolver stop using EDNS.
+    ///
+    /// This happens if a server answers a query with EDNS with FORMERR.
+    /// The query is then repeated without EDNS.
+    pub fn is_edns_fallback(&self) -> bool {
+        self.edns_fallback
+    }
+
+    /// Returns the extended DNS errors contained in the response.
+    pub fn extended_errors(&self) -> &[ExtendedError<Bytes>] {
+        &self.extended_errors
+    }
+
+    /// Returns the kind of error if asking the server failed.
+    ///
+    /// Returns `None` if the exchange succeeded or the server failed only
+    /// by the rcode of its response.
+    pub fn error_kind(&self) -> Option<io::ErrorKind> {
+        self.error_kind
+    }
+
+    /// Returns a description of why asking the server failed.
+    ///
+    /// Returns `None` if the exchange succeeded or the server failed only
+    /// by the rcode of its response.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+impl fmt::Display for ServerReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let transport = match self.transport {
+            Transport::Udp => "UDP",
+            Transport::Tcp => "TCP",
+        };
+        write!(f, "{} ({})", self.server, transport)?;
+        if let Some(rcode) = self.rcode {
+            write!(f, ": {}", rcode)?;
+        }
+        for error in &self.extended_errors {
+            write!(f, ", {}", error)?;
+        }
+        if let Some(error) = self.error.as_ref() {
+            write!(f, ": {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ QueryError ----------------------------------------------------
+
+/// The error of a query that failed on all servers.
+///
+/// When a query fails, the resolver returns an `io::Error` of the same kind
+/// as the last relevant error encountered. This type is contained in that
+/// error and provides the reports of all servers that were asked. Use
+/// [`QueryError::from_io_error`] to get it from the `io::Error`.
+#[derive(Debug)]
+pub struct QueryError {
+    /// The description of the underlying error.
+    message: String,
+
+    /// The reports of all servers asked.
+    reports: Vec<ServerReport>,
+}
+
+impl QueryError {
+    /// Returns the query error contained in an `io::Error` if there is one.
+    pub fn from_io_error(err: &io::Error) -> Option<&Self> {
+        err.get_ref()?.downcast_ref()
+    }
+
+    /// Returns the reports of all servers asked.
+    pub fn reports(&self) -> &[ServerReport] {
+        &self.reports
+    }
+
+    /// Returns all extended DNS errors received from the servers.
+    pub fn extended_errors(
+        &self,
+    ) -> impl Iterator<Item = &ExtendedError<Bytes>> + '_ {
+        self.reports
+            .iter()
+            .flat_map(|report| report.extended_errors.iter())
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)?;
+        for (i, report) in self.reports.iter().enumerate() {
+            f.write_str(if i == 0 { " (" } else { "; " })?;
+            report.fmt(f)?;
+        }
+        if !self.reports.is_empty() {
+            f.write_str(")")?;
+        }
+        Ok(())
     }
 }
 
+impl error::Error for QueryError {}
+
 //------------ ServerInfo ----------------------------------------------------
 
 #[derive(Clone, Debug)]
@@ -444,6 +1521,15 @@ struct ServerInfo {
     ///
     /// We start out with assuming it does and unset it if we get a FORMERR.
     edns: Arc<AtomicBool>,
+
+    /// The statistics on how well the server has been answering.
+    stats: Arc<Mutex<ServerStats>>,
+
+    /// The client cookie we send to this server.
+    client_cookie: [u8; 8],
+
+    /// The last server cookie we received from this server.
+    server_cookie: Arc<Mutex<Option<ServerCookie>>>,
 }
 
 impl ServerInfo {
@@ -455,131 +1541,108 @@ impl ServerInfo {
         self.edns.store(false, Ordering::Relaxed);
     }
 
-    pub fn prepare_message(&self, query: &mut QueryMessage) {
+    /// Records a response received after `rtt`.
+    pub fn record_success(&self, rtt: Duration) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.srtt = Some(match stats.srtt {
+            Some(srtt) => (srtt * 7 + rtt) / 8,
+            None => rtt,
+        });
+        stats.failures = 0;
+    }
+
+    /// Records that asking the server has failed.
+    pub fn record_failure(&self) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.failures = stats.failures.saturating_add(1);
+        stats.last_failure = Some(Instant::now());
+    }
+
+    /// Returns a key for ordering servers from best to worst.
+    ///
+    /// Servers with recent failures come after those without. Within each
+    /// group, servers are ordered by their smoothed round-trip time with
+    /// servers we haven’t heard from yet coming first so they get measured.
+    pub fn rank(&self, now: Instant) -> (u32, Duration) {
+        let stats = self.stats.lock().unwrap();
+        let failures = match stats.last_failure {
+            Some(last) if now.duration_since(last) < FAILURE_HOLD_DOWN => {
+                stats.failures
+            }
+            _ => 0,
+        };
+        (failures, stats.srtt.unwrap_or_default())
+    }
+
+    pub fn prepare_message(
+        &self,
+        query: &mut QueryMessage,
+        options: &ResolvOptions,
+        subnet: Option<&ClientSubnet>,
+    ) {
+        // TLSA records are only useful if validated, so we always ask for
+        // the AD bit for them. See `lookup_dane`.
+        let tlsa = query
+            .as_message()
+            .first_question()
+            .map_or(false, |question| question.qtype() == Rtype::Tlsa);
         query.rewind();
+        query.header_mut().set_ad(options.trust_ad || tlsa);
         if self.does_edns() {
             query
                 .opt(|opt| {
                     opt.set_udp_payload_size(self.conf.udp_payload_size);
+                    if options.use_cookies {
+                        match *self.server_cookie.lock().unwrap() {
+                            Some(server) => {
+                                opt.push(&Cookie::with_server(
+                                    self.client_cookie,
+                                    server,
+                                ))?
+                            }
+                            None => {
+                                opt.push(&Cookie::new(self.client_cookie))?
+                            }
+                        }
+                    }
+                    if let Some(subnet) = subnet {
+                        opt.push(subnet)?
+                    }
                     Ok(())
                 })
                 .unwrap();
         }
     }
 
-    pub async fn query(
-        &self,
-        query: &QueryMessage,
-    ) -> Result<Answer, io::Error> {
-        let res = match self.conf.transport {
-            Transport::Udp => {
-                timeout(
-                    self.conf.request_timeout,
-                    Self::udp_query(
-                        query,
-                        self.conf.addr,
-                        self.conf.recv_size,
-                    ),
-                )
-                .await
-            }
-            Transport::Tcp => {
-                timeout(
-                    self.conf.request_timeout,
-                    Self::tcp_query(query, self.conf.addr),
-                )
-                .await
-            }
+    /// Processes the cookie option of a response.
+    ///
+    /// Remembers the server cookie if the response has one. Returns an
+    /// error if the response carries a client cookie other than ours,
+    /// which means it wasn’t meant for us. Otherwise returns whether the
+    /// server cookie differs from the one we had before.
+    pub fn process_cookie(&self, answer: &Answer) -> Result<bool, io::Error> {
+        let opt = match answer.opt() {
+            Some(opt) => opt,
+            None => return Ok(false),
         };
-        match res {
-            Ok(Ok(answer)) => Ok(answer),
-            Ok(Err(err)) => Err(err),
-            Err(_) => Err(io::Error::new(
-                io::ErrorKind::TimedOut,
-                "request timed out",
-            )),
-        }
-    }
-
-    pub async fn tcp_query(
-        query: &QueryMessage,
-        addr: SocketAddr,
-    ) -> Result<Answer, io::Error> {
-        let mut sock = TcpStream::connect(&addr).await?;
-        sock.write_all(query.as_target().as_stream_slice()).await?;
-
-        // This loop can be infinite because we have a timeout on this whole
-        // thing, anyway.
-        loop {
-            let mut buf = Vec::new();
-            let len = sock.read_u16().await? as u64;
-            AsyncReadExt::take(&mut sock, len)
-                .read_to_end(&mut buf)
-                .await?;
-            if let Ok(answer) = Message::from_octets(buf.into()) {
-                if answer.is_answer(&query.as_message()) {
-                    return Ok(answer.into());
-                }
-            // else try with the next message.
-            } else {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "short buf",
-                ));
-            }
-        }
-    }
-
-    pub async fn udp_query(
-        query: &QueryMessage,
-        addr: SocketAddr,
-        recv_size: usize,
-    ) -> Result<Answer, io::Error> {
-        let sock = Self::udp_bind(addr.is_ipv4()).await?;
-        sock.connect(addr).await?;
-        let sent = sock.send(query.as_target().as_dgram_slice()).await?;
-        if sent != query.as_target().as_dgram_slice().len() {
+        let cookie = match opt.iter::<Cookie>().next() {
+            Some(Ok(cookie)) => cookie,
+            _ => return Ok(false),
+        };
+        if cookie.client() != self.client_cookie {
             return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "short UDP send",
+                io::ErrorKind::InvalidData,
+                "client cookie mismatch",
             ));
         }
-        loop {
-            let mut buf = vec![0; recv_size]; // XXX use uninit'ed mem here.
-            let len = sock.recv(&mut buf).await?;
-            buf.truncate(len);
-
-            // We ignore garbage since there is a timer on this whole thing.
-            let answer = match Message::from_octets(buf.into()) {
-                Ok(answer) => answer,
-                Err(_) => continue,
-            };
-            if !answer.is_answer(&query.as_message()) {
-                continue;
-            }
-            return Ok(answer.into());
-        }
-    }
-
-    async fn udp_bind(v4: bool) -> Result<UdpSocket, io::Error> {
-        let mut i = 0;
-        loop {
-            let local: SocketAddr = if v4 {
-                ([0u8; 4], 0).into()
-            } else {
-                ([0u16; 8], 0).into()
-            };
-            match UdpSocket::bind(&local).await {
-                Ok(sock) => return Ok(sock),
-                Err(err) => {
-                    if i == RETRY_RANDOM_PORT {
-                        return Err(err);
-                    } else {
-                        i += 1
-                    }
-                }
-            }
-        }
+        let server = match cookie.server() {
+            Some(server) => *server,
+            None => return Ok(false),
+        };
+        let mut stored = self.server_cookie.lock().unwrap();
+        let fresh = *stored != Some(server);
+        *stored = Some(server);
+        Ok(fresh)
     }
 }
 
@@ -588,6 +1651,9 @@ impl From<ServerConf> for ServerInfo {
         ServerInfo {
             conf,
             edns: Arc::new(AtomicBool::new(true)),
+            stats: Default::default(),
+            client_cookie: rand::random(),
+            server_cookie: Default::default(),
         }
     }
 }
@@ -598,6 +1664,21 @@ impl<'a> From<&'a ServerConf> for ServerInfo {
     }
 }
 
+//------------ ServerStats ---------------------------------------------------
+
+/// Statistics on how well a server has been answering.
+#[derive(Clone, Debug, Default)]
+struct ServerStats {
+    /// The smoothed round-trip time if there has been a response yet.
+    srtt: Option<Duration>,
+
+    /// The number of failures since the last good response.
+    failures: u32,
+
+    /// When the last failure happened.
+    last_failure: Option<Instant>,
+}
+
 //------------ ServerList ----------------------------------------------------
 
 #[derive(Clone, Debug)]
@@ -637,9 +1718,12 @@ impl ServerList {
         self.servers.is_empty()
     }
 
-    pub fn counter(&self, rotate: bool) -> ServerListCounter {
+    pub fn counter(&self, options: &ResolvOptions) -> ServerListCounter {
+        if options.query_strategy() == QueryStrategy::Adaptive {
+            return ServerListCounter::adaptive(self);
+        }
         let res = ServerListCounter::new(self);
-        if rotate {
+        if options.rotate {
             self.rotate()
         }
         res
@@ -677,12 +1761,21 @@ impl ops::Deref for ServerList {
 struct ServerListCounter {
     cur: usize,
     end: usize,
+
+    /// The indexes of the servers in the order to ask them.
+    ///
+    /// If this is `None`, the servers are asked in list order.
+    order: Option<Vec<usize>>,
 }
 
 impl ServerListCounter {
     fn new(list: &ServerList) -> Self {
         if list.servers.is_empty() {
-            return ServerListCounter { cur: 0, end: 0 };
+            return ServerListCounter {
+                cur: 0,
+                end: 0,
+                order: None,
+            };
         }
 
         // We modulo the start value here to prevent hick-ups towards the
@@ -691,6 +1784,19 @@ impl ServerListCounter {
         ServerListCounter {
             cur: start,
             end: start + list.servers.len(),
+            order: None,
+        }
+    }
+
+    /// Creates a counter ordering the servers by their statistics.
+    fn adaptive(list: &ServerList) -> Self {
+        let now = Instant::now();
+        let mut order: Vec<_> = (0..list.servers.len()).collect();
+        order.sort_by_cached_key(|&idx| list.servers[idx].rank(now));
+        ServerListCounter {
+            cur: 0,
+            end: order.len(),
+            order: Some(order),
         }
     }
 
@@ -706,7 +1812,10 @@ impl ServerListCounter {
     }
 
     pub fn info<'a>(&self, list: &'a ServerList) -> &'a ServerInfo {
-        &list[self.cur % list.servers.len()]
+        match self.order {
+            Some(ref order) => &list[order[self.cur]],
+            None => &list[self.cur % list.servers.len()],
+        }
     }
 }
 
@@ -765,7 +1874,9 @@ impl<'a> Iterator for SearchIter<'a> {
     type Item = SearchSuffix;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(res) = self.resolver.options().search.get(self.pos) {
+        if let Some(res) =
+            self.resolver.core.get().options.search.get(self.pos)
+        {
             self.pos += 1;
             Some(res.clone())
         } else {
@@ -773,3 +1884,427 @@ impl<'a> Iterator for SearchIter<'a> {
         }
     }
 }
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::{Class, ExtendedErrorCode, Rtype};
+    use crate::base::name::Dname;
+    use crate::base::record::Record;
+    use crate::rdata::A;
+    use crate::resolv::stub::upstream::{
+        MockRecord, MockReply, MockUpstream,
+    };
+    use std::io::{Read, Write};
+    use std::net;
+    use std::str::FromStr;
+
+    pub(super) fn name(s: &str) -> Dname<Bytes> {
+        Dname::from_str(s).unwrap()
+    }
+
+    pub(super) fn conf(servers: &[SocketAddr]) -> ResolvConf {
+        let mut conf = ResolvConf::new();
+        for addr in servers {
+            conf.servers.push(ServerConf::new(*addr, Transport::Udp));
+            conf.servers.push(ServerConf::new(*addr, Transport::Tcp));
+        }
+        conf.finalize();
+        conf
+    }
+
+    pub(super) fn a_record(owner: &str, addr: [u8; 4]) -> MockRecord {
+        Record::new(
+            name(owner),
+            Class::In,
+            3600,
+            A::from_octets(addr[0], addr[1], addr[2], addr[3]).into(),
+        )
+    }
+
+    /// Starts a server on localhost answering one UDP and one TCP request.
+    pub(super) fn local_server() -> SocketAddr {
+        fn respond(request: &[u8]) -> Answer {
+            let request = Message::from_octets(request).unwrap();
+            MockReply::new(Rcode::NoError)
+                .answer(a_record("example.com", [192, 0, 2, 1]))
+                .into_answer(&request)
+                .unwrap()
+        }
+
+        let udp = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = udp.local_addr().unwrap();
+        let tcp = net::TcpListener::bind(addr).unwrap();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            let (len, peer) = udp.recv_from(&mut buf).unwrap();
+            let answer = respond(&buf[..len]);
+            udp.send_to(answer.as_slice(), peer).unwrap();
+        });
+        std::thread::spawn(move || {
+            let (mut sock, _) = tcp.accept().unwrap();
+            let mut len = [0u8; 2];
+            sock.read_exact(&mut len).unwrap();
+            let mut buf = vec![0; usize::from(u16::from_be_bytes(len))];
+            sock.read_exact(&mut buf).unwrap();
+            let answer = respond(&buf);
+            let len = answer.as_slice().len() as u16;
+            sock.write_all(&len.to_be_bytes()).unwrap();
+            sock.write_all(answer.as_slice()).unwrap();
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn blast() {
+        let first: SocketAddr = "192.0.2.53:53".parse().unwrap();
+        let second: SocketAddr = "198.51.100.53:53".parse().unwrap();
+        let question = (name("example.com"), Rtype::A);
+        let mock = Arc::new(MockUpstream::new());
+        mock.push_server(
+            first,
+            question.clone(),
+            MockReply::error(io::ErrorKind::ConnectionRefused),
+        );
+        mock.push_server(
+            second,
+            question.clone(),
+            MockReply::new(Rcode::NoError)
+                .answer(a_record("example.com", [192, 0, 2, 1])),
+        );
+        let mut conf = conf(&[first, second]);
+        conf.options.blast = true;
+        let resolver = StubResolver::with_upstream(conf, mock.clone());
+        let answer = resolver.query(question).await.unwrap();
+        assert_eq!(answer.header_counts().ancount(), 1);
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[cfg(feature = "resolv-tokio")]
+    #[tokio::test]
+    async fn staggered() {
+        // A server that never answers followed by one that does.
+        let dead = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let dead_addr = dead.local_addr().unwrap();
+        let live_addr = local_server();
+
+        let mut conf = ResolvConf::new();
+        for addr in [dead_addr, live_addr] {
+            conf.servers.push(ServerConf::new(addr, Transport::Udp));
+        }
+        conf.options.strategy =
+            QueryStrategy::Staggered(Duration::from_millis(50));
+        conf.finalize();
+        let resolver = StubResolver::with_upstream(conf, NetUpstream::new());
+        let start = Instant::now();
+        let answer = resolver
+            .query((name("example.com"), Rtype::A))
+            .await
+            .unwrap();
+        assert_eq!(answer.header_counts().ancount(), 1);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn adaptive() {
+        let first: SocketAddr = "192.0.2.53:53".parse().unwrap();
+        let second: SocketAddr = "198.51.100.53:53".parse().unwrap();
+        let question = (name("example.com"), Rtype::A);
+        let mock = Arc::new(MockUpstream::new());
+        mock.push_server(
+            first,
+            question.clone(),
+            MockReply::error(io::ErrorKind::TimedOut),
+        );
+        mock.push(question.clone(), MockReply::new(Rcode::NoError));
+        mock.push(question.clone(), MockReply::new(Rcode::NoError));
+        let mut conf = conf(&[first, second]);
+        conf.options.strategy = QueryStrategy::Adaptive;
+        let resolver = StubResolver::with_upstream(conf, mock.clone());
+        resolver.query(question.clone()).await.unwrap();
+        resolver.query(question).await.unwrap();
+
+        // The first server failed, so the second query goes to the second
+        // server right away.
+        let servers: Vec<_> =
+            mock.requests().into_iter().map(|(addr, _)| addr).collect();
+        assert_eq!(servers, [first, second, second]);
+    }
+
+    #[tokio::test]
+    async fn bad_cookie() {
+        let question = (name("example.com"), Rtype::A);
+        let bad = || {
+            MockReply::new(Rcode::NoError)
+                .cookie(b"servercookie")
+                .bad_cookie()
+        };
+        let mock = Arc::new(MockUpstream::new());
+        let resolver = StubResolver::with_upstream(
+            conf(&["192.0.2.53:53".parse().unwrap()]),
+            mock.clone(),
+        );
+
+        // A fresh server cookie is learned and the query repeated.
+        mock.push(question.clone(), bad());
+        mock.push(
+            question.clone(),
+            MockReply::new(Rcode::NoError).cookie(b"servercookie"),
+        );
+        let answer = resolver.query(question.clone()).await.unwrap();
+        assert_eq!(answer.header().rcode(), Rcode::NoError);
+        assert_eq!(mock.requests().len(), 2);
+
+        // A known server cookie isn’t good enough anymore.
+        mock.push(question.clone(), bad());
+        mock.push(question.clone(), bad());
+        match resolver.query(question).await {
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::Other),
+            Ok(_) => panic!("BADCOOKIE accepted"),
+        }
+        assert_eq!(mock.requests().len(), 4);
+        assert!(mock.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn changing_bad_cookie() {
+        let question = (name("example.com"), Rtype::A);
+        let mock = Arc::new(MockUpstream::new());
+        for cookie in [&b"firstcookie"[..], b"secondcookie", b"thirdcookie"] {
+            mock.push(
+                question.clone(),
+                MockReply::new(Rcode::NoError).cookie(cookie).bad_cookie(),
+            );
+        }
+        let mut conf = conf(&["192.0.2.53:53".parse().unwrap()]);
+        conf.options.attempts = 1;
+        let resolver = StubResolver::with_upstream(conf, mock.clone());
+
+        // Every response has a new cookie but we only repeat the query
+        // once.
+        match resolver.query(question).await {
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::Other),
+            Ok(_) => panic!("BADCOOKIE accepted"),
+        }
+        assert_eq!(mock.requests().len(), 2);
+        assert!(!mock.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn client_subnet_cache() {
+        let question = (name("example.com"), Rtype::A);
+        let subnet =
+            |addr: &str| ClientSubnet::from_addr(addr.parse().unwrap(), 24);
+        let reply = |scope, addr| {
+            MockReply::new(Rcode::NoError)
+                .answer(a_record("example.com", addr))
+                .client_subnet(scope)
+        };
+        let mock = Arc::new(MockUpstream::new());
+        let mut conf = conf(&["192.0.2.53:53".parse().unwrap()]);
+        conf.options.cache_size = 16;
+        let resolver = StubResolver::with_upstream(conf, mock.clone());
+        let addrs = |answer: Answer| {
+            answer
+                .answer()
+                .unwrap()
+                .limit_to::<A>()
+                .map(|record| record.unwrap().data().addr().octets())
+                .collect::<Vec<_>>()
+        };
+
+        // An answer for a /16 scope is reused within the /16 only.
+        mock.push(question.clone(), reply(16, [192, 0, 2, 1]));
+        mock.push(question.clone(), reply(16, [192, 0, 2, 2]));
+        let answer = resolver
+            .query_with_subnet(question.clone(), subnet("198.51.100.7"))
+            .await
+            .unwrap();
+        assert_eq!(addrs(answer), [[192, 0, 2, 1]]);
+        let answer = resolver
+            .query_with_subnet(question.clone(), subnet("198.51.7.7"))
+            .await
+            .unwrap();
+        assert_eq!(addrs(answer), [[192, 0, 2, 1]]);
+        let answer = resolver
+            .query_with_subnet(question.clone(), subnet("203.0.113.7"))
+            .await
+            .unwrap();
+        assert_eq!(addrs(answer), [[192, 0, 2, 2]]);
+        assert_eq!(mock.requests().len(), 2);
+
+        // Opting out or not sending the option never uses scoped answers
+        // but a global answer is used by everyone.
+        mock.push(question.clone(), reply(0, [192, 0, 2, 3]));
+        let answer = resolver
+            .query_with_subnet(question.clone(), ClientSubnet::opt_out())
+            .await
+            .unwrap();
+        assert_eq!(addrs(answer), [[192, 0, 2, 3]]);
+        let answer = resolver.query(question.clone()).await.unwrap();
+        assert_eq!(addrs(answer), [[192, 0, 2, 3]]);
+        let answer = resolver
+            .query_with_subnet(question, subnet("203.0.113.7"))
+            .await
+            .unwrap();
+        assert_eq!(addrs(answer), [[192, 0, 2, 2]]);
+        assert_eq!(mock.requests().len(), 3);
+        assert!(mock.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn server_reports() {
+        let first: SocketAddr = "192.0.2.53:53".parse().unwrap();
+        let second: SocketAddr = "198.51.100.53:53".parse().unwrap();
+        let question = (name("example.com"), Rtype::A);
+        let mock = Arc::new(MockUpstream::new());
+        let mut conf = conf(&[first, second]);
+        conf.options.attempts = 1;
+        let resolver = StubResolver::with_upstream(conf, mock.clone());
+
+        // A SERVFAIL answer carries the reports of both servers.
+        mock.push_server(
+            first,
+            question.clone(),
+            MockReply::new(Rcode::ServFail)
+                .extended_error(ExtendedErrorCode::DnssecBogus),
+        );
+        mock.push_server(
+            second,
+            question.clone(),
+            MockReply::error(io::ErrorKind::TimedOut),
+        );
+        let answer = resolver.query(question.clone()).await.unwrap();
+        assert_eq!(answer.header().rcode(), Rcode::ServFail);
+        assert_eq!(
+            answer.extended_errors()[0].code(),
+            ExtendedErrorCode::DnssecBogus
+        );
+        let reports = answer.reports();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].server(), first);
+        assert_eq!(reports[0].rcode(), Some(OptRcode::ServFail));
+        assert_eq!(
+            reports[0].extended_errors()[0].code(),
+            ExtendedErrorCode::DnssecBogus
+        );
+        assert_eq!(reports[1].server(), second);
+        assert_eq!(reports[1].rcode(), None);
+        assert!(reports[1].error().is_some());
+
+        // A failed query has the reports in its error.
+        mock.push_server(
+            first,
+            question.clone(),
+            MockReply::error(io::ErrorKind::TimedOut),
+        );
+        mock.push_server(
+            second,
+            question.clone(),
+            MockReply::error(io::ErrorKind::ConnectionRefused),
+        );
+        let err = match resolver.query(question).await {
+            Ok(_) => panic!("query succeeded"),
+            Err(err) => err,
+        };
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        let err = QueryError::from_io_error(&err).unwrap();
+        assert_eq!(err.reports().len(), 2);
+        assert_eq!(
+            err.to_string(),
+            "scripted error (192.0.2.53:53 (UDP): scripted error; \
+             198.51.100.53:53 (UDP): scripted error)"
+        );
+    }
+
+    /// Makes the resolver check its configuration file on the next query.
+    fn expire_conf_check(resolver: &StubResolver) {
+        let mut state = resolver.core.state.lock().unwrap();
+        state.file.as_mut().unwrap().next_check = Instant::now();
+    }
+
+    #[tokio::test]
+    async fn reload_conf() {
+        let first: SocketAddr = "192.0.2.53:53".parse().unwrap();
+        let second: SocketAddr = "198.51.100.53:53".parse().unwrap();
+        let third: SocketAddr = "203.0.113.53:53".parse().unwrap();
+        let question = (name("example.com"), Rtype::A);
+        let mock = Arc::new(MockUpstream::new());
+        for _ in 0..4 {
+            mock.push(question.clone(), MockReply::new(Rcode::NoError));
+        }
+
+        let path = std::env::temp_dir()
+            .join(format!("domain-resolv-conf-test-{}", std::process::id()));
+        std::fs::write(&path, "nameserver 198.51.100.53\n").unwrap();
+        let mut base = conf(&[first]);
+        base.options.strategy = QueryStrategy::Adaptive;
+        base.options.use_cookies = false;
+        let resolver = StubResolver::with_upstream(base, mock.clone());
+        let clone = resolver.clone();
+        resolver.watch_conf_file(&path);
+        clone.query(question.clone()).await.unwrap();
+
+        // Settings not from the file are kept.
+        assert_eq!(clone.options().strategy, QueryStrategy::Adaptive);
+        assert!(!clone.options().use_cookies);
+
+        // The file is only checked again after a while.
+        std::fs::write(&path, "nameserver 203.0.113.53\noptions ndots:2\n")
+            .unwrap();
+        resolver.query(question.clone()).await.unwrap();
+        assert_eq!(clone.options().ndots, 1);
+        expire_conf_check(&resolver);
+        resolver.query(question.clone()).await.unwrap();
+        assert_eq!(clone.options().ndots, 2);
+        assert_eq!(clone.options().strategy, QueryStrategy::Adaptive);
+
+        resolver.set_conf(conf(&[first]));
+        clone.query(question).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let servers: Vec<_> =
+            mock.requests().into_iter().map(|(addr, _)| addr).collect();
+        assert_eq!(servers, [second, second, third, first]);
+    }
+
+    #[tokio::test]
+    async fn no_aaaa() {
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (name("example.com"), Rtype::A),
+            MockReply::new(Rcode::NoError)
+                .answer(a_record("example.com", [192, 0, 2, 1])),
+        );
+        mock.push(
+            (name("nx.example.com"), Rtype::A),
+            MockReply::new(Rcode::NXDomain),
+        );
+        let mut conf = conf(&["192.0.2.53:53".parse().unwrap()]);
+        conf.options.no_aaaa = true;
+        let resolver = StubResolver::with_upstream(conf, mock.clone());
+
+        // AAAA queries are sent as A queries and answered without records.
+        let answer = resolver
+            .query((name("example.com"), Rtype::Aaaa))
+            .await
+            .unwrap();
+        assert_eq!(answer.header().rcode(), Rcode::NoError);
+        assert_eq!(answer.header_counts().ancount(), 0);
+        assert_eq!(answer.first_question().unwrap().qtype(), Rtype::Aaaa);
+        let answer = resolver
+            .query((name("nx.example.com"), Rtype::Aaaa))
+            .await
+            .unwrap();
+        assert_eq!(answer.header().rcode(), Rcode::NXDomain);
+        let qtypes: Vec<_> = mock
+            .requests()
+            .into_iter()
+            .map(|(_, question)| question.qtype())
+            .collect();
+        assert_eq!(qtypes, [Rtype::A, Rtype::A]);
+        assert!(mock.is_exhausted());
+    }
+}
@@ -0,0 +1,398 @@
+//! Observing the activity of a stub resolver.
+//!
+//! A [`QueryObserver`] can be attached to a stub resolver via
+//! [`StubResolver::set_observer`][super::StubResolver::set_observer]. It is
+//! informed about every exchange with an upstream server and about every
+//! answer taken from the cache, which allows tracing queries or collecting
+//! metrics. The [`MetricsObserver`] provided by this module collects
+//! counters and latency histograms for each server.
+
+use super::conf::Transport;
+use super::ServerReport;
+use crate::base::iana::{OptRcode, Rtype};
+use crate::base::name::Dname;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::vec::Vec;
+use std::{fmt, io};
+
+//------------ QueryObserver -------------------------------------------------
+
+/// A type that is informed about the activity of a stub resolver.
+///
+/// All methods have default implementations that do nothing, so an
+/// observer only needs to implement those it is interested in. The methods
+/// are called while the query is being processed and should return
+/// quickly.
+pub trait QueryObserver: fmt::Debug + Send + Sync {
+    /// Called after each exchange with an upstream server.
+    ///
+    /// The report contains the server, transport, start time, and duration
+    /// of the exchange as well as the rcode of the response, whether it
+    /// was truncated or made the resolver fall back to querying without
+    /// EDNS, or the error if the exchange failed.
+    fn exchange(&self, report: &ServerReport) {
+        let _ = report;
+    }
+
+    /// Called when a query is answered from the cache.
+    fn cache_hit(&self, qname: &Dname<Bytes>, qtype: Rtype) {
+        let _ = (qname, qtype);
+    }
+}
+
+impl<T: QueryObserver + ?Sized> QueryObserver for Arc<T> {
+    fn exchange(&self, report: &ServerReport) {
+        (**self).exchange(report)
+    }
+
+    fn cache_hit(&self, qname: &Dname<Bytes>, qtype: Rtype) {
+        (**self).cache_hit(qname, qtype)
+    }
+}
+
+//------------ MetricsObserver -----------------------------------------------
+
+/// An observer collecting metrics for each server.
+///
+/// The observer keeps a [`ServerMetrics`] for every combination of server
+/// address and transport that has been used. In order to read the metrics
+/// while the resolver is using the observer, keep a clone of an
+/// `Arc<MetricsObserver>` and give the resolver the other one.
+#[derive(Debug, Default)]
+pub struct MetricsObserver {
+    /// The metrics.
+    metrics: Mutex<Metrics>,
+}
+
+#[derive(Debug, Default)]
+struct Metrics {
+    /// The metrics for each server and transport.
+    servers: HashMap<(SocketAddr, Transport), ServerMetrics>,
+
+    /// The number of answers taken from the cache.
+    cache_hits: u64,
+}
+
+impl MetricsObserver {
+    /// Creates a new observer without any metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the metrics for a server and transport.
+    ///
+    /// Returns `None` if the server hasn’t been asked via `transport` yet.
+    pub fn server(
+        &self,
+        addr: SocketAddr,
+        transport: Transport,
+    ) -> Option<ServerMetrics> {
+        self.metrics
+            .lock()
+            .unwrap()
+            .servers
+            .get(&(addr, transport))
+            .cloned()
+    }
+
+    /// Returns the metrics for all servers asked so far.
+    pub fn servers(&self) -> Vec<(SocketAddr, Transport, ServerMetrics)> {
+        self.metrics
+            .lock()
+            .unwrap()
+            .servers
+            .iter()
+            .map(|(&(addr, transport), metrics)| {
+                (addr, transport, metrics.clone())
+            })
+            .collect()
+    }
+
+    /// Returns the number of queries answered from the cache.
+    pub fn cache_hits(&self) -> u64 {
+        self.metrics.lock().unwrap().cache_hits
+    }
+}
+
+impl QueryObserver for MetricsObserver {
+    fn exchange(&self, report: &ServerReport) {
+        self.metrics
+            .lock()
+            .unwrap()
+            .servers
+            .entry((report.server(), report.transport()))
+            .or_default()
+            .record(report)
+    }
+
+    fn cache_hit(&self, _qname: &Dname<Bytes>, _qtype: Rtype) {
+        self.metrics.lock().unwrap().cache_hits += 1
+    }
+}
+
+//------------ ServerMetrics -------------------------------------------------
+
+/// The metrics collected for a single server and transport.
+#[derive(Clone, Debug, Default)]
+pub struct ServerMetrics {
+    /// The number of exchanges with the server.
+    exchanges: u64,
+
+    /// The number of exchanges that failed without a response.
+    errors: u64,
+
+    /// The number of exchanges that timed out.
+    timeouts: u64,
+
+    /// The number of truncated responses.
+    truncated: u64,
+
+    /// The number of responses that made us stop using EDNS.
+    edns_fallbacks: u64,
+
+    /// The number of responses for each rcode.
+    rcodes: HashMap<OptRcode, u64>,
+
+    /// The distribution of the time it took to receive a response.
+    latency: LatencyHistogram,
+}
+
+impl ServerMetrics {
+    /// Returns the number of exchanges with the server.
+    pub fn exchanges(&self) -> u64 {
+        self.exchanges
+    }
+
+    /// Returns the number of responses received from the server.
+    pub fn responses(&self) -> u64 {
+        self.exchanges - self.errors
+    }
+
+    /// Returns the number of exchanges that failed without a response.
+    ///
+    /// This includes timeouts.
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Returns the number of exchanges that timed out.
+    pub fn timeouts(&self) -> u64 {
+        self.timeouts
+    }
+
+    /// Returns the number of truncated responses.
+    pub fn truncated(&self) -> u64 {
+        self.truncated
+    }
+
+    /// Returns the number of responses that made the resolver stop using
+    /// EDNS with the server.
+    pub fn edns_fallbacks(&self) -> u64 {
+        self.edns_fallbacks
+    }
+
+    /// Returns the number of responses with the given rcode.
+    pub fn rcode(&self, rcode: OptRcode) -> u64 {
+        self.rcodes.get(&rcode).copied().unwrap_or(0)
+    }
+
+    /// Returns an iterator over the rcodes and their number of responses.
+    pub fn rcodes(&self) -> impl Iterator<Item = (OptRcode, u64)> + '_ {
+        self.rcodes.iter().map(|(&rcode, &count)| (rcode, count))
+    }
+
+    /// Returns the distribution of the time it took to get a response.
+    ///
+    /// Failed exchanges are not included.
+    pub fn latency(&self) -> &LatencyHistogram {
+        &self.latency
+    }
+
+    fn record(&mut self, report: &ServerReport) {
+        self.exchanges += 1;
+        match report.rcode() {
+            Some(rcode) => {
+                *self.rcodes.entry(rcode).or_default() += 1;
+                self.latency.record(report.duration());
+                if report.is_truncated() {
+                    self.truncated += 1
+                }
+                if report.is_edns_fallback() {
+                    self.edns_fallbacks += 1
+                }
+            }
+            None => {
+                self.errors += 1;
+                if report.error_kind() == Some(io::ErrorKind::TimedOut) {
+                    self.timeouts += 1
+                }
+            }
+        }
+    }
+}
+
+//------------ LatencyHistogram ----------------------------------------------
+
+/// The upper bounds of the buckets of a latency histogram.
+const LATENCY_BOUNDS: [Duration; 12] = [
+    Duration::from_millis(1),
+    Duration::from_millis(2),
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(20),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(200),
+    Duration::from_millis(500),
+    Duration::from_millis(1000),
+    Duration::from_millis(2000),
+    Duration::from_millis(5000),
+];
+
+/// A histogram of latencies.
+///
+/// The histogram sorts durations into buckets. Bucket _n_ contains all
+/// durations up to and including the _n_th of the [bounds][Self::bounds]
+/// that are longer than the bound before. A final bucket contains all
+/// durations beyond the last bound.
+#[derive(Clone, Debug, Default)]
+pub struct LatencyHistogram {
+    /// The number of durations in each bucket.
+    buckets: [u64; LATENCY_BOUNDS.len() + 1],
+
+    /// The sum of all durations.
+    sum: Duration,
+}
+
+impl LatencyHistogram {
+    /// Returns the upper bounds of the buckets.
+    ///
+    /// There is one bucket more than there are bounds.
+    pub fn bounds() -> &'static [Duration] {
+        &LATENCY_BOUNDS
+    }
+
+    /// Returns the number of durations in each bucket.
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// Returns the number of durations.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Returns the sum of all durations.
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// Returns the mean duration or `None` if there are no durations.
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count() {
+            0 => None,
+            count => Some(Duration::from_nanos(
+                (self.sum.as_nanos() / u128::from(count)) as u64,
+            )),
+        }
+    }
+
+    fn record(&mut self, duration: Duration) {
+        let idx = LATENCY_BOUNDS
+            .iter()
+            .position(|bound| duration <= *bound)
+            .unwrap_or(LATENCY_BOUNDS.len());
+        self.buckets[idx] += 1;
+        self.sum += duration;
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::{Class, Rcode};
+    use crate::base::record::Record;
+    use crate::rdata::A;
+    use crate::resolv::stub::conf::{ResolvConf, ServerConf};
+    use crate::resolv::stub::upstream::{MockReply, MockUpstream};
+    use crate::resolv::stub::StubResolver;
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn metrics() {
+        let first: SocketAddr = "192.0.2.53:53".parse().unwrap();
+        let second: SocketAddr = "198.51.100.53:53".parse().unwrap();
+        let qname = Dname::<Bytes>::from_str("example.com").unwrap();
+        let question = (qname.clone(), Rtype::A);
+        let mock = Arc::new(MockUpstream::new());
+        mock.push_server(
+            first,
+            question.clone(),
+            MockReply::error(io::ErrorKind::TimedOut),
+        );
+        mock.push_server(
+            second,
+            question.clone(),
+            MockReply::new(Rcode::FormErr),
+        );
+        mock.push_server(
+            second,
+            question.clone(),
+            MockReply::new(Rcode::NoError).answer(Record::new(
+                qname,
+                Class::In,
+                3600,
+                A::from_octets(192, 0, 2, 1).into(),
+            )),
+        );
+
+        let mut conf = ResolvConf::new();
+        for addr in [first, second] {
+            conf.servers.push(ServerConf::new(addr, Transport::Udp));
+        }
+        conf.options.cache_size = 8;
+        conf.finalize();
+        let observer = Arc::new(MetricsObserver::new());
+        let resolver = StubResolver::with_upstream(conf, mock);
+        resolver.set_observer(observer.clone());
+        resolver.query(question.clone()).await.unwrap();
+        resolver.query(question).await.unwrap();
+
+        let metrics = observer.server(first, Transport::Udp).unwrap();
+        assert_eq!(metrics.exchanges(), 1);
+        assert_eq!(metrics.errors(), 1);
+        assert_eq!(metrics.timeouts(), 1);
+        assert_eq!(metrics.latency().count(), 0);
+
+        let metrics = observer.server(second, Transport::Udp).unwrap();
+        assert_eq!(metrics.exchanges(), 2);
+        assert_eq!(metrics.responses(), 2);
+        assert_eq!(metrics.edns_fallbacks(), 1);
+        assert_eq!(metrics.rcode(OptRcode::FormErr), 1);
+        assert_eq!(metrics.rcode(OptRcode::NoError), 1);
+        assert_eq!(metrics.latency().count(), 2);
+
+        assert!(observer.server(second, Transport::Tcp).is_none());
+        assert_eq!(observer.cache_hits(), 1);
+    }
+
+    #[test]
+    fn histogram() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.mean(), None);
+        histogram.record(Duration::from_millis(1));
+        histogram.record(Duration::from_millis(3));
+        histogram.record(Duration::from_secs(8));
+        assert_eq!(histogram.buckets()[0], 1);
+        assert_eq!(histogram.buckets()[2], 1);
+        assert_eq!(histogram.buckets()[LatencyHistogram::bounds().len()], 1);
+        assert_eq!(histogram.count(), 3);
+        assert_eq!(histogram.mean(), Some(Duration::from_millis(2668)));
+    }
+}
@@ -0,0 +1,654 @@
+//! A blocking stub resolver.
+//!
+//! This module provides [`SyncStubResolver`], a stub resolver that
+//! performs its queries synchronously using the socket types of the
+//! standard library. Unlike [`StubResolver::run`][super::StubResolver::run]
+//! it does not need an async runtime and can therefore be used from
+//! plain threads as well as from code that already runs inside a runtime.
+//!
+//! The resolver shares its configuration, server selection, and retry
+//! logic with the asynchronous [`StubResolver`][super::StubResolver].
+
+use super::conf::{
+    self, QueryStrategy, ResolvConf, ResolvOptions, SearchSuffix,
+};
+use super::observer::QueryObserver;
+use super::upstream::{SyncNetUpstream, SyncUpstream};
+use super::{
+    no_aaaa_answer, Answer, Attempts, Outcome, Query, QueryMessage,
+    ResolverCore, ServerInfo, SharedCore, Step,
+};
+use crate::base::name::{ToDname, ToRelativeDname};
+use crate::base::opt::rfc7871::ClientSubnet;
+use crate::base::question::Question;
+use crate::resolv::lookup::addr::{lookup_addr, FoundAddrs};
+use crate::resolv::lookup::host::{lookup_host, search_host, FoundHosts};
+use crate::resolv::lookup::srv::{lookup_srv, ResolvedSrvItem, SrvError};
+use crate::resolv::resolver::{Resolver, SearchNames};
+use bytes::Bytes;
+use futures::executor::block_on;
+use futures::future::{self, Ready};
+use futures::stream::StreamExt;
+use std::io;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+//------------ SyncStubResolver ----------------------------------------------
+
+/// A DNS stub resolver that blocks the current thread.
+///
+/// The resolver is configured just like the
+/// [`StubResolver`][super::StubResolver] – see there for details – but its
+/// methods wait for the result before returning. Messages are exchanged
+/// with the servers through a [`SyncUpstream`], by default a
+/// [`SyncNetUpstream`] using the standard library’s `UdpSocket` and
+/// `TcpStream`. No async runtime is created or required.
+///
+/// All [query strategies][conf::QueryStrategy] are supported. For the
+/// strategies that ask several servers at the same time,
+/// [`Blast`][conf::QueryStrategy::Blast] and
+/// [`Staggered`][conf::QueryStrategy::Staggered], each request is sent
+/// from a thread of its own. Once the first good answer has arrived, the
+/// remaining threads are left to finish in the background, which they do
+/// at the latest when their request times out.
+///
+/// Clones of the resolver share their configuration and observer, so
+/// changing either via one clone affects all of them.
+///
+/// A reference to the resolver also implements the [`Resolver`] trait, so
+/// it can be used with the generic lookup functions. The futures returned
+/// by its `query` method are always ready, i.e., the query is already done
+/// when they are created.
+#[derive(Clone, Debug)]
+pub struct SyncStubResolver {
+    /// The servers, options, and hosts file shared by all clones.
+    core: Arc<SharedCore>,
+
+    /// The upstream exchanging messages with the servers.
+    upstream: Arc<dyn SyncUpstream>,
+}
+
+impl SyncStubResolver {
+    /// Creates a new resolver using the system’s default configuration.
+    pub fn new() -> Self {
+        let res = Self::from_conf(ResolvConf::default());
+        res.core.watch(conf::DEFAULT_PATH.into(), false);
+        res
+    }
+
+    /// Creates a new resolver using the given configuration.
+    pub fn from_conf(conf: ResolvConf) -> Self {
+        Self::with_upstream(conf, SyncNetUpstream::new())
+    }
+
+    /// Creates a new resolver using the given configuration and upstream.
+    ///
+    /// All exchanges with the servers given in `conf` will be made through
+    /// `upstream`.
+    pub fn with_upstream(
+        conf: ResolvConf,
+        upstream: impl SyncUpstream + 'static,
+    ) -> Self {
+        SyncStubResolver {
+            core: Arc::new(SharedCore::new(conf)),
+            upstream: Arc::new(upstream),
+        }
+    }
+
+    /// Returns the current options of the resolver.
+    pub fn options(&self) -> ResolvOptions {
+        self.core.get().options.clone()
+    }
+
+    /// Replaces the configuration of the resolver.
+    ///
+    /// See [`StubResolver::set_conf`][super::StubResolver::set_conf] for
+    /// details.
+    pub fn set_conf(&self, conf: ResolvConf) {
+        self.core.set_conf(conf)
+    }
+
+    /// Reloads the configuration whenever the file at `path` changes.
+    ///
+    /// See
+    /// [`StubResolver::watch_conf_file`][super::StubResolver::watch_conf_file]
+    /// for details.
+    pub fn watch_conf_file<P: Into<PathBuf>>(&self, path: P) {
+        self.core.watch(path.into(), true)
+    }
+
+    /// Sets the observer to report the resolver’s activity to.
+    ///
+    /// The observer replaces any previous observer of this resolver and
+    /// all its clones. See
+    /// [`StubResolver::set_observer`][super::StubResolver::set_observer]
+    /// for details.
+    pub fn set_observer(&self, observer: impl QueryObserver + 'static) {
+        self.core.set_observer(Arc::new(observer))
+    }
+
+    /// Queries the servers for `question` and waits for the answer.
+    pub fn query<N: ToDname, Q: Into<Question<N>>>(
+        &self,
+        question: Q,
+    ) -> Result<Answer, io::Error> {
+        self.query_message(Query::create_message(question.into()), None)
+    }
+
+    /// Queries for `question` using the given EDNS Client Subnet option.
+    ///
+    /// This is the blocking version of
+    /// [`StubResolver::query_with_subnet`][super::StubResolver::query_with_subnet].
+    pub fn query_with_subnet<N: ToDname, Q: Into<Question<N>>>(
+        &self,
+        question: Q,
+        subnet: ClientSubnet,
+    ) -> Result<Answer, io::Error> {
+        self.query_message(
+            Query::create_message(question.into()),
+            Some(subnet),
+        )
+    }
+
+    /// Answers a query message.
+    ///
+    /// If `subnet` is `None`, the client subnet from the options is used.
+    fn query_message(
+        &self,
+        message: QueryMessage,
+        subnet: Option<ClientSubnet>,
+    ) -> Result<Answer, io::Error> {
+        let core = self.core.get();
+        let subnet = subnet.or(core.options.client_subnet);
+        if let Some(answer) = core.hosts_answer(&message) {
+            return Ok(answer);
+        }
+        let no_aaaa = core.no_aaaa_request(&message);
+        let query = no_aaaa.as_ref().unwrap_or(&message);
+        let answer = match core.cached_answer(query, subnet.as_ref()) {
+            Some(answer) => answer,
+            None => {
+                let answer = self.run(&core, query, subnet)?;
+                core.cache_answer(query, subnet.as_ref(), &answer);
+                answer
+            }
+        };
+        match no_aaaa {
+            Some(_) => no_aaaa_answer(&message, answer),
+            None => Ok(answer),
+        }
+    }
+
+    /// Asks the servers until there is an answer or we have to give up.
+    fn run(
+        &self,
+        core: &ResolverCore,
+        message: &QueryMessage,
+        subnet: Option<ClientSubnet>,
+    ) -> Result<Answer, io::Error> {
+        let attempts = Attempts::new(core, subnet)?;
+        match core.options.query_strategy() {
+            QueryStrategy::Sequential | QueryStrategy::Adaptive => {
+                self.run_sequential(attempts, message)
+            }
+            QueryStrategy::Blast => {
+                self.run_parallel(attempts, message, None)
+            }
+            QueryStrategy::Staggered(delay) => {
+                self.run_parallel(attempts, message, Some(delay))
+            }
+        }
+    }
+
+    /// Asks one server after the other until there is an answer.
+    fn run_sequential(
+        &self,
+        mut attempts: Attempts,
+        message: &QueryMessage,
+    ) -> Result<Answer, io::Error> {
+        let mut request = message.clone();
+        loop {
+            let server = attempts.prepare(&mut request);
+            let start = Instant::now();
+            let res = self.upstream.query(&server.conf, &request);
+            match attempts.process(res, start.elapsed()) {
+                Step::Done(res) => return res,
+                Step::GiveUp => return attempts.into_error(),
+                Step::Continue => {}
+            }
+        }
+    }
+
+    /// Asks several servers at the same time.
+    ///
+    /// This follows the same rules as the asynchronous resolver: If
+    /// `delay` is `None`, all servers are asked at once. Otherwise, the
+    /// servers are asked in order, moving on to the next server whenever
+    /// `delay` has passed or a server has failed. The first good answer
+    /// wins. A truncated answer makes us continue sequentially with the
+    /// stream servers.
+    ///
+    /// Each exchange happens on a thread of its own which reports back
+    /// through a channel. Every round gets a new channel so that late
+    /// results of an earlier round are dropped.
+    fn run_parallel(
+        &self,
+        mut attempts: Attempts,
+        message: &QueryMessage,
+        delay: Option<Duration>,
+    ) -> Result<Answer, io::Error> {
+        loop {
+            let round = attempts.round();
+            let (tx, rx) = mpsc::channel();
+            let mut next = 0;
+            let mut inflight = 0;
+            let start = match delay {
+                None => round.len(),
+                Some(_) => 1,
+            };
+            while next < start {
+                self.exchange(&attempts, round[next], next, message, &tx);
+                next += 1;
+                inflight += 1;
+            }
+            let mut deadline = delay.map(|delay| Instant::now() + delay);
+
+            while inflight > 0 {
+                // If there are servers waiting, we only wait until the
+                // deadline to start the next one.
+                let recv = match deadline {
+                    Some(deadline) if next < round.len() => rx.recv_timeout(
+                        deadline.saturating_duration_since(Instant::now()),
+                    ),
+                    _ => {
+                        rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                    }
+                };
+                let (index, res, elapsed) = match recv {
+                    Ok(item) => item,
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.exchange(
+                            &attempts,
+                            round[next],
+                            next,
+                            message,
+                            &tx,
+                        );
+                        next += 1;
+                        inflight += 1;
+                        deadline = delay.map(|delay| Instant::now() + delay);
+                        continue;
+                    }
+                    // We are holding on to a sender, so this can only
+                    // happen if an upstream panicked.
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                inflight -= 1;
+                let server = round[index];
+                match attempts.evaluate(server, res, elapsed) {
+                    Outcome::Answer(answer) => return Ok(answer),
+                    Outcome::Retry => {
+                        self.exchange(&attempts, server, index, message, &tx);
+                        inflight += 1;
+                    }
+                    Outcome::Truncated(answer) => {
+                        if attempts.switch_to_stream() {
+                            return self.run_sequential(attempts, message);
+                        } else {
+                            return Ok(answer);
+                        }
+                    }
+                    Outcome::Failed => {
+                        if next < round.len() {
+                            self.exchange(
+                                &attempts,
+                                round[next],
+                                next,
+                                message,
+                                &tx,
+                            );
+                            next += 1;
+                            inflight += 1;
+                            deadline =
+                                delay.map(|delay| Instant::now() + delay);
+                        }
+                    }
+                }
+            }
+
+            if !attempts.next_round() {
+                return attempts.into_error();
+            }
+        }
+    }
+
+    /// Asks `server` on a new thread.
+    ///
+    /// The result is sent to `tx` together with `index`, the position of
+    /// the server in the current round.
+    fn exchange(
+        &self,
+        attempts: &Attempts,
+        server: &ServerInfo,
+        index: usize,
+        message: &QueryMessage,
+        tx: &mpsc::Sender<Exchange>,
+    ) {
+        let mut message = message.clone();
+        server.prepare_message(
+            &mut message,
+            &attempts.core.options,
+            attempts.subnet.as_ref(),
+        );
+        let conf = server.conf.clone();
+        let upstream = self.upstream.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            let res = upstream.query(&conf, &message);
+            // The receiver is gone if another server answered first.
+            let _ = tx.send((index, res, start.elapsed()));
+        });
+    }
+}
+
+/// The result of asking a server: the server’s position in the round, its
+/// response, and how long it took.
+type Exchange = (usize, Result<Answer, io::Error>, Duration);
+
+impl SyncStubResolver {
+    /// Looks up the host names for an IP address.
+    ///
+    /// This is the blocking version of [`lookup_addr`].
+    pub fn lookup_addr(
+        &self,
+        addr: IpAddr,
+    ) -> Result<FoundAddrs<&Self>, io::Error> {
+        block_on(lookup_addr(&self, addr))
+    }
+
+    /// Looks up the IP addresses of a host.
+    ///
+    /// This is the blocking version of [`lookup_host`].
+    pub fn lookup_host(
+        &self,
+        qname: impl ToDname,
+    ) -> Result<FoundHosts<&Self>, io::Error> {
+        block_on(lookup_host(&self, qname))
+    }
+
+    /// Looks up the IP addresses of a host using the search list.
+    ///
+    /// This is the blocking version of [`search_host`].
+    pub fn search_host(
+        &self,
+        qname: impl ToRelativeDname,
+    ) -> Result<FoundHosts<&Self>, io::Error> {
+        block_on(search_host(&self, qname))
+    }
+
+    /// Looks up and resolves the SRV records of a service.
+    ///
+    /// This is the blocking version of [`lookup_srv`]. Since the resulting
+    /// stream can’t be used without a runtime, the targets of the records
+    /// are resolved right away. Targets whose addresses could not be
+    /// looked up are left out of the result.
+    ///
+    /// The function returns `Ok(None)` if the service is decidedly not
+    /// available.
+    pub fn lookup_srv(
+        &self,
+        service: impl ToRelativeDname,
+        name: impl ToDname,
+        fallback_port: u16,
+    ) -> Result<Option<Vec<ResolvedSrvItem>>, SrvError> {
+        block_on(async {
+            let found = match lookup_srv(&self, service, name, fallback_port)
+                .await?
+            {
+                Some(found) => found,
+                None => return Ok(None),
+            };
+            Ok(Some(
+                found
+                    .into_stream(&self)
+                    .filter_map(|item| future::ready(item.ok()))
+                    .collect()
+                    .await,
+            ))
+        })
+    }
+}
+
+impl Default for SyncStubResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver for &SyncStubResolver {
+    type Octets = Bytes;
+    type Answer = Answer;
+    type Query = Ready<Result<Answer, io::Error>>;
+
+    fn query<N, Q>(&self, question: Q) -> Self::Query
+    where
+        N: ToDname,
+        Q: Into<Question<N>>,
+    {
+        future::ready(SyncStubResolver::query(self, question))
+    }
+}
+
+impl<'a> SearchNames for &'a SyncStubResolver {
+    type Name = SearchSuffix;
+    type Iter = SyncSearchIter<'a>;
+
+    fn search_iter(&self) -> Self::Iter {
+        SyncSearchIter {
+            resolver: self,
+            pos: 0,
+        }
+    }
+}
+
+//------------ SyncSearchIter ------------------------------------------------
+
+/// An iterator over the search suffixes of a [`SyncStubResolver`].
+#[derive(Clone, Debug)]
+pub struct SyncSearchIter<'a> {
+    resolver: &'a SyncStubResolver,
+    pos: usize,
+}
+
+impl<'a> Iterator for SyncSearchIter<'a> {
+    type Item = SearchSuffix;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self
+            .resolver
+            .core
+            .get()
+            .options
+            .search
+            .get(self.pos)?
+            .clone();
+        self.pos += 1;
+        Some(res)
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::iana::{Class, Rcode, Rtype};
+    use crate::base::name::{Dname, RelativeDname};
+    use crate::base::record::Record;
+    use crate::rdata::{Srv, A};
+    use crate::resolv::stub::conf::{ServerConf, Transport};
+    use crate::resolv::stub::upstream::{MockReply, MockUpstream};
+    use std::net::SocketAddr;
+    use std::str::FromStr;
+
+    fn name(s: &str) -> Dname<Bytes> {
+        Dname::from_str(s).unwrap()
+    }
+
+    fn resolver(mock: &Arc<MockUpstream>) -> SyncStubResolver {
+        let mut conf = ResolvConf::new();
+        conf.servers.push(ServerConf::new(
+            "192.0.2.53:53".parse().unwrap(),
+            Transport::Udp,
+        ));
+        conf.finalize();
+        SyncStubResolver::with_upstream(conf, mock.clone())
+    }
+
+    fn push_a(mock: &MockUpstream, host: &str, addr: [u8; 4]) {
+        mock.push(
+            (name(host), Rtype::A),
+            MockReply::new(Rcode::NoError).answer(Record::new(
+                name(host),
+                Class::In,
+                3600,
+                A::from_octets(addr[0], addr[1], addr[2], addr[3]).into(),
+            )),
+        );
+        mock.push((name(host), Rtype::Aaaa), MockReply::new(Rcode::NoError));
+    }
+
+    #[test]
+    fn host_and_srv() {
+        let mock = Arc::new(MockUpstream::new());
+        push_a(&mock, "www.example.com", [192, 0, 2, 1]);
+        let resolver = resolver(&mock);
+        let found = resolver.lookup_host(name("www.example.com")).unwrap();
+        assert_eq!(
+            found.iter().collect::<Vec<_>>(),
+            [IpAddr::from([192, 0, 2, 1])]
+        );
+
+        mock.push(
+            (name("_ldap._tcp.example.com"), Rtype::Srv),
+            MockReply::new(Rcode::NoError).answer(Record::new(
+                name("_ldap._tcp.example.com"),
+                Class::In,
+                3600,
+                Srv::new(10, 0, 389, name("ldap.example.com")).into(),
+            )),
+        );
+        push_a(&mock, "ldap.example.com", [192, 0, 2, 2]);
+        let found = resolver
+            .lookup_srv(
+                RelativeDname::from_slice(b"\x05_ldap\x04_tcp").unwrap(),
+                name("example.com"),
+                389,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].resolved(), ["192.0.2.2:389".parse().unwrap()]);
+    }
+
+    #[test]
+    fn servfail() {
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (name("example.com"), Rtype::A),
+            MockReply::new(Rcode::ServFail),
+        );
+        let answer = resolver(&mock)
+            .query((name("example.com"), Rtype::A))
+            .unwrap();
+        assert_eq!(answer.header().rcode(), Rcode::ServFail);
+    }
+
+    fn two_servers(
+        mock: &Arc<MockUpstream>,
+        strategy: QueryStrategy,
+    ) -> (SocketAddr, SocketAddr, SyncStubResolver) {
+        let first: SocketAddr = "192.0.2.53:53".parse().unwrap();
+        let second: SocketAddr = "198.51.100.53:53".parse().unwrap();
+        let mut conf = ResolvConf::new();
+        for addr in [first, second] {
+            conf.servers.push(ServerConf::new(addr, Transport::Udp));
+        }
+        conf.options.strategy = strategy;
+        conf.finalize();
+        (
+            first,
+            second,
+            SyncStubResolver::with_upstream(conf, mock.clone()),
+        )
+    }
+
+    #[test]
+    fn blast() {
+        let mock = Arc::new(MockUpstream::new());
+        let (first, second, resolver) =
+            two_servers(&mock, QueryStrategy::Blast);
+        let question = (name("example.com"), Rtype::A);
+        mock.push_server(
+            first,
+            question.clone(),
+            MockReply::error(io::ErrorKind::ConnectionRefused),
+        );
+        mock.push_server(
+            second,
+            question.clone(),
+            MockReply::new(Rcode::NoError).answer(Record::new(
+                name("example.com"),
+                Class::In,
+                3600,
+                A::from_octets(192, 0, 2, 1).into(),
+            )),
+        );
+        let answer = resolver.query(question).unwrap();
+        assert_eq!(answer.header_counts().ancount(), 1);
+    }
+
+    #[test]
+    fn staggered() {
+        // The first server fails, which must start the second server
+        // right away rather than after the delay.
+        let mock = Arc::new(MockUpstream::new());
+        let (first, second, resolver) = two_servers(
+            &mock,
+            QueryStrategy::Staggered(Duration::from_secs(10)),
+        );
+        let question = (name("example.com"), Rtype::A);
+        mock.push_server(
+            first,
+            question.clone(),
+            MockReply::error(io::ErrorKind::ConnectionRefused),
+        );
+        mock.push_server(
+            second,
+            question.clone(),
+            MockReply::new(Rcode::NoError),
+        );
+        let start = Instant::now();
+        let answer = resolver.query(question).unwrap();
+        assert_eq!(answer.header().rcode(), Rcode::NoError);
+        assert!(start.elapsed() < Duration::from_secs(2));
+        let servers: Vec<_> =
+            mock.requests().into_iter().map(|(addr, _)| addr).collect();
+        assert_eq!(servers, [first, second]);
+    }
+
+    #[tokio::test]
+    async fn inside_runtime() {
+        let mock = Arc::new(MockUpstream::new());
+        push_a(&mock, "www.example.com", [192, 0, 2, 1]);
+        let resolver = resolver(&mock);
+        let found = resolver.lookup_host(name("www.example.com")).unwrap();
+        assert_eq!(found.iter().count(), 1);
+    }
+}
@@ -0,0 +1,947 @@
+//! Exchanging messages with upstream servers.
+//!
+//! The stub resolver doesn’t talk to the network directly. Instead, it
+//! hands each request together with the configuration of the server it
+//! has picked to a value implementing the [`Upstream`] trait and waits for
+//! the answer to come back.
+//!
+//! Normally, this is [`NetUpstream`] which sends the request over UDP or
+//! TCP as determined by the server configuration using the sockets of an
+//! async [runtime][crate::resolv::runtime]. If you want to test code
+//! using the stub resolver without having to run actual name servers, you
+//! can use [`MockUpstream`] instead. It answers requests from a script of
+//! replies you prepare beforehand.
+//!
+//! The blocking [`SyncStubResolver`][super::sync::SyncStubResolver] uses
+//! the [`SyncUpstream`] trait instead which performs the exchange before
+//! returning. Its network implementation is [`SyncNetUpstream`] which uses
+//! the socket types of the standard library. [`MockUpstream`] implements
+//! both traits.
+
+use super::conf::{ServerConf, Transport};
+use super::{Answer, QueryMessage};
+use crate::base::iana::{ExtendedErrorCode, OptRcode, Rcode};
+use crate::base::message::Message;
+use crate::base::message_builder::MessageBuilder;
+use crate::base::name::{Dname, ToDname};
+use crate::base::opt::rfc7871::ClientSubnet;
+use crate::base::opt::rfc7873::{Cookie, ServerCookie};
+use crate::base::opt::rfc8914::ExtendedError;
+use crate::base::question::Question;
+use crate::base::record::Record;
+use crate::rdata::AllRecordData;
+#[cfg(any(
+    feature = "resolv-tokio",
+    feature = "resolv-async-std",
+    feature = "resolv-smol"
+))]
+use crate::resolv::runtime::DefaultRuntime;
+use crate::resolv::runtime::{
+    timeout, DgramSocket, Runtime, RuntimeFuture, StreamSocket,
+};
+use bytes::Bytes;
+use std::boxed::Box;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io::{Read, Write};
+use std::net;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::time::Instant;
+use std::vec::Vec;
+use std::{fmt, io};
+
+//------------ Module Configuration ------------------------------------------
+
+/// How many times do we try a new random port if we get ‘address in use.’
+const RETRY_RANDOM_PORT: usize = 10;
+
+//------------ Upstream ------------------------------------------------------
+
+/// A type that can exchange a request with an upstream server.
+///
+/// The stub resolver calls the [`query`][Self::query] method for each
+/// attempt at answering a question. It passes in the configuration of the
+/// server it would like to ask and the request message. The returned future
+/// should resolve into the server’s response or an error if there wasn’t
+/// any. In particular, implementations should enforce the server’s
+/// `request_timeout` and return an error of kind `TimedOut` if it passes.
+///
+/// All the logic of picking servers, retrying, and falling back to stream
+/// transports lives in the resolver. An implementation only needs to
+/// perform one single exchange.
+pub trait Upstream: fmt::Debug + Send + Sync {
+    /// Sends `request` to `server` and returns a future for the response.
+    fn query<'a>(
+        &'a self,
+        server: &'a ServerConf,
+        request: &'a QueryMessage,
+    ) -> UpstreamQuery<'a>;
+
+    /// Returns a future that resolves after `duration` has passed.
+    ///
+    /// The resolver uses this to delay requests to additional servers
+    /// when using the [staggered][super::conf::QueryStrategy::Staggered]
+    /// query strategy.
+    /// The default implementation never resolves, so the next server is
+    /// only asked once the previous one has failed.
+    fn sleep(&self, duration: Duration) -> RuntimeFuture<'static, ()> {
+        let _ = duration;
+        Box::pin(futures::future::pending())
+    }
+}
+
+impl<T: Upstream + ?Sized> Upstream for Arc<T> {
+    fn query<'a>(
+        &'a self,
+        server: &'a ServerConf,
+        request: &'a QueryMessage,
+    ) -> UpstreamQuery<'a> {
+        T::query(self, server, request)
+    }
+
+    fn sleep(&self, duration: Duration) -> RuntimeFuture<'static, ()> {
+        T::sleep(self, duration)
+    }
+}
+
+/// The future returned by [`Upstream::query`].
+pub type UpstreamQuery<'a> =
+    Pin<Box<dyn Future<Output = Result<Answer, io::Error>> + Send + 'a>>;
+
+//------------ NetUpstream ---------------------------------------------------
+
+/// The default upstream sending requests over the network.
+///
+/// Requests are sent via UDP or TCP depending on the `transport` of the
+/// server configuration. The sockets and timers are provided by the async
+/// runtime `R`. If a runtime feature is enabled, [`new`][Self::new] creates
+/// an upstream using the [`DefaultRuntime`]. Otherwise, you can pick a
+/// runtime via [`with_runtime`][Self::with_runtime].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NetUpstream<R> {
+    /// The runtime providing sockets and timers.
+    runtime: R,
+}
+
+#[cfg(any(
+    feature = "resolv-tokio",
+    feature = "resolv-async-std",
+    feature = "resolv-smol"
+))]
+impl NetUpstream<DefaultRuntime> {
+    /// Creates a new network upstream using the default runtime.
+    pub fn new() -> Self {
+        Self::with_runtime(DefaultRuntime::default())
+    }
+}
+
+impl<R: Runtime> NetUpstream<R> {
+    /// Creates a new network upstream using the given runtime.
+    pub fn with_runtime(runtime: R) -> Self {
+        NetUpstream { runtime }
+    }
+
+    async fn query_server(
+        &self,
+        server: &ServerConf,
+        request: &QueryMessage,
+    ) -> Result<Answer, io::Error> {
+        let res = match server.transport {
+            Transport::Udp => {
+                timeout(
+                    &self.runtime,
+                    server.request_timeout,
+                    self.udp_query(request, server.addr, server.recv_size),
+                )
+                .await
+            }
+            Transport::Tcp => {
+                timeout(
+                    &self.runtime,
+                    server.request_timeout,
+                    self.tcp_query(request, server.addr),
+                )
+                .await
+            }
+        };
+        match res {
+            Some(Ok(answer)) => Ok(answer),
+            Some(Err(err)) => Err(err),
+            None => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "request timed out",
+            )),
+        }
+    }
+
+    async fn tcp_query(
+        &self,
+        query: &QueryMessage,
+        addr: SocketAddr,
+    ) -> Result<Answer, io::Error> {
+        let mut sock = self.runtime.connect_stream(addr).await?;
+        sock.write_all(query.as_target().as_stream_slice()).await?;
+
+        // This loop can be infinite because we have a timeout on this whole
+        // thing, anyway.
+        loop {
+            let mut len = [0u8; 2];
+            sock.read_exact(&mut len).await?;
+            let mut buf = vec![0; usize::from(u16::from_be_bytes(len))];
+            sock.read_exact(&mut buf).await?;
+            if let Ok(answer) = Message::from_octets(Bytes::from(buf)) {
+                if answer.is_answer(&query.as_message()) {
+                    return Ok(answer.into());
+                }
+            // else try with the next message.
+            } else {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "short buf",
+                ));
+            }
+        }
+    }
+
+    async fn udp_query(
+        &self,
+        query: &QueryMessage,
+        addr: SocketAddr,
+        recv_size: usize,
+    ) -> Result<Answer, io::Error> {
+        let sock = self.udp_bind(addr.is_ipv4()).await?;
+        sock.connect(addr).await?;
+        let sent = sock.send(query.as_target().as_dgram_slice()).await?;
+        if sent != query.as_target().as_dgram_slice().len() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "short UDP send",
+            ));
+        }
+        loop {
+            let mut buf = vec![0; recv_size]; // XXX use uninit'ed mem here.
+            let len = sock.recv(&mut buf).await?;
+            buf.truncate(len);
+
+            // We ignore garbage since there is a timer on this whole thing.
+            let answer = match Message::from_octets(Bytes::from(buf)) {
+                Ok(answer) => answer,
+                Err(_) => continue,
+            };
+            if !answer.is_answer(&query.as_message()) {
+                continue;
+            }
+            return Ok(answer.into());
+        }
+    }
+
+    async fn udp_bind(&self, v4: bool) -> Result<R::DgramSocket, io::Error> {
+        let mut i = 0;
+        loop {
+            let local: SocketAddr = if v4 {
+                ([0u8; 4], 0).into()
+            } else {
+                ([0u16; 8], 0).into()
+            };
+            match self.runtime.bind_dgram(local).await {
+                Ok(sock) => return Ok(sock),
+                Err(err) => {
+                    if i == RETRY_RANDOM_PORT {
+                        return Err(err);
+                    } else {
+                        i += 1
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<R: Runtime> Upstream for NetUpstream<R> {
+    fn query<'a>(
+        &'a self,
+        server: &'a ServerConf,
+        request: &'a QueryMessage,
+    ) -> UpstreamQuery<'a> {
+        Box::pin(self.query_server(server, request))
+    }
+
+    fn sleep(&self, duration: Duration) -> RuntimeFuture<'static, ()> {
+        self.runtime.sleep(duration)
+    }
+}
+
+//------------ SyncUpstream --------------------------------------------------
+
+/// A type that can exchange a request with an upstream server, blocking.
+///
+/// This is the blocking equivalent of [`Upstream`] used by the
+/// [`SyncStubResolver`][super::sync::SyncStubResolver]. The
+/// [`query`][Self::query] method performs a single exchange and returns
+/// the server’s response or an error. As with [`Upstream`],
+/// implementations should enforce the server’s `request_timeout` and
+/// return an error of kind `TimedOut` if it passes.
+pub trait SyncUpstream: fmt::Debug + Send + Sync {
+    /// Sends `request` to `server` and waits for the response.
+    fn query(
+        &self,
+        server: &ServerConf,
+        request: &QueryMessage,
+    ) -> Result<Answer, io::Error>;
+}
+
+impl<T: SyncUpstream + ?Sized> SyncUpstream for Arc<T> {
+    fn query(
+        &self,
+        server: &ServerConf,
+        request: &QueryMessage,
+    ) -> Result<Answer, io::Error> {
+        T::query(self, server, request)
+    }
+}
+
+//------------ SyncNetUpstream -----------------------------------------------
+
+/// The default blocking upstream sending requests over the network.
+///
+/// Requests are sent via the standard library’s `UdpSocket` or `TcpStream`
+/// depending on the `transport` of the server configuration. No async
+/// runtime is necessary.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SyncNetUpstream;
+
+impl SyncNetUpstream {
+    /// Creates a new blocking network upstream.
+    pub fn new() -> Self {
+        SyncNetUpstream
+    }
+
+    fn tcp_query(
+        query: &QueryMessage,
+        addr: SocketAddr,
+        deadline: Instant,
+    ) -> Result<Answer, io::Error> {
+        let mut sock = net::TcpStream::connect_timeout(
+            &addr,
+            Self::remaining(deadline)?,
+        )?;
+        sock.set_write_timeout(Some(Self::remaining(deadline)?))?;
+        sock.write_all(query.as_target().as_stream_slice())?;
+        loop {
+            sock.set_read_timeout(Some(Self::remaining(deadline)?))?;
+            let mut len = [0u8; 2];
+            sock.read_exact(&mut len)?;
+            let mut buf = vec![0; usize::from(u16::from_be_bytes(len))];
+            sock.read_exact(&mut buf)?;
+            if let Ok(answer) = Message::from_octets(Bytes::from(buf)) {
+                if answer.is_answer(&query.as_message()) {
+                    return Ok(answer.into());
+                }
+            // else try with the next message.
+            } else {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "short buf",
+                ));
+            }
+        }
+    }
+
+    fn udp_query(
+        query: &QueryMessage,
+        addr: SocketAddr,
+        recv_size: usize,
+        deadline: Instant,
+    ) -> Result<Answer, io::Error> {
+        let sock = Self::udp_bind(addr.is_ipv4())?;
+        sock.connect(addr)?;
+        let sent = sock.send(query.as_target().as_dgram_slice())?;
+        if sent != query.as_target().as_dgram_slice().len() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "short UDP send",
+            ));
+        }
+        loop {
+            sock.set_read_timeout(Some(Self::remaining(deadline)?))?;
+            let mut buf = vec![0; recv_size];
+            let len = sock.recv(&mut buf)?;
+            buf.truncate(len);
+
+            // We ignore garbage until the deadline passes.
+            let answer = match Message::from_octets(Bytes::from(buf)) {
+                Ok(answer) => answer,
+                Err(_) => continue,
+            };
+            if !answer.is_answer(&query.as_message()) {
+                continue;
+            }
+            return Ok(answer.into());
+        }
+    }
+
+    fn udp_bind(v4: bool) -> Result<net::UdpSocket, io::Error> {
+        let mut i = 0;
+        loop {
+            let local: SocketAddr = if v4 {
+                ([0u8; 4], 0).into()
+            } else {
+                ([0u16; 8], 0).into()
+            };
+            match net::UdpSocket::bind(local) {
+                Ok(sock) => return Ok(sock),
+                Err(err) => {
+                    if i == RETRY_RANDOM_PORT {
+                        return Err(err);
+                    } else {
+                        i += 1
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the time left until `deadline` or a timeout error.
+    fn remaining(
+        deadline: Instant,
+    ) -> Result<std::time::Duration, io::Error> {
+        deadline
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::TimedOut, "request timed out")
+            })
+    }
+}
+
+impl SyncUpstream for SyncNetUpstream {
+    fn query(
+        &self,
+        server: &ServerConf,
+        request: &QueryMessage,
+    ) -> Result<Answer, io::Error> {
+        let deadline = Instant::now() + server.request_timeout;
+        let res = match server.transport {
+            Transport::Udp => Self::udp_query(
+                request,
+                server.addr,
+                server.recv_size,
+                deadline,
+            ),
+            Transport::Tcp => Self::tcp_query(request, server.addr, deadline),
+        };
+        res.map_err(|err| match err.kind() {
+            // Socket timeouts show up as either of these depending on the
+            // platform.
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+                io::Error::new(io::ErrorKind::TimedOut, "request timed out")
+            }
+            _ => err,
+        })
+    }
+}
+
+//------------ MockUpstream --------------------------------------------------
+
+/// An upstream answering requests from a script.
+///
+/// The script is a list of replies, each registered for a specific
+/// question and, optionally, a specific server via [`push`][Self::push]
+/// or [`push_server`][Self::push_server]. When a request arrives, the
+/// first reply registered for its question and the server it was sent to
+/// is removed from the script and returned. If there is no such reply, the
+/// request fails with an error of kind `NotFound`.
+///
+/// All requests received are logged and can be inspected via
+/// [`requests`][Self::requests], so a test can check which servers have
+/// been asked which questions in what order.
+///
+/// Since the mock upstream is shared with the resolver, you will usually
+/// wrap it in an arc and hand a clone of that arc to
+/// [`StubResolver::with_upstream`][super::StubResolver::with_upstream].
+#[derive(Debug, Default)]
+pub struct MockUpstream {
+    /// The replies still waiting to be given out.
+    script: Mutex<VecDeque<MockEntry>>,
+
+    /// The requests received so far.
+    requests: Mutex<Vec<(SocketAddr, Question<Dname<Bytes>>)>>,
+}
+
+/// A single entry of the script.
+#[derive(Debug)]
+struct MockEntry {
+    server: Option<SocketAddr>,
+    question: Question<Dname<Bytes>>,
+    reply: MockReply,
+}
+
+impl MockUpstream {
+    /// Creates a new mock upstream with an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a reply for a question sent to any server.
+    pub fn push(
+        &self,
+        question: impl Into<Question<Dname<Bytes>>>,
+        reply: MockReply,
+    ) {
+        self.push_entry(None, question.into(), reply)
+    }
+
+    /// Adds a reply for a question sent to the server at `addr`.
+    pub fn push_server(
+        &self,
+        addr: SocketAddr,
+        question: impl Into<Question<Dname<Bytes>>>,
+        reply: MockReply,
+    ) {
+        self.push_entry(Some(addr), question.into(), reply)
+    }
+
+    fn push_entry(
+        &self,
+        server: Option<SocketAddr>,
+        question: Question<Dname<Bytes>>,
+        reply: MockReply,
+    ) {
+        self.script.lock().unwrap().push_back(MockEntry {
+            server,
+            question,
+            reply,
+        })
+    }
+
+    /// Returns the server address and question of all requests so far.
+    pub fn requests(&self) -> Vec<(SocketAddr, Question<Dname<Bytes>>)> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Returns whether all scripted replies have been used up.
+    pub fn is_exhausted(&self) -> bool {
+        self.script.lock().unwrap().is_empty()
+    }
+
+    fn answer(
+        &self,
+        server: &ServerConf,
+        request: &QueryMessage,
+    ) -> Result<Answer, io::Error> {
+        let request = request.as_message();
+        let question = match request.first_question() {
+            Some(question) => Question::new(
+                question.qname().to_bytes(),
+                question.qtype(),
+                question.qclass(),
+            ),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "request without question",
+                ))
+            }
+        };
+        self.requests
+            .lock()
+            .unwrap()
+            .push((server.addr, question.clone()));
+        let reply = {
+            let mut script = self.script.lock().unwrap();
+            let pos = script.iter().position(|entry| {
+                entry.question == question
+                    && entry
+                        .server
+                        .map(|addr| addr == server.addr)
+                        .unwrap_or(true)
+            });
+            match pos.and_then(|pos| script.remove(pos)) {
+                Some(entry) => entry.reply,
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "no scripted reply",
+                    ))
+                }
+            }
+        };
+        reply.into_answer(&request)
+    }
+}
+
+impl Upstream for MockUpstream {
+    fn query<'a>(
+        &'a self,
+        server: &'a ServerConf,
+        request: &'a QueryMessage,
+    ) -> UpstreamQuery<'a> {
+        Box::pin(futures::future::ready(self.answer(server, request)))
+    }
+}
+
+impl SyncUpstream for MockUpstream {
+    fn query(
+        &self,
+        server: &ServerConf,
+        request: &QueryMessage,
+    ) -> Result<Answer, io::Error> {
+        self.answer(server, request)
+    }
+}
+
+//------------ MockReply -----------------------------------------------------
+
+/// A scripted reply of a [`MockUpstream`].
+///
+/// A reply either is a response message or an error. Response messages
+/// are started via [`new`][Self::new] and then filled with records. The
+/// header ID and question section are taken from the request when the
+/// reply is given out. Errors are created via [`error`][Self::error] and
+/// are returned as the result of the exchange instead of an answer.
+#[derive(Clone, Debug)]
+pub struct MockReply {
+    kind: Result<MockMessage, io::ErrorKind>,
+}
+
+/// The record type used in mock replies.
+pub type MockRecord =
+    Record<Dname<Bytes>, AllRecordData<Bytes, Dname<Bytes>>>;
+
+#[derive(Clone, Debug)]
+struct MockMessage {
+    rcode: Rcode,
+    aa: bool,
+    tc: bool,
+    ad: bool,
+    answer: Vec<MockRecord>,
+    authority: Vec<MockRecord>,
+    additional: Vec<MockRecord>,
+    cookie: Option<ServerCookie>,
+    bad_cookie: bool,
+    subnet_scope: Option<u8>,
+    extended_errors: Vec<ExtendedErrorCode>,
+}
+
+impl MockReply {
+    /// Creates a new, empty response with the given rcode.
+    pub fn new(rcode: Rcode) -> Self {
+        MockReply {
+            kind: Ok(MockMessage {
+                rcode,
+                aa: false,
+                tc: false,
+                ad: false,
+                answer: Vec::new(),
+                authority: Vec::new(),
+                additional: Vec::new(),
+                cookie: None,
+                bad_cookie: false,
+                subnet_scope: None,
+                extended_errors: Vec::new(),
+            }),
+        }
+    }
+
+    /// Creates a reply that fails the exchange with an error of `kind`.
+    ///
+    /// Use `io::ErrorKind::TimedOut` to simulate a server not answering.
+    pub fn error(kind: io::ErrorKind) -> Self {
+        MockReply { kind: Err(kind) }
+    }
+
+    /// Adds a record to the answer section.
+    pub fn answer(mut self, record: impl Into<MockRecord>) -> Self {
+        if let Ok(ref mut msg) = self.kind {
+            msg.answer.push(record.into())
+        }
+        self
+    }
+
+    /// Adds a record to the authority section.
+    pub fn authority(mut self, record: impl Into<MockRecord>) -> Self {
+        if let Ok(ref mut msg) = self.kind {
+            msg.authority.push(record.into())
+        }
+        self
+    }
+
+    /// Adds a record to the additional section.
+    pub fn additional(mut self, record: impl Into<MockRecord>) -> Self {
+        if let Ok(ref mut msg) = self.kind {
+            msg.additional.push(record.into())
+        }
+        self
+    }
+
+    /// Sets the AA bit in the response.
+    pub fn authoritative(mut self) -> Self {
+        if let Ok(ref mut msg) = self.kind {
+            msg.aa = true
+        }
+        self
+    }
+
+    /// Sets the TC bit in the response.
+    pub fn truncated(mut self) -> Self {
+        if let Ok(ref mut msg) = self.kind {
+            msg.tc = true
+        }
+        self
+    }
+
+    /// Sets the AD bit in the response.
+    ///
+    /// As described in section 5.7 of RFC 6840, the bit is only set if the
+    /// request had the AD bit set, too.
+    pub fn authenticated(mut self) -> Self {
+        if let Ok(ref mut msg) = self.kind {
+            msg.ad = true
+        }
+        self
+    }
+
+    /// Adds a cookie option with the given server cookie.
+    ///
+    /// The client cookie is taken from the request. If the request didn’t
+    /// have a cookie, the option is left out.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `server` isn’t a valid server cookie.
+    pub fn cookie(mut self, server: &[u8]) -> Self {
+        if let Ok(ref mut msg) = self.kind {
+            msg.cookie = Some(ServerCookie::from_slice(server).unwrap())
+        }
+        self
+    }
+
+    /// Sets the extended rcode of the response to BADCOOKIE.
+    pub fn bad_cookie(mut self) -> Self {
+        if let Ok(ref mut msg) = self.kind {
+            msg.bad_cookie = true
+        }
+        self
+    }
+
+    /// Adds a client subnet option with the given scope prefix length.
+    ///
+    /// The family, source prefix length, and address are taken from the
+    /// request. If the request didn’t have a client subnet option, the
+    /// option is left out.
+    pub fn client_subnet(mut self, scope_prefix_len: u8) -> Self {
+        if let Ok(ref mut msg) = self.kind {
+            msg.subnet_scope = Some(scope_prefix_len)
+        }
+        self
+    }
+
+    /// Adds an Extended DNS Error option with the given code.
+    pub fn extended_error(mut self, code: ExtendedErrorCode) -> Self {
+        if let Ok(ref mut msg) = self.kind {
+            msg.extended_errors.push(code)
+        }
+        self
+    }
+
+    pub(super) fn into_answer(
+        self,
+        request: &Message<&[u8]>,
+    ) -> Result<Answer, io::Error> {
+        let msg = match self.kind {
+            Ok(msg) => msg,
+            Err(kind) => return Err(io::Error::new(kind, "scripted error")),
+        };
+        let short_buf = |_| io::Error::new(io::ErrorKind::Other, "short buf");
+        let rcode = if msg.bad_cookie {
+            OptRcode::BadCookie.rcode()
+        } else {
+            msg.rcode
+        };
+        let mut builder = MessageBuilder::new_bytes()
+            .start_answer(request, rcode)
+            .map_err(short_buf)?;
+        builder.as_builder_mut().header_mut().set_aa(msg.aa);
+        builder.as_builder_mut().header_mut().set_tc(msg.tc);
+        builder
+            .as_builder_mut()
+            .header_mut()
+            .set_ad(msg.ad && request.header().ad());
+        for record in &msg.answer {
+            builder.push(record).map_err(short_buf)?;
+        }
+        let mut builder = builder.authority();
+        for record in &msg.authority {
+            builder.push(record).map_err(short_buf)?;
+        }
+        let mut builder = builder.additional();
+        for record in &msg.additional {
+            builder.push(record).map_err(short_buf)?;
+        }
+        let client = request
+            .opt()
+            .and_then(|opt| opt.iter::<Cookie>().next())
+            .and_then(Result::ok)
+            .map(Cookie::client);
+        let subnet = match msg.subnet_scope {
+            Some(scope) => request
+                .opt()
+                .and_then(|opt| opt.iter::<ClientSubnet>().next())
+                .and_then(Result::ok)
+                .map(|subnet| {
+                    ClientSubnet::new(
+                        subnet.source_prefix_len(),
+                        scope,
+                        subnet.addr(),
+                    )
+                }),
+            None => None,
+        };
+        if msg.bad_cookie
+            || (msg.cookie.is_some() && client.is_some())
+            || subnet.is_some()
+            || !msg.extended_errors.is_empty()
+        {
+            builder
+                .opt(|opt| {
+                    if msg.bad_cookie {
+                        opt.set_rcode(OptRcode::BadCookie)
+                    }
+                    if let (Some(client), Some(server)) = (client, msg.cookie)
+                    {
+                        opt.push(&Cookie::with_server(client, server))?
+                    }
+                    if let Some(subnet) = subnet {
+                        opt.push(&subnet)?
+                    }
+                    for &code in &msg.extended_errors {
+                        opt.push(&ExtendedError::<&[u8]>::from(code))?
+                    }
+                    Ok(())
+                })
+                .map_err(short_buf)?;
+        }
+        Ok(builder.into_message().into())
+    }
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+mod test {
+    use super::super::test::{a_record, conf, local_server, name};
+    use super::*;
+    use crate::base::iana::Rtype;
+    use crate::resolv::stub::{Query, StubResolver};
+    use std::net::IpAddr;
+
+    #[tokio::test]
+    async fn lookup_host_from_script() {
+        let mock = Arc::new(MockUpstream::new());
+        mock.push(
+            (name("example.com"), Rtype::A),
+            MockReply::new(Rcode::NoError)
+                .answer(a_record("example.com", [192, 0, 2, 1]))
+                .answer(a_record("example.com", [192, 0, 2, 2])),
+        );
+        mock.push(
+            (name("example.com"), Rtype::Aaaa),
+            MockReply::new(Rcode::NoError),
+        );
+        let resolver = StubResolver::with_upstream(
+            conf(&["192.0.2.53:53".parse().unwrap()]),
+            mock.clone(),
+        );
+        let found = resolver.lookup_host(name("example.com")).await.unwrap();
+        let addrs: Vec<IpAddr> = found.iter().collect();
+        assert_eq!(
+            addrs,
+            [IpAddr::from([192, 0, 2, 1]), IpAddr::from([192, 0, 2, 2])]
+        );
+        assert!(mock.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn failover_and_truncation() {
+        let first: SocketAddr = "192.0.2.53:53".parse().unwrap();
+        let second: SocketAddr = "198.51.100.53:53".parse().unwrap();
+        let question = (name("example.com"), Rtype::A);
+        let mock = Arc::new(MockUpstream::new());
+        mock.push_server(
+            first,
+            question.clone(),
+            MockReply::error(io::ErrorKind::TimedOut),
+        );
+        mock.push_server(
+            second,
+            question.clone(),
+            MockReply::new(Rcode::NoError).truncated(),
+        );
+        mock.push(
+            question.clone(),
+            MockReply::new(Rcode::NoError)
+                .answer(a_record("example.com", [192, 0, 2, 1])),
+        );
+        let resolver =
+            StubResolver::with_upstream(conf(&[first, second]), mock.clone());
+        let answer = resolver.query(question).await.unwrap();
+        assert!(!answer.is_truncated());
+        assert_eq!(answer.header_counts().ancount(), 1);
+        let servers: Vec<_> =
+            mock.requests().into_iter().map(|(addr, _)| addr).collect();
+        assert_eq!(servers, [first, second, first]);
+    }
+
+    #[tokio::test]
+    async fn unscripted_request() {
+        let resolver = StubResolver::with_upstream(
+            conf(&["192.0.2.53:53".parse().unwrap()]),
+            MockUpstream::new(),
+        );
+        assert_eq!(
+            resolver
+                .query((name("example.com"), Rtype::A))
+                .await
+                .err()
+                .map(|err| err.kind()),
+            Some(io::ErrorKind::NotFound)
+        );
+    }
+
+    async fn net_exchange<R: Runtime>(runtime: R) {
+        let addr = local_server();
+        let upstream = NetUpstream::with_runtime(runtime);
+        let request =
+            Query::create_message((name("example.com"), Rtype::A).into());
+        for transport in [Transport::Udp, Transport::Tcp] {
+            let answer = upstream
+                .query(&ServerConf::new(addr, transport), &request)
+                .await
+                .unwrap();
+            assert_eq!(answer.header_counts().ancount(), 1);
+        }
+    }
+
+    #[cfg(feature = "resolv-tokio")]
+    #[tokio::test]
+    async fn net_tokio() {
+        net_exchange(crate::resolv::runtime::TokioRuntime).await
+    }
+
+    #[cfg(feature = "resolv-async-std")]
+    #[test]
+    fn net_async_std() {
+        async_std::task::block_on(net_exchange(
+            crate::resolv::runtime::AsyncStdRuntime,
+        ))
+    }
+
+    #[cfg(feature = "resolv-smol")]
+    #[test]
+    fn net_smol() {
+        smol::block_on(net_exchange(crate::resolv::runtime::SmolRuntime))
+    }
+}
@@ -0,0 +1,241 @@
+//! Diffie-Hellman key agreement and MD5 for TKEY.
+//!
+//! The Diffie-Hellman exchange mode of TKEY as defined in [RFC 2930] uses
+//! finite field Diffie-Hellman with the groups of [RFC 2539] and derives
+//! the shared secret via MD5. Neither of these is provided by _ring,_ so
+//! this module wraps the constant-time modular arithmetic of the
+//! _crypto-bigint_ crate and the MD5 implementation of the _md-5_ crate.
+//! Intermediate copies of private values and shared secrets are zeroized
+//! before they are dropped.
+//!
+//! [RFC 2539]: https://tools.ietf.org/html/rfc2539
+//! [RFC 2930]: https://tools.ietf.org/html/rfc2930
+#![cfg(feature = "tkey")]
+
+use crypto_bigint::modular::runtime_mod::{DynResidue, DynResidueParams};
+use crypto_bigint::{Encoding, U1024, U256};
+use md5::{Digest, Md5};
+use zeroize::Zeroize;
+
+//------------ Well-known Primes ---------------------------------------------
+
+/// The 768 bit prime of the first Oakley group.
+///
+/// This is well-known prime 1 in RFC 2539.
+pub const OAKLEY_768: &[u8] = &[
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xc9, 0x0f, 0xda, 0xa2,
+    0x21, 0x68, 0xc2, 0x34, 0xc4, 0xc6, 0x62, 0x8b, 0x80, 0xdc, 0x1c, 0xd1,
+    0x29, 0x02, 0x4e, 0x08, 0x8a, 0x67, 0xcc, 0x74, 0x02, 0x0b, 0xbe, 0xa6,
+    0x3b, 0x13, 0x9b, 0x22, 0x51, 0x4a, 0x08, 0x79, 0x8e, 0x34, 0x04, 0xdd,
+    0xef, 0x95, 0x19, 0xb3, 0xcd, 0x3a, 0x43, 0x1b, 0x30, 0x2b, 0x0a, 0x6d,
+    0xf2, 0x5f, 0x14, 0x37, 0x4f, 0xe1, 0x35, 0x6d, 0x6d, 0x51, 0xc2, 0x45,
+    0xe4, 0x85, 0xb5, 0x76, 0x62, 0x5e, 0x7e, 0xc6, 0xf4, 0x4c, 0x42, 0xe9,
+    0xa6, 0x3a, 0x36, 0x20, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+
+/// The 1024 bit prime of the second Oakley group.
+///
+/// This is well-known prime 2 in RFC 2539.
+pub const OAKLEY_1024: &[u8] = &[
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xc9, 0x0f, 0xda, 0xa2,
+    0x21, 0x68, 0xc2, 0x34, 0xc4, 0xc6, 0x62, 0x8b, 0x80, 0xdc, 0x1c, 0xd1,
+    0x29, 0x02, 0x4e, 0x08, 0x8a, 0x67, 0xcc, 0x74, 0x02, 0x0b, 0xbe, 0xa6,
+    0x3b, 0x13, 0x9b, 0x22, 0x51, 0x4a, 0x08, 0x79, 0x8e, 0x34, 0x04, 0xdd,
+    0xef, 0x95, 0x19, 0xb3, 0xcd, 0x3a, 0x43, 0x1b, 0x30, 0x2b, 0x0a, 0x6d,
+    0xf2, 0x5f, 0x14, 0x37, 0x4f, 0xe1, 0x35, 0x6d, 0x6d, 0x51, 0xc2, 0x45,
+    0xe4, 0x85, 0xb5, 0x76, 0x62, 0x5e, 0x7e, 0xc6, 0xf4, 0x4c, 0x42, 0xe9,
+    0xa6, 0x37, 0xed, 0x6b, 0x0b, 0xff, 0x5c, 0xb6, 0xf4, 0x06, 0xb7, 0xed,
+    0xee, 0x38, 0x6b, 0xfb, 0x5a, 0x89, 0x9f, 0xa5, 0xae, 0x9f, 0x24, 0x11,
+    0x7c, 0x4b, 0x1f, 0xe6, 0x49, 0x28, 0x66, 0x51, 0xec, 0xe6, 0x53, 0x81,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+
+/// The generator used with both well-known primes.
+pub const GENERATOR: u32 = 2;
+
+/// The maximum length of a prime and thus of all values in octets.
+pub const MAX_LEN: usize = 128;
+
+/// The maximum length of a private value in octets.
+pub const MAX_PRIVATE_LEN: usize = 32;
+
+//------------ Public Functions ----------------------------------------------
+
+/// Calculates the public value for a private value.
+///
+/// Writes the public value in big-endian octets without leading zeros to
+/// the beginning of `target` and returns the number of octets written.
+///
+/// The `prime` must be one of the well-known primes and `private` must not
+/// be longer than [`MAX_PRIVATE_LEN`].
+pub fn public_value(
+    prime: &[u8],
+    private: &[u8],
+    target: &mut [u8; MAX_LEN],
+) -> usize {
+    write(&pow(prime, &U1024::from_u32(GENERATOR), private), target)
+}
+
+/// Calculates the shared secret from a private and the peer’s public value.
+///
+/// Writes the secret in big-endian octets without leading zeros to the
+/// beginning of `target` and returns the number of octets written.
+///
+/// Returns `None` if the peer’s public value is not within the range of
+/// acceptable values, i.e., is not greater than one and less than the prime
+/// minus one.
+pub fn shared_secret(
+    prime: &[u8],
+    private: &[u8],
+    public: &[u8],
+    target: &mut [u8; MAX_LEN],
+) -> Option<usize> {
+    let public = read(public)?;
+    let max = read(prime)?.wrapping_sub(&U1024::ONE);
+    if public <= U1024::ONE || public >= max {
+        return None;
+    }
+    let mut secret = pow(prime, &public, private);
+    let res = write(&secret, target);
+    secret.zeroize();
+    Some(res)
+}
+
+/// Calculates the MD5 digest over the concatenation of `parts`.
+pub fn md5(parts: &[&[u8]]) -> [u8; 16] {
+    let mut hasher = Md5::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Calculates `base` to the power of `exponent` modulo `prime`.
+///
+/// The calculation takes the same time for all exponents.
+fn pow(prime: &[u8], base: &U1024, exponent: &[u8]) -> U1024 {
+    assert!(exponent.len() <= MAX_PRIVATE_LEN);
+    let prime = read(prime).expect("prime too long");
+    let mut buf = [0u8; MAX_PRIVATE_LEN];
+    buf[MAX_PRIVATE_LEN - exponent.len()..].copy_from_slice(exponent);
+    let mut exponent = U256::from_be_slice(&buf);
+    buf.zeroize();
+    let mut residue =
+        DynResidue::new(base, DynResidueParams::new(&prime)).pow(&exponent);
+    exponent.zeroize();
+    let res = residue.retrieve();
+    residue.zeroize();
+    res
+}
+
+/// Reads a value from big-endian octets.
+///
+/// Returns `None` if the value is longer than [`MAX_LEN`].
+fn read(octets: &[u8]) -> Option<U1024> {
+    if octets.len() > MAX_LEN {
+        return None;
+    }
+    let mut buf = [0u8; MAX_LEN];
+    buf[MAX_LEN - octets.len()..].copy_from_slice(octets);
+    Some(U1024::from_be_slice(&buf))
+}
+
+/// Writes a value as big-endian octets without leading zeros.
+///
+/// Returns the number of octets written to the beginning of `target`.
+fn write(value: &U1024, target: &mut [u8; MAX_LEN]) -> usize {
+    let mut buf = value.to_be_bytes();
+    let start = buf.iter().take_while(|x| **x == 0).count();
+    let len = MAX_LEN - start;
+    target[..len].copy_from_slice(&buf[start..]);
+    buf.zeroize();
+    len
+}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+
+    fn hex(octets: &[u8]) -> std::string::String {
+        octets
+            .iter()
+            .map(|octet| format!("{:02x}", octet))
+            .collect()
+    }
+
+    #[test]
+    fn md5_vectors() {
+        assert_eq!(hex(&md5(&[])), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(
+            hex(&md5(&[b"a", b"bc"])),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            hex(&md5(&[
+                b"12345678901234567890123456789012345678901234567890\
+                         123456789012345678901234567890"
+            ])),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+    }
+
+    #[test]
+    fn public_values() {
+        let private = b"\x01\x23\x45\x67\x89\xab\xcd\xef\xfe\xdc\xba\x98\
+                        \x76\x54\x32\x10\x01\x23\x45\x67\x89\xab\xcd\xef\
+                        \xfe\xdc\xba\x98\x76\x54\x32\x10";
+        let mut buf = [0; MAX_LEN];
+        let len = public_value(OAKLEY_768, private, &mut buf);
+        assert_eq!(
+            hex(&buf[..len]),
+            "6b6af96ab39987e4046b3efe205d349eccaf5321191e8117ddda3bfff4931d0f\
+             65d950e7da7fcfdd4c05976f0bb8fbd25340ecd605caada01b902348eff51f24\
+             689297f99cfea692f676e87569f0e1da47a328bbe57afe38b6b55c40fa84a6a8"
+        );
+        let len = public_value(OAKLEY_1024, private, &mut buf);
+        assert_eq!(
+            hex(&buf[..len]),
+            "8bcb3cfc178d5229fa41738a82f420843ea6a4debff3e17b2e666b82e8eea453\
+             7dfa42894fb91e90aaf6a2adbc44a68fadbdcc3b77d78cae86c0a39a7b300f2d\
+             b230ebb2799c624c9c8335b06fb848c73456c710a231408382e43063451013cb\
+             2570e41b5d960474b8f7a1bf2f124b84dea830ade7c5a816955f11c4881c40e2"
+        );
+    }
+
+    #[test]
+    fn agreement() {
+        let (mut one, mut two) = ([0; MAX_LEN], [0; MAX_LEN]);
+        let one_len = public_value(OAKLEY_1024, b"\x12\x34\x56", &mut one);
+        let two_len = public_value(OAKLEY_1024, b"\xfe\xdc\xba", &mut two);
+        let (mut one_secret, mut two_secret) = ([0; MAX_LEN], [0; MAX_LEN]);
+        let one_secret_len = shared_secret(
+            OAKLEY_1024,
+            b"\x12\x34\x56",
+            &two[..two_len],
+            &mut one_secret,
+        )
+        .unwrap();
+        let two_secret_len = shared_secret(
+            OAKLEY_1024,
+            b"\xfe\xdc\xba",
+            &one[..one_len],
+            &mut two_secret,
+        )
+        .unwrap();
+        assert_eq!(
+            &one_secret[..one_secret_len],
+            &two_secret[..two_secret_len]
+        );
+
+        assert!(
+            shared_secret(OAKLEY_768, b"\x01", b"\x01", &mut one).is_none()
+        );
+        assert!(shared_secret(OAKLEY_768, b"\x01", OAKLEY_768, &mut one)
+            .is_none());
+    }
+}
@@ -40,6 +40,11 @@
 //! is implementations for a hash map as well as a single key (the latter
 //! mostly for testing).
 //!
+//! Keys don’t have to be configured manually on both sides. The [`tkey`]
+//! module, available with the `tkey` feature, provides means to establish
+//! new keys via a Diffie-Hellman exchange and to delete them again using
+//! the TKEY protocol.
+//!
 //! [RFC 2104]: https://tools.ietf.org/html/rfc2104
 //! [RFC 2845]: https://tools.ietf.org/html/rfc2845
 //! [RFC 4635]: https://tools.ietf.org/html/rfc4653
@@ -51,10 +56,13 @@
 //! [`ServerTransaction`]: struct.ServerTransaction.html
 //! [`ClientSequence`]: struct.ClientSequence.html
 //! [`ServerSequence`]: struct.ServerSequence.html
+//! [`tkey`]: tkey/index.html
 #![cfg(feature = "tsig")]
 #![cfg_attr(docsrs, doc(cfg(feature = "tsig")))]
 
+mod dh;
 mod interop;
+pub mod tkey;
 
 use crate::base::header::HeaderSection;
 use crate::base::iana::{Class, Rcode, TsigRcode};
@@ -1329,11 +1337,12 @@ where
         let mut start = section.pos();
         let mut record = section.next()?;
         loop {
+            let pos = section.pos();
             record = match section.next() {
                 Some(record) => record,
                 None => break,
             };
-            start = section.pos();
+            start = pos;
         }
         record
             .ok()?
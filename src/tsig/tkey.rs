@@ -0,0 +1,1194 @@
+//! Establishing and deleting TSIG keys via TKEY.
+//!
+//! [RFC 2930] defines the [TKEY] record type and a protocol for agreeing on
+//! the shared secret keys used by TSIG. A client sends a query for the key
+//! name with record type TKEY and a TKEY record in the additional section
+//! describing what it wants to do. The server responds with a TKEY record
+//! in the answer section.
+//!
+//! This module implements two of the modes defined by the RFC. In
+//! _Diffie-Hellman exchange_ mode, both sides add a Diffie-Hellman public
+//! key in a KEY record as defined by [RFC 2539] and derive the same secret
+//! from the exchanged values. The client side is provided by
+//! [`ClientDhExchange`], the server side by [`ServerDhExchange`]. Both
+//! produce a [`Key`] that can be used for signing further transactions and
+//! added to a [`KeyStore`]. Only the two well-known groups of RFC 2539 are
+//! supported.
+//!
+//! In _key deletion_ mode, the client asks the server to forget a key
+//! established earlier. The request has to be signed with the key in
+//! question. This is provided by [`ClientKeyDeletion`] and
+//! [`ServerKeyDeletion`].
+//!
+//! A server can use [`request_mode`] to determine which of the types to use
+//! for a received TKEY query.
+//!
+//! This module is only available if the `tkey` feature is enabled.
+//!
+//! [RFC 2539]: https://tools.ietf.org/html/rfc2539
+//! [RFC 2930]: https://tools.ietf.org/html/rfc2930
+//! [TKEY]: ../../rdata/rfc2930/struct.Tkey.html
+//! [`ClientDhExchange`]: struct.ClientDhExchange.html
+//! [`ServerDhExchange`]: struct.ServerDhExchange.html
+//! [`ClientKeyDeletion`]: struct.ClientKeyDeletion.html
+//! [`ServerKeyDeletion`]: struct.ServerKeyDeletion.html
+//! [`Key`]: ../struct.Key.html
+//! [`KeyStore`]: ../trait.KeyStore.html
+//! [`request_mode`]: fn.request_mode.html
+#![cfg(feature = "tkey")]
+#![cfg_attr(docsrs, doc(cfg(feature = "tkey")))]
+
+use super::dh;
+use super::{
+    Algorithm, ClientTransaction, Key, ServerTransaction, ValidationError,
+};
+use crate::base::iana::{Class, Rcode, Rtype, SecAlg, TkeyMode, TsigRcode};
+use crate::base::message::{Message, RecordSection};
+use crate::base::message_builder::{AdditionalBuilder, MessageBuilder};
+use crate::base::name::{Dname, ParsedDname, ToDname};
+use crate::base::octets::{
+    OctetsBuilder, OctetsRef, OctetsVec, ParseError, ShortBuf,
+};
+use crate::base::rdata::UnknownRecordData;
+use crate::base::record::Record;
+use crate::base::serial::Serial;
+use crate::rdata::rfc2845::Time48;
+use crate::rdata::rfc2930::Tkey;
+use core::fmt;
+use ring::{hmac, rand};
+use zeroize::Zeroize;
+
+//------------ Constants -----------------------------------------------------
+
+/// The length of the nonce sent in the key data of a TKEY record.
+const NONCE_LEN: usize = 32;
+
+/// The length of the private Diffie-Hellman value in octets.
+const PRIVATE_LEN: usize = 32;
+
+/// The maximum length of the record data of a KEY record we create.
+///
+/// This is the flags, protocol, and algorithm followed by prime, generator,
+/// and public value, each with a two octet length.
+const KEY_RDATA_LEN: usize = 4 + 3 + 2 + 2 + dh::MAX_LEN;
+
+/// The flags of the KEY records we create.
+///
+/// This marks the key as usable for both authentication and confidentiality
+/// and as belonging to a host.
+const KEY_FLAGS: u16 = 0x0200;
+
+/// The protocol of the KEY records we create.
+///
+/// This is the value for DNSSEC.
+const KEY_PROTOCOL: u8 = 3;
+
+/// The maximum length of the shared secret.
+const SECRET_LEN: usize = dh::MAX_LEN;
+
+//------------ request_mode --------------------------------------------------
+
+/// Returns the mode of a received TKEY request.
+///
+/// Returns `None` if there is no TKEY record in the additional section of
+/// the message.
+pub fn request_mode<Octets>(message: &Message<Octets>) -> Option<TkeyMode>
+where
+    Octets: AsRef<[u8]>,
+    for<'a> &'a Octets: OctetsRef,
+{
+    find_tkey(message.additional().ok()?)
+        .ok()?
+        .map(|tkey| tkey.data().mode())
+}
+
+//------------ DhGroup -------------------------------------------------------
+
+/// The Diffie-Hellman groups available for key agreement.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DhGroup {
+    /// The 768 bit group, well-known prime 1 of RFC 2539.
+    Oakley768,
+
+    /// The 1024 bit group, well-known prime 2 of RFC 2539.
+    Oakley1024,
+}
+
+impl DhGroup {
+    /// Returns the index of the group’s prime in the table of RFC 2539.
+    fn index(self) -> u8 {
+        match self {
+            DhGroup::Oakley768 => 1,
+            DhGroup::Oakley1024 => 2,
+        }
+    }
+
+    /// Returns the group’s prime as big-endian octets.
+    fn prime(self) -> &'static [u8] {
+        match self {
+            DhGroup::Oakley768 => dh::OAKLEY_768,
+            DhGroup::Oakley1024 => dh::OAKLEY_1024,
+        }
+    }
+
+    /// Returns the group for a prime and generator given in a KEY record.
+    fn from_key(prime: &[u8], generator: &[u8]) -> Option<Self> {
+        let group = match *prime {
+            [1] | [0, 1] => DhGroup::Oakley768,
+            [2] | [0, 2] => DhGroup::Oakley1024,
+            _ if prime == dh::OAKLEY_768 => DhGroup::Oakley768,
+            _ if prime == dh::OAKLEY_1024 => DhGroup::Oakley1024,
+            _ => return None,
+        };
+        if prime.len() > 2 {
+            let start = generator.iter().take_while(|x| **x == 0).count();
+            if generator[start..] != dh::GENERATOR.to_be_bytes()[3..] {
+                return None;
+            }
+        }
+        Some(group)
+    }
+}
+
+//------------ ClientDhExchange ----------------------------------------------
+
+/// The client side of a Diffie-Hellman key exchange.
+///
+/// You start an exchange by calling [`request`], which adds a TKEY query
+/// with a fresh Diffie-Hellman public key to a message and returns a value
+/// of this type that keeps the private key. Once the server’s response has
+/// been received, you pass it to [`answer`] to derive the new TSIG key.
+///
+/// [`request`]: #method.request
+/// [`answer`]: #method.answer
+#[derive(Debug)]
+pub struct ClientDhExchange {
+    /// The algorithm of the requested key.
+    algorithm: Algorithm,
+
+    /// Our Diffie-Hellman key.
+    dh: DhKey,
+
+    /// The nonce we sent in the TKEY record.
+    nonce: [u8; NONCE_LEN],
+}
+
+impl ClientDhExchange {
+    /// Creates a request for a new key.
+    ///
+    /// The request asks for a key called `name` to be used with `algorithm`.
+    /// The key is to be valid for `lifetime` seconds starting at `now`. The
+    /// Diffie-Hellman key is created for `group` using `rng`, which is also
+    /// used for creating the nonce.
+    ///
+    /// The method adds the question and the TKEY and KEY records to
+    /// `builder` and returns the message ready for adding further records to
+    /// the additional section together with the exchange.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if `lifetime` is greater than `2^31 - 1`.
+    pub fn request<Target, N>(
+        name: N,
+        algorithm: Algorithm,
+        group: DhGroup,
+        lifetime: u32,
+        rng: &dyn rand::SecureRandom,
+        builder: MessageBuilder<Target>,
+        now: Time48,
+    ) -> Result<(Self, AdditionalBuilder<Target>), TkeyError>
+    where
+        Target: OctetsBuilder + AsRef<[u8]> + AsMut<[u8]>,
+        N: ToDname,
+    {
+        let dh = DhKey::generate(group, rng)?;
+        let mut nonce = [0; NONCE_LEN];
+        rng.fill(&mut nonce)?;
+        let inception = Serial::from(u64::from(now) as u32);
+        let mut builder = builder.question();
+        builder.push((&name, Rtype::Tkey, Class::Any))?;
+        let mut builder = builder.additional();
+        builder.push((
+            &name,
+            Class::Any,
+            0,
+            Tkey::new(
+                algorithm.to_dname(),
+                inception,
+                inception.add(lifetime),
+                TkeyMode::DiffieHellman,
+                TsigRcode::NoError,
+                nonce.as_ref(),
+                b"".as_ref(),
+            ),
+        ))?;
+        builder.push((
+            &name,
+            Class::In,
+            0,
+            UnknownRecordData::from_octets(Rtype::Key, dh.rdata()),
+        ))?;
+        Ok((
+            ClientDhExchange {
+                algorithm,
+                dh,
+                nonce,
+            },
+            builder,
+        ))
+    }
+
+    /// Derives the new key from the server’s response.
+    ///
+    /// The name of the key is taken from the TKEY record of the response
+    /// since the server may have changed it.
+    pub fn answer<Octets>(
+        &self,
+        message: &Message<Octets>,
+    ) -> Result<Key, TkeyError>
+    where
+        Octets: AsRef<[u8]>,
+        for<'a> &'a Octets: OctetsRef,
+    {
+        let rcode = message.header().rcode();
+        if rcode != Rcode::NoError {
+            return Err(TkeyError::Rcode(rcode));
+        }
+        let tkey = match find_tkey(message.answer()?)? {
+            Some(tkey) => tkey,
+            None => return Err(TkeyError::FormErr),
+        };
+        if tkey.data().error() != TsigRcode::NoError {
+            return Err(TkeyError::Server(tkey.data().error()));
+        }
+        if tkey.data().mode() != TkeyMode::DiffieHellman
+            || Algorithm::from_dname(tkey.data().algorithm())
+                != Some(self.algorithm)
+        {
+            return Err(TkeyError::FormErr);
+        }
+        let key = match find_dh_key(message.answer()?)? {
+            Some(key) => key,
+            None => return Err(TkeyError::FormErr),
+        };
+        let (group, public) = match parse_dh_key(key.data().data().as_ref()) {
+            Some(some) => some,
+            None => return Err(TkeyError::FormErr),
+        };
+        if group != self.dh.group {
+            return Err(TkeyError::BadKey);
+        }
+        let name = tkey
+            .owner()
+            .to_dname::<OctetsVec>()
+            .map_err(|_| TkeyError::FormErr)?;
+        let mut secret = [0; SECRET_LEN];
+        let len = self
+            .dh
+            .derive_secret(
+                public,
+                &self.nonce,
+                tkey.data().key_slice(),
+                &mut secret,
+            )
+            .ok_or(TkeyError::BadKey)?;
+        let key = new_key(self.algorithm, &secret[..len], name);
+        secret.zeroize();
+        Ok(key)
+    }
+
+    /// Returns the algorithm of the requested key.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+}
+
+//------------ ServerDhExchange ----------------------------------------------
+
+/// The server side of a Diffie-Hellman key exchange.
+///
+/// A received TKEY request in Diffie-Hellman exchange mode is given to
+/// [`request`] which derives the new key right away. It can be retrieved
+/// via [`key`] or [`into_key`] and should be added to the server’s key
+/// store. The response to be sent back to the client is created with
+/// [`answer`].
+///
+/// The validity period of the new key is taken from the request. It can be
+/// retrieved via [`inception`] and [`expiration`].
+///
+/// [`request`]: #method.request
+/// [`answer`]: #method.answer
+/// [`key`]: #method.key
+/// [`into_key`]: #method.into_key
+/// [`inception`]: #method.inception
+/// [`expiration`]: #method.expiration
+#[derive(Debug)]
+pub struct ServerDhExchange {
+    /// The new key.
+    key: Key,
+
+    /// The start of the validity period of the key.
+    inception: Serial,
+
+    /// The end of the validity period of the key.
+    expiration: Serial,
+
+    /// Our Diffie-Hellman key.
+    dh: DhKey,
+
+    /// The nonce we are sending in the TKEY record.
+    nonce: [u8; NONCE_LEN],
+}
+
+impl ServerDhExchange {
+    /// Processes a request.
+    ///
+    /// The request must contain a TKEY record in Diffie-Hellman exchange
+    /// mode and a KEY record with the client’s public key in its additional
+    /// section. The method creates a Diffie-Hellman key for the same group
+    /// and a nonce using `rng` and derives the new key.
+    ///
+    /// If anything is wrong with the request, returns an error that can be
+    /// used to create the error response.
+    pub fn request<Octets>(
+        message: &Message<Octets>,
+        rng: &dyn rand::SecureRandom,
+    ) -> Result<Self, ServerTkeyError>
+    where
+        Octets: AsRef<[u8]>,
+        for<'a> &'a Octets: OctetsRef,
+    {
+        let tkey = match find_tkey(message.additional()?)? {
+            Some(tkey) => tkey,
+            None => return Err(ServerTkeyError::form_err()),
+        };
+        if tkey.data().mode() != TkeyMode::DiffieHellman {
+            return Err(ServerTkeyError::tkey(TsigRcode::BadMode));
+        }
+        let algorithm = match Algorithm::from_dname(tkey.data().algorithm()) {
+            Some(algorithm) => algorithm,
+            None => return Err(ServerTkeyError::tkey(TsigRcode::BadAlg)),
+        };
+        let key = match find_dh_key(message.additional()?)? {
+            Some(key) => key,
+            None => return Err(ServerTkeyError::tkey(TsigRcode::BadKey)),
+        };
+        let (group, public) = match parse_dh_key(key.data().data().as_ref()) {
+            Some(some) => some,
+            None => return Err(ServerTkeyError::tkey(TsigRcode::BadKey)),
+        };
+        let dh = DhKey::generate(group, rng)
+            .map_err(|_| ServerTkeyError::new(Rcode::ServFail))?;
+        let mut nonce = [0; NONCE_LEN];
+        rng.fill(&mut nonce)
+            .map_err(|_| ServerTkeyError::new(Rcode::ServFail))?;
+        let name = tkey
+            .owner()
+            .to_dname::<OctetsVec>()
+            .map_err(|_| ServerTkeyError::form_err())?;
+        let mut secret = [0; SECRET_LEN];
+        let len = dh
+            .derive_secret(
+                public,
+                tkey.data().key_slice(),
+                &nonce,
+                &mut secret,
+            )
+            .ok_or_else(|| ServerTkeyError::tkey(TsigRcode::BadKey))?;
+        let key = new_key(algorithm, &secret[..len], name);
+        secret.zeroize();
+        Ok(ServerDhExchange {
+            key,
+            inception: tkey.data().inception(),
+            expiration: tkey.data().expiration(),
+            dh,
+            nonce,
+        })
+    }
+
+    /// Produces the response.
+    ///
+    /// The response contains the TKEY record and our KEY record in the
+    /// answer section. The client’s KEY record is echoed in the additional
+    /// section. The returned builder can be used to add further records to
+    /// the additional section.
+    pub fn answer<Octets, Target>(
+        &self,
+        message: &Message<Octets>,
+        builder: MessageBuilder<Target>,
+    ) -> Result<AdditionalBuilder<Target>, ShortBuf>
+    where
+        Octets: AsRef<[u8]>,
+        for<'a> &'a Octets: OctetsRef,
+        Target: OctetsBuilder + AsRef<[u8]> + AsMut<[u8]>,
+    {
+        let mut builder = builder.start_answer(message, Rcode::NoError)?;
+        builder.push((
+            self.key.name(),
+            Class::Any,
+            0,
+            Tkey::new(
+                self.key.algorithm().to_dname(),
+                self.inception,
+                self.expiration,
+                TkeyMode::DiffieHellman,
+                TsigRcode::NoError,
+                self.nonce.as_ref(),
+                b"".as_ref(),
+            ),
+        ))?;
+        builder.push((
+            self.key.name(),
+            Class::In,
+            0,
+            UnknownRecordData::from_octets(Rtype::Key, self.dh.rdata()),
+        ))?;
+        let mut builder = builder.additional();
+        let client_key = message
+            .additional()
+            .ok()
+            .and_then(|section| find_dh_key(section).ok())
+            .flatten();
+        if let Some(client_key) = client_key {
+            builder.push(client_key)?;
+        }
+        Ok(builder)
+    }
+
+    /// Returns a reference to the new key.
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+
+    /// Converts the exchange into the new key.
+    pub fn into_key(self) -> Key {
+        self.key
+    }
+
+    /// Returns the start of the validity period of the new key.
+    pub fn inception(&self) -> Serial {
+        self.inception
+    }
+
+    /// Returns the end of the validity period of the new key.
+    pub fn expiration(&self) -> Serial {
+        self.expiration
+    }
+}
+
+//------------ ClientKeyDeletion ---------------------------------------------
+
+/// The client side of deleting a key.
+///
+/// A request for deleting a key is created via [`request`]. It is signed
+/// with the key to be deleted. The server’s response is then checked via
+/// [`answer`]. If this succeeds, the server has deleted the key.
+///
+/// [`request`]: #method.request
+/// [`answer`]: #method.answer
+#[derive(Clone, Debug)]
+pub struct ClientKeyDeletion<K> {
+    /// The transaction for signing the request.
+    transaction: ClientTransaction<K>,
+}
+
+impl<K: AsRef<Key>> ClientKeyDeletion<K> {
+    /// Creates a request for deleting `key`.
+    ///
+    /// The method adds the question and the TKEY record to `builder` and
+    /// signs the message with `key`. Since the TSIG record has to be the
+    /// last record of the message, the message must not be altered anymore
+    /// after this.
+    pub fn request<Target>(
+        key: K,
+        builder: MessageBuilder<Target>,
+        now: Time48,
+    ) -> Result<(Self, AdditionalBuilder<Target>), ShortBuf>
+    where
+        Target: OctetsBuilder + AsRef<[u8]> + AsMut<[u8]>,
+    {
+        let time = Serial::from(u64::from(now) as u32);
+        let mut builder = builder.question();
+        builder.push((key.as_ref().name(), Rtype::Tkey, Class::Any))?;
+        let mut builder = builder.additional();
+        builder.push((
+            key.as_ref().name(),
+            Class::Any,
+            0,
+            Tkey::new(
+                key.as_ref().algorithm().to_dname(),
+                time,
+                time,
+                TkeyMode::KeyDeletion,
+                TsigRcode::NoError,
+                b"".as_ref(),
+                b"".as_ref(),
+            ),
+        ))?;
+        let transaction = ClientTransaction::request(key, &mut builder, now)?;
+        Ok((ClientKeyDeletion { transaction }, builder))
+    }
+
+    /// Checks the server’s response.
+    ///
+    /// Validates the signature of the response and removes the TSIG record
+    /// from the message. Returns an error if the signature is invalid or
+    /// the server refused to delete the key.
+    pub fn answer<Octets>(
+        &self,
+        message: &mut Message<Octets>,
+        now: Time48,
+    ) -> Result<(), TkeyError>
+    where
+        Octets: AsRef<[u8]> + AsMut<[u8]>,
+        for<'a> &'a Octets: OctetsRef,
+    {
+        self.transaction.answer(message, now)?;
+        let rcode = message.header().rcode();
+        if rcode != Rcode::NoError {
+            return Err(TkeyError::Rcode(rcode));
+        }
+        let tkey = match find_tkey(message.answer()?)? {
+            Some(tkey) => tkey,
+            None => return Err(TkeyError::FormErr),
+        };
+        if tkey.data().error() != TsigRcode::NoError {
+            return Err(TkeyError::Server(tkey.data().error()));
+        }
+        if tkey.data().mode() != TkeyMode::KeyDeletion {
+            return Err(TkeyError::FormErr);
+        }
+        Ok(())
+    }
+
+    /// Returns a reference to the key to be deleted.
+    pub fn key(&self) -> &Key {
+        self.transaction.key()
+    }
+}
+
+//------------ ServerKeyDeletion ---------------------------------------------
+
+/// The server side of deleting a key.
+///
+/// A request for deleting a key has to be signed with that key, so it first
+/// has to be processed by the `request` function of [`ServerTransaction`].
+/// The resulting transaction and the message are then given to
+/// [`request`]. If [`is_accepted`] returns `true` afterwards, the key
+/// returned by [`key`] should be removed from the key store. In any case,
+/// the response is created and signed via [`answer`].
+///
+/// [`ServerTransaction`]: ../struct.ServerTransaction.html
+/// [`request`]: #method.request
+/// [`is_accepted`]: #method.is_accepted
+/// [`key`]: #method.key
+/// [`answer`]: #method.answer
+#[derive(Clone, Debug)]
+pub struct ServerKeyDeletion<K> {
+    /// The transaction for signing the response.
+    transaction: ServerTransaction<K>,
+
+    /// The response code for the response.
+    rcode: Rcode,
+
+    /// The error for the TKEY record of the response.
+    error: TsigRcode,
+}
+
+impl<K: AsRef<Key>> ServerKeyDeletion<K> {
+    /// Processes a request signed by `transaction`’s key.
+    ///
+    /// The request is accepted if it contains a TKEY record in key deletion
+    /// mode for the key that signed it. Otherwise the response will carry
+    /// the appropriate error.
+    pub fn request<Octets>(
+        transaction: ServerTransaction<K>,
+        message: &Message<Octets>,
+    ) -> Self
+    where
+        Octets: AsRef<[u8]>,
+        for<'a> &'a Octets: OctetsRef,
+    {
+        let (rcode, error) = match message.additional().and_then(find_tkey) {
+            Ok(Some(tkey)) => {
+                let key = transaction.key();
+                if tkey.data().mode() != TkeyMode::KeyDeletion {
+                    (Rcode::NoError, TsigRcode::BadMode)
+                } else if *tkey.owner() != *key.name()
+                    || *tkey.data().algorithm() != key.algorithm().to_dname()
+                {
+                    (Rcode::NoError, TsigRcode::BadKey)
+                } else {
+                    (Rcode::NoError, TsigRcode::NoError)
+                }
+            }
+            _ => (Rcode::FormErr, TsigRcode::NoError),
+        };
+        ServerKeyDeletion {
+            transaction,
+            rcode,
+            error,
+        }
+    }
+
+    /// Returns whether the key should be deleted.
+    pub fn is_accepted(&self) -> bool {
+        self.rcode == Rcode::NoError && self.error == TsigRcode::NoError
+    }
+
+    /// Returns a reference to the key to be deleted.
+    pub fn key(&self) -> &Key {
+        self.transaction.key()
+    }
+
+    /// Produces the signed response.
+    pub fn answer<Octets, Target>(
+        self,
+        message: &Message<Octets>,
+        builder: MessageBuilder<Target>,
+        now: Time48,
+    ) -> Result<AdditionalBuilder<Target>, ShortBuf>
+    where
+        Octets: AsRef<[u8]>,
+        for<'a> &'a Octets: OctetsRef,
+        Target: OctetsBuilder + AsRef<[u8]> + AsMut<[u8]>,
+    {
+        let mut builder = builder.start_answer(message, self.rcode)?;
+        if self.rcode == Rcode::NoError {
+            push_tkey_echo(message, &mut builder, self.error)?;
+        }
+        let mut builder = builder.additional();
+        self.transaction.answer(&mut builder, now)?;
+        Ok(builder)
+    }
+}
+
+//------------ DhKey ---------------------------------------------------------
+
+/// A Diffie-Hellman key pair.
+struct DhKey {
+    /// The group of the key.
+    group: DhGroup,
+
+    /// The private value.
+    private: [u8; PRIVATE_LEN],
+
+    /// The record data of a KEY record with the public value.
+    rdata: [u8; KEY_RDATA_LEN],
+
+    /// The length of the record data.
+    rdata_len: usize,
+}
+
+impl DhKey {
+    /// Generates a new key pair.
+    fn generate(
+        group: DhGroup,
+        rng: &dyn rand::SecureRandom,
+    ) -> Result<Self, ring::error::Unspecified> {
+        let mut private = [0; PRIVATE_LEN];
+        rng.fill(&mut private)?;
+        let mut public = [0; dh::MAX_LEN];
+        let public_len =
+            dh::public_value(group.prime(), &private, &mut public);
+
+        let mut rdata = [0; KEY_RDATA_LEN];
+        rdata[..2].copy_from_slice(&KEY_FLAGS.to_be_bytes());
+        rdata[2] = KEY_PROTOCOL;
+        rdata[3] = SecAlg::Dh.to_int();
+        rdata[4..7].copy_from_slice(&[0, 1, group.index()]);
+        rdata[7..9].copy_from_slice(&[0, 0]);
+        rdata[9..11].copy_from_slice(&(public_len as u16).to_be_bytes());
+        rdata[11..11 + public_len].copy_from_slice(&public[..public_len]);
+        Ok(DhKey {
+            group,
+            private,
+            rdata,
+            rdata_len: 11 + public_len,
+        })
+    }
+
+    /// Returns the record data of the KEY record for our public value.
+    fn rdata(&self) -> &[u8] {
+        &self.rdata[..self.rdata_len]
+    }
+
+    /// Derives the keying material.
+    ///
+    /// Calculates the shared secret from our private value and the peer’s
+    /// `public` value, then derives the keying material from the
+    /// key data of the TKEY records of the query and the response as
+    /// described in section 4.1 of RFC 2930:
+    ///
+    /// ```text
+    /// XOR(DH value, MD5(query data | DH value) | MD5(server data | DH value))
+    /// ```
+    ///
+    /// The shorter operand of the XOR is padded with zeros at the end, i.e.,
+    /// the result has the length of the longer operand.
+    ///
+    /// Writes the result to the beginning of `target` and returns its
+    /// length or returns `None` if the peer’s public value is unacceptable.
+    fn derive_secret(
+        &self,
+        public: &[u8],
+        query_data: &[u8],
+        server_data: &[u8],
+        target: &mut [u8; SECRET_LEN],
+    ) -> Option<usize> {
+        let mut shared = [0; dh::MAX_LEN];
+        let shared_len = dh::shared_secret(
+            self.group.prime(),
+            &self.private,
+            public,
+            &mut shared,
+        )?;
+        let mut digests = [0; 32];
+        let len = {
+            let shared = &shared[..shared_len];
+            digests[..16].copy_from_slice(&dh::md5(&[query_data, shared]));
+            digests[16..].copy_from_slice(&dh::md5(&[server_data, shared]));
+            let (long, short) = if shared.len() > digests.len() {
+                (shared, digests.as_ref())
+            } else {
+                (digests.as_ref(), shared)
+            };
+            target[..long.len()].copy_from_slice(long);
+            for (target, short) in target.iter_mut().zip(short) {
+                *target ^= *short;
+            }
+            long.len()
+        };
+        shared.zeroize();
+        digests.zeroize();
+        Some(len)
+    }
+}
+
+//--- Drop
+
+impl Drop for DhKey {
+    fn drop(&mut self) {
+        self.private.zeroize();
+    }
+}
+
+//--- Debug
+
+impl fmt::Debug for DhKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DhKey").field("group", &self.group).finish()
+    }
+}
+
+//------------ Helper Functions ----------------------------------------------
+
+/// A TKEY record parsed from a message.
+type MessageTkey<'a, Octets> = Record<
+    ParsedDname<&'a Octets>,
+    Tkey<<&'a Octets as OctetsRef>::Range, ParsedDname<&'a Octets>>,
+>;
+
+/// A KEY record parsed from a message.
+type MessageKey<'a, Octets> = Record<
+    ParsedDname<&'a Octets>,
+    UnknownRecordData<<&'a Octets as OctetsRef>::Range>,
+>;
+
+/// Returns the first TKEY record in a section.
+fn find_tkey<Octets>(
+    section: RecordSection<&Octets>,
+) -> Result<Option<MessageTkey<'_, Octets>>, ParseError>
+where
+    for<'a> &'a Octets: OctetsRef,
+{
+    for record in section {
+        if let Some(record) = record?.into_record::<Tkey<_, _>>()? {
+            return Ok(Some(record));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the first KEY record with a usable Diffie-Hellman key in a section.
+fn find_dh_key<Octets>(
+    section: RecordSection<&Octets>,
+) -> Result<Option<MessageKey<'_, Octets>>, ParseError>
+where
+    for<'a> &'a Octets: OctetsRef,
+{
+    for record in section {
+        let record = record?;
+        if record.rtype() != Rtype::Key {
+            continue;
+        }
+        if let Some(record) = record.into_record::<UnknownRecordData<_>>()? {
+            if parse_dh_key(record.data().data().as_ref()).is_some() {
+                return Ok(Some(record));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Parses the record data of a KEY record with a Diffie-Hellman key.
+///
+/// Returns the group and the public value if the record contains a key for
+/// one of the supported groups.
+fn parse_dh_key(rdata: &[u8]) -> Option<(DhGroup, &[u8])> {
+    if rdata.len() < 4
+        || rdata[0] & 0xC0 == 0xC0
+        || rdata[2] != KEY_PROTOCOL
+        || rdata[3] != SecAlg::Dh.to_int()
+    {
+        return None;
+    }
+    let (prime, data) = split_field(&rdata[4..])?;
+    let (generator, data) = split_field(data)?;
+    let (public, data) = split_field(data)?;
+    if !data.is_empty() {
+        return None;
+    }
+    Some((DhGroup::from_key(prime, generator)?, public))
+}
+
+/// Splits a field with a two octet length from the front of `data`.
+fn split_field(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    if data.len() < 2 {
+        return None;
+    }
+    let len = usize::from(u16::from_be_bytes([data[0], data[1]]));
+    let data = &data[2..];
+    if data.len() < len {
+        return None;
+    }
+    Some(data.split_at(len))
+}
+
+/// Creates a key that doesn’t allow truncation.
+fn new_key(
+    algorithm: Algorithm,
+    secret: &[u8],
+    name: Dname<OctetsVec>,
+) -> Key {
+    Key {
+        key: hmac::Key::new(algorithm.into_hmac_algorithm(), secret),
+        name,
+        min_mac_len: algorithm.native_len(),
+        signing_len: algorithm.native_len(),
+    }
+}
+
+/// Adds the TKEY record of a request with the given error to the answer.
+///
+/// Does nothing if the request doesn’t contain a TKEY record.
+fn push_tkey_echo<Octets, Target>(
+    message: &Message<Octets>,
+    builder: &mut crate::base::message_builder::AnswerBuilder<Target>,
+    error: TsigRcode,
+) -> Result<(), ShortBuf>
+where
+    Octets: AsRef<[u8]>,
+    for<'a> &'a Octets: OctetsRef,
+    Target: OctetsBuilder + AsRef<[u8]> + AsMut<[u8]>,
+{
+    let tkey = match message.additional().and_then(find_tkey) {
+        Ok(Some(tkey)) => tkey,
+        _ => return Ok(()),
+    };
+    builder.push((
+        tkey.owner(),
+        tkey.class(),
+        0,
+        Tkey::new(
+            tkey.data().algorithm(),
+            tkey.data().inception(),
+            tkey.data().expiration(),
+            tkey.data().mode(),
+            error,
+            b"".as_ref(),
+            b"".as_ref(),
+        ),
+    ))
+}
+
+//============ Error Types ===================================================
+
+//------------ TkeyError -----------------------------------------------------
+
+/// An error happened while processing a TKEY exchange on the client side.
+#[derive(Clone, Copy, Debug)]
+pub enum TkeyError {
+    /// The request didn’t fit into the message.
+    ShortBuf,
+
+    /// Generating random values failed.
+    GenerationFailed,
+
+    /// The response was malformed or didn’t fit the request.
+    FormErr,
+
+    /// The server responded with an error response code.
+    Rcode(Rcode),
+
+    /// The server reported an error in the response’s TKEY record.
+    Server(TsigRcode),
+
+    /// The server’s Diffie-Hellman key couldn’t be used.
+    BadKey,
+
+    /// The TSIG signature of the response was invalid.
+    Validation(ValidationError),
+}
+
+//--- From
+
+impl From<ShortBuf> for TkeyError {
+    fn from(_: ShortBuf) -> Self {
+        TkeyError::ShortBuf
+    }
+}
+
+impl From<ParseError> for TkeyError {
+    fn from(_: ParseError) -> Self {
+        TkeyError::FormErr
+    }
+}
+
+impl From<ValidationError> for TkeyError {
+    fn from(err: ValidationError) -> Self {
+        TkeyError::Validation(err)
+    }
+}
+
+impl From<ring::error::Unspecified> for TkeyError {
+    fn from(_: ring::error::Unspecified) -> Self {
+        TkeyError::GenerationFailed
+    }
+}
+
+//--- Display and Error
+
+impl fmt::Display for TkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TkeyError::ShortBuf => f.write_str("message too short"),
+            TkeyError::GenerationFailed => {
+                f.write_str("generating random values failed")
+            }
+            TkeyError::FormErr => f.write_str("format error"),
+            TkeyError::Rcode(rcode) => {
+                write!(f, "server responded with {}", rcode)
+            }
+            TkeyError::Server(error) => {
+                write!(f, "server reported {}", error)
+            }
+            TkeyError::BadKey => f.write_str("unusable server key"),
+            TkeyError::Validation(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TkeyError {}
+
+//------------ ServerTkeyError -----------------------------------------------
+
+/// A received TKEY request couldn’t be processed.
+///
+/// A value of this type carries all information necessary to produce the
+/// error response to be sent back to the client via [`build_message`].
+///
+/// [`build_message`]: #method.build_message
+#[derive(Clone, Copy, Debug)]
+pub struct ServerTkeyError {
+    /// The response code of the response.
+    rcode: Rcode,
+
+    /// The error for the TKEY record of the response.
+    error: TsigRcode,
+}
+
+impl ServerTkeyError {
+    /// Creates an error with the given response code.
+    fn new(rcode: Rcode) -> Self {
+        ServerTkeyError {
+            rcode,
+            error: TsigRcode::NoError,
+        }
+    }
+
+    /// Creates an error for a malformed request.
+    fn form_err() -> Self {
+        Self::new(Rcode::FormErr)
+    }
+
+    /// Creates an error reported in the TKEY record.
+    fn tkey(error: TsigRcode) -> Self {
+        ServerTkeyError {
+            rcode: Rcode::NoError,
+            error,
+        }
+    }
+
+    /// Returns the response code of the response.
+    pub fn rcode(&self) -> Rcode {
+        self.rcode
+    }
+
+    /// Returns the error for the TKEY record of the response.
+    pub fn error(&self) -> TsigRcode {
+        self.error
+    }
+
+    /// Produces the error response.
+    ///
+    /// If the error is reported in the TKEY record, the request’s TKEY
+    /// record is echoed in the answer section with the error set.
+    pub fn build_message<Octets, Target>(
+        self,
+        msg: &Message<Octets>,
+        builder: MessageBuilder<Target>,
+    ) -> Result<AdditionalBuilder<Target>, ShortBuf>
+    where
+        Octets: AsRef<[u8]>,
+        for<'a> &'a Octets: OctetsRef,
+        Target: OctetsBuilder + AsRef<[u8]> + AsMut<[u8]>,
+    {
+        let mut builder = builder.start_answer(msg, self.rcode)?;
+        if self.rcode == Rcode::NoError {
+            push_tkey_echo(msg, &mut builder, self.error)?;
+        }
+        Ok(builder.additional())
+    }
+}
+
+//--- From
+
+impl From<ParseError> for ServerTkeyError {
+    fn from(_: ParseError) -> Self {
+        Self::form_err()
+    }
+}
+
+//--- Display and Error
+
+impl fmt::Display for ServerTkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.rcode == Rcode::NoError {
+            self.error.fmt(f)
+        } else {
+            self.rcode.fmt(f)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ServerTkeyError {}
+
+//============ Testing =======================================================
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+    use ring::rand::SystemRandom;
+    use std::vec::Vec;
+
+    fn builder() -> MessageBuilder<Vec<u8>> {
+        MessageBuilder::new_vec()
+    }
+
+    #[test]
+    fn dh_exchange() {
+        let rng = SystemRandom::new();
+        let now = Time48::from_u64(1_600_000_000);
+        let (client, request) = ClientDhExchange::request(
+            Dname::<Vec<u8>>::from_str("client.key.").unwrap(),
+            Algorithm::Sha256,
+            DhGroup::Oakley1024,
+            3600,
+            &rng,
+            builder(),
+            now,
+        )
+        .unwrap();
+        let request = request.into_message();
+        assert_eq!(request_mode(&request), Some(TkeyMode::DiffieHellman));
+
+        let server = ServerDhExchange::request(&request, &rng).unwrap();
+        assert_eq!(server.expiration(), server.inception().add(3600));
+        let response =
+            server.answer(&request, builder()).unwrap().into_message();
+        let client_key = client.answer(&response).unwrap();
+        let server_key = server.into_key();
+        assert_eq!(client_key.name(), server_key.name());
+        assert_eq!(client_key.algorithm(), Algorithm::Sha256);
+
+        // Both keys must produce the same signatures.
+        let tag = |key: &Key| hmac::sign(&key.key, b"test").as_ref().to_vec();
+        assert_eq!(tag(&client_key), tag(&server_key));
+    }
+
+    #[test]
+    fn dh_exchange_bad_mode() {
+        let rng = SystemRandom::new();
+        let key = Key::new(
+            Algorithm::Sha256,
+            b"secret",
+            Dname::from_str("client.key.").unwrap(),
+            None,
+            None,
+        )
+        .unwrap();
+        let (_, request) =
+            ClientKeyDeletion::request(&key, builder(), Time48::now())
+                .unwrap();
+        let request = request.into_message();
+        let err = ServerDhExchange::request(&request, &rng).unwrap_err();
+        assert_eq!(err.error(), TsigRcode::BadMode);
+        let response = err
+            .build_message(&request, builder())
+            .unwrap()
+            .into_message();
+        let client = ClientDhExchange {
+            algorithm: Algorithm::Sha256,
+            dh: DhKey::generate(DhGroup::Oakley768, &rng).unwrap(),
+            nonce: [0; NONCE_LEN],
+        };
+        assert!(matches!(
+            client.answer(&response),
+            Err(TkeyError::Server(TsigRcode::BadMode))
+        ));
+    }
+
+    #[test]
+    fn key_deletion() {
+        let key = Key::new(
+            Algorithm::Sha256,
+            b"secret",
+            Dname::from_str("client.key.").unwrap(),
+            None,
+            None,
+        )
+        .unwrap();
+        let now = Time48::now();
+        let (client, request) =
+            ClientKeyDeletion::request(&key, builder(), now).unwrap();
+        let mut request = request.into_message();
+        assert_eq!(request_mode(&request), Some(TkeyMode::KeyDeletion));
+
+        let transaction =
+            ServerTransaction::request(&&key, &mut request, now)
+                .unwrap()
+                .unwrap();
+        let server = ServerKeyDeletion::request(transaction, &request);
+        assert!(server.is_accepted());
+        let mut response = server
+            .answer(&request, builder(), now)
+            .unwrap()
+            .into_message();
+        client.answer(&mut response, now).unwrap();
+    }
+}